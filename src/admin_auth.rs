@@ -0,0 +1,161 @@
+use actix_web::dev::Payload;
+use actix_web::error::{ErrorInternalServerError, ErrorUnauthorized};
+use actix_web::http::header;
+use actix_web::web::Data;
+use actix_web::{FromRequest, HttpRequest};
+use anyhow::Context;
+use ethers::types::transaction::eip712::EIP712Domain;
+use ethers::types::H160;
+use std::future::{ready, Ready};
+
+use crate::model::AppState;
+use crate::typed_data::{eip712_struct, recover_digest_signer, typed_data_digest};
+
+// Extractor guarding the admin endpoints, requests are let through only if they carry the
+// configured bearer token in the 'Authorization' header (or if no token is configured)
+pub struct AdminAuth;
+
+impl FromRequest for AdminAuth {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(authorize_admin_request(req))
+    }
+}
+
+fn authorize_admin_request(req: &HttpRequest) -> Result<AdminAuth, actix_web::Error> {
+    let Some(app_state) = req.app_data::<Data<AppState>>() else {
+        return Err(ErrorInternalServerError("App state not configured!"));
+    };
+
+    let Some(admin_auth_token) = app_state.admin_auth_token.as_ref() else {
+        return Ok(AdminAuth);
+    };
+
+    let bearer_token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|header_value| header_value.to_str().ok())
+        .and_then(|header_value| header_value.strip_prefix("Bearer "));
+
+    match bearer_token {
+        Some(bearer_token)
+            if constant_time_eq(bearer_token.as_bytes(), admin_auth_token.as_bytes()) =>
+        {
+            Ok(AdminAuth)
+        }
+        _ => Err(ErrorUnauthorized("Missing or invalid admin bearer token!")),
+    }
+}
+
+// Compare without returning early so that the response time doesn't leak the token prefix
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .fold(0u8, |diff, (byte_a, byte_b)| diff | (byte_a ^ byte_b))
+            == 0
+}
+
+eip712_struct! {
+    #[derive(Debug, Clone)]
+    pub struct MutableConfigMessage as "MutableConfig" {
+        enclave: H160 => "address enclave",
+        gas_address: H160 => "address gasAddress",
+        nonce: u64 => "uint256 nonce",
+        sign_timestamp: u64 => "uint256 signTimestamp",
+    }
+}
+
+/// EIP-712 digest the enclave owner signs to set the gas wallet of the enclave.
+///
+/// The enclave address ties the signature to this enclave and the nonce keeps it from being
+/// replayed once accepted.
+pub fn mutable_config_digest(
+    domain: &EIP712Domain,
+    enclave_address: H160,
+    gas_address: H160,
+    nonce: u64,
+    sign_timestamp: u64,
+) -> [u8; 32] {
+    typed_data_digest(
+        domain,
+        &MutableConfigMessage {
+            enclave: enclave_address,
+            gas_address,
+            nonce,
+            sign_timestamp,
+        },
+    )
+}
+
+/// Recovers the address that signed the digest from a hex encoded 65 bytes (r, s, v) signature.
+pub fn recover_signer_address(digest: &[u8; 32], signature_hex: &str) -> anyhow::Result<H160> {
+    let signature_bytes = hex::decode(signature_hex.trim_start_matches("0x"))
+        .context("Failed to hex decode the signature")?;
+
+    recover_digest_signer(digest, &signature_bytes)
+}
+
+#[cfg(test)]
+mod admin_auth_tests {
+    use ethers::utils::public_key_to_address;
+    use k256::ecdsa::SigningKey;
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::model::Eip712Domains;
+    use crate::test_util::{GAS_WALLET_PUBLIC_ADDRESS, OWNER_ADDRESS};
+
+    #[test]
+    fn test_recover_signer_address() {
+        let signer_key = SigningKey::random(&mut OsRng);
+        let digest = mutable_config_digest(
+            &Eip712Domains::default().serverless_gateway,
+            H160::random(),
+            H160::random(),
+            1,
+            1000,
+        );
+
+        let (rs, v) = signer_key.sign_prehash_recoverable(&digest).unwrap();
+        let mut signature = rs.to_bytes().to_vec();
+        signature.push(27 + v.to_byte());
+
+        assert_eq!(
+            recover_signer_address(&digest, &hex::encode(&signature)).unwrap(),
+            public_key_to_address(signer_key.verifying_key())
+        );
+        // The recovery id is accepted with or without the 27 offset
+        signature[64] -= 27;
+        assert_eq!(
+            recover_signer_address(&digest, &format!("0x{}", hex::encode(&signature))).unwrap(),
+            public_key_to_address(signer_key.verifying_key())
+        );
+
+        assert!(recover_signer_address(&digest, &hex::encode(&signature[0..64])).is_err());
+        assert!(recover_signer_address(&digest, "zz").is_err());
+    }
+
+    #[test]
+    fn test_mutable_config_digest() {
+        assert_eq!(
+            hex::encode(mutable_config_digest(
+                &Eip712Domains::default().serverless_gateway,
+                OWNER_ADDRESS.parse().unwrap(),
+                GAS_WALLET_PUBLIC_ADDRESS.parse().unwrap(),
+                1,
+                1700000010
+            )),
+            "7a25093f1a41e95b91ba455907911dd935cd635b15d1f094be2d07a432976299"
+        );
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+    }
+}
@@ -1,28 +1,44 @@
-use abi::encode;
 use actix_web::web::{Data, Json};
 use actix_web::{get, post, HttpResponse, Responder};
-use anyhow::Context;
-use ethers::abi::{encode_packed, Token};
+use anyhow::{anyhow, Context};
 use ethers::prelude::*;
-use ethers::utils::keccak256;
-use k256::elliptic_curve::generic_array::sequence::Lengthen;
-use log::info;
+use log::{error, info};
 use serde_json::json;
-use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::atomic::Ordering;
 use std::sync::RwLock;
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::contract_abi::{
-    GatewayJobsContract, GatewaysContract, RelayContract, RelaySubscriptionsContract,
+use crate::admin_auth::{mutable_config_digest, recover_signer_address, AdminAuth};
+use crate::chain_util::{
+    build_request_chain_client, fetch_request_chain_data, ChainConnector, WsHttpConnector,
 };
+use crate::constant::{
+    DEFAULT_SUBSCRIPTION_PREVIEW_INSTANCES, MAX_SUBSCRIPTION_PREVIEW_INSTANCES,
+    MUTABLE_CONFIG_SIGNATURE_VALIDITY,
+};
+use crate::contract_abi::{GatewayJobsContract, GatewaysContract};
+use crate::deregistration::run_deregistration;
+use crate::event_decoder::uint_to_u64;
+use crate::gas_wallet_rotation::rotate_gas_wallet;
+use crate::job_subscription_management::preview_subscription_job;
 use crate::model::{
-    AppState, ContractsClient, GatewayData, GatewayDetailsResponse, ImmutableConfig, MutableConfig,
-    RequestChainClient, RequestChainData, SignedRegistrationBody, SignedRegistrationResponse,
+    AppState, ContractsClient, DeadlineQueue, DeregisterGatewayBody, GasWallet, GatewayData,
+    GatewayDetailsResponse, ImmutableConfig, MutableConfig, OwnerSignerConfig, RegisterGatewayBody,
+    RegistrationTarget, RequestChainClient, RequestChainData, SchedulePreviewBody,
+    SchedulePreviewResponse, SignedChainsUpdateBody, SignedChainsUpdateResponse,
+    SignedRegistrationBody, SignedRegistrationResponse, SubscriptionCatchUpMetrics,
+    SubscriptionJobScheduler, SubscriptionKey, SubscriptionPreviewBody, SubscriptionTriggerLimiter,
+    TxnQueueMetricsResponse,
+};
+use crate::sealed_state::persist_sealed_state;
+use crate::self_registration::submit_registration;
+use crate::typed_data::{
+    sign_typed_data, AddChainsMessage, GatewaysRegisterMessage, RelayRegisterMessage,
+    RemoveChainsMessage,
 };
-use crate::HttpProviderType;
 
 #[get("/")]
 async fn index() -> impl Responder {
@@ -33,6 +49,7 @@ async fn index() -> impl Responder {
 // Endpoint exposed to inject immutable gateway config parameters
 #[post("/immutable-config")]
 async fn inject_immutable_config(
+    _admin_auth: AdminAuth,
     Json(immutable_config): Json<ImmutableConfig>,
     app_state: Data<AppState>,
 ) -> impl Responder {
@@ -52,8 +69,10 @@ async fn inject_immutable_config(
     // Initialize owner address for the enclave
     *app_state.enclave_owner.lock().unwrap() = owner_address;
     *immutable_params_injected_guard = true;
+    drop(immutable_params_injected_guard);
 
     info!("Immutable params configured!");
+    persist_sealed_state(&app_state).await;
 
     HttpResponse::Ok().body("Immutable params configured!")
 }
@@ -61,143 +80,100 @@ async fn inject_immutable_config(
 // Endpoint exposed to inject mutable gateway config parameters
 #[post("/mutable-config")]
 async fn inject_mutable_config(
+    _admin_auth: AdminAuth,
     Json(mutable_config): Json<MutableConfig>,
     app_state: Data<AppState>,
 ) -> impl Responder {
-    let mut bytes32_gas_key = [0u8; 32];
-    if let Err(err) = hex::decode_to_slice(&mutable_config.gas_key_hex, &mut bytes32_gas_key) {
-        return HttpResponse::BadRequest().body(format!(
-            "Failed to hex decode the gas private key into 32 bytes: {:?}",
-            err
+    // The gas wallet can only be set by the enclave owner
+    if !*app_state.immutable_params_injected.lock().unwrap() {
+        return HttpResponse::BadRequest().body("Immutable params not configured yet!");
+    }
+
+    if mutable_config
+        .sign_timestamp
+        .abs_diff(app_state.clock.now())
+        > MUTABLE_CONFIG_SIGNATURE_VALIDITY
+    {
+        return HttpResponse::Unauthorized().body("Mutable config signature expired!");
+    }
+
+    let mutable_config_nonce = *app_state.mutable_config_nonce.lock().unwrap();
+    if mutable_config.nonce <= mutable_config_nonce {
+        return HttpResponse::Unauthorized().body(format!(
+            "Mutable config nonce already used, expected a nonce greater than {}",
+            mutable_config_nonce
         ));
     }
 
-    // Initialize local wallet with operator's gas key to send signed transactions to the common chain and request chains
-    let gas_wallet = LocalWallet::from_bytes(&bytes32_gas_key);
-    let Ok(gas_wallet) = gas_wallet else {
+    let gas_address = mutable_config.gas_wallet.address();
+    let Ok(gas_address) = gas_address else {
+        return HttpResponse::BadRequest().body(format!("{:#}", gas_address.unwrap_err()));
+    };
+    let digest = mutable_config_digest(
+        &app_state.eip712_domains.serverless_gateway,
+        app_state.enclave_address,
+        gas_address,
+        mutable_config.nonce,
+        mutable_config.sign_timestamp,
+    );
+    let signer_address = recover_signer_address(&digest, &mutable_config.owner_signature_hex);
+    let Ok(signer_address) = signer_address else {
         return HttpResponse::BadRequest().body(format!(
-            "Invalid gas private key provided: {:?}",
-            gas_wallet.unwrap_err()
+            "Invalid owner signature provided: {:?}",
+            signer_address.unwrap_err()
         ));
     };
-    let gas_wallet = gas_wallet.with_chain_id(app_state.common_chain_id);
-
-    let mut wallet_guard = app_state.wallet.lock().unwrap();
-    if *wallet_guard == Some(gas_wallet.clone()) {
-        return HttpResponse::NotAcceptable().body("The same wallet address already set.");
+    if signer_address != *app_state.enclave_owner.lock().unwrap() {
+        return HttpResponse::Unauthorized()
+            .body("Mutable config not signed by the enclave owner!");
     }
 
-    let contracts_client_guard = app_state.contracts_client.lock().unwrap();
-
-    if contracts_client_guard.is_some() {
-        info!("Updating Contracts Client with the new wallet address");
-        let gas_address = gas_wallet.address();
+    // Initialize the operator's gas wallet to send signed transactions to the common chain and
+    // request chains only once the owner has signed for its address
+    let gas_wallet =
+        GasWallet::from_config(&mutable_config.gas_wallet, app_state.common_chain_id).await;
+    let Ok(gas_wallet) = gas_wallet else {
+        return HttpResponse::BadRequest().body(format!("{:#}", gas_wallet.unwrap_err()));
+    };
 
-        // Build Common Chain http rpc client
-        let common_chain_http_rpc_client =
-            Provider::<Http>::try_from(&app_state.common_chain_http_url);
-        let Ok(common_chain_http_rpc_client) = common_chain_http_rpc_client else {
-            return HttpResponse::InternalServerError().body(format!(
-                "Failed to connect to the common chain http rpc server {}: {}",
-                app_state.common_chain_http_url,
-                common_chain_http_rpc_client.unwrap_err()
+    {
+        // Nonces must increase so that an accepted signature can't be replayed, checked again as
+        // another config may have been accepted while the wallet was set up
+        let mut mutable_config_nonce_guard = app_state.mutable_config_nonce.lock().unwrap();
+        if mutable_config.nonce <= *mutable_config_nonce_guard {
+            return HttpResponse::Unauthorized().body(format!(
+                "Mutable config nonce already used, expected a nonce greater than {}",
+                *mutable_config_nonce_guard
             ));
-        };
-        let common_chain_http_rpc_client = Arc::new(
-            common_chain_http_rpc_client
-                .with_signer(gas_wallet.clone())
-                .nonce_manager(gas_address),
-        );
-
-        // Build Request Chain Client's http rpc client
-        let mut request_chain_contract_clients: HashMap<
-            u64,
-            (
-                RelayContract<HttpProviderType>,
-                RelaySubscriptionsContract<HttpProviderType>,
-            ),
-        > = HashMap::new();
-        let request_chain_clients_clone: HashMap<u64, Arc<RequestChainClient>> =
-            contracts_client_guard
-                .as_ref()
-                .unwrap()
-                .request_chain_clients
-                .clone();
-        for (&chain_id, request_chain_client) in request_chain_clients_clone.iter() {
-            let request_chain_http_rpc_client =
-                Provider::<Http>::try_from(&request_chain_client.http_rpc_url);
-            let Ok(request_chain_http_rpc_client) = request_chain_http_rpc_client else {
-                return HttpResponse::InternalServerError().body(format!(
-                    "Failed to connect to the request chain {} http rpc server {}: {}",
-                    chain_id,
-                    request_chain_client.http_rpc_url,
-                    request_chain_http_rpc_client.unwrap_err()
-                ));
-            };
-
-            let gas_wallet = gas_wallet.clone().with_chain_id(chain_id);
-            let request_chain_http_rpc_client = Arc::new(
-                request_chain_http_rpc_client
-                    .with_signer(gas_wallet.clone())
-                    .nonce_manager(gas_address),
-            );
-
-            let relay_contract = RelayContract::new(
-                request_chain_client.relay_address,
-                request_chain_http_rpc_client.clone(),
-            );
-
-            let relay_subs_contract = RelaySubscriptionsContract::new(
-                request_chain_client.relay_subscriptions_address,
-                request_chain_http_rpc_client,
-            );
-            request_chain_contract_clients.insert(chain_id, (relay_contract, relay_subs_contract));
         }
 
-        // Updating Gateway Jobs Contract's http rpc client with the new wallet address
-        let gateway_jobs_contract = GatewayJobsContract::new(
-            app_state.gateway_jobs_contract_addr,
-            common_chain_http_rpc_client,
-        );
-
-        let mut gateway_jobs_contract_write_guard = contracts_client_guard
-            .as_ref()
-            .unwrap()
-            .gateway_jobs_contract
-            .write()
-            .unwrap();
-        *gateway_jobs_contract_write_guard = gateway_jobs_contract;
-
-        // Updating request chain contract's http rpc client with the new wallet address
-        for request_chain_client in contracts_client_guard
-            .as_ref()
-            .unwrap()
-            .request_chain_clients
-            .values()
-        {
-            let (relay_contract, relay_subs_contract) = request_chain_contract_clients
-                .get(&request_chain_client.chain_id)
-                .unwrap()
-                .clone();
-
-            let mut relay_contract_write_guard =
-                request_chain_client.relay_contract.write().unwrap();
-            *relay_contract_write_guard = relay_contract;
+        if *app_state.wallet.lock().unwrap() == Some(gas_wallet.clone()) {
+            return HttpResponse::NotAcceptable().body("The same wallet address already set.");
+        }
 
-            let mut relay_subs_contract_write_guard = request_chain_client
-                .relay_subscriptions_contract
-                .write()
-                .unwrap();
-            *relay_subs_contract_write_guard = relay_subs_contract;
+        // Stage the new wallet, only one rotation runs at a time
+        let mut staged_wallet_guard = app_state.staged_wallet.lock().unwrap();
+        if staged_wallet_guard.is_some() {
+            return HttpResponse::Conflict().body("A gas wallet rotation is already in progress!");
         }
+        *staged_wallet_guard = Some(gas_wallet.clone());
+        *mutable_config_nonce_guard = mutable_config.nonce;
+    }
+
+    let rotation_result =
+        rotate_gas_wallet(&app_state, gas_wallet, mutable_config.sweep_funds).await;
+    *app_state.staged_wallet.lock().unwrap() = None;
+    if let Err(err) = rotation_result {
+        return HttpResponse::InternalServerError()
+            .body(format!("Failed to rotate the gas wallet: {:?}", err));
     }
-    *wallet_guard = Some(gas_wallet.clone());
 
     app_state
         .mutable_params_injected
         .store(true, Ordering::SeqCst);
 
     info!("Mutable params configured!");
+    persist_sealed_state(&app_state).await;
 
     HttpResponse::Ok().body("Mutable params configured!")
 }
@@ -205,366 +181,605 @@ async fn inject_mutable_config(
 // Endpoint exposed to retrieve the metadata required to register the enclave on the common chain and request chains
 #[get("/signed-registration-message")]
 async fn export_signed_registration_message(
+    _admin_auth: AdminAuth,
     Json(signed_registration_body): Json<SignedRegistrationBody>,
     app_state: Data<AppState>,
 ) -> impl Responder {
+    match generate_signed_registration(&app_state, signed_registration_body.chain_ids).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(response) => response,
+    }
+}
+
+// Endpoint exposed to register the enclave on the common chain and request chains with the
+// transactions of its owner signed by a remote signer, the owner key itself is never sent to the
+// enclave and the registration with it is submitted by the 'register' subcommand instead
+#[post("/register")]
+async fn register_gateway(
+    _admin_auth: AdminAuth,
+    Json(register_gateway_body): Json<RegisterGatewayBody>,
+    app_state: Data<AppState>,
+) -> impl Responder {
+    if let OwnerSignerConfig::Local { .. } = register_gateway_body.owner_signer {
+        return HttpResponse::BadRequest()
+            .body("Owner key not accepted, only a remote owner signer can be used!");
+    }
+
+    let owner_signer = match register_gateway_body.owner_signer.connect().await {
+        Ok(owner_signer) => owner_signer,
+        Err(err) => {
+            return HttpResponse::BadRequest()
+                .body(format!("Failed to set up the owner signer: {:#}", err));
+        }
+    };
+    // the owner must be injected before the registration message can be signed
+    if *app_state.immutable_params_injected.lock().unwrap()
+        && owner_signer.address() != *app_state.enclave_owner.lock().unwrap()
+    {
+        return HttpResponse::BadRequest()
+            .body("Owner signer address doesn't match the enclave owner!");
+    }
+
+    let signed_registration =
+        match generate_signed_registration(&app_state, register_gateway_body.chain_ids).await {
+            Ok(signed_registration) => signed_registration,
+            Err(response) => return response,
+        };
+
+    let response = submit_registration(
+        &RegistrationTarget::from(app_state.as_ref()),
+        owner_signer,
+        &signed_registration,
+        &register_gateway_body.attestation,
+        register_gateway_body.stake_amount,
+    )
+    .await;
+
+    if response.registered() {
+        HttpResponse::Ok().json(response)
+    } else {
+        HttpResponse::InternalServerError().json(response)
+    }
+}
+
+// Endpoint exposed to deregister the gateway, it is drained first and torn down once it is
+// deregistered on every chain. As for the registration, the owner key itself is never sent to the
+// enclave and the deregistration with it is submitted by the 'deregister' subcommand instead
+#[post("/deregister")]
+async fn deregister_gateway(
+    _admin_auth: AdminAuth,
+    Json(deregister_gateway_body): Json<DeregisterGatewayBody>,
+    app_state: Data<AppState>,
+) -> impl Responder {
+    if let Some(OwnerSignerConfig::Local { .. }) = deregister_gateway_body.owner_signer {
+        return HttpResponse::BadRequest().body(
+            "Owner key not accepted, only a remote owner signer can be used! Deregister with the owner key through the 'deregister' subcommand instead",
+        );
+    }
+
+    if !app_state.registered.load(Ordering::SeqCst) {
+        return HttpResponse::BadRequest().body("Enclave not registered yet!");
+    }
+
+    let owner_signer = match deregister_gateway_body.owner_signer {
+        Some(owner_signer) => match owner_signer.connect().await {
+            Ok(owner_signer) => Some(owner_signer),
+            Err(err) => {
+                return HttpResponse::BadRequest()
+                    .body(format!("Failed to set up the owner signer: {:#}", err));
+            }
+        },
+        None => None,
+    };
+    if let Some(owner_signer) = owner_signer.as_ref() {
+        if owner_signer.address() != *app_state.enclave_owner.lock().unwrap() {
+            return HttpResponse::BadRequest()
+                .body("Owner signer address doesn't match the enclave owner!");
+        }
+    }
+
+    if app_state.deregistering.swap(true, Ordering::SeqCst) {
+        return HttpResponse::BadRequest().body("Deregistration already in progress!");
+    }
+
+    // The draining period can last long past the request
+    tokio::spawn(async move {
+        if let Err(err) = run_deregistration(&app_state, owner_signer).await {
+            error!("Failed to deregister the gateway: {:?}", err);
+            app_state.deregistering.store(false, Ordering::SeqCst);
+        }
+    });
+
+    HttpResponse::Accepted().body("Deregistration started!")
+}
+
+/// Signs the registration messages of the enclave for the given request chains and starts
+/// listening for the registration, the error is the response to return to the admin.
+pub async fn generate_signed_registration(
+    app_state: &Data<AppState>,
+    requested_chain_ids: Vec<u64>,
+) -> Result<SignedRegistrationResponse, HttpResponse> {
     // if gateway is already registered, return error
     {
         if app_state.registered.load(Ordering::SeqCst) {
-            return HttpResponse::BadRequest().body("Enclave has already been registered.");
+            return Err(HttpResponse::BadRequest().body("Enclave has already been registered."));
         }
     }
 
     // check if event listener is active and verify the request_chain_ids
-    let chain_ids: HashSet<u64> = signed_registration_body
-        .chain_ids
-        .clone()
-        .into_iter()
-        .collect();
+    let chain_ids = normalize_chain_ids(requested_chain_ids);
+    let chain_id_set: HashSet<u64> = chain_ids.iter().copied().collect();
 
     // there should be atleast one request chain id
     if chain_ids.is_empty() {
-        return HttpResponse::BadRequest().body("Atleast one request chain id is required!");
+        return Err(HttpResponse::BadRequest().body("Atleast one request chain id is required!"));
     }
 
     {
         // verify that the app state request chain ids are same as the signed registration body chain ids
         let request_chain_ids_guard = app_state.request_chain_ids.lock().unwrap();
-        if !request_chain_ids_guard.is_empty() && *request_chain_ids_guard != chain_ids {
-            return HttpResponse::BadRequest().json(json!({
+        if !request_chain_ids_guard.is_empty() && *request_chain_ids_guard != chain_id_set {
+            return Err(HttpResponse::BadRequest().json(json!({
                     "message": "Request chain ids mismatch!",
                     "chain_ids": *request_chain_ids_guard,
-            }));
+            })));
         }
     }
 
     // if immutable or mutable params are not configured, return error
     if !*app_state.immutable_params_injected.lock().unwrap() {
-        return HttpResponse::BadRequest().body("Immutable params not configured yet!");
+        return Err(HttpResponse::BadRequest().body("Immutable params not configured yet!"));
     }
 
     // if mutable params are not configured, return error
     if !app_state.mutable_params_injected.load(Ordering::SeqCst) {
-        return HttpResponse::BadRequest().body("Mutable params not configured yet!");
+        return Err(HttpResponse::BadRequest().body("Mutable params not configured yet!"));
     }
 
     // if wallet is not configured, return error
     if app_state.wallet.lock().unwrap().is_none() {
-        return HttpResponse::BadRequest().body("Mutable param wallet not configured yet!");
+        return Err(HttpResponse::BadRequest().body("Mutable param wallet not configured yet!"));
     };
 
-    // generate common chain signature
+    // the gateway may have been registered before a restart
+    match detect_registration(app_state).await {
+        Ok(true) => {
+            return Err(HttpResponse::BadRequest().body("Enclave has already been registered."));
+        }
+        Ok(false) => {}
+        Err(err) => {
+            return Err(HttpResponse::InternalServerError().body(format!(
+                "Failed to detect the registration of the enclave on chain: {:#}",
+                err
+            )));
+        }
+    }
+
+    // generate the common chain and request chain signatures
     let enclave_owner = app_state.enclave_owner.lock().unwrap().clone();
+    let signed_registration = sign_registration(app_state, enclave_owner, &chain_ids);
+    let Ok(signed_registration) = signed_registration else {
+        return Err(HttpResponse::InternalServerError()
+            .body(format!("{:#}", signed_registration.unwrap_err())));
+    };
+
+    // Build the contracts client unless the registration is already being listened for
+    let contracts_client = if *app_state
+        .registration_events_listener_active
+        .lock()
+        .unwrap()
+    {
+        None
+    } else {
+        let contracts_client =
+            build_contracts_client(app_state, enclave_owner, &chain_id_set).await;
+        let Ok(contracts_client) = contracts_client else {
+            return Err(HttpResponse::InternalServerError()
+                .body(format!("{:#}", contracts_client.unwrap_err())));
+        };
+        Some(contracts_client)
+    };
+
+    {
+        let mut request_chain_ids_guard = app_state.request_chain_ids.lock().unwrap();
+        if request_chain_ids_guard.is_empty() {
+            *request_chain_ids_guard = chain_id_set;
+        } else {
+            if *request_chain_ids_guard != chain_id_set {
+                return Err(HttpResponse::BadRequest().json(json!({
+                    "message": "Request chain ids mismatch!",
+                    "chain_ids": *request_chain_ids_guard,
+                })));
+            }
+        }
+    }
+
+    if let Some(contracts_client) = contracts_client {
+        let mut registration_events_listener_active_guard = app_state
+            .registration_events_listener_active
+            .lock()
+            .unwrap();
+
+        if *registration_events_listener_active_guard == false {
+            *app_state.contracts_client.lock().unwrap() = Some(Arc::clone(&contracts_client));
+
+            let app_state_clone = app_state.clone();
+            tokio::spawn(async move {
+                Arc::clone(&contracts_client)
+                    .wait_for_registration(app_state_clone.clone())
+                    .await;
+                // Start the ContractsClient service
+                contracts_client.start(app_state_clone);
+            });
+
+            *registration_events_listener_active_guard = true;
+        }
+    }
+    persist_sealed_state(app_state).await;
+
+    Ok(signed_registration)
+}
+
+/// Sorts the request chain ids and drops the duplicates, the registration is signed, returned
+/// and submitted with this list.
+pub fn normalize_chain_ids(mut chain_ids: Vec<u64>) -> Vec<u64> {
+    chain_ids.sort_unstable();
+    chain_ids.dedup();
+    chain_ids
+}
+
+/// Signs the registration of the enclave on the common chain, with the request chain ids in the
+/// given order, and on the request chains.
+pub fn sign_registration(
+    app_state: &AppState,
+    enclave_owner: H160,
+    chain_ids: &[u64],
+) -> anyhow::Result<SignedRegistrationResponse> {
     let sign_timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
 
-    let common_chain_register_typehash =
-        keccak256("Register(address owner,uint256[] chainIds,uint256 signTimestamp)");
-
-    let chain_ids_tokens: Vec<Token> = (&chain_ids)
-        .into_iter()
-        .map(|&x| Token::Uint(x.into()))
-        .collect::<Vec<Token>>();
-
-    let chain_ids_bytes = keccak256(encode_packed(&[Token::Array(chain_ids_tokens)]).unwrap());
-    let hash_struct = keccak256(encode(&[
-        Token::FixedBytes(common_chain_register_typehash.to_vec()),
-        Token::Address(enclave_owner),
-        Token::FixedBytes(chain_ids_bytes.into()),
-        Token::Uint(sign_timestamp.into()),
-    ]));
-
-    let gateways_domain_separator = keccak256(encode(&[
-        Token::FixedBytes(keccak256("EIP712Domain(string name,string version)").to_vec()),
-        Token::FixedBytes(keccak256("marlin.oyster.Gateways").to_vec()),
-        Token::FixedBytes(keccak256("1").to_vec()),
-    ]));
-
-    let digest = encode_packed(&[
-        Token::String("\x19\x01".to_string()),
-        Token::FixedBytes(gateways_domain_separator.to_vec()),
-        Token::FixedBytes(hash_struct.to_vec()),
-    ]);
-
-    let Ok(digest) = digest else {
-        return HttpResponse::InternalServerError().body(format!(
-            "Failed to encode the registration message for signing: {:?}",
-            digest.unwrap_err()
-        ));
+    let gateways_register = GatewaysRegisterMessage {
+        owner: enclave_owner,
+        chain_ids: chain_ids.iter().map(|&chain_id| chain_id.into()).collect(),
+        sign_timestamp,
     };
-    let digest = keccak256(digest);
 
     // Sign the digest using enclave key
-    let sig = app_state
-        .enclave_signer_key
-        .sign_prehash_recoverable(&digest);
-    let Ok((rs, v)) = sig else {
-        return HttpResponse::InternalServerError().body(format!(
-            "Failed to sign the registration message using enclave key: {:?}",
-            sig.unwrap_err()
-        ));
+    let common_chain_signature = sign_typed_data(
+        app_state.enclave_signer.as_ref(),
+        &app_state.eip712_domains.gateways,
+        &gateways_register,
+    )
+    .context("Failed to sign the registration message using enclave key")?;
+
+    let relay_register = RelayRegisterMessage {
+        owner: enclave_owner,
+        sign_timestamp,
     };
-    let common_chain_signature = hex::encode(rs.to_bytes().append(27 + v.to_byte()).to_vec());
-
-    // create request chain signature and add it to the request chain signatures map
-    let request_chain_register_typehash =
-        keccak256("Register(address owner,uint256 signTimestamp)");
-    let hash_struct = keccak256(encode(&[
-        Token::FixedBytes(request_chain_register_typehash.to_vec()),
-        Token::Address(enclave_owner),
-        Token::Uint(sign_timestamp.into()),
-    ]));
-
-    let request_chain_domain_separator = keccak256(encode(&[
-        Token::FixedBytes(keccak256("EIP712Domain(string name,string version)").to_vec()),
-        Token::FixedBytes(keccak256("marlin.oyster.Relay").to_vec()),
-        Token::FixedBytes(keccak256("1").to_vec()),
-    ]));
-
-    let digest = encode_packed(&[
-        Token::String("\x19\x01".to_string()),
-        Token::FixedBytes(request_chain_domain_separator.to_vec()),
-        Token::FixedBytes(hash_struct.to_vec()),
-    ]);
-
-    let Ok(digest) = digest else {
-        return HttpResponse::InternalServerError().body(format!(
-            "Failed to encode the registration message for signing: {:?}",
-            digest.unwrap_err()
-        ));
+
+    // Sign the digest using enclave key
+    let request_chain_signature = sign_typed_data(
+        app_state.enclave_signer.as_ref(),
+        &app_state.eip712_domains.relay,
+        &relay_register,
+    )
+    .context("Failed to sign the registration message using enclave key")?;
+
+    Ok(SignedRegistrationResponse {
+        enclave_address: app_state.enclave_address,
+        owner: enclave_owner,
+        sign_timestamp: sign_timestamp.try_into().unwrap(),
+        chain_ids: chain_ids.to_vec(),
+        common_chain_signature: hex::encode(common_chain_signature),
+        request_chain_signature: hex::encode(request_chain_signature),
+    })
+}
+
+// Endpoint exposed to retrieve the signature adding request chains to the registered enclave with
+// 'addChains'
+#[get("/signed-add-chains-message")]
+async fn export_signed_add_chains_message(
+    _admin_auth: AdminAuth,
+    Json(signed_chains_update_body): Json<SignedChainsUpdateBody>,
+    app_state: Data<AppState>,
+) -> impl Responder {
+    sign_chains_update(&app_state, signed_chains_update_body.chain_ids, true)
+}
+
+// Endpoint exposed to retrieve the signature removing request chains from the registered enclave
+// with 'removeChains'
+#[get("/signed-remove-chains-message")]
+async fn export_signed_remove_chains_message(
+    _admin_auth: AdminAuth,
+    Json(signed_chains_update_body): Json<SignedChainsUpdateBody>,
+    app_state: Data<AppState>,
+) -> impl Responder {
+    sign_chains_update(&app_state, signed_chains_update_body.chain_ids, false)
+}
+
+fn sign_chains_update(app_state: &AppState, chain_ids: Vec<u64>, add: bool) -> HttpResponse {
+    if !app_state.registered.load(Ordering::SeqCst) {
+        return HttpResponse::BadRequest().body("Enclave not registered yet!");
+    }
+
+    if chain_ids.is_empty() {
+        return HttpResponse::BadRequest().body("Atleast one request chain id is required!");
+    }
+
+    // the added chains must not be served yet and the removed ones must be
+    let invalid_chain_ids: Vec<u64> = {
+        let request_chain_ids_guard = app_state.request_chain_ids.lock().unwrap();
+        chain_ids
+            .iter()
+            .copied()
+            .filter(|chain_id| request_chain_ids_guard.contains(chain_id) == add)
+            .collect()
     };
+    if !invalid_chain_ids.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "message": if add {
+                "Request chains already served!"
+            } else {
+                "Request chains not served!"
+            },
+            "chain_ids": invalid_chain_ids,
+        }));
+    }
 
-    let digest = keccak256(digest);
+    let sign_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let message_chain_ids: Vec<U256> = chain_ids.iter().map(|&chain_id| chain_id.into()).collect();
 
     // Sign the digest using enclave key
-    let sig = app_state
-        .enclave_signer_key
-        .sign_prehash_recoverable(&digest);
-    let Ok((rs, v)) = sig else {
+    let sig = if add {
+        sign_typed_data(
+            app_state.enclave_signer.as_ref(),
+            &app_state.eip712_domains.gateways,
+            &AddChainsMessage {
+                chain_ids: message_chain_ids,
+                sign_timestamp,
+            },
+        )
+    } else {
+        sign_typed_data(
+            app_state.enclave_signer.as_ref(),
+            &app_state.eip712_domains.gateways,
+            &RemoveChainsMessage {
+                chain_ids: message_chain_ids,
+                sign_timestamp,
+            },
+        )
+    };
+    let Ok(sig) = sig else {
         return HttpResponse::InternalServerError().body(format!(
-            "Failed to sign the registration message using enclave key: {:?}",
+            "Failed to sign the chains update message using enclave key: {:?}",
             sig.unwrap_err()
         ));
     };
 
-    let request_chain_signature = hex::encode(rs.to_bytes().append(27 + v.to_byte()).to_vec());
+    HttpResponse::Ok().json(SignedChainsUpdateResponse {
+        enclave_address: app_state.enclave_address,
+        sign_timestamp: sign_timestamp.try_into().unwrap(),
+        chain_ids,
+        signature: hex::encode(sig),
+    })
+}
+
+// Builds the client of the contracts on the common chain and the given request chains, sending
+// the transactions with the gas wallet of the enclave
+pub async fn build_contracts_client(
+    app_state: &AppState,
+    enclave_owner: Address,
+    chain_ids: &HashSet<u64>,
+) -> anyhow::Result<Arc<ContractsClient>> {
+    let Some(gas_wallet) = app_state.wallet.lock().unwrap().clone() else {
+        return Err(anyhow!("Mutable param wallet not configured yet!"));
+    };
+
+    let chain_connector = Arc::new(WsHttpConnector);
 
     // Create GatewaysContract instance
-    let common_chain_http_rpc_client = Provider::<Http>::try_from(&app_state.common_chain_http_url);
-    let Ok(common_chain_http_rpc_client) = common_chain_http_rpc_client else {
-        return HttpResponse::InternalServerError().body(format!(
-            "Failed to connect to the common chain http rpc server {}: {}",
-            app_state.common_chain_http_url,
-            common_chain_http_rpc_client.unwrap_err()
-        ));
-    };
-    let common_chain_http_rpc_client = Arc::new(common_chain_http_rpc_client);
+    let common_chain_http_rpc_client = Arc::new(
+        chain_connector
+            .connect_http(&app_state.common_chain_http_url)
+            .await?,
+    );
     let gateways_contract = GatewaysContract::new(
         app_state.gateways_contract_addr,
         common_chain_http_rpc_client.clone(),
     );
 
-    let Ok(common_chain_block_number) = common_chain_http_rpc_client.get_block_number().await
-    else {
-        return HttpResponse::InternalServerError().body(
-                format!("Failed to fetch the latest block number of the common chain for initiating event listening!")
-            );
-    };
+    let common_chain_block_number = common_chain_http_rpc_client
+        .get_block_number()
+        .await
+        .context(
+            "Failed to fetch the latest block number of the common chain for initiating event listening!",
+        )?;
 
     let mut request_chains_data: HashMap<u64, RequestChainData> = HashMap::new();
 
-    // iterate over all chain ids and get their registration signatures
-    for &chain_id in &chain_ids {
-        // get request chain rpc url
-        let request_chain_info = gateways_contract.request_chains(U256::from(chain_id)).await;
-        if request_chain_info.is_err() {
-            return HttpResponse::InternalServerError().body(format!(
-                "Failed to fetch the request chain data for chain id {}: {}",
-                chain_id,
-                request_chain_info.unwrap_err()
-            ));
-        }
-        let (relay_address, relay_subscriptions_address, http_rpc_url, ws_rpc_url) =
-            request_chain_info.unwrap();
-
-        let http_rpc_client = Provider::<Http>::try_from(&http_rpc_url);
-        let Ok(http_rpc_client) = http_rpc_client else {
-            return HttpResponse::InternalServerError().body(format!(
-                "Failed to connect to the request chain {} http rpc server {}: {}",
-                chain_id,
-                http_rpc_url,
-                http_rpc_client.unwrap_err()
-            ));
-        };
-
-        let block_number = http_rpc_client
-            .get_block_number()
-            .await
-            .context("Failed to get the latest block number of the request chain")
-            .unwrap()
-            .as_u64();
-
+    // iterate over all chain ids and get their rpc urls and contract addresses
+    for &chain_id in chain_ids {
         request_chains_data.insert(
             chain_id,
-            RequestChainData {
-                relay_address,
-                relay_subscriptions_address,
-                http_rpc_url: http_rpc_url.to_string(),
-                ws_rpc_url: ws_rpc_url.to_string(),
-                block_number,
-            },
+            fetch_request_chain_data(chain_connector.as_ref(), &gateways_contract, chain_id)
+                .await?,
         );
     }
 
-    let wallet_guard = app_state.wallet.lock().unwrap();
-
-    let signer_wallet = wallet_guard
-        .clone()
-        .unwrap()
-        .with_chain_id(app_state.common_chain_id);
-    let signer_address = signer_wallet.address();
-
-    let common_chain_http_rpc_client = Provider::<Http>::try_from(&app_state.common_chain_http_url);
-    let Ok(common_chain_http_rpc_client) = common_chain_http_rpc_client else {
-        return HttpResponse::InternalServerError().body(format!(
-            "Failed to connect to the common chain http rpc server {}: {}",
-            app_state.common_chain_http_url,
-            common_chain_http_rpc_client.unwrap_err()
-        ));
-    };
-    let common_chain_http_rpc_client = Arc::new(
-        common_chain_http_rpc_client
-            .with_signer(signer_wallet.clone())
-            .nonce_manager(signer_address),
-    );
+    let common_chain_http_rpc_client = chain_connector
+        .connect_gas_wallet(
+            &app_state.common_chain_http_url,
+            gas_wallet.clone().with_chain_id(app_state.common_chain_id),
+        )
+        .await?;
 
     let mut request_chain_clients: HashMap<u64, Arc<RequestChainClient>> = HashMap::new();
 
-    for &chain_id in &chain_ids {
-        let request_chain_data = request_chains_data.get(&chain_id).unwrap();
-
-        let signer_wallet = wallet_guard.clone().unwrap().with_chain_id(chain_id);
-
-        let request_chain_http_rpc_client =
-            Provider::<Http>::try_from(&request_chain_data.http_rpc_url);
-        let Ok(request_chain_http_rpc_client) = request_chain_http_rpc_client else {
-            return HttpResponse::InternalServerError().body(format!(
-                "Failed to connect to the request chain {} http rpc server {}: {}",
+    for (&chain_id, request_chain_data) in request_chains_data.iter() {
+        request_chain_clients.insert(
+            chain_id,
+            build_request_chain_client(
+                chain_connector.as_ref(),
+                &gas_wallet,
                 chain_id,
-                request_chain_data.http_rpc_url,
-                request_chain_http_rpc_client.unwrap_err()
-            ));
-        };
-
-        let request_chain_http_rpc_client = Arc::new(
-            request_chain_http_rpc_client
-                .with_signer(signer_wallet)
-                .nonce_manager(signer_address),
-        );
-        let relay_contract = RelayContract::new(
-            request_chain_data.relay_address,
-            request_chain_http_rpc_client.clone(),
+                request_chain_data,
+            )
+            .await?,
         );
+    }
 
-        let relay_subs_contract = RelaySubscriptionsContract::new(
-            request_chain_data.relay_subscriptions_address,
-            request_chain_http_rpc_client.clone(),
-        );
+    let gateway_epoch_state: Arc<RwLock<BTreeMap<u64, BTreeMap<Address, GatewayData>>>> =
+        Arc::new(RwLock::new(BTreeMap::new()));
+    let gateway_epoch_state_waitlist = Arc::new(RwLock::new(HashMap::new()));
 
-        let request_chain_client = Arc::from(RequestChainClient {
-            chain_id,
-            relay_address: request_chain_data.relay_address,
-            relay_subscriptions_address: request_chain_data.relay_subscriptions_address,
-            relay_contract: Arc::new(RwLock::new(relay_contract)),
-            relay_subscriptions_contract: Arc::new(RwLock::new(relay_subs_contract)),
-            ws_rpc_url: request_chain_data.ws_rpc_url.to_string(),
-            http_rpc_url: request_chain_data.http_rpc_url.to_string(),
-            request_chain_start_block_number: request_chain_data.block_number,
-            confirmation_blocks: 5, // TODO: fetch from contract
-            last_seen_block: Arc::new(0.into()),
-        });
-        request_chain_clients.insert(chain_id, request_chain_client);
-    }
+    let gateway_jobs_contract = GatewayJobsContract::new(
+        app_state.gateway_jobs_contract_addr,
+        common_chain_http_rpc_client.clone(),
+    );
 
-    let mut request_chain_ids_guard = app_state.request_chain_ids.lock().unwrap();
-    if request_chain_ids_guard.is_empty() {
-        *request_chain_ids_guard = chain_ids.clone();
-    } else {
-        if *request_chain_ids_guard != chain_ids {
-            return HttpResponse::BadRequest().json(json!({
-                "message": "Request chain ids mismatch!",
-                "chain_ids": *request_chain_ids_guard,
-            }));
-        }
-    }
+    let subscription_job_scheduler = Arc::new(RwLock::new(SubscriptionJobScheduler::default()));
+    let subscription_jobs = Arc::new(RwLock::new(HashMap::new()));
 
-    let mut registration_events_listener_active_guard = app_state
+    Ok(Arc::new(ContractsClient {
+        enclave_owner,
+        enclave_signer: app_state.enclave_signer.clone(),
+        enclave_address: app_state.enclave_address,
+        common_chain_ws_url: app_state.common_chain_ws_url.clone(),
+        common_chain_http_url: app_state.common_chain_http_url.clone(),
+        common_chain_client: common_chain_http_rpc_client,
+        gateways_contract_address: app_state.gateways_contract_addr,
+        gateway_jobs_contract: Arc::new(RwLock::new(gateway_jobs_contract)),
+        request_chain_clients: Arc::new(RwLock::new(request_chain_clients)),
+        request_chain_listeners: Arc::new(Mutex::new(HashMap::new())),
+        request_chain_senders: Arc::new(Mutex::new(None)),
+        service_tasks: Arc::new(Mutex::new(Vec::new())),
+        gateway_epoch_state,
+        active_jobs: Arc::new(RwLock::new(HashMap::new())),
+        current_jobs: Arc::new(RwLock::new(HashMap::new())),
+        epoch: app_state.epoch,
+        time_interval: app_state.time_interval,
+        offset_for_epoch: app_state.offset_for_epoch,
+        gateway_epoch_state_waitlist,
+        common_chain_start_block_number: Arc::new(Mutex::new(common_chain_block_number.as_u64())),
+        subscription_job_scheduler,
+        subscription_jobs,
+        clock: app_state.clock.clone(),
+        subscription_catch_up_policy: app_state.subscription_catch_up_policy,
+        subscription_catch_up_metrics: Arc::new(SubscriptionCatchUpMetrics::default()),
+        subscription_instance_history: Arc::new(RwLock::new(HashMap::new())),
+        subscription_trigger_limiter: Arc::new(SubscriptionTriggerLimiter::new(
+            app_state.subscription_trigger.clone(),
+        )),
+        common_chain_txn_queue: Arc::new(DeadlineQueue::default()),
+        request_chain_txn_queue: Arc::new(DeadlineQueue::default()),
+        eip712_domains: app_state.eip712_domains.clone(),
+        gas_wallet_lock: Arc::new(tokio::sync::RwLock::new(())),
+        chain_connector,
+    }))
+}
+
+/// Looks up the registration of the enclave on chain and, if it is registered on the common chain
+/// and all of its request chains, starts the contracts client right away instead of waiting for
+/// the registration events. The owner and the request chains the enclave is registered with on the
+/// common chain are adopted even if the registration is not complete yet.
+///
+/// Returns whether the gateway is registered.
+pub async fn detect_registration(app_state: &Data<AppState>) -> anyhow::Result<bool> {
+    if app_state.registered.load(Ordering::SeqCst) {
+        return Ok(true);
+    }
+    // The registration listener reports the existing registration itself
+    if *app_state
         .registration_events_listener_active
         .lock()
-        .unwrap();
+        .unwrap()
+    {
+        return Ok(false);
+    }
+    // The contracts client can't be started without the gas wallet
+    if app_state.wallet.lock().unwrap().is_none() {
+        return Ok(false);
+    }
 
-    if *registration_events_listener_active_guard == false {
-        let mut contracts_client_guard = app_state.contracts_client.lock().unwrap();
+    let common_chain_http_rpc_client = WsHttpConnector
+        .connect_http(&app_state.common_chain_http_url)
+        .await?;
+    let gateways_contract = GatewaysContract::new(
+        app_state.gateways_contract_addr,
+        Arc::new(common_chain_http_rpc_client),
+    );
 
-        let gateway_epoch_state: Arc<RwLock<BTreeMap<u64, BTreeMap<Address, GatewayData>>>> =
-            Arc::new(RwLock::new(BTreeMap::new()));
-        let gateway_epoch_state_waitlist = Arc::new(RwLock::new(HashMap::new()));
+    let (owner, _, _, _) = gateways_contract
+        .gateways(app_state.enclave_address)
+        .await
+        .context("Failed to fetch the gateway from the common chain")?;
+    if owner == Address::zero() {
+        return Ok(false);
+    }
 
-        let gateway_jobs_contract = GatewayJobsContract::new(
-            app_state.gateway_jobs_contract_addr,
-            common_chain_http_rpc_client.clone(),
-        );
+    let immutable_params_injected = *app_state.immutable_params_injected.lock().unwrap();
+    let enclave_owner = *app_state.enclave_owner.lock().unwrap();
+    if immutable_params_injected && owner != enclave_owner {
+        return Err(anyhow!(
+            "Enclave registered on chain by {:?} instead of the configured owner {:?}",
+            owner,
+            enclave_owner
+        ));
+    }
 
-        let subscription_job_instance_heap = Arc::new(RwLock::new(BinaryHeap::new()));
-        let subscription_jobs = Arc::new(RwLock::new(HashMap::new()));
+    let chain_ids = gateways_contract
+        .get_gateway_chain_ids(app_state.enclave_address)
+        .await
+        .context("Failed to fetch the request chains of the gateway from the common chain")?
+        .into_iter()
+        .map(uint_to_u64)
+        .collect::<Result<HashSet<u64>, _>>()
+        .context("Request chain id of the gateway on the common chain out of range")?;
+    {
+        let request_chain_ids_guard = app_state.request_chain_ids.lock().unwrap();
+        if !request_chain_ids_guard.is_empty() && *request_chain_ids_guard != chain_ids {
+            return Err(anyhow!(
+                "Enclave registered on chain with the request chains {:?} instead of {:?}",
+                chain_ids,
+                *request_chain_ids_guard
+            ));
+        }
+    }
 
-        let contracts_client = Arc::new(ContractsClient {
-            enclave_owner,
-            enclave_signer_key: app_state.enclave_signer_key.clone(),
-            enclave_address: app_state.enclave_address,
-            common_chain_ws_url: app_state.common_chain_ws_url.clone(),
-            common_chain_http_url: app_state.common_chain_http_url.clone(),
-            gateways_contract_address: app_state.gateways_contract_addr,
-            gateway_jobs_contract: Arc::new(RwLock::new(gateway_jobs_contract)),
-            request_chain_clients,
-            gateway_epoch_state,
-            request_chain_ids: chain_ids.clone(),
-            active_jobs: Arc::new(RwLock::new(HashMap::new())),
-            current_jobs: Arc::new(RwLock::new(HashMap::new())),
-            epoch: app_state.epoch,
-            time_interval: app_state.time_interval,
-            offset_for_epoch: app_state.offset_for_epoch,
-            gateway_epoch_state_waitlist,
-            common_chain_start_block_number: Arc::new(Mutex::new(
-                common_chain_block_number.as_u64(),
-            )),
-            subscription_job_instance_heap,
-            subscription_jobs,
-        });
+    let contracts_client = build_contracts_client(app_state, owner, &chain_ids).await?;
+    let registered = contracts_client
+        .registered_on_chain()
+        .await
+        .context("Failed to check the registration on the request chains")?;
 
-        *contracts_client_guard = Some(Arc::clone(&contracts_client));
+    *app_state.enclave_owner.lock().unwrap() = owner;
+    *app_state.immutable_params_injected.lock().unwrap() = true;
+    *app_state.request_chain_ids.lock().unwrap() = chain_ids;
 
-        let app_state_clone = app_state.clone();
-        tokio::spawn(async move {
-            contracts_client
-                .wait_for_registration(app_state_clone)
-                .await;
-        });
+    if registered {
+        let mut registration_events_listener_active_guard = app_state
+            .registration_events_listener_active
+            .lock()
+            .unwrap();
+        if *registration_events_listener_active_guard {
+            return Ok(false);
+        }
 
+        info!("Gateway already registered on chain, starting the contracts client");
+        *app_state.contracts_client.lock().unwrap() = Some(Arc::clone(&contracts_client));
+        app_state.registered.store(true, Ordering::SeqCst);
         *registration_events_listener_active_guard = true;
-    }
 
-    let response = SignedRegistrationResponse {
-        owner: enclave_owner,
-        sign_timestamp: sign_timestamp.try_into().unwrap(),
-        chain_ids: signed_registration_body.chain_ids,
-        common_chain_signature,
-        request_chain_signature,
-    };
+        contracts_client.start(app_state.clone());
+    }
+    persist_sealed_state(app_state).await;
 
-    HttpResponse::Ok().json(response)
+    Ok(registered)
 }
 
 // Endpoint exposed to retrieve gateway enclave details
@@ -579,14 +794,7 @@ async fn get_gateway_details(app_state: Data<AppState>) -> impl Responder {
     }
 
     let response = GatewayDetailsResponse {
-        enclave_public_key: "0x".to_string()
-            + &hex::encode(
-                &app_state
-                    .enclave_signer_key
-                    .verifying_key()
-                    .to_encoded_point(false)
-                    .as_bytes()[1..],
-            ),
+        enclave_public_key: app_state.enclave_signer.public_key_hex(),
         enclave_address: app_state.enclave_address,
         owner_address: *app_state.enclave_owner.lock().unwrap(),
         gas_address: app_state.wallet.lock().unwrap().clone().unwrap().address(),
@@ -595,23 +803,100 @@ async fn get_gateway_details(app_state: Data<AppState>) -> impl Responder {
     HttpResponse::Ok().json(response)
 }
 
-#[cfg(test)]
-mod api_impl_tests {
-    use super::*;
+// Endpoint exposed to preview the upcoming instances of a subscription job and the statuses of its
+// past instances, admin only as it reveals the gateway assignments ahead of time
+#[get("/subscription-preview")]
+async fn get_subscription_preview(
+    _admin_auth: AdminAuth,
+    Json(subscription_preview_body): Json<SubscriptionPreviewBody>,
+    app_state: Data<AppState>,
+) -> impl Responder {
+    let Some(contracts_client) = app_state.contracts_client.lock().unwrap().clone() else {
+        return HttpResponse::BadRequest().body("Subscription jobs are not being tracked yet!");
+    };
 
-    use std::collections::BTreeSet;
-    use std::str::FromStr;
+    let num_instances = subscription_preview_body
+        .num_instances
+        .unwrap_or(DEFAULT_SUBSCRIPTION_PREVIEW_INSTANCES)
+        .min(MAX_SUBSCRIPTION_PREVIEW_INSTANCES);
+
+    match preview_subscription_job(
+        &contracts_client,
+        SubscriptionKey::new(
+            subscription_preview_body.request_chain_id,
+            subscription_preview_body.subscription_id,
+        ),
+        num_instances,
+    ) {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(err) => HttpResponse::NotFound().body(err.to_string()),
+    }
+}
+
+// Endpoint exposed to read the depth and the age of the oldest item of the outgoing transaction
+// queues
+#[get("/txn-queue-metrics")]
+async fn get_txn_queue_metrics(app_state: Data<AppState>) -> impl Responder {
+    let Some(contracts_client) = app_state.contracts_client.lock().unwrap().clone() else {
+        return HttpResponse::BadRequest().body("Transactions are not being queued yet!");
+    };
+
+    HttpResponse::Ok().json(TxnQueueMetricsResponse {
+        common_chain: contracts_client.common_chain_txn_queue.metrics(),
+        request_chain: contracts_client.request_chain_txn_queue.metrics(),
+    })
+}
+
+#[get("/schedule-preview")]
+async fn get_schedule_preview(
+    Json(schedule_preview_body): Json<SchedulePreviewBody>,
+) -> impl Responder {
+    let schedule = match schedule_preview_body.schedule.build(
+        schedule_preview_body.starttime,
+        schedule_preview_body.termination_time,
+    ) {
+        Ok(schedule) => schedule,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+
+    let num_instances = schedule_preview_body
+        .num_instances
+        .unwrap_or(DEFAULT_SUBSCRIPTION_PREVIEW_INSTANCES)
+        .min(MAX_SUBSCRIPTION_PREVIEW_INSTANCES);
+
+    HttpResponse::Ok().json(SchedulePreviewResponse {
+        first_trigger_time: schedule.first_trigger_time(),
+        trigger_times: schedule.upcoming_trigger_times(
+            schedule_preview_body
+                .from
+                .unwrap_or(schedule_preview_body.starttime),
+            num_instances,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod api_impl_tests {
+    use super::*;
+
+    use std::collections::BTreeSet;
+    use std::str::FromStr;
 
-    use abi::{encode, encode_packed, Token};
     use actix_web::{body::MessageBody, http};
     use ethers::types::{Address, H160};
-    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+    use ethers::utils::public_key_to_address;
+    use k256::ecdsa::VerifyingKey;
     use serde_json::json;
 
+    use crate::model::{
+        Eip712Domains, GatewayJobType, JobMode, ResponseJob, UpcomingSubscriptionInstance,
+    };
     use crate::test_util::{
-        generate_app_state, new_app, CHAIN_ID, GAS_WALLET_KEY, GAS_WALLET_PUBLIC_ADDRESS,
-        OWNER_ADDRESS,
+        add_gateway_epoch_state, generate_app_state, generate_generic_subscription_job, new_app,
+        owner_signed_mutable_config, sign_mutable_config, CHAIN_ID, GAS_WALLET_KEY,
+        GAS_WALLET_PUBLIC_ADDRESS, OWNER_ADDRESS, OWNER_KEY,
     };
+    use crate::typed_data::verify_typed_data;
 
     // Test the response for the 'index' endpoint
     #[tokio::test]
@@ -714,12 +999,14 @@ mod api_impl_tests {
         );
     }
 
-    fn wallet_from_hex(hex: &str) -> LocalWallet {
+    fn wallet_from_hex(hex: &str) -> GasWallet {
         let mut bytes32 = [0u8; 32];
         let _ = hex::decode_to_slice(hex, &mut bytes32);
-        LocalWallet::from_bytes(&bytes32)
-            .unwrap()
-            .with_chain_id(CHAIN_ID)
+        GasWallet::Local(
+            LocalWallet::from_bytes(&bytes32)
+                .unwrap()
+                .with_chain_id(CHAIN_ID),
+        )
     }
 
     // Test the various response cases for the 'mutable-config' endpoint
@@ -728,11 +1015,42 @@ mod api_impl_tests {
         let app_state = generate_app_state().await;
         let app = actix_web::test::init_service(new_app(app_state.clone())).await;
 
+        // Inject a valid private key for gas wallet before the enclave owner is configured
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Immutable params not configured yet!"
+        );
+        assert!(!app_state.mutable_params_injected.load(Ordering::SeqCst));
+        assert_eq!(*app_state.wallet.lock().unwrap(), None);
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/immutable-config")
+            .set_json(&json!({
+                "owner_address_hex": OWNER_ADDRESS
+            }))
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
         // Inject invalid hex private key string
         let req = actix_web::test::TestRequest::post()
             .uri("/mutable-config")
             .set_json(&json!({
-                "gas_key_hex": "0x32255"
+                "gas_wallet": {
+                    "type": "local",
+                    "gas_key_hex": "0x32255",
+                },
+                "nonce": 1,
+                "sign_timestamp": app_state.clock.now(),
+                "owner_signature_hex": ""
             }))
             .to_request();
 
@@ -743,7 +1061,6 @@ mod api_impl_tests {
             resp.into_body().try_into_bytes().unwrap(),
             "Failed to hex decode the gas private key into 32 bytes: OddLength".as_bytes()
         );
-        assert!(!*app_state.immutable_params_injected.lock().unwrap());
         assert!(!app_state.mutable_params_injected.load(Ordering::SeqCst));
         assert_eq!(*app_state.wallet.lock().unwrap(), None);
 
@@ -751,7 +1068,13 @@ mod api_impl_tests {
         let req = actix_web::test::TestRequest::post()
             .uri("/mutable-config")
             .set_json(&json!({
-                "gas_key_hex": "ffffffffffffffffffffffffffffffffffffffffff"
+                "gas_wallet": {
+                    "type": "local",
+                    "gas_key_hex": "ffffffffffffffffffffffffffffffffffffffffff",
+                },
+                "nonce": 1,
+                "sign_timestamp": app_state.clock.now(),
+                "owner_signature_hex": ""
             }))
             .to_request();
 
@@ -763,7 +1086,6 @@ mod api_impl_tests {
             "Failed to hex decode the gas private key into 32 bytes: InvalidStringLength"
                 .as_bytes()
         );
-        assert!(!*app_state.immutable_params_injected.lock().unwrap());
         assert!(!app_state.mutable_params_injected.load(Ordering::SeqCst));
         assert_eq!(*app_state.wallet.lock().unwrap(), None);
 
@@ -771,7 +1093,13 @@ mod api_impl_tests {
         let req = actix_web::test::TestRequest::post()
             .uri("/mutable-config")
             .set_json(&json!({
-                "gas_key_hex": "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+                "gas_wallet": {
+                    "type": "local",
+                    "gas_key_hex": "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+                },
+                "nonce": 1,
+                "sign_timestamp": app_state.clock.now(),
+                "owner_signature_hex": ""
             }))
             .to_request();
 
@@ -782,16 +1110,72 @@ mod api_impl_tests {
             resp.into_body().try_into_bytes().unwrap(),
             "Invalid gas private key provided: EcdsaError(signature::Error { source: None })"
         );
-        assert!(!*app_state.immutable_params_injected.lock().unwrap());
         assert!(!app_state.mutable_params_injected.load(Ordering::SeqCst));
         assert_eq!(*app_state.wallet.lock().unwrap(), None);
 
-        // Inject a valid private key for gas wallet
+        // Inject a valid private key for gas wallet signed by someone other than the owner
         let req = actix_web::test::TestRequest::post()
             .uri("/mutable-config")
-            .set_json(&json!({
-                "gas_key_hex": GAS_WALLET_KEY
-            }))
+            .set_json(&sign_mutable_config(
+                &app_state,
+                GAS_WALLET_KEY,
+                GAS_WALLET_KEY,
+                1,
+                app_state.clock.now(),
+            ))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Mutable config not signed by the enclave owner!"
+        );
+        assert!(!app_state.mutable_params_injected.load(Ordering::SeqCst));
+        assert_eq!(*app_state.wallet.lock().unwrap(), None);
+
+        // Inject a valid private key for gas wallet with an expired owner signature
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&sign_mutable_config(
+                &app_state,
+                GAS_WALLET_KEY,
+                OWNER_KEY,
+                1,
+                app_state.clock.now() - MUTABLE_CONFIG_SIGNATURE_VALIDITY - 1,
+            ))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Mutable config signature expired!"
+        );
+        assert!(!app_state.mutable_params_injected.load(Ordering::SeqCst));
+        assert_eq!(*app_state.wallet.lock().unwrap(), None);
+
+        // Inject a valid private key for gas wallet with a malformed owner signature
+        let mut mutable_config = owner_signed_mutable_config(&app_state, GAS_WALLET_KEY);
+        mutable_config["owner_signature_hex"] = json!("0x1234");
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&mutable_config)
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert!(!app_state.mutable_params_injected.load(Ordering::SeqCst));
+        assert_eq!(*app_state.wallet.lock().unwrap(), None);
+
+        // Inject a valid private key for gas wallet signed by the owner
+        let mutable_config = owner_signed_mutable_config(&app_state, GAS_WALLET_KEY);
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&mutable_config)
             .to_request();
 
         let resp = actix_web::test::call_service(&app, req).await;
@@ -801,12 +1185,13 @@ mod api_impl_tests {
             resp.into_body().try_into_bytes().unwrap(),
             "Mutable params configured!"
         );
-        assert!(!*app_state.immutable_params_injected.lock().unwrap());
+        assert!(*app_state.immutable_params_injected.lock().unwrap());
         assert!(app_state.mutable_params_injected.load(Ordering::SeqCst));
         assert_eq!(
             *app_state.wallet.lock().unwrap(),
             Some(wallet_from_hex(GAS_WALLET_KEY))
         );
+        assert_eq!(*app_state.mutable_config_nonce.lock().unwrap(), 1);
         assert!(!app_state.registered.load(Ordering::SeqCst));
         assert!(app_state.contracts_client.lock().unwrap().is_none());
         assert!(!*app_state
@@ -814,16 +1199,22 @@ mod api_impl_tests {
             .lock()
             .unwrap());
 
-        // Build contracts client to verify the contracts client public address
+        // Replay the accepted mutable config
         let req = actix_web::test::TestRequest::post()
-            .uri("/immutable-config")
-            .set_json(&json!({
-                "owner_address_hex": OWNER_ADDRESS
-            }))
+            .uri("/mutable-config")
+            .set_json(&mutable_config)
             .to_request();
+
         let resp = actix_web::test::call_service(&app, req).await;
-        assert_eq!(resp.status(), http::StatusCode::OK);
 
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Mutable config nonce already used, expected a nonce greater than 1"
+        );
+        assert_eq!(*app_state.mutable_config_nonce.lock().unwrap(), 1);
+
+        // Build contracts client to verify the contracts client public address
         let req = actix_web::test::TestRequest::get()
             .uri("/signed-registration-message")
             .set_json(&json!({
@@ -852,9 +1243,7 @@ mod api_impl_tests {
         // Inject the same valid private key for gas wallet again
         let req = actix_web::test::TestRequest::post()
             .uri("/mutable-config")
-            .set_json(&json!({
-                "gas_key_hex": GAS_WALLET_KEY
-            }))
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
             .to_request();
 
         let resp = actix_web::test::call_service(&app, req).await;
@@ -894,9 +1283,7 @@ mod api_impl_tests {
         // Inject another valid private key for gas wallet
         let req = actix_web::test::TestRequest::post()
             .uri("/mutable-config")
-            .set_json(&json!({
-                "gas_key_hex": GAS_WALLET_KEY_2
-            }))
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY_2))
             .to_request();
 
         let resp = actix_web::test::call_service(&app, req).await;
@@ -912,6 +1299,7 @@ mod api_impl_tests {
             *app_state.wallet.lock().unwrap(),
             Some(wallet_from_hex(GAS_WALLET_KEY_2))
         );
+        assert_eq!(*app_state.mutable_config_nonce.lock().unwrap(), 2);
         assert_eq!(
             app_state
                 .contracts_client
@@ -930,6 +1318,210 @@ mod api_impl_tests {
         );
     }
 
+    // Test the 'mutable-config' endpoint with the gas key held in an encrypted keystore
+    #[tokio::test]
+    async fn inject_mutable_config_keystore_test() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/immutable-config")
+            .set_json(&json!({
+                "owner_address_hex": OWNER_ADDRESS
+            }))
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let keystore_dir =
+            std::env::temp_dir().join(format!("gas_keystore_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&keystore_dir).unwrap();
+        LocalWallet::encrypt_keystore(
+            &keystore_dir,
+            &mut rand::thread_rng(),
+            hex::decode(GAS_WALLET_KEY).unwrap(),
+            "password",
+            Some("keystore"),
+        )
+        .unwrap();
+        std::fs::write(keystore_dir.join("password"), "password").unwrap();
+
+        // The owner signs the gas address, whichever way the gas key is held
+        let mut mutable_config = owner_signed_mutable_config(&app_state, GAS_WALLET_KEY);
+        mutable_config["gas_wallet"] = json!({
+            "type": "keystore",
+            "keystore_file": keystore_dir.join("keystore"),
+            "password_file": keystore_dir.join("password"),
+            "address": GAS_WALLET_PUBLIC_ADDRESS,
+        });
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&mutable_config)
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+        std::fs::remove_dir_all(&keystore_dir).unwrap();
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert!(app_state.mutable_params_injected.load(Ordering::SeqCst));
+        assert_eq!(
+            *app_state.wallet.lock().unwrap(),
+            Some(wallet_from_hex(GAS_WALLET_KEY))
+        );
+
+        // Inject a keystore that can't be unlocked signed by someone other than the owner, the
+        // signature is rejected before the keystore is read
+        let mut mutable_config = sign_mutable_config(
+            &app_state,
+            GAS_WALLET_KEY,
+            GAS_WALLET_KEY,
+            2,
+            app_state.clock.now(),
+        );
+        mutable_config["gas_wallet"] = json!({
+            "type": "keystore",
+            "keystore_file": keystore_dir.join("keystore"),
+            "password_file": keystore_dir.join("password"),
+            "address": GAS_WALLET_PUBLIC_ADDRESS,
+        });
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&mutable_config)
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Mutable config not signed by the enclave owner!"
+        );
+
+        // Inject a keystore that can't be unlocked
+        let mut mutable_config = owner_signed_mutable_config(&app_state, GAS_WALLET_KEY);
+        mutable_config["gas_wallet"] = json!({
+            "type": "keystore",
+            "keystore_file": keystore_dir.join("keystore"),
+            "password_file": keystore_dir.join("password"),
+            "address": GAS_WALLET_PUBLIC_ADDRESS,
+        });
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&mutable_config)
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert!(resp
+            .into_body()
+            .try_into_bytes()
+            .unwrap()
+            .starts_with("Failed to read the gas keystore password".as_bytes()));
+        assert_eq!(*app_state.mutable_config_nonce.lock().unwrap(), 1);
+    }
+
+    // Test the admin endpoints reject the requests without the configured bearer token
+    #[tokio::test]
+    async fn admin_auth_token_test() {
+        let mut app_state = Arc::into_inner(generate_app_state().await.into_inner()).unwrap();
+        app_state.admin_auth_token = Some("admin-token".to_owned());
+        let app_state = Data::new(app_state);
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        // Inject the owner address without a token
+        let req = actix_web::test::TestRequest::post()
+            .uri("/immutable-config")
+            .set_json(&json!({
+                "owner_address_hex": OWNER_ADDRESS
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Missing or invalid admin bearer token!"
+        );
+        assert!(!*app_state.immutable_params_injected.lock().unwrap());
+
+        // Inject the owner address with a wrong token
+        let req = actix_web::test::TestRequest::post()
+            .uri("/immutable-config")
+            .insert_header((http::header::AUTHORIZATION, "Bearer admin-tokem"))
+            .set_json(&json!({
+                "owner_address_hex": OWNER_ADDRESS
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        assert!(!*app_state.immutable_params_injected.lock().unwrap());
+
+        // Inject the owner address with the configured token
+        let req = actix_web::test::TestRequest::post()
+            .uri("/immutable-config")
+            .insert_header((http::header::AUTHORIZATION, "Bearer admin-token"))
+            .set_json(&json!({
+                "owner_address_hex": OWNER_ADDRESS
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert!(*app_state.immutable_params_injected.lock().unwrap());
+
+        // The other admin endpoints are guarded as well
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        assert!(!app_state.mutable_params_injected.load(Ordering::SeqCst));
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-registration-message")
+            .set_json(&json!({
+                "chain_ids": [CHAIN_ID]
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/subscription-preview")
+            .set_json(&json!({
+                "request_chain_id": CHAIN_ID,
+                "subscription_id": "0x1",
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        // The public endpoints don't need the token
+        let req = actix_web::test::TestRequest::get()
+            .uri("/gateway-details")
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Mutable params not configured yet!"
+        );
+    }
+
     fn recover_key(
         chain_ids: Vec<u64>,
         enclave_owner: H160,
@@ -938,93 +1530,34 @@ mod api_impl_tests {
         request_chain_signature: String,
         verifying_key: VerifyingKey,
     ) -> bool {
-        let common_chain_register_typehash =
-            keccak256("Register(address owner,uint256[] chainIds,uint256 signTimestamp)");
+        let eip712_domains = Eip712Domains::default();
+        let enclave_address = public_key_to_address(&verifying_key);
 
         let chain_ids = chain_ids.into_iter().collect::<BTreeSet<u64>>();
-        let chain_ids_tokens: Vec<Token> = chain_ids
-            .clone()
-            .into_iter()
-            .map(|x| Token::Uint(x.into()))
-            .collect::<Vec<Token>>();
-        let chain_ids_bytes = keccak256(encode_packed(&[Token::Array(chain_ids_tokens)]).unwrap());
-        let hash_struct = keccak256(encode(&[
-            Token::FixedBytes(common_chain_register_typehash.to_vec()),
-            Token::Address(enclave_owner),
-            Token::FixedBytes(chain_ids_bytes.into()),
-            Token::Uint(sign_timestamp.into()),
-        ]));
-
-        let domain_separator = keccak256(encode(&[
-            Token::FixedBytes(keccak256("EIP712Domain(string name,string version)").to_vec()),
-            Token::FixedBytes(keccak256("marlin.oyster.Gateways").to_vec()),
-            Token::FixedBytes(keccak256("1").to_vec()),
-        ]));
-
-        let digest = encode_packed(&[
-            Token::String("\x19\x01".to_string()),
-            Token::FixedBytes(domain_separator.to_vec()),
-            Token::FixedBytes(hash_struct.to_vec()),
-        ])
-        .unwrap();
-        let digest = keccak256(digest);
-
-        let signature = Signature::from_slice(
-            hex::decode(&common_chain_signature[0..128])
-                .unwrap()
-                .as_slice(),
-        )
-        .unwrap();
-        let v =
-            RecoveryId::try_from((hex::decode(&common_chain_signature[128..]).unwrap()[0]) - 27)
-                .unwrap();
-        let common_chain_recovered_key =
-            VerifyingKey::recover_from_prehash(&digest, &signature, v).unwrap();
-
-        if common_chain_recovered_key != verifying_key {
+        let gateways_register = GatewaysRegisterMessage {
+            owner: enclave_owner,
+            chain_ids: chain_ids.into_iter().map(U256::from).collect(),
+            sign_timestamp: sign_timestamp as u64,
+        };
+        if !verify_typed_data(
+            &eip712_domains.gateways,
+            &gateways_register,
+            &hex::decode(common_chain_signature).unwrap(),
+            enclave_address,
+        ) {
             return false;
         }
 
-        // create request chain signature and add it to the request chain signatures map
-        let request_chain_register_typehash =
-            keccak256("Register(address owner,uint256 signTimestamp)");
-        let hash_struct = keccak256(encode(&[
-            Token::FixedBytes(request_chain_register_typehash.to_vec()),
-            Token::Address(enclave_owner),
-            Token::Uint(sign_timestamp.into()),
-        ]));
-
-        let request_chain_domain_separator = keccak256(encode(&[
-            Token::FixedBytes(keccak256("EIP712Domain(string name,string version)").to_vec()),
-            Token::FixedBytes(keccak256("marlin.oyster.Relay").to_vec()),
-            Token::FixedBytes(keccak256("1").to_vec()),
-        ]));
-
-        let digest = encode_packed(&[
-            Token::String("\x19\x01".to_string()),
-            Token::FixedBytes(request_chain_domain_separator.to_vec()),
-            Token::FixedBytes(hash_struct.to_vec()),
-        ])
-        .unwrap();
-        let digest = keccak256(digest);
-
-        let signature = Signature::from_slice(
-            hex::decode(&request_chain_signature[0..128])
-                .unwrap()
-                .as_slice(),
+        let relay_register = RelayRegisterMessage {
+            owner: enclave_owner,
+            sign_timestamp: sign_timestamp as u64,
+        };
+        verify_typed_data(
+            &eip712_domains.relay,
+            &relay_register,
+            &hex::decode(request_chain_signature).unwrap(),
+            enclave_address,
         )
-        .unwrap();
-        let v =
-            RecoveryId::try_from((hex::decode(&request_chain_signature[128..]).unwrap()[0]) - 27)
-                .unwrap();
-        let request_chain_recovered_key =
-            VerifyingKey::recover_from_prehash(&digest, &signature, v).unwrap();
-
-        if request_chain_recovered_key != verifying_key {
-            return false;
-        }
-
-        true
     }
 
     #[tokio::test]
@@ -1107,9 +1640,7 @@ mod api_impl_tests {
         // Inject a valid private key
         let req = actix_web::test::TestRequest::post()
             .uri("/mutable-config")
-            .set_json(&json!({
-                "gas_key_hex": GAS_WALLET_KEY
-            }))
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
             .to_request();
 
         let resp = actix_web::test::call_service(&app, req).await;
@@ -1193,7 +1724,7 @@ mod api_impl_tests {
         let mut chain_id_set: HashSet<u64> = HashSet::new();
         chain_id_set.insert(CHAIN_ID);
 
-        let verifying_key = app_state.enclave_signer_key.verifying_key().to_owned();
+        let verifying_key = app_state.enclave_signer.verifying_key();
 
         let response = response.unwrap();
         assert!(*app_state.immutable_params_injected.lock().unwrap());
@@ -1235,7 +1766,7 @@ mod api_impl_tests {
         let mut chain_id_set: HashSet<u64> = HashSet::new();
         chain_id_set.insert(CHAIN_ID);
 
-        let verifying_key = app_state.enclave_signer_key.verifying_key().to_owned();
+        let verifying_key = app_state.enclave_signer.verifying_key();
 
         let response = response.unwrap();
         assert!(*app_state.immutable_params_injected.lock().unwrap());
@@ -1301,11 +1832,262 @@ mod api_impl_tests {
     }
 
     #[tokio::test]
-    async fn get_gateway_details_test() {
+    async fn detect_registration_test() {
         let app_state = generate_app_state().await;
-        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
 
-        // Get gateway details without adding wallet and gas key
+        // Nothing to detect without the gas wallet to run the contracts client with
+        assert!(!detect_registration(&app_state).await.unwrap());
+        assert!(!*app_state.immutable_params_injected.lock().unwrap());
+        assert!(app_state.contracts_client.lock().unwrap().is_none());
+
+        // The enclave key of the tests is not registered on chain
+        *app_state.wallet.lock().unwrap() = Some(GasWallet::Local(
+            GAS_WALLET_KEY.parse::<LocalWallet>().unwrap(),
+        ));
+        assert!(!detect_registration(&app_state).await.unwrap());
+        assert!(!*app_state.immutable_params_injected.lock().unwrap());
+        assert!(!app_state.registered.load(Ordering::SeqCst));
+        assert!(!*app_state
+            .registration_events_listener_active
+            .lock()
+            .unwrap());
+        assert_eq!(*app_state.request_chain_ids.lock().unwrap(), HashSet::new());
+
+        app_state.registered.store(true, Ordering::SeqCst);
+        assert!(detect_registration(&app_state).await.unwrap());
+    }
+
+    // Test the various response cases for the 'signed-add-chains-message' and
+    // 'signed-remove-chains-message' endpoints
+    #[tokio::test]
+    async fn export_signed_chains_update_message_test() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        // Get signature before the enclave is registered
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-add-chains-message")
+            .set_json(&json!({
+                "chain_ids": [CHAIN_ID]
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Enclave not registered yet!"
+        );
+
+        app_state.registered.store(true, Ordering::SeqCst);
+        app_state.request_chain_ids.lock().unwrap().insert(CHAIN_ID);
+
+        // Get signature with no chain ids
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-remove-chains-message")
+            .set_json(&json!({
+                "chain_ids": []
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Atleast one request chain id is required!"
+        );
+
+        // Get signature adding a chain already served
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-add-chains-message")
+            .set_json(&json!({
+                "chain_ids": [CHAIN_ID, 1]
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            serde_json::to_string(&json!({
+                "message": "Request chains already served!",
+                "chain_ids": [CHAIN_ID],
+            }))
+            .unwrap()
+        );
+
+        // Get signature removing a chain not served
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-remove-chains-message")
+            .set_json(&json!({
+                "chain_ids": [1]
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            serde_json::to_string(&json!({
+                "message": "Request chains not served!",
+                "chain_ids": [1],
+            }))
+            .unwrap()
+        );
+
+        // Get valid signatures adding and removing chains
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-add-chains-message")
+            .set_json(&json!({
+                "chain_ids": [1]
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let response: SignedChainsUpdateResponse =
+            serde_json::from_slice(&resp.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(response.enclave_address, app_state.enclave_address);
+        assert_eq!(response.chain_ids, vec![1]);
+        assert!(verify_typed_data(
+            &Eip712Domains::default().gateways,
+            &AddChainsMessage {
+                chain_ids: vec![U256::from(1)],
+                sign_timestamp: response.sign_timestamp as u64,
+            },
+            &hex::decode(response.signature).unwrap(),
+            app_state.enclave_address,
+        ));
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-remove-chains-message")
+            .set_json(&json!({
+                "chain_ids": [CHAIN_ID]
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let response: SignedChainsUpdateResponse =
+            serde_json::from_slice(&resp.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(response.chain_ids, vec![CHAIN_ID]);
+        assert!(verify_typed_data(
+            &Eip712Domains::default().gateways,
+            &RemoveChainsMessage {
+                chain_ids: vec![U256::from(CHAIN_ID)],
+                sign_timestamp: response.sign_timestamp as u64,
+            },
+            &hex::decode(response.signature).unwrap(),
+            app_state.enclave_address,
+        ));
+    }
+
+    // Test that the 'register' endpoint never accepts the owner key
+    #[tokio::test]
+    async fn register_gateway_local_owner_signer_test() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/register")
+            .set_json(&json!({
+                "chain_ids": [CHAIN_ID],
+                "owner_signer": {
+                    "type": "local",
+                    "owner_key_hex": OWNER_KEY,
+                },
+                "attestation": {
+                    "attestation_signature": "0x01",
+                    "enclave_pub_key": "0x02",
+                    "pcr0": "0x03",
+                    "pcr1": "0x04",
+                    "pcr2": "0x05",
+                    "timestamp_in_milliseconds": 1718602200000u64,
+                },
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Owner key not accepted, only a remote owner signer can be used!"
+        );
+        assert!(!app_state.registered.load(Ordering::SeqCst));
+    }
+
+    // Test the rejected requests of the 'deregister' endpoint
+    #[tokio::test]
+    async fn deregister_gateway_test() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        // Deregister before the enclave is registered
+        let req = actix_web::test::TestRequest::post()
+            .uri("/deregister")
+            .set_json(&json!({}))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Enclave not registered yet!"
+        );
+
+        app_state.registered.store(true, Ordering::SeqCst);
+
+        // Deregister with the owner key
+        let req = actix_web::test::TestRequest::post()
+            .uri("/deregister")
+            .set_json(&json!({
+                "owner_signer": {
+                    "type": "local",
+                    "owner_key_hex": OWNER_KEY
+                }
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Owner key not accepted, only a remote owner signer can be used! Deregister with the owner key through the 'deregister' subcommand instead"
+        );
+        assert!(!app_state.deregistering.load(Ordering::SeqCst));
+
+        // Deregister while the deregistration is in progress
+        app_state.deregistering.store(true, Ordering::SeqCst);
+        let req = actix_web::test::TestRequest::post()
+            .uri("/deregister")
+            .set_json(&json!({}))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Deregistration already in progress!"
+        );
+        assert!(app_state.registered.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn get_gateway_details_test() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        // Get gateway details without adding wallet and gas key
         let req = actix_web::test::TestRequest::get()
             .uri("/gateway-details")
             .to_request();
@@ -1350,9 +2132,7 @@ mod api_impl_tests {
         // Inject a valid private gas key
         let req = actix_web::test::TestRequest::post()
             .uri("/mutable-config")
-            .set_json(&json!({
-                "gas_key_hex": GAS_WALLET_KEY
-            }))
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
             .to_request();
 
         let resp = actix_web::test::call_service(&app, req).await;
@@ -1381,7 +2161,7 @@ mod api_impl_tests {
             enclave_public_key: "0x".to_string()
                 + &hex::encode(
                     &app_state
-                        .enclave_signer_key
+                        .enclave_signer
                         .verifying_key()
                         .to_encoded_point(false)
                         .as_bytes()[1..],
@@ -1398,4 +2178,278 @@ mod api_impl_tests {
         assert_eq!(response.owner_address, expected_response.owner_address);
         assert_eq!(response.gas_address, expected_response.gas_address);
     }
+
+    #[tokio::test]
+    async fn get_subscription_preview_test() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        // Preview before the contracts client is initialized
+        let req = actix_web::test::TestRequest::get()
+            .uri("/subscription-preview")
+            .set_json(&json!({
+                "request_chain_id": CHAIN_ID,
+                "subscription_id": "0x1",
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Subscription jobs are not being tracked yet!"
+        );
+
+        // Configure the enclave and initialize the contracts client
+        let req = actix_web::test::TestRequest::post()
+            .uri("/immutable-config")
+            .set_json(&json!({
+                "owner_address_hex": OWNER_ADDRESS
+            }))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-registration-message")
+            .set_json(&json!({
+                "chain_ids": [CHAIN_ID]
+            }))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+
+        let contracts_client = app_state.contracts_client.lock().unwrap().clone().unwrap();
+        add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
+
+        let subscription_job = generate_generic_subscription_job(None, Some(100));
+        contracts_client
+            .subscription_jobs
+            .write()
+            .unwrap()
+            .insert(subscription_job.key(), subscription_job.clone());
+        contracts_client
+            .subscription_job_scheduler
+            .write()
+            .unwrap()
+            .schedule(subscription_job.key(), subscription_job.starttime.as_u64());
+
+        // Preview of an unknown subscription
+        let req = actix_web::test::TestRequest::get()
+            .uri("/subscription-preview")
+            .set_json(&json!({
+                "request_chain_id": CHAIN_ID,
+                "subscription_id": "0x2",
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            format!(
+                "No Subscription Job found for the subscription id: 2 of the Request Chain: {}",
+                CHAIN_ID
+            )
+        );
+
+        // Preview of the next 3 instances
+        let req = actix_web::test::TestRequest::get()
+            .uri("/subscription-preview")
+            .set_json(&json!({
+                "request_chain_id": CHAIN_ID,
+                "subscription_id": "0x1",
+                "num_instances": 3,
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let response: serde_json::Value =
+            serde_json::from_slice(&resp.into_body().try_into_bytes().unwrap()).unwrap();
+        let upcoming_instances: Vec<UpcomingSubscriptionInstance> =
+            serde_json::from_value(response["upcoming_instances"].clone()).unwrap();
+        let starttime = subscription_job.starttime.as_u64();
+
+        assert_eq!(response["request_chain_id"], json!(CHAIN_ID));
+        assert_eq!(upcoming_instances.len(), 3);
+        for (instance_index, upcoming_instance) in upcoming_instances.iter().enumerate() {
+            assert_eq!(upcoming_instance.instance_index, instance_index as u64);
+            assert_eq!(
+                upcoming_instance.trigger_time,
+                starttime + instance_index as u64 * 10
+            );
+            assert_eq!(upcoming_instance.gateway, Some(app_state.enclave_address));
+            assert_eq!(upcoming_instance.gateway_error, None);
+        }
+        assert_eq!(response["past_instances"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn get_txn_queue_metrics_test() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/txn-queue-metrics")
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Transactions are not being queued yet!"
+        );
+
+        // Configure the enclave and initialize the contracts client
+        let req = actix_web::test::TestRequest::post()
+            .uri("/immutable-config")
+            .set_json(&json!({
+                "owner_address_hex": OWNER_ADDRESS
+            }))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-registration-message")
+            .set_json(&json!({
+                "chain_ids": [CHAIN_ID]
+            }))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+
+        let contracts_client = app_state.contracts_client.lock().unwrap().clone().unwrap();
+        contracts_client.request_chain_txn_queue.push(
+            ResponseJob {
+                job_id: U256::one(),
+                instance_index: 0,
+                request_chain_id: CHAIN_ID,
+                output: Bytes::default(),
+                total_time: U256::from(1000),
+                error_code: 0,
+                job_type: GatewayJobType::JobResponded,
+                gateway_address: None,
+                job_mode: JobMode::Once,
+            },
+            u64::MAX,
+        );
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/txn-queue-metrics")
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let response: TxnQueueMetricsResponse =
+            serde_json::from_slice(&resp.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(response.common_chain.depth, 0);
+        assert_eq!(response.common_chain.oldest_item_age_ms, None);
+        assert_eq!(response.request_chain.depth, 1);
+        assert!(response.request_chain.oldest_item_age_ms.is_some());
+        assert_eq!(response.request_chain.dropped_items, 0);
+    }
+
+    #[tokio::test]
+    async fn get_schedule_preview_test() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        // 2024-01-01T00:00:00Z
+        let starttime = 1_704_067_200;
+
+        // Invalid cron expression
+        let req = actix_web::test::TestRequest::get()
+            .uri("/schedule-preview")
+            .set_json(&json!({
+                "schedule": {
+                    "type": "cron",
+                    "expression": "0 25 * * *",
+                },
+                "starttime": starttime,
+                "termination_time": starttime + 86400,
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "Invalid schedule: Cron field value 25 is out of the range 0-23"
+        );
+
+        // Every 6 hours from the second day
+        let req = actix_web::test::TestRequest::get()
+            .uri("/schedule-preview")
+            .set_json(&json!({
+                "schedule": {
+                    "type": "cron",
+                    "expression": "0 */6 * * *",
+                },
+                "starttime": starttime,
+                "termination_time": starttime + 2 * 86400,
+                "from": starttime + 86400,
+                "num_instances": 10,
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            serde_json::to_string(&json!({
+                "first_trigger_time": starttime,
+                "trigger_times": [
+                    starttime + 86400,
+                    starttime + 86400 + 6 * 3600,
+                    starttime + 86400 + 12 * 3600,
+                    starttime + 86400 + 18 * 3600,
+                    starttime + 2 * 86400,
+                ],
+            }))
+            .unwrap()
+        );
+
+        // Last day of the month
+        let req = actix_web::test::TestRequest::get()
+            .uri("/schedule-preview")
+            .set_json(&json!({
+                "schedule": {
+                    "type": "calendar",
+                    "every_months": 1,
+                    "day_of_month": 31,
+                },
+                "starttime": starttime,
+                "termination_time": starttime + 365 * 86400,
+                "num_instances": 2,
+            }))
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let response: SchedulePreviewResponse =
+            serde_json::from_slice(&resp.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(
+            response.trigger_times,
+            vec![starttime + 30 * 86400, starttime + 59 * 86400]
+        );
+    }
 }
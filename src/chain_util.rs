@@ -1,35 +1,116 @@
-use abi::encode;
-use anyhow::Result;
-use ethers::abi::{encode_packed, FixedBytes, Token};
+use anyhow::{Context, Result};
+use ethers::abi::FixedBytes;
 use ethers::prelude::*;
 use ethers::types::{Address, U256};
-use ethers::utils::keccak256;
 use futures_core::stream::Stream;
-use k256::ecdsa::SigningKey;
-use k256::elliptic_curve::generic_array::sequence::Lengthen;
 use log::{error, info};
+use std::collections::HashSet;
+use std::fmt::Debug;
 use std::future::Future;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::time;
 
 use crate::constant::{
     MAX_RETRY_ON_PROVIDER_ERROR, MAX_TX_RECEIPT_RETRIES, WAIT_BEFORE_CHECKING_BLOCK,
 };
+use crate::contract_abi::{
+    GatewayJobsContract, GatewaysContract, RelayContract, RelayJobsReturn,
+    RelaySubscriptionsContract,
+};
+use crate::enclave_signer::EnclaveSigner;
 use crate::error::ServerlessError;
-use crate::model::{Job, JobMode, RequestChainClient};
+use crate::event_decoder::uint_to_u64;
+use crate::model::{
+    ContractsClient, Eip712Domains, GasWallet, Job, JobKey, JobMode, RequestChainClient,
+    RequestChainData,
+};
+use crate::typed_data::{
+    sign_typed_data, JobResponseMessage, ReassignGatewayMessage, RelayJobMessage,
+};
+use crate::HttpProviderType;
+
+// Connects the contracts client to the chains, with the pubsub providers its event listeners
+// subscribe through, the providers of its queries and the middlewares sending the transactions of
+// the gas wallet
+pub trait ChainConnector<M>: Debug + Send + Sync + 'static {
+    type Pubsub: PubsubClient + 'static;
+    type Reader: Middleware + 'static;
+
+    fn connect_pubsub(
+        &self,
+        ws_rpc_url: &str,
+    ) -> impl Future<Output = Result<Provider<Self::Pubsub>>> + Send;
+
+    // Read-only provider for the calls and queries not sent with the gas wallet
+    fn connect_http(&self, http_rpc_url: &str)
+        -> impl Future<Output = Result<Self::Reader>> + Send;
+
+    // Middleware signing with the gas wallet, with the nonce synced from the pending transactions
+    fn connect_gas_wallet(
+        &self,
+        http_rpc_url: &str,
+        gas_wallet: GasWallet,
+    ) -> impl Future<Output = Result<Arc<M>>> + Send;
+}
+
+// Websocket providers for the listeners and http providers for the transactions
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WsHttpConnector;
+
+impl ChainConnector<HttpProviderType> for WsHttpConnector {
+    type Pubsub = Ws;
+    type Reader = Provider<Http>;
 
-pub trait LogsProvider {
-    fn common_chain_jobs<'a>(
+    async fn connect_pubsub(&self, ws_rpc_url: &str) -> Result<Provider<Ws>> {
+        Provider::<Ws>::connect(ws_rpc_url).await.context(format!(
+            "Failed to connect to the ws rpc server {}",
+            ws_rpc_url
+        ))
+    }
+
+    async fn connect_http(&self, http_rpc_url: &str) -> Result<Provider<Http>> {
+        Provider::<Http>::try_from(http_rpc_url).context(format!(
+            "Failed to connect to the http rpc server {}",
+            http_rpc_url
+        ))
+    }
+
+    async fn connect_gas_wallet(
+        &self,
+        http_rpc_url: &str,
+        gas_wallet: GasWallet,
+    ) -> Result<Arc<HttpProviderType>> {
+        let gas_address = gas_wallet.address();
+        let http_rpc_client = self
+            .connect_http(http_rpc_url)
+            .await?
+            .with_signer(gas_wallet)
+            .nonce_manager(gas_address);
+
+        http_rpc_client
+            .initialize_nonce(Some(BlockNumber::Pending.into()))
+            .await
+            .context(format!(
+                "Failed to sync the nonce of the gas wallet from {}",
+                http_rpc_url
+            ))?;
+
+        Ok(Arc::new(http_rpc_client))
+    }
+}
+
+pub trait LogsProvider<M: Middleware> {
+    fn common_chain_jobs<'a, P: PubsubClient>(
         &'a self,
-        common_chain_ws_client: &'a Provider<Ws>,
-    ) -> impl Future<Output = Result<SubscriptionStream<'a, Ws, Log>>>;
+        common_chain_ws_client: &'a Provider<P>,
+    ) -> impl Future<Output = Result<SubscriptionStream<'a, P, Log>>>;
 
-    fn req_chain_jobs<'a>(
+    fn req_chain_jobs<'a, P: PubsubClient>(
         &'a self,
-        req_chain_ws_client: &'a Provider<Ws>,
-        req_chain_client: &'a RequestChainClient,
+        req_chain_ws_client: &'a Provider<P>,
+        req_chain_client: &'a RequestChainClient<M>,
     ) -> impl Future<Output = Result<impl Stream<Item = Log> + Unpin>>;
 
     fn gateways_job_relayed_logs<'a, P: HttpProviderLogs>(
@@ -40,7 +121,7 @@ pub trait LogsProvider {
 
     fn request_chain_historic_subscription_jobs<'a, P: HttpProviderLogs>(
         &'a self,
-        req_chain_client: &'a RequestChainClient,
+        req_chain_client: &'a RequestChainClient<M>,
         req_chain_http_provider: &'a P,
     ) -> impl Future<Output = Result<Vec<Log>>>;
 }
@@ -49,26 +130,214 @@ pub trait HttpProviderLogs {
     async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>, ServerlessError>;
 }
 
-pub struct HttpProvider {
-    pub url: String,
+impl<M: Middleware> HttpProviderLogs for Arc<M> {
+    async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>, ServerlessError> {
+        Middleware::get_logs(self.as_ref(), filter)
+            .await
+            .map_err(|err| ServerlessError::ProviderError(err.to_string()))
+    }
+}
+
+// On-chain state of a relayed job as stored in the GatewayJobs contract
+pub trait GatewayJobsView {
+    async fn relay_job_state(&self, job_id: U256) -> Result<RelayJobsReturn, ServerlessError>;
 }
 
-impl HttpProvider {
-    pub fn new(url: String) -> Self {
-        Self { url }
+impl<M: Middleware + 'static> GatewayJobsView for GatewayJobsContract<M> {
+    async fn relay_job_state(&self, job_id: U256) -> Result<RelayJobsReturn, ServerlessError> {
+        let (
+            exec_start_time,
+            usdc_deposit,
+            job_owner,
+            is_resource_unavailable,
+            sequence_id,
+            env,
+            gateway,
+        ) = self
+            .relay_jobs(job_id)
+            .call()
+            .await
+            .map_err(|err| ServerlessError::ProviderError(err.to_string()))?;
+
+        Ok(RelayJobsReturn {
+            exec_start_time,
+            usdc_deposit,
+            job_owner,
+            is_resource_unavailable,
+            sequence_id,
+            env,
+            gateway,
+        })
     }
 }
 
-impl HttpProviderLogs for HttpProvider {
-    async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>, ServerlessError> {
-        let provider = Provider::<Http>::try_from(&self.url).unwrap();
-        let logs = provider.get_logs(filter).await.unwrap();
-        Ok(logs)
+// Request chains served by the gateways as stored in the Gateways contract
+pub trait GatewaysView {
+    // Request chains the gateway of the enclave is registered with
+    async fn gateway_chain_ids(
+        &self,
+        enclave_address: Address,
+    ) -> Result<HashSet<u64>, ServerlessError>;
+
+    // Contracts and rpc urls of the request chain, along with its latest block
+    async fn request_chain_data(&self, chain_id: u64) -> Result<RequestChainData, ServerlessError>;
+}
+
+impl<M: Middleware + 'static, C: ChainConnector<M>> GatewaysView for ContractsClient<M, C> {
+    async fn gateway_chain_ids(
+        &self,
+        enclave_address: Address,
+    ) -> Result<HashSet<u64>, ServerlessError> {
+        let chain_ids = GatewaysContract::new(
+            self.gateways_contract_address,
+            self.common_chain_client.clone(),
+        )
+        .get_gateway_chain_ids(enclave_address)
+        .call()
+        .await
+        .map_err(|err| ServerlessError::ProviderError(err.to_string()))?;
+
+        chain_ids.into_iter().map(uint_to_u64).collect()
     }
+
+    async fn request_chain_data(&self, chain_id: u64) -> Result<RequestChainData, ServerlessError> {
+        let gateways_contract = GatewaysContract::new(
+            self.gateways_contract_address,
+            self.common_chain_client.clone(),
+        );
+
+        fetch_request_chain_data(self.chain_connector.as_ref(), &gateways_contract, chain_id)
+            .await
+            .map_err(|err| ServerlessError::ProviderError(format!("{:#}", err)))
+    }
+}
+
+// Fees and balances of subscription jobs on a request chain
+pub trait RelayFundsView {
+    // Maximum fee charged for a single job instance of the given env and user timeout
+    async fn job_fee(&self, env: u8, user_timeout: U256) -> Result<U256, ServerlessError>;
+
+    // USDC balance left with the RelaySubscriptions contract for the subscription
+    async fn job_subscription_balance(
+        &self,
+        subscription_id: U256,
+    ) -> Result<U256, ServerlessError>;
+}
+
+impl<M: Middleware + 'static> RelayFundsView for RequestChainClient<M> {
+    async fn job_fee(&self, env: u8, user_timeout: U256) -> Result<U256, ServerlessError> {
+        let relay_contract = self.relay_contract.read().unwrap().clone();
+
+        let execution_fee_per_ms = relay_contract
+            .get_job_execution_fee_per_ms(env)
+            .call()
+            .await
+            .map_err(|err| ServerlessError::ProviderError(err.to_string()))?;
+        let gateway_fee_per_job = relay_contract
+            .gateway_fee_per_job()
+            .call()
+            .await
+            .map_err(|err| ServerlessError::ProviderError(err.to_string()))?;
+
+        Ok(execution_fee_per_ms * user_timeout + gateway_fee_per_job)
+    }
+
+    async fn job_subscription_balance(
+        &self,
+        subscription_id: U256,
+    ) -> Result<U256, ServerlessError> {
+        let relay_subscriptions_contract =
+            self.relay_subscriptions_contract.read().unwrap().clone();
+
+        let job_subscription = relay_subscriptions_contract
+            .job_subscriptions(subscription_id)
+            .call()
+            .await
+            .map_err(|err| ServerlessError::ProviderError(err.to_string()))?;
+
+        Ok(job_subscription.6.usdc_deposit)
+    }
+}
+
+// Fetches the contract addresses and rpc urls of the request chain from the common chain, along
+// with its latest block to start listening from
+pub async fn fetch_request_chain_data<M: Middleware + 'static, N, C: ChainConnector<N>>(
+    chain_connector: &C,
+    gateways_contract: &GatewaysContract<M>,
+    chain_id: u64,
+) -> Result<RequestChainData> {
+    let (relay_address, relay_subscriptions_address, http_rpc_url, ws_rpc_url) = gateways_contract
+        .request_chains(U256::from(chain_id))
+        .await
+        .context(format!(
+            "Failed to fetch the request chain data for chain id {}",
+            chain_id
+        ))?;
+
+    let http_rpc_client = chain_connector
+        .connect_http(&http_rpc_url)
+        .await
+        .context(format!(
+            "Failed to connect to the request chain {} http rpc server",
+            chain_id
+        ))?;
+
+    let block_number = http_rpc_client
+        .get_block_number()
+        .await
+        .context("Failed to get the latest block number of the request chain")?
+        .as_u64();
+
+    Ok(RequestChainData {
+        relay_address,
+        relay_subscriptions_address,
+        http_rpc_url: http_rpc_url.to_string(),
+        ws_rpc_url: ws_rpc_url.to_string(),
+        block_number,
+    })
+}
+
+// Builds the client of the contracts on the request chain, sending the transactions with the gas
+// wallet of the enclave
+pub async fn build_request_chain_client<M: Middleware + 'static, C: ChainConnector<M>>(
+    chain_connector: &C,
+    gas_wallet: &GasWallet,
+    chain_id: u64,
+    request_chain_data: &RequestChainData,
+) -> Result<Arc<RequestChainClient<M>>> {
+    let request_chain_http_rpc_client = chain_connector
+        .connect_gas_wallet(
+            &request_chain_data.http_rpc_url,
+            gas_wallet.clone().with_chain_id(chain_id),
+        )
+        .await?;
+    let relay_contract = RelayContract::new(
+        request_chain_data.relay_address,
+        request_chain_http_rpc_client.clone(),
+    );
+
+    let relay_subs_contract = RelaySubscriptionsContract::new(
+        request_chain_data.relay_subscriptions_address,
+        request_chain_http_rpc_client.clone(),
+    );
+
+    Ok(Arc::new(RequestChainClient {
+        chain_id,
+        relay_address: request_chain_data.relay_address,
+        relay_subscriptions_address: request_chain_data.relay_subscriptions_address,
+        relay_contract: Arc::new(RwLock::new(relay_contract)),
+        relay_subscriptions_contract: Arc::new(RwLock::new(relay_subs_contract)),
+        ws_rpc_url: request_chain_data.ws_rpc_url.to_string(),
+        http_rpc_url: request_chain_data.http_rpc_url.to_string(),
+        http_rpc_client: request_chain_http_rpc_client,
+        request_chain_start_block_number: request_chain_data.block_number,
+        confirmation_blocks: 5, // TODO: fetch from contract
+        last_seen_block: Arc::new(0.into()),
+    }))
 }
 
-pub async fn get_block_number_by_timestamp(
-    provider: &Provider<Http>,
+pub async fn get_block_number_by_timestamp<M: Middleware>(
+    provider: &M,
     target_timestamp: u64,
 ) -> Option<u64> {
     let mut block_number: u64 = 0;
@@ -194,8 +463,9 @@ pub async fn get_block_number_by_timestamp(
 }
 
 pub async fn sign_relay_job_request(
-    signer_key: &SigningKey,
-    job_id: U256,
+    enclave_signer: &dyn EnclaveSigner,
+    eip712_domains: &Eip712Domains,
+    job_key: JobKey,
     codehash: &FixedBytes,
     code_inputs: &Bytes,
     user_timeout: U256,
@@ -209,61 +479,32 @@ pub async fn sign_relay_job_request(
         .unwrap()
         .as_secs();
 
-    let relay_job_typehash = keccak256(
-            "RelayJob(uint256 jobId,bytes32 codeHash,bytes codeInputs,uint256 deadline,uint256 jobRequestTimestamp,uint8 sequenceId,address jobOwner,uint8 env,uint256 signTimestamp)"
-        );
-
-    let code_inputs_hash = keccak256(code_inputs);
-
-    let token_list = [
-        Token::FixedBytes(relay_job_typehash.to_vec()),
-        Token::Uint(job_id),
-        Token::FixedBytes(codehash.clone()),
-        Token::FixedBytes(code_inputs_hash.to_vec()),
-        Token::Uint(user_timeout),
-        Token::Uint(job_start_time),
-        Token::Uint(sequence_number.into()),
-        Token::Address(*job_owner),
-        Token::Uint(env.into()),
-        Token::Uint(sign_timestamp.into()),
-    ];
-
-    let hash_struct = keccak256(encode(&token_list));
-
-    let gateway_jobs_domain_separator = keccak256(encode(&[
-        Token::FixedBytes(keccak256("EIP712Domain(string name,string version)").to_vec()),
-        Token::FixedBytes(keccak256("marlin.oyster.GatewayJobs").to_vec()),
-        Token::FixedBytes(keccak256("1").to_vec()),
-    ]));
-
-    let digest = encode_packed(&[
-        Token::String("\x19\x01".to_string()),
-        Token::FixedBytes(gateway_jobs_domain_separator.to_vec()),
-        Token::FixedBytes(hash_struct.to_vec()),
-    ]);
-
-    let Ok(digest) = digest else {
-        eprintln!("Failed to encode the digest: {:#?}", digest.err());
-        return None;
+    let relay_job = RelayJobMessage {
+        job_id: job_key.common_chain_job_id(),
+        code_hash: H256::from_slice(codehash),
+        code_inputs: code_inputs.clone(),
+        deadline: user_timeout,
+        job_request_timestamp: job_start_time,
+        sequence_id: sequence_number,
+        job_owner: *job_owner,
+        env,
+        sign_timestamp,
     };
-    let digest = keccak256(digest);
 
     // Sign the digest using enclave key
-    let sig = signer_key.sign_prehash_recoverable(&digest);
-    let Ok((rs, v)) = sig else {
+    let sig = sign_typed_data(enclave_signer, &eip712_domains.gateway_jobs, &relay_job);
+    let Ok(sig) = sig else {
         eprintln!("Failed to sign the digest: {:#?}", sig.err());
         return None;
     };
 
-    Some((
-        hex::encode(rs.to_bytes().append(27 + v.to_byte()).to_vec()),
-        sign_timestamp,
-    ))
+    Some((hex::encode(sig), sign_timestamp))
 }
 
 pub async fn sign_reassign_gateway_relay_request(
-    signer_key: &SigningKey,
-    job_id: U256,
+    enclave_signer: &dyn EnclaveSigner,
+    eip712_domains: &Eip712Domains,
+    job_key: JobKey,
     gateway_operator_old: &Address,
     job_owner: &Address,
     sequence_number: u8,
@@ -274,55 +515,32 @@ pub async fn sign_reassign_gateway_relay_request(
         .unwrap()
         .as_secs();
 
-    let reassign_gateway_relay_typehash = keccak256(
-            "ReassignGateway(uint256 jobId,address gatewayOld,address jobOwner,uint8 sequenceId,uint256 jobRequestTimestamp,uint256 signTimestamp)"
-        );
-
-    let token_list = [
-        Token::FixedBytes(reassign_gateway_relay_typehash.to_vec()),
-        Token::Uint(job_id),
-        Token::Address(*gateway_operator_old),
-        Token::Address(*job_owner),
-        Token::Uint(sequence_number.into()),
-        Token::Uint(job_start_time),
-        Token::Uint(sign_timestamp.into()),
-    ];
-
-    let hash_struct = keccak256(encode(&token_list));
-
-    let gateway_jobs_domain_separator = keccak256(encode(&[
-        Token::FixedBytes(keccak256("EIP712Domain(string name,string version)").to_vec()),
-        Token::FixedBytes(keccak256("marlin.oyster.GatewayJobs").to_vec()),
-        Token::FixedBytes(keccak256("1").to_vec()),
-    ]));
-
-    let digest = encode_packed(&[
-        Token::String("\x19\x01".to_string()),
-        Token::FixedBytes(gateway_jobs_domain_separator.to_vec()),
-        Token::FixedBytes(hash_struct.to_vec()),
-    ]);
-
-    let Ok(digest) = digest else {
-        eprintln!("Failed to encode the digest: {:#?}", digest.err());
-        return None;
+    let reassign_gateway = ReassignGatewayMessage {
+        job_id: job_key.common_chain_job_id(),
+        gateway_old: *gateway_operator_old,
+        job_owner: *job_owner,
+        sequence_id: sequence_number,
+        job_request_timestamp: job_start_time,
+        sign_timestamp,
     };
-    let digest = keccak256(digest);
 
     // Sign the digest using enclave key
-    let sig = signer_key.sign_prehash_recoverable(&digest);
-    let Ok((rs, v)) = sig else {
+    let sig = sign_typed_data(
+        enclave_signer,
+        &eip712_domains.gateway_jobs,
+        &reassign_gateway,
+    );
+    let Ok(sig) = sig else {
         eprintln!("Failed to sign the digest: {:#?}", sig.err());
         return None;
     };
 
-    Some((
-        hex::encode(rs.to_bytes().append(27 + v.to_byte()).to_vec()),
-        sign_timestamp,
-    ))
+    Some((hex::encode(sig), sign_timestamp))
 }
 
 pub async fn sign_job_response_request(
-    signer_key: &SigningKey,
+    enclave_signer: &dyn EnclaveSigner,
+    eip712_domains: &Eip712Domains,
     job_id: U256,
     output: Bytes,
     total_time: U256,
@@ -334,69 +552,37 @@ pub async fn sign_job_response_request(
         .unwrap()
         .as_secs();
 
-    let job_response_typehash = keccak256(
-        "JobResponse(uint256 jobId,bytes output,uint256 totalTime,uint8 errorCode,uint256 signTimestamp)"
-    );
-
-    let output_hash = keccak256(output);
-
-    let token_list = [
-        Token::FixedBytes(job_response_typehash.to_vec()),
-        Token::Uint(job_id),
-        Token::FixedBytes(output_hash.to_vec()),
-        Token::Uint(total_time),
-        Token::Uint(error_code.into()),
-        Token::Uint(sign_timestamp.into()),
-    ];
-
-    let hash_struct = keccak256(encode(&token_list));
+    let job_response = JobResponseMessage {
+        job_id,
+        output,
+        total_time,
+        error_code,
+        sign_timestamp,
+    };
 
-    let contract_name;
-    if job_mode == JobMode::Once {
-        contract_name = "marlin.oyster.Relay";
+    // Responses are verified by the contract the job was requested on
+    let domain = if job_mode == JobMode::Once {
+        &eip712_domains.relay
     } else {
-        contract_name = "marlin.oyster.RelaySubscriptions";
-    }
-
-    let gateway_jobs_domain_separator = keccak256(encode(&[
-        Token::FixedBytes(keccak256("EIP712Domain(string name,string version)").to_vec()),
-        Token::FixedBytes(keccak256(contract_name).to_vec()),
-        Token::FixedBytes(keccak256("1").to_vec()),
-    ]));
-
-    let digest = encode_packed(&[
-        Token::String("\x19\x01".to_string()),
-        Token::FixedBytes(gateway_jobs_domain_separator.to_vec()),
-        Token::FixedBytes(hash_struct.to_vec()),
-    ]);
-
-    let Ok(digest) = digest else {
-        eprintln!("Failed to encode the digest: {:#?}", digest.err());
-        return None;
+        &eip712_domains.relay_subscriptions
     };
-    let digest = keccak256(digest);
 
     // Sign the digest using enclave key
-    let sig = signer_key.sign_prehash_recoverable(&digest);
-    let Ok((rs, v)) = sig else {
+    let sig = sign_typed_data(enclave_signer, domain, &job_response);
+    let Ok(sig) = sig else {
         eprintln!("Failed to sign the digest: {:#?}", sig.err());
         return None;
     };
 
-    Some((
-        hex::encode(rs.to_bytes().append(27 + v.to_byte()).to_vec()),
-        sign_timestamp,
-    ))
+    Some((hex::encode(sig), sign_timestamp))
 }
 
-pub async fn confirm_event(
+pub async fn confirm_event<M: Middleware>(
     mut log: Log,
-    http_rpc_url: &String,
+    provider: &M,
     confirmation_blocks: u64,
     last_seen_block: Arc<AtomicU64>,
 ) -> Log {
-    let provider: Provider<Http> = Provider::<Http>::try_from(http_rpc_url).unwrap();
-
     let log_transaction_hash = log.transaction_hash.unwrap_or(H256::zero());
     // Verify transaction hash is of valid length and not 0
     if log_transaction_hash == H256::zero() {
@@ -455,3 +641,49 @@ pub async fn confirm_event(
     }
     log
 }
+
+#[cfg(test)]
+mod chain_util_tests {
+    use super::*;
+
+    fn generate_confirmable_log(block_number: u64) -> Log {
+        Log {
+            block_number: Some(block_number.into()),
+            transaction_hash: Some(H256::random()),
+            removed: Some(false),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_confirm_event_with_mocked_provider() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(TransactionReceipt::default()).unwrap();
+
+        let log = confirm_event(
+            generate_confirmable_log(10),
+            &provider,
+            5,
+            Arc::new(AtomicU64::new(15)),
+        )
+        .await;
+
+        assert_eq!(log.removed, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_confirm_event_reorged_with_mocked_provider() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(Option::<TransactionReceipt>::None).unwrap();
+
+        let log = confirm_event(
+            generate_confirmable_log(10),
+            &provider,
+            5,
+            Arc::new(AtomicU64::new(15)),
+        )
+        .await;
+
+        assert_eq!(log.removed, Some(true));
+    }
+}
@@ -0,0 +1,169 @@
+use ethers::providers::Middleware;
+use ethers::types::BlockNumber;
+use log::error;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time;
+
+use crate::constant::COMMON_CHAIN_CLOCK_SYNC_INTERVAL;
+use crate::error::ServerlessError;
+
+/// Source of the current unix timestamp (in seconds) used for the epoch cycle math and
+/// the subscription scheduling.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> u64;
+}
+
+/// Clock backed by the local system time.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+    }
+}
+
+/// Clock derived from the timestamp of the latest common chain block.
+///
+/// The block timestamp is refreshed periodically and the local monotonic time elapsed since
+/// the last refresh is added on top of it, so that `now` keeps moving between refreshes.
+/// Until the first refresh succeeds, the local system time is used. A refresh behind the
+/// time already returned doesn't move `now` backwards, it holds until the block time catches up.
+#[derive(Debug)]
+pub struct BlockTimestampClock {
+    block_timestamp: AtomicU64,
+    synced_at: Mutex<Instant>,
+    last_now: AtomicU64,
+}
+
+impl BlockTimestampClock {
+    pub fn new() -> Self {
+        Self {
+            block_timestamp: AtomicU64::new(0),
+            synced_at: Mutex::new(Instant::now()),
+            last_now: AtomicU64::new(0),
+        }
+    }
+
+    pub async fn sync<M: Middleware>(&self, provider: &M) -> Result<(), ServerlessError> {
+        let block = provider
+            .get_block(BlockNumber::Latest)
+            .await
+            .map_err(|err| ServerlessError::ProviderError(err.to_string()))?;
+        let Some(block) = block else {
+            return Err(ServerlessError::ProviderError(
+                "Latest block not found".to_string(),
+            ));
+        };
+
+        let mut synced_at_guard = self.synced_at.lock().unwrap();
+        self.block_timestamp
+            .store(block.timestamp.as_u64(), Ordering::SeqCst);
+        *synced_at_guard = Instant::now();
+        Ok(())
+    }
+
+    pub async fn sync_with_chain<M: Middleware>(self: Arc<Self>, provider: Arc<M>) {
+        loop {
+            if let Err(err) = self.sync(provider.as_ref()).await {
+                error!("Failed to sync the clock with the common chain: {}", err);
+            }
+            time::sleep(Duration::from_secs(COMMON_CHAIN_CLOCK_SYNC_INTERVAL)).await;
+        }
+    }
+}
+
+impl Default for BlockTimestampClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for BlockTimestampClock {
+    fn now(&self) -> u64 {
+        let synced_at_guard = self.synced_at.lock().unwrap();
+        let block_timestamp = self.block_timestamp.load(Ordering::SeqCst);
+        let now = if block_timestamp == 0 {
+            SystemClock.now()
+        } else {
+            block_timestamp + synced_at_guard.elapsed().as_secs()
+        };
+        self.last_now.fetch_max(now, Ordering::SeqCst).max(now)
+    }
+}
+
+#[cfg(test)]
+mod clock_tests {
+    use ethers::providers::Provider;
+    use ethers::types::{Block, TxHash};
+
+    use super::*;
+
+    #[test]
+    fn test_block_timestamp_clock_before_sync() {
+        let clock = BlockTimestampClock::new();
+
+        let system_time = SystemClock.now();
+        let now = clock.now();
+
+        assert!(now >= system_time);
+        assert!(now <= system_time + 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_timestamp_clock_sync() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(Block::<TxHash> {
+            timestamp: 1000.into(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let clock = BlockTimestampClock::new();
+        clock.sync(&provider).await.unwrap();
+
+        assert_eq!(clock.now(), 1000);
+    }
+
+    #[tokio::test]
+    async fn test_block_timestamp_clock_sync_no_block() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(Option::<Block<TxHash>>::None).unwrap();
+
+        let clock = BlockTimestampClock::new();
+        let res = clock.sync(&provider).await;
+
+        assert!(res.is_err());
+        assert!(clock.now() > 1000);
+    }
+
+    #[tokio::test]
+    async fn test_block_timestamp_clock_sync_backwards() {
+        let clock = BlockTimestampClock::new();
+
+        let (provider, mock) = Provider::mocked();
+        mock.push(Block::<TxHash> {
+            timestamp: 1000.into(),
+            ..Default::default()
+        })
+        .unwrap();
+        clock.sync(&provider).await.unwrap();
+        assert_eq!(clock.now(), 1000);
+
+        // Re-synced with a block behind the time already returned
+        let (provider, mock) = Provider::mocked();
+        mock.push(Block::<TxHash> {
+            timestamp: 990.into(),
+            ..Default::default()
+        })
+        .unwrap();
+        clock.sync(&provider).await.unwrap();
+        assert_eq!(clock.now(), 1000);
+    }
+}
@@ -1,33 +1,27 @@
 use anyhow::{Context, Error, Result};
-use ethers::abi::{decode, ParamType, Token};
 use ethers::prelude::*;
-use ethers::utils::keccak256;
 use log::{error, info};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeMap;
 use std::sync::{Arc, RwLock};
-use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc::Sender;
 use tokio::time::{self, Duration, Instant};
 
-use crate::chain_util::get_block_number_by_timestamp;
-use crate::constant::{
-    COMMON_CHAIN_GATEWAY_CHAIN_ADDED_EVENT, COMMON_CHAIN_GATEWAY_CHAIN_REMOVED_EVENT,
-    COMMON_CHAIN_GATEWAY_DEREGISTERED_EVENT, COMMON_CHAIN_GATEWAY_REGISTERED_EVENT,
-    GATEWAY_BLOCK_STATES_TO_MAINTAIN,
+use crate::chain_util::{get_block_number_by_timestamp, ChainConnector};
+use crate::constant::GATEWAY_BLOCK_STATES_TO_MAINTAIN;
+use crate::contract_abi::{
+    ChainAddedFilter, ChainRemovedFilter, CommonChainGatewayDeregisteredFilter,
+    CommonChainGatewayRegisteredFilter, GatewaysContract,
 };
-use crate::contract_abi::GatewaysContract;
+use crate::event_decoder::{decode_event, topic};
 use crate::model::{ContractsClient, GatewayData, Job};
 
 // Initialize the gateway epoch state
-pub async fn gateway_epoch_state_service(
+pub async fn gateway_epoch_state_service<M: Middleware + 'static, C: ChainConnector<M>>(
     current_time: u64,
-    common_chain_rpc_http_url: String,
-    contracts_client: Arc<ContractsClient>,
+    provider: Arc<M>,
+    contracts_client: Arc<ContractsClient<M, C>>,
     tx: Sender<Job>,
 ) {
-    let provider: Provider<Http> = Provider::<Http>::try_from(&common_chain_rpc_http_url).unwrap();
-    let provider = Arc::new(provider);
-
     let current_cycle = (current_time - contracts_client.epoch) / contracts_client.time_interval;
     let initial_epoch_cycle: u64;
     let mut cycle_number: u64;
@@ -50,11 +44,7 @@ pub async fn gateway_epoch_state_service(
 
         cycle_number = initial_epoch_cycle;
         while cycle_number <= current_cycle {
-            let _current_cycle = (SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("Time went backwards")
-                .as_secs()
-                - contracts_client.epoch)
+            let _current_cycle = (contracts_client.clock.now() - contracts_client.epoch)
                 / contracts_client.time_interval;
 
             if _current_cycle >= GATEWAY_BLOCK_STATES_TO_MAINTAIN + cycle_number {
@@ -63,7 +53,7 @@ pub async fn gateway_epoch_state_service(
 
             let success = generate_gateway_epoch_state_for_cycle(
                 contract_address_clone,
-                &provider_clone,
+                provider_clone.as_ref(),
                 com_chain_gateway_contract_clone.clone(),
                 &gateway_epoch_state_clone,
                 cycle_number,
@@ -89,10 +79,7 @@ pub async fn gateway_epoch_state_service(
 
     let next_cycle_timestamp: i64 =
         (contracts_client.epoch + ((current_cycle + 1) * contracts_client.time_interval)) as i64;
-    let current_time = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_secs() as i64;
+    let current_time = contracts_client.clock.now() as i64;
 
     let interval_start_instant = if next_cycle_timestamp > current_time {
         Instant::now() + Duration::from_secs((next_cycle_timestamp - current_time) as u64)
@@ -109,11 +96,7 @@ pub async fn gateway_epoch_state_service(
         interval.tick().await;
 
         loop {
-            let current_cycle = (SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("Time went backwards")
-                .as_secs()
-                - contracts_client.epoch)
+            let current_cycle = (contracts_client.clock.now() - contracts_client.epoch)
                 / contracts_client.time_interval;
 
             if current_cycle >= GATEWAY_BLOCK_STATES_TO_MAINTAIN + cycle_number {
@@ -122,7 +105,7 @@ pub async fn gateway_epoch_state_service(
 
             let success = generate_gateway_epoch_state_for_cycle(
                 contracts_client.gateways_contract_address,
-                &provider.clone(),
+                provider.as_ref(),
                 common_chain_gateways_contract.clone(),
                 &contracts_client.gateway_epoch_state,
                 cycle_number,
@@ -143,11 +126,7 @@ pub async fn gateway_epoch_state_service(
             callback_for_gateway_epoch_waitlist(contracts_client.clone(), cycle_number, tx.clone())
                 .await;
 
-            let _current_cycle = (SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("Time went backwards")
-                .as_secs()
-                - contracts_client.epoch)
+            let _current_cycle = (contracts_client.clock.now() - contracts_client.epoch)
                 / contracts_client.time_interval;
 
             if cycle_number == _current_cycle {
@@ -157,6 +136,7 @@ pub async fn gateway_epoch_state_service(
         }
         prune_old_cycle_states(
             &contracts_client.gateway_epoch_state,
+            contracts_client.clock.now(),
             contracts_client.epoch,
             contracts_client.time_interval,
         )
@@ -166,10 +146,10 @@ pub async fn gateway_epoch_state_service(
     }
 }
 
-pub async fn generate_gateway_epoch_state_for_cycle(
+pub async fn generate_gateway_epoch_state_for_cycle<M: Middleware + 'static>(
     contract_address: Address,
-    provider: &Provider<Http>,
-    com_chain_gateway_contract: Arc<GatewaysContract<Provider<Http>>>,
+    provider: &M,
+    com_chain_gateway_contract: Arc<GatewaysContract<M>>,
     gateway_epoch_state: &Arc<RwLock<BTreeMap<u64, BTreeMap<Address, GatewayData>>>>,
     cycle_number: u64,
     epoch: u64,
@@ -212,7 +192,7 @@ pub async fn generate_gateway_epoch_state_for_cycle(
 
     // in case of no blocks created in this epoch cycle -
     // to_block_number can be less than from_block_number
-    let mut to_block_number = get_block_number_by_timestamp(&provider, timestamp_to_fetch).await;
+    let mut to_block_number = get_block_number_by_timestamp(provider, timestamp_to_fetch).await;
     if to_block_number.is_none() {
         error!(
             "Failed to get block number for timestamp {}",
@@ -262,29 +242,30 @@ pub async fn generate_gateway_epoch_state_for_cycle(
         .from_block(from_block_number)
         .to_block(to_block_number)
         .topic0(vec![
-            keccak256(COMMON_CHAIN_GATEWAY_REGISTERED_EVENT),
-            keccak256(COMMON_CHAIN_GATEWAY_DEREGISTERED_EVENT),
-            keccak256(COMMON_CHAIN_GATEWAY_CHAIN_ADDED_EVENT),
-            keccak256(COMMON_CHAIN_GATEWAY_CHAIN_REMOVED_EVENT),
+            CommonChainGatewayRegisteredFilter::signature(),
+            CommonChainGatewayDeregisteredFilter::signature(),
+            ChainAddedFilter::signature(),
+            ChainRemovedFilter::signature(),
         ]);
 
     let logs = provider
         .get_logs(&event_filter)
         .await
-        .context("Failed to get logs for the gateway contract")
-        .unwrap();
+        .context("Failed to get logs for the gateway contract")?;
 
     for log in logs {
-        let ref topics = log.topics;
+        let Ok(topic0) = topic(&log, 0) else {
+            continue;
+        };
 
-        if topics[0] == keccak256(COMMON_CHAIN_GATEWAY_REGISTERED_EVENT).into() {
+        if topic0 == CommonChainGatewayRegisteredFilter::signature() {
             process_gateway_registered_event(log, to_block_number, &mut current_cycle_state_epoch)
                 .await;
-        } else if topics[0] == keccak256(COMMON_CHAIN_GATEWAY_DEREGISTERED_EVENT).into() {
+        } else if topic0 == CommonChainGatewayDeregisteredFilter::signature() {
             process_gateway_deregistered_event(log, &mut current_cycle_state_epoch).await;
-        } else if topics[0] == keccak256(COMMON_CHAIN_GATEWAY_CHAIN_ADDED_EVENT).into() {
+        } else if topic0 == ChainAddedFilter::signature() {
             process_chain_added_event(log, &mut current_cycle_state_epoch).await;
-        } else if topics[0] == keccak256(COMMON_CHAIN_GATEWAY_CHAIN_REMOVED_EVENT).into() {
+        } else if topic0 == ChainRemovedFilter::signature() {
             process_chain_removed_event(log, &mut current_cycle_state_epoch).await;
         }
     }
@@ -331,32 +312,15 @@ async fn process_gateway_registered_event(
     to_block_number: u64,
     current_cycle_state_epoch: &mut BTreeMap<Address, GatewayData>,
 ) {
-    let address = Address::from_slice(log.topics[1][12..].try_into().unwrap());
-
-    let decoded = decode(
-        &vec![ParamType::Array(Box::new(ParamType::Uint(256)))],
-        &log.data.0,
-    );
-
-    if decoded.is_err() {
-        error!(
-            "Failed to decode gateway registered event {}",
-            decoded.err().unwrap()
-        );
-        return;
-    }
-
-    let decoded = decoded.unwrap();
-
-    let mut req_chain_ids = BTreeSet::new();
-
-    if let Token::Array(array_tokens) = decoded[0].clone() {
-        for token in array_tokens {
-            if let Token::Uint(request_chain_id) = token {
-                req_chain_ids.insert(U256::from(request_chain_id).as_u64());
-            }
+    let gateway_registered_event = match decode_event::<CommonChainGatewayRegisteredFilter>(&log) {
+        Ok(gateway_registered_event) => gateway_registered_event,
+        Err(err) => {
+            error!("Failed to decode gateway registered event {}", err);
+            return;
         }
-    }
+    };
+
+    let address = gateway_registered_event.enclave_address;
 
     current_cycle_state_epoch.insert(
         address,
@@ -364,7 +328,11 @@ async fn process_gateway_registered_event(
             last_block_number: to_block_number,
             address,
             stake_amount: U256::zero(), // gateways call is used to get the stake amount
-            req_chain_ids,
+            req_chain_ids: gateway_registered_event
+                .chain_ids
+                .into_iter()
+                .filter_map(|chain_id| u64::try_from(chain_id).ok())
+                .collect(),
             draining: false,
         },
     );
@@ -374,32 +342,36 @@ async fn process_gateway_deregistered_event(
     log: Log,
     current_cycle_state_epoch: &mut BTreeMap<Address, GatewayData>,
 ) {
-    let address = Address::from_slice(log.topics[1][12..].try_into().unwrap());
+    let gateway_deregistered_event =
+        match decode_event::<CommonChainGatewayDeregisteredFilter>(&log) {
+            Ok(gateway_deregistered_event) => gateway_deregistered_event,
+            Err(err) => {
+                error!("Failed to decode gateway deregistered event {}", err);
+                return;
+            }
+        };
 
-    current_cycle_state_epoch.remove(&address);
+    current_cycle_state_epoch.remove(&gateway_deregistered_event.enclave_address);
 }
 
 async fn process_chain_added_event(
     log: Log,
     current_cycle_state_epoch: &mut BTreeMap<Address, GatewayData>,
 ) {
-    let address = Address::from_slice(log.topics[1][12..].try_into().unwrap());
-
-    let decoded = decode(&vec![ParamType::Uint(256)], &log.data.0);
-
-    if decoded.is_err() {
-        error!(
-            "Failed to decode gateway registered event {}",
-            decoded.err().unwrap()
-        );
-        return;
-    }
-
-    let decoded = decoded.unwrap();
-    let chain_id = decoded[0].clone().into_uint().unwrap().as_u64();
+    let chain_added_event = match decode_event::<ChainAddedFilter>(&log) {
+        Ok(chain_added_event) => chain_added_event,
+        Err(err) => {
+            error!("Failed to decode chain added event {}", err);
+            return;
+        }
+    };
 
-    if let Some(gateway_data) = current_cycle_state_epoch.get_mut(&address) {
-        gateway_data.req_chain_ids.insert(chain_id);
+    if let Some(gateway_data) =
+        current_cycle_state_epoch.get_mut(&chain_added_event.enclave_address)
+    {
+        if let Ok(chain_id) = u64::try_from(chain_added_event.chain_id) {
+            gateway_data.req_chain_ids.insert(chain_id);
+        }
     }
 }
 
@@ -407,37 +379,30 @@ async fn process_chain_removed_event(
     log: Log,
     current_cycle_state_epoch: &mut BTreeMap<Address, GatewayData>,
 ) {
-    let address = Address::from_slice(log.topics[1][12..].try_into().unwrap());
-
-    let decoded = decode(&vec![ParamType::Uint(256)], &log.data.0);
-
-    if decoded.is_err() {
-        error!(
-            "Failed to decode gateway registered event {}",
-            decoded.err().unwrap()
-        );
-        return;
-    }
-
-    let decoded = decoded.unwrap();
-    let chain_id = decoded[0].clone().into_uint().unwrap().as_u64();
+    let chain_removed_event = match decode_event::<ChainRemovedFilter>(&log) {
+        Ok(chain_removed_event) => chain_removed_event,
+        Err(err) => {
+            error!("Failed to decode chain removed event {}", err);
+            return;
+        }
+    };
 
-    if let Some(gateway_data) = current_cycle_state_epoch.get_mut(&address) {
-        gateway_data.req_chain_ids.remove(&chain_id);
+    if let Some(gateway_data) =
+        current_cycle_state_epoch.get_mut(&chain_removed_event.enclave_address)
+    {
+        if let Ok(chain_id) = u64::try_from(chain_removed_event.chain_id) {
+            gateway_data.req_chain_ids.remove(&chain_id);
+        }
     }
 }
 
 async fn prune_old_cycle_states(
     gateway_epoch_state: &Arc<RwLock<BTreeMap<u64, BTreeMap<Address, GatewayData>>>>,
+    current_time: u64,
     epoch: u64,
     time_interval: u64,
 ) {
-    let current_cycle = (SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_secs()
-        - epoch)
-        / time_interval;
+    let current_cycle = (current_time - epoch) / time_interval;
     let mut cycles_to_remove = vec![];
 
     // scope for the read lock
@@ -463,8 +428,8 @@ async fn prune_old_cycle_states(
     }
 }
 
-async fn callback_for_gateway_epoch_waitlist(
-    contracts_client: Arc<ContractsClient>,
+async fn callback_for_gateway_epoch_waitlist<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: Arc<ContractsClient<M, C>>,
     cycle_number: u64,
     tx: Sender<Job>,
 ) {
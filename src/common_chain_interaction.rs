@@ -1,10 +1,8 @@
 use actix_web::web::Data;
 use anyhow::{Context, Result};
-use ethers::abi::{decode, ParamType};
 use ethers::prelude::*;
 use ethers::providers::Provider;
 use ethers::types::Address;
-use ethers::utils::keccak256;
 use futures_core::stream::Stream;
 use hex::FromHex;
 use log::{error, info};
@@ -12,49 +10,98 @@ use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 use std::collections::HashSet;
 use std::error::Error;
+use std::future::Future;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tokio::time;
 
 use crate::chain_util::{
     confirm_event, sign_job_response_request, sign_reassign_gateway_relay_request,
-    sign_relay_job_request, HttpProvider, HttpProviderLogs, LogsProvider,
+    sign_relay_job_request, ChainConnector, GatewayJobsView, HttpProviderLogs, LogsProvider,
 };
 use crate::common_chain_gateway_state_service::gateway_epoch_state_service;
 use crate::constant::{
-    COMMON_CHAIN_GATEWAY_REASSIGNED_EVENT, COMMON_CHAIN_GATEWAY_REGISTERED_EVENT,
-    COMMON_CHAIN_JOB_RELAYED_EVENT, COMMON_CHAIN_JOB_RESOURCE_UNAVAILABLE_EVENT,
-    COMMON_CHAIN_JOB_RESPONDED_EVENT, GATEWAY_BLOCK_STATES_TO_MAINTAIN,
-    GATEWAY_STAKE_ADJUSTMENT_FACTOR, MAX_GATEWAY_RETRIES, MIN_GATEWAY_STAKE,
-    REQUEST_CHAIN_GATEWAY_REGISTERED_EVENT, REQUEST_CHAIN_JOB_CANCELLED_EVENT,
-    REQUEST_CHAIN_JOB_RELAYED_EVENT, REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT,
-    REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT,
-    REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT, REQUEST_RELAY_TIMEOUT,
+    GATEWAY_BLOCK_STATES_TO_MAINTAIN, GATEWAY_STAKE_ADJUSTMENT_FACTOR, MAX_GATEWAY_RETRIES,
+    MIN_GATEWAY_STAKE, REQUEST_RELAY_TIMEOUT, RESPONSE_RELAY_TIMEOUT,
 };
+use crate::contract_abi::{
+    CommonChainGatewayRegisteredFilter, CommonChainJobRelayedFilter, CommonChainJobRespondedFilter,
+    GatewayReassignedFilter, GatewaysContract, JobCancelledFilter, JobResourceUnavailableFilter,
+    JobSubscriptionFundsDepositedFilter, JobSubscriptionFundsWithdrawnFilter,
+    JobSubscriptionJobParamsUpdatedFilter, JobSubscriptionStartedFilter,
+    JobSubscriptionTerminatedFilter, JobSubscriptionTerminationParamsUpdatedFilter,
+    RequestChainGatewayRegisteredFilter, RequestChainJobRelayedFilter,
+};
+use crate::deadline_queue::forward_to_deadline_queue;
 use crate::error::ServerlessError;
+use crate::event_decoder::{decode_event, topic, uint_to_u64};
 use crate::job_subscription_management::{
     add_subscription_job, job_subscription_manager, process_historic_job_subscriptions,
-    update_subscription_job_params, update_subscription_job_termination_params,
+    record_subscription_instance, remove_subscription_job, update_subscription_job_funds_deposited,
+    update_subscription_job_funds_withdrawn, update_subscription_job_params,
+    update_subscription_job_termination_params,
 };
 use crate::model::{
-    AppState, ContractsClient, GatewayData, GatewayJobType, Job, JobMode, JobSubscriptionAction,
-    JobSubscriptionChannelType, RegisterType, RegisteredData, RequestChainClient, ResponseJob,
+    AppState, ContractsClient, DequeuedItem, GatewayData, GatewayJobType, Job, JobKey, JobMode,
+    JobSubscriptionAction, JobSubscriptionChannelType, RegisterType, RegisteredData,
+    RequestChainClient, RequestChainSenders, ResponseJob, SubscriptionInstanceStatus,
 };
+use crate::sealed_state::persist_sealed_state;
+use crate::subscription_trigger_limiter::subscription_trigger_dispatcher;
 
-impl ContractsClient {
+impl<M: Middleware + 'static, C: ChainConnector<M>> ContractsClient<M, C> {
+    // Returns once the enclave is registered on the common chain and all request chains, marking
+    // it registered in the app state
     pub async fn wait_for_registration(self: Arc<Self>, app_state: Data<AppState>) {
         info!("Waiting for registration on the Common Chain and all Request Chains...");
         // create a channel to communicate with the main thread
         let (tx, mut rx) = channel::<RegisteredData>(100);
 
+        // The subscriptions below only see the future events, so report the chains the enclave
+        // got registered on before the listeners started
+        {
+            let tx_clone = tx.clone();
+            let self_clone = Arc::clone(&self);
+            tokio::spawn(async move {
+                let (common_chain_registered, request_chain_ids_registered) =
+                    match self_clone.registration_status().await {
+                        Ok(registration_status) => registration_status,
+                        Err(err) => {
+                            error!(
+                                "Failed to fetch the registration status on chain: {:?}",
+                                err
+                            );
+                            return;
+                        }
+                    };
+
+                if common_chain_registered {
+                    let _ = tx_clone
+                        .send(RegisteredData {
+                            register_type: RegisterType::CommonChain,
+                            chain_id: None,
+                        })
+                        .await;
+                }
+                for chain_id in request_chain_ids_registered {
+                    let _ = tx_clone
+                        .send(RegisteredData {
+                            register_type: RegisterType::RequestChain,
+                            chain_id: Some(chain_id),
+                        })
+                        .await;
+                }
+            });
+        }
+
         let common_chain_block_number = *self.common_chain_start_block_number.lock().unwrap();
 
         let common_chain_registered_filter = Filter::new()
             .address(self.gateways_contract_address)
             .select(common_chain_block_number..)
-            .topic0(vec![keccak256(COMMON_CHAIN_GATEWAY_REGISTERED_EVENT)])
+            .topic0(vec![CommonChainGatewayRegisteredFilter::signature()])
             .topic1(self.enclave_address)
             .topic2(self.enclave_owner);
 
@@ -62,17 +109,20 @@ impl ContractsClient {
         let self_clone = Arc::clone(&self);
         tokio::spawn(async move {
             'socket_loop: loop {
-                let common_chain_ws_provider =
-                    match Provider::<Ws>::connect(&self_clone.common_chain_ws_url).await {
-                        Ok(common_chain_ws_provider) => common_chain_ws_provider,
-                        Err(err) => {
-                            error!(
-                                "Failed to connect to the common chain websocket provider: {}",
-                                err
-                            );
-                            continue;
-                        }
-                    };
+                let common_chain_ws_provider = match self_clone
+                    .chain_connector
+                    .connect_pubsub(&self_clone.common_chain_ws_url)
+                    .await
+                {
+                    Ok(common_chain_ws_provider) => common_chain_ws_provider,
+                    Err(err) => {
+                        error!(
+                            "Failed to connect to the common chain websocket provider: {:#}",
+                            err
+                        );
+                        continue;
+                    }
+                };
 
                 // TODO: an error here can effect can panic the thread. What possible errors are possible?
                 let mut common_chain_stream = common_chain_ws_provider
@@ -86,6 +136,20 @@ impl ContractsClient {
                         continue;
                     }
 
+                    match decode_event::<CommonChainGatewayRegisteredFilter>(&log) {
+                        Ok(gateway_registered_event)
+                            if gateway_registered_event.enclave_address
+                                == self_clone.enclave_address
+                                && gateway_registered_event.owner == self_clone.enclave_owner => {}
+                        _ => {
+                            error!(
+                                "Invalid GatewayRegistered event on the Common Chain: {:?}",
+                                log
+                            );
+                            continue;
+                        }
+                    }
+
                     *self_clone.common_chain_start_block_number.lock().unwrap() = log
                         .block_number
                         .unwrap_or(common_chain_block_number.into())
@@ -95,7 +159,7 @@ impl ContractsClient {
                         register_type: RegisterType::CommonChain,
                         chain_id: None,
                     };
-                    tx_clone.send(registered_data).await.unwrap();
+                    let _ = tx_clone.send(registered_data).await;
 
                     info!("Common Chain Registered");
                     break 'socket_loop;
@@ -104,27 +168,36 @@ impl ContractsClient {
         });
 
         // listen to all the request chains for the GatewayRegistered event
-        for request_chain_client in self.request_chain_clients.values().cloned() {
+        let request_chain_clients: Vec<Arc<RequestChainClient<M>>> = self
+            .request_chain_clients
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        for request_chain_client in request_chain_clients {
             let request_chain_registered_filter = Filter::new()
                 .address(request_chain_client.relay_address)
                 .select(request_chain_client.request_chain_start_block_number..)
-                .topic0(vec![keccak256(REQUEST_CHAIN_GATEWAY_REGISTERED_EVENT)])
+                .topic0(vec![RequestChainGatewayRegisteredFilter::signature()])
                 .topic1(self.enclave_owner)
                 .topic2(self.enclave_address);
 
             let tx_clone = tx.clone();
             let request_chain_client_clone = request_chain_client.clone();
+            let enclave_owner = self.enclave_owner;
+            let enclave_address = self.enclave_address;
+            let chain_connector = self.chain_connector.clone();
             tokio::spawn(async move {
                 'socket_loop: loop {
-                    let request_chain_ws_provider = match Provider::<Ws>::connect(
-                        request_chain_client_clone.ws_rpc_url.clone(),
-                    )
-                    .await
+                    let request_chain_ws_provider = match chain_connector
+                        .connect_pubsub(&request_chain_client_clone.ws_rpc_url)
+                        .await
                     {
                         Ok(request_chain_ws_provider) => request_chain_ws_provider,
                         Err(err) => {
                             error!(
-                                "Failed to connect to the request chain websocket provider: {}",
+                                "Failed to connect to the request chain websocket provider: {:#}",
                                 err
                             );
                             continue;
@@ -141,7 +214,7 @@ impl ContractsClient {
                     while let Some(log) = request_chain_stream.next().await {
                         let log = confirm_event(
                             log,
-                            &request_chain_client_clone.http_rpc_url,
+                            request_chain_client_clone.http_rpc_client.as_ref(),
                             request_chain_client_clone.confirmation_blocks,
                             request_chain_client_clone.last_seen_block.clone(),
                         )
@@ -151,11 +224,25 @@ impl ContractsClient {
                             continue;
                         }
 
+                        match decode_event::<RequestChainGatewayRegisteredFilter>(&log) {
+                            Ok(gateway_registered_event)
+                                if gateway_registered_event.owner == enclave_owner
+                                    && gateway_registered_event.enclave_address
+                                        == enclave_address => {}
+                            _ => {
+                                error!(
+                                    "Invalid GatewayRegistered event on the Request Chain ID: {:?}: {:?}",
+                                    request_chain_client.chain_id, log
+                                );
+                                continue;
+                            }
+                        }
+
                         let registered_data = RegisteredData {
                             register_type: RegisterType::RequestChain,
                             chain_id: Some(request_chain_client.chain_id),
                         };
-                        tx_clone.send(registered_data).await.unwrap();
+                        let _ = tx_clone.send(registered_data).await;
 
                         info!(
                             "Request Chain ID: {:?} Registered",
@@ -168,11 +255,7 @@ impl ContractsClient {
         }
 
         let mut common_chain_registered = false;
-        let mut req_chain_ids_not_registered: HashSet<u64> = self
-            .request_chain_clients
-            .keys()
-            .cloned()
-            .collect::<HashSet<u64>>();
+        let mut req_chain_ids_not_registered: HashSet<u64> = self.request_chain_ids();
         while let Some(registered_data) = rx.recv().await {
             match registered_data.register_type {
                 RegisterType::CommonChain => {
@@ -187,31 +270,62 @@ impl ContractsClient {
                 // All registration completed on common chain and all request chains
                 // Mark registered in the app state
                 app_state.registered.store(true, Ordering::SeqCst);
-                // Start the ContractsClient service
-                tokio::spawn(async move {
-                    let _ = self.run().await;
-                });
+                persist_sealed_state(&app_state).await;
                 break;
             }
         }
     }
 
+    // Whether the enclave is registered by its owner on the common chain and every request chain
+    pub async fn registered_on_chain(&self) -> Result<bool> {
+        let (common_chain_registered, request_chain_ids_registered) =
+            self.registration_status().await?;
+
+        Ok(common_chain_registered && request_chain_ids_registered == self.request_chain_ids())
+    }
+
+    // Whether the enclave is registered by its owner on the common chain, along with the request
+    // chains it is registered on
+    pub async fn registration_status(&self) -> Result<(bool, HashSet<u64>)> {
+        let gateways_contract = GatewaysContract::new(
+            self.gateways_contract_address,
+            self.common_chain_client.clone(),
+        );
+        let (owner, _, _, _) = gateways_contract.gateways(self.enclave_address).await?;
+        let common_chain_registered = owner == self.enclave_owner;
+
+        let request_chain_clients: Vec<(u64, Arc<RequestChainClient<M>>)> = self
+            .request_chain_clients
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(&chain_id, request_chain_client)| (chain_id, request_chain_client.clone()))
+            .collect();
+
+        let mut request_chain_ids_registered = HashSet::new();
+        for (chain_id, request_chain_client) in request_chain_clients {
+            let relay_contract = request_chain_client.relay_contract.read().unwrap().clone();
+            if relay_contract.gateway_owners(self.enclave_address).await? == self.enclave_owner {
+                request_chain_ids_registered.insert(chain_id);
+            }
+        }
+
+        Ok((common_chain_registered, request_chain_ids_registered))
+    }
+
     pub async fn run(self: Arc<Self>) -> Result<(), Box<dyn Error>> {
         // setup for the listening events on Request Chain and calling Common Chain functions
         let (req_chain_tx, com_chain_rx) = channel::<Job>(100);
         // Start the gateway epoch state service
         {
-            let service_start_time = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+            let service_start_time = self.clock.now();
             let contracts_client_clone = self.clone();
             let tx_clone = req_chain_tx.clone();
-            let common_chain_http_url_clone = self.common_chain_http_url.clone();
-            tokio::spawn(async move {
+            let common_chain_client_clone = self.common_chain_client.clone();
+            self.spawn_service(async move {
                 gateway_epoch_state_service(
                     service_start_time,
-                    common_chain_http_url_clone,
+                    common_chain_client_clone,
                     contracts_client_clone,
                     tx_clone,
                 )
@@ -226,7 +340,7 @@ impl ContractsClient {
             let req_chain_tx_clone = req_chain_tx.clone();
             let job_subscription_tx_clone = job_subscription_tx.clone();
 
-            tokio::spawn(async move {
+            self.spawn_service(async move {
                 process_historic_job_subscriptions(
                     &contracts_client_clone,
                     req_chain_tx_clone,
@@ -238,7 +352,7 @@ impl ContractsClient {
             let contracts_client_clone = self.clone();
             let req_chain_tx_clone = req_chain_tx.clone();
 
-            tokio::spawn(async move {
+            self.spawn_service(async move {
                 let _ = job_subscription_manager(
                     contracts_client_clone,
                     job_subscription_rx,
@@ -246,10 +360,17 @@ impl ContractsClient {
                 )
                 .await;
             });
+
+            let contracts_client_clone = self.clone();
+            let req_chain_tx_clone = req_chain_tx.clone();
+
+            self.spawn_service(async move {
+                subscription_trigger_dispatcher(contracts_client_clone, req_chain_tx_clone).await;
+            });
         }
 
         let self_clone = Arc::clone(&self);
-        tokio::spawn(async move {
+        self.spawn_service(async move {
             let _ = self_clone.txns_to_common_chain(com_chain_rx).await;
         });
         let _ = &self
@@ -259,7 +380,7 @@ impl ContractsClient {
         // setup for the listening events on Common Chain and calling Request Chain functions
         let (com_chain_tx, req_chain_rx) = channel::<ResponseJob>(100);
         let self_clone = Arc::clone(&self);
-        tokio::spawn(async move {
+        self.spawn_service(async move {
             let _ = self_clone.txns_to_request_chain(req_chain_rx).await;
         });
         let _ = &self
@@ -274,28 +395,79 @@ impl ContractsClient {
         job_subscription_tx: Sender<JobSubscriptionChannelType>,
     ) -> Result<()> {
         info!("Initializing Request Chain Clients for all request chains...");
-        let chains_ids = self.request_chain_ids.clone();
-
-        for chain_id in chains_ids {
-            let self_clone = Arc::clone(&self);
-            let req_chain_tx_clone = req_chain_tx.clone();
-            let job_subscription_tx_clone = job_subscription_tx.clone();
+        *self.request_chain_senders.lock().unwrap() = Some(RequestChainSenders {
+            req_chain_tx,
+            job_subscription_tx,
+        });
 
-            // Spawn a new task for each Request Chain Contract
-            tokio::spawn(async move {
-                _ = self_clone
-                    .handle_single_request_chain_events(
-                        req_chain_tx_clone,
-                        chain_id,
-                        job_subscription_tx_clone,
-                    )
-                    .await;
-            });
+        for chain_id in self.request_chain_ids() {
+            self.start_request_chain_listener(chain_id);
         }
 
         Ok(())
     }
 
+    // Spawns the listener of the events of the request chain, replacing the previous one if any
+    pub fn start_request_chain_listener(self: &Arc<Self>, chain_id: u64) {
+        let Some(request_chain_senders) = self.request_chain_senders.lock().unwrap().clone() else {
+            return;
+        };
+
+        let self_clone = Arc::clone(self);
+        let listener = tokio::spawn(async move {
+            self_clone
+                .handle_single_request_chain_events(
+                    request_chain_senders.req_chain_tx,
+                    chain_id,
+                    request_chain_senders.job_subscription_tx,
+                )
+                .await;
+        });
+
+        if let Some(previous_listener) = self
+            .request_chain_listeners
+            .lock()
+            .unwrap()
+            .insert(chain_id, listener.abort_handle())
+        {
+            previous_listener.abort();
+        }
+    }
+
+    pub fn stop_request_chain_listener(&self, chain_id: u64) {
+        if let Some(listener) = self
+            .request_chain_listeners
+            .lock()
+            .unwrap()
+            .remove(&chain_id)
+        {
+            listener.abort();
+        }
+    }
+
+    // Spawns a long running service of the client, aborted when the client is stopped
+    pub fn spawn_service<F>(&self, service: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let task = tokio::spawn(service);
+        self.service_tasks.lock().unwrap().push(task.abort_handle());
+    }
+
+    /// Stops the services of the client along with the listeners of every chain. The tasks
+    /// spawned for the jobs in progress wind down on their own, their transactions are no longer
+    /// picked up.
+    pub fn stop(&self) {
+        for task in self.service_tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+        for (_, listener) in self.request_chain_listeners.lock().unwrap().drain() {
+            listener.abort();
+        }
+        // Drop the senders so that the queues forwarding the transactions close
+        *self.request_chain_senders.lock().unwrap() = None;
+    }
+
     async fn handle_single_request_chain_events(
         self: &Arc<Self>,
         req_chain_tx: Sender<Job>,
@@ -303,34 +475,48 @@ impl ContractsClient {
         job_subscription_tx: Sender<JobSubscriptionChannelType>,
     ) {
         loop {
-            let req_chain_ws_client =
-                match Provider::<Ws>::connect(&self.request_chain_clients[&chain_id].ws_rpc_url)
-                    .await
-                {
-                    Ok(req_chain_ws_client) => req_chain_ws_client,
-                    Err(err) => {
-                        error!(
-                            "Failed to connect to the request chain websocket provider: {}",
-                            err
-                        );
-                        continue;
-                    }
-                };
+            let Some(request_chain_client) = self.request_chain_client(chain_id) else {
+                info!("Request Chain ID: {:?}, no longer served", chain_id);
+                return;
+            };
+
+            let req_chain_ws_client = match self
+                .chain_connector
+                .connect_pubsub(&request_chain_client.ws_rpc_url)
+                .await
+            {
+                Ok(req_chain_ws_client) => req_chain_ws_client,
+                Err(err) => {
+                    error!(
+                        "Failed to connect to the request chain websocket provider: {:#}",
+                        err
+                    );
+                    continue;
+                }
+            };
 
             let mut stream = self
-                .req_chain_jobs(&req_chain_ws_client, &self.request_chain_clients[&chain_id])
+                .req_chain_jobs(&req_chain_ws_client, &request_chain_client)
                 .await
                 .unwrap();
             while let Some(log) = stream.next().await {
-                let ref topics = log.topics;
                 if log.removed.unwrap_or(true) {
                     continue;
                 }
 
-                if topics[0] == keccak256(REQUEST_CHAIN_JOB_RELAYED_EVENT).into() {
+                let Ok(topic0) = topic(&log, 0) else {
+                    error!(
+                        "Request Chain ID: {:?}, Log without topics: {:?}",
+                        chain_id, log
+                    );
+                    continue;
+                };
+
+                if topic0 == RequestChainJobRelayedFilter::signature() {
                     info!(
                         "Request Chain ID: {:?}, JobPlace jobID: {:?}",
-                        chain_id, log.topics[1]
+                        chain_id,
+                        topic(&log, 1).ok()
                     );
 
                     let self_clone = Arc::clone(&self);
@@ -345,22 +531,39 @@ impl ContractsClient {
                                 .await;
                         }
                     });
-                } else if topics[0] == keccak256(REQUEST_CHAIN_JOB_CANCELLED_EVENT).into() {
+                } else if topic0 == JobCancelledFilter::signature() {
+                    let job_cancelled_event = match decode_event::<JobCancelledFilter>(&log) {
+                        Ok(job_cancelled_event) => job_cancelled_event,
+                        Err(err) => {
+                            error!("Error while decoding JobCancelled event: {}", err);
+                            continue;
+                        }
+                    };
+
                     info!(
                         "Request Chain ID: {:?}, JobCancelled jobID: {:?}",
-                        chain_id, log.topics[1]
+                        chain_id, job_cancelled_event.job_id
                     );
 
                     let self_clone = Arc::clone(&self);
                     tokio::spawn(async move {
                         self_clone
-                            .cancel_job_with_job_id(log.topics[1].into_uint())
+                            .cancel_job_with_job_id(JobKey::once(
+                                chain_id,
+                                job_cancelled_event.job_id,
+                            ))
                             .await;
                     });
-                } else if topics[0]
-                    == keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT).into()
-                {
-                    let subscription_id: U256 = log.topics[1].into_uint();
+                } else if topic0 == JobSubscriptionStartedFilter::signature() {
+                    let subscription_id = match decode_event::<JobSubscriptionStartedFilter>(&log) {
+                        Ok(job_subscription_started_event) => {
+                            job_subscription_started_event.job_subs_id
+                        }
+                        Err(err) => {
+                            error!("Error while decoding JobSubscriptionStarted event: {}", err);
+                            continue;
+                        }
+                    };
 
                     info!(
                         "Request Chain ID: {:?}, JobSubscriptionStarted jobID: {:?}",
@@ -380,41 +583,90 @@ impl ContractsClient {
                             false,
                         );
 
-                        if res.is_ok() {
+                        if let Ok(Some(subscription_key)) = res {
                             job_subscription_tx_clone
                                 .send(JobSubscriptionChannelType {
                                     subscription_action: JobSubscriptionAction::Add,
-                                    subscription_id,
+                                    subscription_key,
                                 })
                                 .await
                                 .unwrap();
                         }
                     });
-                } else if topics[0]
-                    == keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT).into()
-                {
+                } else if topic0 == JobSubscriptionJobParamsUpdatedFilter::signature() {
                     info!(
                         "Request Chain ID: {:?}, JobSubscriptionJobParamsUpdated jobID: {:?}",
-                        chain_id, log.topics[1]
+                        chain_id,
+                        topic(&log, 1).ok()
                     );
 
                     let self_clone = Arc::clone(&self);
 
                     tokio::spawn(async move {
-                        let _ = update_subscription_job_params(&self_clone, log);
+                        let _ = update_subscription_job_params(&self_clone, log, chain_id);
                     });
-                } else if topics[0]
-                    == keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT)
-                        .into()
-                {
+                } else if topic0 == JobSubscriptionTerminationParamsUpdatedFilter::signature() {
                     info!(
                         "Request Chain ID: {:?}, JobSubscriptionTerminationParamsUpdated jobID: {:?}",
-                        chain_id, log.topics[1]
+                        chain_id,
+                        topic(&log, 1).ok()
+                    );
+
+                    let self_clone = Arc::clone(&self);
+                    tokio::spawn(async move {
+                        let _ =
+                            update_subscription_job_termination_params(&self_clone, log, chain_id);
+                    });
+                } else if topic0 == JobSubscriptionTerminatedFilter::signature() {
+                    info!(
+                        "Request Chain ID: {:?}, JobSubscriptionTerminated jobID: {:?}",
+                        chain_id,
+                        topic(&log, 1).ok()
                     );
 
                     let self_clone = Arc::clone(&self);
+                    let job_subscription_tx_clone = job_subscription_tx.clone();
                     tokio::spawn(async move {
-                        let _ = update_subscription_job_termination_params(&self_clone, log);
+                        let Ok(subscription_key) =
+                            remove_subscription_job(&self_clone, log, chain_id)
+                        else {
+                            return;
+                        };
+                        job_subscription_tx_clone
+                            .send(JobSubscriptionChannelType {
+                                subscription_action: JobSubscriptionAction::Remove,
+                                subscription_key,
+                            })
+                            .await
+                            .unwrap();
+                    });
+                } else if topic0 == JobSubscriptionFundsDepositedFilter::signature()
+                    || topic0 == JobSubscriptionFundsWithdrawnFilter::signature()
+                {
+                    info!(
+                        "Request Chain ID: {:?}, JobSubscription funds updated jobID: {:?}",
+                        chain_id,
+                        topic(&log, 1).ok()
+                    );
+
+                    let self_clone = Arc::clone(&self);
+                    let job_subscription_tx_clone = job_subscription_tx.clone();
+                    tokio::spawn(async move {
+                        let res = if topic0 == JobSubscriptionFundsDepositedFilter::signature() {
+                            update_subscription_job_funds_deposited(&self_clone, log, chain_id)
+                        } else {
+                            update_subscription_job_funds_withdrawn(&self_clone, log, chain_id)
+                        };
+                        let Ok(subscription_key) = res else {
+                            return;
+                        };
+                        job_subscription_tx_clone
+                            .send(JobSubscriptionChannelType {
+                                subscription_action: JobSubscriptionAction::Update,
+                                subscription_key,
+                            })
+                            .await
+                            .unwrap();
                     });
                 } else {
                     error!("Request Chain ID: {:?}, Unknown event: {:?}", chain_id, log);
@@ -429,43 +681,42 @@ impl ContractsClient {
         sequence_number: u8,
         request_chain_id: u64,
     ) -> Result<Job, ServerlessError> {
-        let types = vec![
-            ParamType::FixedBytes(32),
-            ParamType::Bytes,
-            ParamType::Uint(256),
-            ParamType::Uint(256),
-            ParamType::Uint(256),
-            ParamType::Uint(256),
-            ParamType::Address,
-            ParamType::Address,
-            ParamType::Uint(256),
-            ParamType::Uint(256),
-        ];
-
-        let decoded = decode(&types, &log.data.0);
-        let decoded = match decoded {
-            Ok(decoded) => decoded,
+        let job_relayed_event = match decode_event::<RequestChainJobRelayedFilter>(&log) {
+            Ok(job_relayed_event) => job_relayed_event,
             Err(err) => {
                 error!("Error while decoding event: {}", err);
-                return Err(ServerlessError::LogDecodeFailure);
+                return Err(err);
             }
         };
 
-        let job_id = log.topics[1].into_uint();
+        let job_key = JobKey::once(request_chain_id, job_relayed_event.job_id);
+        if let Err(err) = job_key.validate() {
+            error!("{}", err);
+            return Err(err);
+        }
+
+        // The job is scheduled on u64 timestamps
+        if let Err(err) = uint_to_u64(job_relayed_event.start_time)
+            .and_then(|_| uint_to_u64(job_relayed_event.user_timeout))
+        {
+            error!("Job times out of range in JobRelayed event: {:?}", log);
+            return Err(err);
+        }
 
         Ok(Job {
-            job_id,
+            job_id: job_relayed_event.job_id,
+            instance_index: 0,
             request_chain_id,
-            tx_hash: decoded[0].clone().into_fixed_bytes().unwrap(),
-            code_input: decoded[1].clone().into_bytes().unwrap().into(),
-            user_timeout: decoded[2].clone().into_uint().unwrap(),
-            starttime: decoded[8].clone().into_uint().unwrap(),
+            tx_hash: job_relayed_event.codehash.to_vec(),
+            code_input: job_relayed_event.code_inputs,
+            user_timeout: job_relayed_event.user_timeout,
+            starttime: job_relayed_event.start_time,
             job_owner: log.address,
             job_type: GatewayJobType::JobRelay,
             sequence_number,
             gateway_address: None,
             job_mode: JobMode::Once,
-            env: log.topics[2].into_uint().as_u64() as u8,
+            env: job_relayed_event.env,
         })
     }
 
@@ -473,7 +724,7 @@ impl ContractsClient {
         let gateway_address = self
             .select_gateway_for_job_id(
                 job.clone(),
-                job.starttime.as_u64(), // TODO: Update seed
+                job.starttime.low_u64(), // TODO: Update seed
                 job.sequence_number,
             )
             .await;
@@ -487,29 +738,45 @@ impl ContractsClient {
                     return;
                 }
 
+                record_subscription_instance(
+                    &self,
+                    &job.key(),
+                    SubscriptionInstanceStatus::Assigned {
+                        gateway: gateway_address,
+                    },
+                );
+
                 if gateway_address == self.enclave_address {
                     // scope for the write lock
                     {
                         self.active_jobs
                             .write()
                             .unwrap()
-                            .insert(job.job_id, job.clone());
+                            .insert(job.key(), job.clone());
                     }
-                    tx.send(job).await.unwrap();
+                    // the client may have been stopped in the meantime
+                    let _ = tx.send(job).await;
                 } else {
                     // scope for the write lock
                     {
                         self.current_jobs
                             .write()
                             .unwrap()
-                            .insert(job.job_id, job.clone());
+                            .insert(job.key(), job.clone());
                     }
                     let self_clone = Arc::clone(&self);
                     tokio::spawn(async move {
-                        let common_chain_http_provider =
-                            HttpProvider::new(self_clone.common_chain_http_url.clone());
+                        let common_chain_http_provider = self_clone.common_chain_client.clone();
+                        let gateway_jobs_contract =
+                            self_clone.gateway_jobs_contract.read().unwrap().clone();
                         self_clone
-                            .job_relayed_slash_timer(job, None, tx, &common_chain_http_provider)
+                            .job_relayed_slash_timer(
+                                job,
+                                None,
+                                tx,
+                                &common_chain_http_provider,
+                                &gateway_jobs_contract,
+                            )
                             .await;
                     });
                 }
@@ -519,69 +786,70 @@ impl ContractsClient {
                     "Job Id: {}, Error while selecting gateway: {}",
                     job.job_id, err
                 );
+                record_subscription_instance(
+                    &self,
+                    &job.key(),
+                    SubscriptionInstanceStatus::Skipped {
+                        reason: err.to_string(),
+                    },
+                );
             }
         }
     }
 
-    async fn job_relayed_slash_timer<'a, P: HttpProviderLogs>(
+    async fn job_relayed_slash_timer<'a, P: HttpProviderLogs, V: GatewayJobsView>(
         self: Arc<Self>,
         job: Job,
         mut job_timeout: Option<u64>,
         tx: Sender<Job>,
         common_chain_http_provider: &'a P,
+        gateway_jobs_view: &'a V,
     ) {
         if job_timeout.is_none() {
             job_timeout = Some(REQUEST_RELAY_TIMEOUT);
         }
         time::sleep(Duration::from_secs(job_timeout.unwrap())).await;
 
-        // TODO: Issue with event logs -
-        // get_logs might not provide the latest logs for the latest block
-        // SOLUTION 1 - Wait for the next block.
-        //          Problem: Extra time spent here waiting.
+        // The JobRelayed events received on the common chain subscription resolve the job
+        // by removing it from the current jobs before the deadline.
+        if !self.is_job_pending_relay(&job) {
+            info!(
+                "Job ID: {:?}, JobRelayed event received before the deadline",
+                job.job_id
+            );
+            return;
+        }
 
-        let logs = self
-            .gateways_job_relayed_logs(job.clone(), common_chain_http_provider)
+        // Final check against the contract state at the deadline
+        let job_relayed = match gateway_jobs_view
+            .relay_job_state(job.key().common_chain_job_id())
             .await
-            .context("Failed to get logs")
-            .unwrap();
-
-        for log in logs {
-            let ref topics = log.topics;
-            if topics[0] == keccak256(COMMON_CHAIN_JOB_RELAYED_EVENT).into() {
-                let decoded = decode(
-                    &vec![
-                        ParamType::Uint(256),
-                        ParamType::Uint(8),
-                        ParamType::Address,
-                        ParamType::Address,
-                    ],
-                    &log.data.0,
-                )
-                .unwrap();
+        {
+            Ok(relay_job_state) => is_job_relayed_by(
+                &job,
+                relay_job_state.gateway,
+                relay_job_state.env,
+                relay_job_state.job_owner,
+            ),
+            Err(err) => {
+                error!(
+                    "Job ID: {:?}, Failed to fetch the relay job state, checking the logs: {}",
+                    job.job_id, err
+                );
+                self.job_relayed_in_logs(&job, common_chain_http_provider)
+                    .await
+            }
+        };
 
-                let job_id = log.topics[1].into_uint();
-                let env = log.topics[1].into_uint().as_u64() as u8;
-                let job_owner = decoded[2].clone().into_address().unwrap();
-                let gateway_operator = decoded[3].clone().into_address().unwrap();
+        if job_relayed {
+            info!("Job ID: {:?}, Job relayed by the gateway", job.job_id);
+            self.remove_current_job(&job);
+            return;
+        }
 
-                if job_id == job.job_id
-                    && env == job.env
-                    && job_owner == job.job_owner
-                    && gateway_operator != Address::zero()
-                    && gateway_operator == job.gateway_address.unwrap()
-                {
-                    info!(
-                        "Job ID: {:?}, JobRelayed event triggered for job ID: {:?}",
-                        job.job_id, job_id
-                    );
-                    // scope for the write lock
-                    {
-                        let _ = self.current_jobs.write().unwrap().remove(&job.job_id);
-                    }
-                    return;
-                }
-            }
+        // The job may have been resolved while the final check was in flight
+        if !self.is_job_pending_relay(&job) {
+            return;
         }
 
         info!("Job ID: {:?}, JobRelayed event not triggered", job.job_id);
@@ -589,7 +857,58 @@ impl ContractsClient {
         // slash the previous gateway
         let mut job_clone = job.clone();
         job_clone.job_type = GatewayJobType::SlashGatewayJob;
-        tx.send(job_clone).await.unwrap();
+        let _ = tx.send(job_clone).await;
+    }
+
+    async fn job_relayed_in_logs<'a, P: HttpProviderLogs>(
+        self: &Arc<Self>,
+        job: &Job,
+        common_chain_http_provider: &'a P,
+    ) -> bool {
+        let logs = match self
+            .gateways_job_relayed_logs(job.clone(), common_chain_http_provider)
+            .await
+        {
+            Ok(logs) => logs,
+            Err(err) => {
+                error!(
+                    "Job ID: {:?}, Failed to get JobRelayed logs: {:?}",
+                    job.job_id, err
+                );
+                return false;
+            }
+        };
+
+        logs.iter().any(|log| {
+            decode_event::<CommonChainJobRelayedFilter>(log).is_ok_and(|job_relayed_event| {
+                job_relayed_event.job_id == job.key().common_chain_job_id()
+                    && is_job_relayed_by(
+                        job,
+                        job_relayed_event.gateway,
+                        job_relayed_event.env,
+                        job_relayed_event.job_owner,
+                    )
+            })
+        })
+    }
+
+    fn is_job_pending_relay(self: &Arc<Self>, job: &Job) -> bool {
+        let current_jobs = self.current_jobs.read().unwrap();
+        current_jobs.get(&job.key()).is_some_and(|current_job| {
+            current_job.sequence_number == job.sequence_number
+                && current_job.gateway_address == job.gateway_address
+        })
+    }
+
+    fn remove_current_job(self: &Arc<Self>, job: &Job) {
+        let mut current_jobs = self.current_jobs.write().unwrap();
+        let is_same_relay = current_jobs.get(&job.key()).is_some_and(|current_job| {
+            current_job.sequence_number == job.sequence_number
+                && current_job.gateway_address == job.gateway_address
+        });
+        if is_same_relay {
+            current_jobs.remove(&job.key());
+        }
     }
 
     async fn select_gateway_for_job_id(
@@ -598,16 +917,16 @@ impl ContractsClient {
         seed: u64,
         skips: u8,
     ) -> Result<Address, ServerlessError> {
-        let job_cycle =
-            (job.starttime.as_u64() - self.epoch - self.offset_for_epoch) / self.time_interval;
+        let job_starttime = uint_to_u64(job.starttime)?;
+        let job_cycle = job_starttime
+            .checked_sub(self.epoch + self.offset_for_epoch)
+            .ok_or(ServerlessError::JobStartedBeforeEpoch(job_starttime))?
+            / self.time_interval;
 
         let all_gateways_data: Vec<GatewayData>;
 
         {
-            let ts = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+            let ts = self.clock.now();
             let current_cycle = (ts - self.epoch - self.offset_for_epoch) / self.time_interval;
             if current_cycle >= GATEWAY_BLOCK_STATES_TO_MAINTAIN + job_cycle {
                 return Err(ServerlessError::JobOlderThanMaintainedBlockStates);
@@ -628,54 +947,13 @@ impl ContractsClient {
             }
         }
 
-        if all_gateways_data.is_empty() {
-            return Err(ServerlessError::NoGatewaysRegisteredInCycle(job_cycle));
-        }
-
-        // create a weighted probability distribution for gateways based on stake amount
-        // For example, if there are 3 gateways with stake amounts 100, 200, 300
-        // then the distribution array will be [100, 300, 600]
-        let mut stake_distribution: Vec<u128> = vec![];
-        let mut total_stake: u128 = 0;
-        let mut gateway_addresses_of_req_chain: Vec<H160> = vec![];
-
-        for gateway_data in all_gateways_data.iter() {
-            if gateway_data.req_chain_ids.contains(&job.request_chain_id)
-                && gateway_data.stake_amount > *MIN_GATEWAY_STAKE
-                && gateway_data.draining == false
-            {
-                gateway_addresses_of_req_chain.push(gateway_data.address.clone());
-                total_stake +=
-                    (gateway_data.stake_amount / *GATEWAY_STAKE_ADJUSTMENT_FACTOR).as_u128();
-                stake_distribution.push(total_stake);
-            }
-        }
-
-        if total_stake == 0 {
-            return Err(ServerlessError::NoValidGatewaysForChain(
-                job_cycle,
-                job.request_chain_id,
-            ));
-        }
-
-        // random number between 1 to total_stake from the seed for the weighted random selection.
-        // use this seed in std_rng to generate a random number between 1 to total_stake
-        // skipping skips numbers from the random number generated
-        // skips comes from sequence_number of the job which starts from 1. That's why its (skips-1)
-        let mut rng = StdRng::seed_from_u64(seed);
-        for _ in 0..skips - 1 {
-            let _ = rng.gen_range(1..=total_stake);
-        }
-        let random_number = rng.gen_range(1..=total_stake);
-
-        // select the gateway based on the random number
-        let res = stake_distribution.binary_search_by(|&probe| probe.cmp(&random_number));
-
-        let index = match res {
-            Ok(index) => index,
-            Err(index) => index,
-        };
-        let selected_gateway_address = gateway_addresses_of_req_chain[index];
+        let selected_gateway_address = select_gateway(
+            &all_gateways_data,
+            job.request_chain_id,
+            job_cycle,
+            seed,
+            skips,
+        )?;
 
         info!(
             "Job ID: {:?}, Gateway Address: {:?}",
@@ -685,26 +963,40 @@ impl ContractsClient {
         Ok(selected_gateway_address)
     }
 
-    async fn cancel_job_with_job_id(self: Arc<Self>, job_id: U256) {
+    async fn cancel_job_with_job_id(self: Arc<Self>, job_key: JobKey) {
         info!(
-            "Remove the Job ID: {:} from the active and current jobs list",
-            job_id
+            "Remove the Job: {:?} from the active and current jobs list",
+            job_key
         );
 
-        let job: Option<Job> = self.active_jobs.write().unwrap().remove(&job_id);
+        let job: Option<Job> = self.active_jobs.write().unwrap().remove(&job_key);
 
         if job.is_none() {
-            let _job: Option<Job> = self.current_jobs.write().unwrap().remove(&job_id);
+            let _job: Option<Job> = self.current_jobs.write().unwrap().remove(&job_key);
             if _job.is_some() {
-                info!("Job ID: {:?} removed from current jobs", job_id);
+                info!("Job: {:?} removed from current jobs", job_key);
             }
         } else {
-            info!("Job ID: {:?} removed from active jobs", job_id);
+            info!("Job: {:?} removed from active jobs", job_key);
         }
     }
 
-    async fn txns_to_common_chain(self: Arc<Self>, mut rx: Receiver<Job>) {
-        while let Some(job) = rx.recv().await {
+    async fn txns_to_common_chain(self: Arc<Self>, rx: Receiver<Job>) {
+        tokio::spawn(forward_to_deadline_queue(
+            rx,
+            self.common_chain_txn_queue.clone(),
+            |job: &Job| job.relay_txn_deadline(),
+        ));
+
+        loop {
+            let job = match self.common_chain_txn_queue.pop(self.clock.as_ref()).await {
+                DequeuedItem::Due(job) => job,
+                DequeuedItem::Expired(job) => {
+                    self.drop_expired_relay_txn(job);
+                    continue;
+                }
+            };
+
             match job.job_type {
                 GatewayJobType::JobRelay => {
                     self.relay_job_txn(job).await;
@@ -719,11 +1011,31 @@ impl ContractsClient {
         }
     }
 
+    // Gives up on the relay of a job past its deadline, the slash timer of the relay finds it
+    // resolved instead of reassigning it
+    fn drop_expired_relay_txn(self: &Arc<Self>, job: Job) {
+        error!(
+            "Job ID: {:?}, Dropping the {:?} transaction past its deadline",
+            job.job_id, job.job_type
+        );
+        self.remove_current_job(&job);
+        record_subscription_instance(
+            self,
+            &job.key(),
+            SubscriptionInstanceStatus::Skipped {
+                reason: "Relay deadline passed".to_owned(),
+            },
+        );
+    }
+
     async fn relay_job_txn(self: &Arc<Self>, job: Job) {
         info!("Creating a transaction for relayJob");
+        // Hold off the rotation of the gas wallet until the transaction is settled
+        let _gas_wallet_permit = self.gas_wallet_lock.read().await;
         let (signature, sign_timestamp) = sign_relay_job_request(
-            &self.enclave_signer_key,
-            job.job_id,
+            self.enclave_signer.as_ref(),
+            &self.eip712_domains,
+            job.key(),
             &job.tx_hash,
             &job.code_input,
             job.user_timeout,
@@ -742,7 +1054,7 @@ impl ContractsClient {
 
         let txn = self.gateway_jobs_contract.read().unwrap().relay_job(
             signature,
-            job.job_id,
+            job.key().common_chain_job_id(),
             tx_hash,
             job.code_input,
             job.user_timeout,
@@ -790,9 +1102,12 @@ impl ContractsClient {
 
     async fn reassign_gateway_relay_txn(self: &Arc<Self>, job: Job) {
         info!("Creating a transaction for reassignGatewayRelay");
+        // Hold off the rotation of the gas wallet until the transaction is settled
+        let _gas_wallet_permit = self.gas_wallet_lock.read().await;
         let (signature, sign_timestamp) = sign_reassign_gateway_relay_request(
-            &self.enclave_signer_key,
-            job.job_id,
+            self.enclave_signer.as_ref(),
+            &self.eip712_domains,
+            job.key(),
             job.gateway_address.as_ref().unwrap(),
             &job.job_owner,
             job.sequence_number,
@@ -811,7 +1126,7 @@ impl ContractsClient {
             .unwrap()
             .reassign_gateway_relay(
                 job.gateway_address.unwrap(),
-                job.job_id,
+                job.key().common_chain_job_id(),
                 signature,
                 job.sequence_number,
                 job.starttime,
@@ -861,29 +1176,40 @@ impl ContractsClient {
         req_chain_tx: Sender<Job>,
     ) {
         loop {
-            let common_chain_ws_provider =
-                match Provider::<Ws>::connect(&self.common_chain_ws_url).await {
-                    Ok(common_chain_ws_provider) => common_chain_ws_provider,
-                    Err(err) => {
-                        error!(
-                            "Failed to connect to the common chain websocket provider: {}",
-                            err
-                        );
-                        continue;
-                    }
-                };
+            let common_chain_ws_provider = match self
+                .chain_connector
+                .connect_pubsub(&self.common_chain_ws_url)
+                .await
+            {
+                Ok(common_chain_ws_provider) => common_chain_ws_provider,
+                Err(err) => {
+                    error!(
+                        "Failed to connect to the common chain websocket provider: {:#}",
+                        err
+                    );
+                    continue;
+                }
+            };
             let mut stream = self
                 .common_chain_jobs(&common_chain_ws_provider)
                 .await
                 .unwrap();
 
             while let Some(log) = stream.next().await {
-                let ref topics = log.topics;
+                let Ok(topic0) = topic(&log, 0) else {
+                    error!("Log without topics: {:?}", log);
+                    continue;
+                };
 
-                if topics[0] == keccak256(COMMON_CHAIN_JOB_RESPONDED_EVENT).into() {
+                if topic0 == CommonChainJobRelayedFilter::signature() {
+                    let self_clone = Arc::clone(&self);
+                    tokio::spawn(async move {
+                        self_clone.job_relayed_event_handler(log).await;
+                    });
+                } else if topic0 == CommonChainJobRespondedFilter::signature() {
                     info!(
                         "JobResponded event triggered for Job ID: {:?}",
-                        log.topics[1]
+                        topic(&log, 1).ok()
                     );
                     let self_clone = Arc::clone(&self);
                     let com_chain_tx = com_chain_tx.clone();
@@ -904,15 +1230,14 @@ impl ContractsClient {
                             }
                         }
                     });
-                } else if topics[0] == keccak256(COMMON_CHAIN_JOB_RESOURCE_UNAVAILABLE_EVENT).into()
-                {
+                } else if topic0 == JobResourceUnavailableFilter::signature() {
                     info!("JobResourceUnavailable event triggered");
                     let self_clone = Arc::clone(&self);
                     tokio::spawn(async move {
                         self_clone.job_resource_unavailable_handler(log).await;
                     });
-                } else if topics[0] == keccak256(COMMON_CHAIN_GATEWAY_REASSIGNED_EVENT).into() {
-                    info!("GatewayReassigned for Job ID: {:?}", log.topics[1]);
+                } else if topic0 == GatewayReassignedFilter::signature() {
+                    info!("GatewayReassigned for Job ID: {:?}", topic(&log, 1).ok());
                     let self_clone = Arc::clone(&self);
                     let req_chain_tx = req_chain_tx.clone();
                     tokio::spawn(async move {
@@ -931,36 +1256,36 @@ impl ContractsClient {
         self: &Arc<Self>,
         log: Log,
     ) -> Result<ResponseJob, ServerlessError> {
-        let job_id = log.topics[1].into_uint();
+        let job_responded_event = match decode_event::<CommonChainJobRespondedFilter>(&log) {
+            Ok(job_responded_event) => job_responded_event,
+            Err(err) => {
+                error!("Error while decoding event: {}", err);
+                return Err(err);
+            }
+        };
+        let job_key = JobKey::from_common_chain_job_id(job_responded_event.job_id)
+            .map_err(|_| ServerlessError::JobDoesNotBelongToEnclave)?;
+        record_subscription_instance(self, &job_key, SubscriptionInstanceStatus::Responded);
 
         // Check if job belongs to the enclave
         let active_jobs = self.active_jobs.read().unwrap();
-        let job = active_jobs.get(&job_id);
+        let job = active_jobs.get(&job_key);
         if job.is_none() {
             return Err(ServerlessError::JobDoesNotBelongToEnclave);
         }
 
         let job = job.unwrap();
 
-        let types = vec![ParamType::Bytes, ParamType::Uint(256), ParamType::Uint(8)];
-
-        let decoded = decode(&types, &log.data.0);
-        let decoded = match decoded {
-            Ok(decoded) => decoded,
-            Err(err) => {
-                error!("Error while decoding event: {}", err);
-                return Err(ServerlessError::LogDecodeFailure);
-            }
-        };
         let request_chain_id = job.request_chain_id;
         let job_mode = job.job_mode;
 
         Ok(ResponseJob {
-            job_id,
+            job_id: job.job_id,
+            instance_index: job.instance_index,
             request_chain_id,
-            output: decoded[0].clone().into_bytes().unwrap().into(),
-            total_time: decoded[1].clone().into_uint().unwrap(),
-            error_code: decoded[2].clone().into_uint().unwrap().low_u64() as u8,
+            output: job_responded_event.output,
+            total_time: job_responded_event.total_time,
+            error_code: job_responded_event.error_code,
             job_type: GatewayJobType::JobResponded,
             gateway_address: None,
             job_mode,
@@ -980,7 +1305,7 @@ impl ContractsClient {
                 .active_jobs
                 .read()
                 .unwrap()
-                .get(&response_job.job_id)
+                .get(&response_job.key())
                 .cloned();
         }
         if job.is_some() {
@@ -1015,7 +1340,7 @@ impl ContractsClient {
             //     response_job.gateway_address = Some(gateway_address);
             // }
             // if response_job.gateway_address.unwrap() == self.enclave_address {
-            tx.send(response_job).await.unwrap();
+            let _ = tx.send(response_job).await;
             // } else {
             //     self.job_responded_slash_timer(response_job.clone(), tx.clone())
             //         .await
@@ -1028,10 +1353,11 @@ impl ContractsClient {
         // The retry number check is to make sure we are removing the correct job from the active jobs list
         // In a case where this txn took longer than the REQUEST_RELAY_TIMEOUT, the job might have been retried
         // and the active_jobs list might have the same job_id with a different retry number.
-        if active_jobs.contains_key(&job.job_id)
-            && active_jobs[&job.job_id].sequence_number == job.sequence_number
+        let job_key = job.key();
+        if active_jobs.contains_key(&job_key)
+            && active_jobs[&job_key].sequence_number == job.sequence_number
         {
-            active_jobs.remove(&job.job_id);
+            active_jobs.remove(&job_key);
         }
     }
 
@@ -1100,26 +1426,87 @@ impl ContractsClient {
     //     Ok(())
     // }
 
+    async fn job_relayed_event_handler(self: Arc<Self>, log: Log) {
+        let job_relayed_event = match decode_event::<CommonChainJobRelayedFilter>(&log) {
+            Ok(job_relayed_event) => job_relayed_event,
+            Err(err) => {
+                error!("Error while decoding JobRelayed event: {}", err);
+                return;
+            }
+        };
+
+        // Not relayed by the gateways
+        let Ok(job_key) = JobKey::from_common_chain_job_id(job_relayed_event.job_id) else {
+            return;
+        };
+        record_subscription_instance(
+            &self,
+            &job_key,
+            SubscriptionInstanceStatus::Relayed {
+                gateway: job_relayed_event.gateway,
+            },
+        );
+
+        let job: Option<Job>;
+        // scope for the read lock
+        {
+            job = self.current_jobs.read().unwrap().get(&job_key).cloned();
+        }
+        let Some(job) = job else {
+            return;
+        };
+
+        if is_job_relayed_by(
+            &job,
+            job_relayed_event.gateway,
+            job_relayed_event.env,
+            job_relayed_event.job_owner,
+        ) {
+            info!(
+                "Job ID: {:?}, JobRelayed event triggered by the selected gateway",
+                job.job_id
+            );
+            self.remove_current_job(&job);
+        }
+    }
+
     async fn job_resource_unavailable_handler(self: Arc<Self>, log: Log) {
-        let job_id = log.topics[1].into_uint();
+        let job_resource_unavailable_event =
+            match decode_event::<JobResourceUnavailableFilter>(&log) {
+                Ok(job_resource_unavailable_event) => job_resource_unavailable_event,
+                Err(err) => {
+                    error!("Error while decoding JobResourceUnavailable event: {}", err);
+                    return;
+                }
+            };
 
-        self.cancel_job_with_job_id(job_id).await;
+        let Ok(job_key) = JobKey::from_common_chain_job_id(job_resource_unavailable_event.job_id)
+        else {
+            return;
+        };
+        self.cancel_job_with_job_id(job_key).await;
     }
 
     async fn gateway_reassigned_handler(self: Arc<Self>, log: Log, req_chain_tx: Sender<Job>) {
-        let job_id = log.topics[1].into_uint();
-
-        let types = vec![ParamType::Address, ParamType::Address, ParamType::Uint(8)];
-        let decoded = decode(&types, &log.data.0).unwrap();
+        let gateway_reassigned_event = match decode_event::<GatewayReassignedFilter>(&log) {
+            Ok(gateway_reassigned_event) => gateway_reassigned_event,
+            Err(err) => {
+                error!("Error while decoding GatewayReassigned event: {}", err);
+                return;
+            }
+        };
 
-        let old_gateway = decoded[0].clone().into_address().unwrap();
-        let sequence_number = decoded[2].clone().into_uint().unwrap().low_u64() as u8;
+        let Ok(job_key) = JobKey::from_common_chain_job_id(gateway_reassigned_event.job_id) else {
+            return;
+        };
+        let old_gateway = gateway_reassigned_event.prev_gateway;
+        let sequence_number = gateway_reassigned_event.sequence_id;
 
         let mut job: Job;
 
         if old_gateway == self.enclave_address {
             let active_jobs_guard = self.active_jobs.read().unwrap();
-            let active_job = active_jobs_guard.get(&job_id);
+            let active_job = active_jobs_guard.get(&job_key);
             if active_job.is_some() {
                 job = active_job.unwrap().clone();
                 drop(active_jobs_guard);
@@ -1128,7 +1515,7 @@ impl ContractsClient {
             }
         } else {
             let current_jobs_guard = self.current_jobs.read().unwrap();
-            let current_job = current_jobs_guard.get(&job_id);
+            let current_job = current_jobs_guard.get(&job_key);
             if current_job.is_some() {
                 job = current_job.unwrap().clone();
                 drop(current_jobs_guard);
@@ -1141,11 +1528,18 @@ impl ContractsClient {
             return;
         }
 
-        self.clone().cancel_job_with_job_id(job_id).await;
+        self.clone().cancel_job_with_job_id(job_key).await;
 
         job.sequence_number += 1;
         if job.sequence_number > MAX_GATEWAY_RETRIES {
             info!("Job ID: {:?}, Max retries reached", job.job_id);
+            record_subscription_instance(
+                &self,
+                &job.key(),
+                SubscriptionInstanceStatus::Skipped {
+                    reason: "Max gateway retries reached".to_owned(),
+                },
+            );
             return;
         }
         job.gateway_address = None;
@@ -1156,13 +1550,33 @@ impl ContractsClient {
         });
     }
 
-    async fn txns_to_request_chain(self: Arc<Self>, mut rx: Receiver<ResponseJob>) -> Result<()> {
-        while let Some(response_job) = rx.recv().await {
+    async fn txns_to_request_chain(self: Arc<Self>, rx: Receiver<ResponseJob>) -> Result<()> {
+        let self_clone = self.clone();
+        tokio::spawn(forward_to_deadline_queue(
+            rx,
+            self.request_chain_txn_queue.clone(),
+            move |response_job: &ResponseJob| self_clone.response_txn_deadline(response_job),
+        ));
+
+        loop {
+            let response_job = match self.request_chain_txn_queue.pop(self.clock.as_ref()).await {
+                DequeuedItem::Due(response_job) => response_job,
+                DequeuedItem::Expired(response_job) => {
+                    error!(
+                        "Job ID: {:?}, Dropping the response transaction past its deadline",
+                        response_job.job_id
+                    );
+                    self.remove_response_job_from_active_jobs(response_job.key())
+                        .await;
+                    continue;
+                }
+            };
+
             match response_job.job_type {
                 GatewayJobType::JobResponded => {
-                    let response_job_job_id = response_job.job_id.clone();
+                    let response_job_key = response_job.key();
                     self.job_response_txn(response_job).await;
-                    self.remove_response_job_from_active_jobs(response_job_job_id)
+                    self.remove_response_job_from_active_jobs(response_job_key)
                         .await;
                 }
                 // Currently, slashing is not implemented for the JobResponded event
@@ -1177,18 +1591,48 @@ impl ContractsClient {
                 }
             }
         }
-        Ok(())
+    }
+
+    // Deadline of the response transaction on the request chain, the user timeout from the job
+    // start time with a buffer to relay the response. Responses of the jobs no longer tracked,
+    // or with times out of the u64 range, are never dropped.
+    fn response_txn_deadline(self: &Arc<Self>, response_job: &ResponseJob) -> u64 {
+        let active_jobs = self.active_jobs.read().unwrap();
+        let Some(job) = active_jobs.get(&response_job.key()) else {
+            return u64::MAX;
+        };
+
+        let (Ok(starttime), Ok(user_timeout)) =
+            (uint_to_u64(job.starttime), uint_to_u64(job.user_timeout))
+        else {
+            return u64::MAX;
+        };
+
+        // User timeout is in milliseconds
+        starttime
+            .saturating_add(user_timeout.div_ceil(1000))
+            .saturating_add(RESPONSE_RELAY_TIMEOUT)
     }
 
     async fn job_response_txn(self: &Arc<Self>, response_job: ResponseJob) {
         info!("Creating a transaction for jobResponse");
+        // Hold off the rotation of the gas wallet until the transaction is settled
+        let _gas_wallet_permit = self.gas_wallet_lock.read().await;
 
-        let response_job_id = response_job.job_id;
+        let response_job_id = response_job.key().request_chain_job_id();
 
-        let req_chain_client = &self.request_chain_clients[&response_job.request_chain_id];
+        let Some(req_chain_client) = self.request_chain_client(response_job.request_chain_id)
+        else {
+            error!(
+                "Job ID: {:?}, Request Chain {} is not served by the gateway",
+                response_job.job_id, response_job.request_chain_id
+            );
+            return;
+        };
 
         let (signature, sign_timestamp) = sign_job_response_request(
-            &self.enclave_signer_key,
+            self.enclave_signer.as_ref(),
+            &self.eip712_domains,
             response_job_id,
             response_job.output.clone(),
             response_job.total_time,
@@ -1270,17 +1714,83 @@ impl ContractsClient {
         }
     }
 
-    async fn remove_response_job_from_active_jobs(self: &Arc<Self>, job_id: U256) {
+    async fn remove_response_job_from_active_jobs(self: &Arc<Self>, job_key: JobKey) {
         let mut active_jobs = self.active_jobs.write().unwrap();
-        active_jobs.remove(&job_id);
+        active_jobs.remove(&job_key);
     }
 }
 
-impl LogsProvider for ContractsClient {
-    async fn common_chain_jobs<'a>(
+// Weighted random selection, by the stake amounts, of one of the gateways serving the request chain
+pub fn select_gateway(
+    all_gateways_data: &[GatewayData],
+    request_chain_id: u64,
+    job_cycle: u64,
+    seed: u64,
+    skips: u8,
+) -> Result<Address, ServerlessError> {
+    if all_gateways_data.is_empty() {
+        return Err(ServerlessError::NoGatewaysRegisteredInCycle(job_cycle));
+    }
+
+    // create a weighted probability distribution for gateways based on stake amount
+    // For example, if there are 3 gateways with stake amounts 100, 200, 300
+    // then the distribution array will be [100, 300, 600]
+    let mut stake_distribution: Vec<u128> = vec![];
+    let mut total_stake: u128 = 0;
+    let mut gateway_addresses_of_req_chain: Vec<H160> = vec![];
+
+    for gateway_data in all_gateways_data.iter() {
+        if gateway_data.req_chain_ids.contains(&request_chain_id)
+            && gateway_data.stake_amount > *MIN_GATEWAY_STAKE
+            && gateway_data.draining == false
+        {
+            gateway_addresses_of_req_chain.push(gateway_data.address.clone());
+            total_stake += (gateway_data.stake_amount / *GATEWAY_STAKE_ADJUSTMENT_FACTOR).as_u128();
+            stake_distribution.push(total_stake);
+        }
+    }
+
+    if total_stake == 0 {
+        return Err(ServerlessError::NoValidGatewaysForChain(
+            job_cycle,
+            request_chain_id,
+        ));
+    }
+
+    // random number between 1 to total_stake from the seed for the weighted random selection.
+    // use this seed in std_rng to generate a random number between 1 to total_stake
+    // skipping skips numbers from the random number generated
+    // skips comes from sequence_number of the job which starts from 1. That's why its (skips-1)
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..skips - 1 {
+        let _ = rng.gen_range(1..=total_stake);
+    }
+    let random_number = rng.gen_range(1..=total_stake);
+
+    // select the gateway based on the random number
+    let res = stake_distribution.binary_search_by(|&probe| probe.cmp(&random_number));
+
+    let index = match res {
+        Ok(index) => index,
+        Err(index) => index,
+    };
+
+    Ok(gateway_addresses_of_req_chain[index])
+}
+
+// Whether the relay of a job on the common chain was done by the gateway selected for it
+fn is_job_relayed_by(job: &Job, gateway: Address, env: u8, job_owner: Address) -> bool {
+    gateway != Address::zero()
+        && Some(gateway) == job.gateway_address
+        && env == job.env
+        && job_owner == job.job_owner
+}
+
+impl<M: Middleware + 'static, C: ChainConnector<M>> LogsProvider<M> for ContractsClient<M, C> {
+    async fn common_chain_jobs<'a, P: PubsubClient>(
         &'a self,
-        common_chain_ws_provider: &'a Provider<Ws>,
-    ) -> Result<SubscriptionStream<'a, Ws, Log>> {
+        common_chain_ws_provider: &'a Provider<P>,
+    ) -> Result<SubscriptionStream<'a, P, Log>> {
         info!("Subscribing to events for Common Chain");
 
         let common_chain_start_block_number =
@@ -1289,9 +1799,10 @@ impl LogsProvider for ContractsClient {
             .address(self.gateway_jobs_contract.read().unwrap().address())
             .select(common_chain_start_block_number..)
             .topic0(vec![
-                keccak256(COMMON_CHAIN_JOB_RESPONDED_EVENT),
-                keccak256(COMMON_CHAIN_JOB_RESOURCE_UNAVAILABLE_EVENT),
-                keccak256(COMMON_CHAIN_GATEWAY_REASSIGNED_EVENT),
+                CommonChainJobRelayedFilter::signature(),
+                CommonChainJobRespondedFilter::signature(),
+                JobResourceUnavailableFilter::signature(),
+                GatewayReassignedFilter::signature(),
             ]);
 
         let stream = common_chain_ws_provider
@@ -1303,10 +1814,10 @@ impl LogsProvider for ContractsClient {
         Ok(stream)
     }
 
-    async fn req_chain_jobs<'a>(
+    async fn req_chain_jobs<'a, P: PubsubClient>(
         &'a self,
-        req_chain_ws_client: &'a Provider<Ws>,
-        req_chain_client: &'a RequestChainClient,
+        req_chain_ws_client: &'a Provider<P>,
+        req_chain_client: &'a RequestChainClient<M>,
     ) -> Result<impl Stream<Item = Log> + Unpin> {
         info!(
             "Subscribing to events for Req Chain chain_id: {}",
@@ -1319,11 +1830,14 @@ impl LogsProvider for ContractsClient {
             ])
             .select(req_chain_client.request_chain_start_block_number..)
             .topic0(vec![
-                keccak256(REQUEST_CHAIN_JOB_RELAYED_EVENT),
-                keccak256(REQUEST_CHAIN_JOB_CANCELLED_EVENT),
-                keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT),
-                keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT),
-                keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT),
+                RequestChainJobRelayedFilter::signature(),
+                JobCancelledFilter::signature(),
+                JobSubscriptionStartedFilter::signature(),
+                JobSubscriptionJobParamsUpdatedFilter::signature(),
+                JobSubscriptionTerminationParamsUpdatedFilter::signature(),
+                JobSubscriptionTerminatedFilter::signature(),
+                JobSubscriptionFundsDepositedFilter::signature(),
+                JobSubscriptionFundsWithdrawnFilter::signature(),
             ]);
 
         // register subscription
@@ -1340,7 +1854,7 @@ impl LogsProvider for ContractsClient {
             .then(|log| {
                 confirm_event(
                     log,
-                    &req_chain_client.http_rpc_url,
+                    req_chain_client.http_rpc_client.as_ref(),
                     req_chain_client.confirmation_blocks,
                     req_chain_client.last_seen_block.clone(),
                 )
@@ -1360,32 +1874,34 @@ impl LogsProvider for ContractsClient {
         let job_relayed_event_filter = Filter::new()
             .address(self.gateway_jobs_contract.read().unwrap().address())
             .select(common_chain_start_block_number..)
-            .topic0(vec![keccak256(COMMON_CHAIN_JOB_RELAYED_EVENT)])
-            .topic1(job.job_id);
+            .topic0(vec![CommonChainJobRelayedFilter::signature()])
+            .topic1(job.key().common_chain_job_id());
 
         let logs = common_chain_http_provider
             .get_logs(&job_relayed_event_filter)
-            .await
-            .unwrap();
+            .await?;
 
         Ok(logs)
     }
 
     async fn request_chain_historic_subscription_jobs<'a, P: HttpProviderLogs>(
         &'a self,
-        req_chain_client: &'a RequestChainClient,
+        req_chain_client: &'a RequestChainClient<M>,
         http_provider: &'a P,
     ) -> Result<Vec<Log>> {
         let event_filter = Filter::new()
             .address(req_chain_client.relay_subscriptions_address)
             .select(..req_chain_client.request_chain_start_block_number - 1)
             .topic0(vec![
-                keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT),
-                keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT),
-                keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT),
+                JobSubscriptionStartedFilter::signature(),
+                JobSubscriptionJobParamsUpdatedFilter::signature(),
+                JobSubscriptionTerminationParamsUpdatedFilter::signature(),
+                JobSubscriptionTerminatedFilter::signature(),
+                JobSubscriptionFundsDepositedFilter::signature(),
+                JobSubscriptionFundsWithdrawnFilter::signature(),
             ]);
 
-        let logs = http_provider.get_logs(&event_filter).await.unwrap();
+        let logs = http_provider.get_logs(&event_filter).await?;
         Ok(logs)
     }
 }
@@ -1399,19 +1915,21 @@ mod common_chain_interaction_tests {
     use serde_json::json;
 
     use crate::test_util::{
-        add_gateway_epoch_state, generate_contracts_client, MockHttpProvider, CHAIN_ID,
-        GATEWAY_JOBS_CONTRACT_ADDR, RELAY_CONTRACT_ADDR,
+        add_gateway_epoch_state, generate_contracts_client, job_subscription_id, relay_job_id,
+        MockHttpProvider, CHAIN_ID, GATEWAY_JOBS_CONTRACT_ADDR, RELAY_CONTRACT_ADDR,
     };
 
     use super::*;
 
-    async fn generate_job_relayed_log(job_id: Option<U256>, job_starttime: u64) -> Log {
-        let job_id = job_id.unwrap_or(U256::one());
+    const OTHER_CHAIN_ID: u64 = CHAIN_ID + 1;
+
+    async fn generate_job_relayed_log(job_id: Option<u64>, job_starttime: u64) -> Log {
+        let job_id = relay_job_id(CHAIN_ID, job_id.unwrap_or(1));
 
         Log {
             address: H160::from_str(RELAY_CONTRACT_ADDR).unwrap(),
             topics: vec![
-                keccak256(REQUEST_CHAIN_JOB_RELAYED_EVENT).into(),
+                RequestChainJobRelayedFilter::signature(),
                 H256::from_uint(&job_id),
                 H256::from_uint(&U256::one()),
             ],
@@ -1443,14 +1961,14 @@ mod common_chain_interaction_tests {
         }
     }
 
-    async fn generate_job_responded_log(job_id: Option<U256>) -> Log {
-        let job_id = job_id.unwrap_or(U256::one());
+    async fn generate_job_responded_log(job_id: Option<u64>) -> Log {
+        let job_id = relay_job_id(CHAIN_ID, job_id.unwrap_or(1));
 
         Log {
             address: H160::from_str(GATEWAY_JOBS_CONTRACT_ADDR).unwrap(),
             topics: vec![
-                keccak256(COMMON_CHAIN_JOB_RESPONDED_EVENT).into(),
-                H256::from_uint(&job_id),
+                CommonChainJobRespondedFilter::signature(),
+                H256::from_uint(&JobKey::once(CHAIN_ID, job_id).common_chain_job_id()),
             ],
             data: encode(&[
                 Token::Bytes([].into()),
@@ -1462,11 +1980,12 @@ mod common_chain_interaction_tests {
         }
     }
 
-    async fn generate_generic_job(job_id: Option<U256>, job_starttime: Option<u64>) -> Job {
-        let job_id = job_id.unwrap_or(U256::one());
+    async fn generate_generic_job(job_id: Option<u64>, job_starttime: u64) -> Job {
+        let job_id = relay_job_id(CHAIN_ID, job_id.unwrap_or(1));
 
         Job {
             job_id,
+            instance_index: 0,
             request_chain_id: CHAIN_ID,
             tx_hash: hex::decode(
                 "9468bb6a8e85ed11e292c8cac0c1539df691c8d8ec62e7dbfa9f1bd7f504e46e".to_owned(),
@@ -1478,14 +1997,7 @@ mod common_chain_interaction_tests {
             .unwrap()
             .into(),
             user_timeout: U256::from(2000),
-            starttime: U256::from(
-                job_starttime.unwrap_or(
-                    SystemTime::now()
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs(),
-                ),
-            ),
+            starttime: U256::from(job_starttime),
             job_owner: H160::from_str(RELAY_CONTRACT_ADDR).unwrap(),
             job_type: GatewayJobType::JobRelay,
             sequence_number: 1 as u8,
@@ -1495,11 +2007,12 @@ mod common_chain_interaction_tests {
         }
     }
 
-    async fn generate_generic_response_job(job_id: Option<U256>) -> ResponseJob {
-        let job_id = job_id.unwrap_or(U256::one());
+    async fn generate_generic_response_job(job_id: Option<u64>) -> ResponseJob {
+        let job_id = relay_job_id(CHAIN_ID, job_id.unwrap_or(1));
 
         ResponseJob {
             job_id,
+            instance_index: 0,
             request_chain_id: CHAIN_ID,
             output: Bytes::default(),
             total_time: U256::from(1000),
@@ -1514,14 +2027,11 @@ mod common_chain_interaction_tests {
     async fn test_get_job_from_job_relay_event() {
         let contracts_client = generate_contracts_client().await;
 
-        let job_starttime = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let job_starttime = contracts_client.clock.now();
 
         let log = generate_job_relayed_log(None, job_starttime).await;
 
-        let expected_job = generate_generic_job(None, Some(job_starttime)).await;
+        let expected_job = generate_generic_job(None, job_starttime).await;
 
         let job = contracts_client
             .get_job_from_job_relay_event(log, 1 as u8, CHAIN_ID)
@@ -1539,7 +2049,7 @@ mod common_chain_interaction_tests {
         let log = Log {
             address: H160::from_str(RELAY_CONTRACT_ADDR).unwrap(),
             topics: vec![
-                keccak256(REQUEST_CHAIN_JOB_RELAYED_EVENT).into(),
+                RequestChainJobRelayedFilter::signature(),
                 H256::from_uint(&U256::one()),
                 H256::from_uint(&U256::one()),
             ],
@@ -1555,11 +2065,27 @@ mod common_chain_interaction_tests {
         assert_eq!(job.err().unwrap(), ServerlessError::LogDecodeFailure);
     }
 
+    #[tokio::test]
+    async fn test_get_job_from_job_relay_event_missing_topics() {
+        let contracts_client = generate_contracts_client().await;
+
+        // Indexed env topic is missing
+        let mut log = generate_job_relayed_log(None, 1000).await;
+        log.topics.truncate(2);
+
+        let job = contracts_client
+            .get_job_from_job_relay_event(log, 1 as u8, CHAIN_ID)
+            .await;
+
+        // expect an error
+        assert_eq!(job.err().unwrap(), ServerlessError::LogDecodeFailure);
+    }
+
     #[tokio::test]
     async fn test_select_gateway_for_job_id() {
         let contracts_client = generate_contracts_client().await;
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
 
         add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
 
@@ -1571,11 +2097,29 @@ mod common_chain_interaction_tests {
         assert_eq!(gateway_address, contracts_client.enclave_address);
     }
 
+    #[tokio::test]
+    async fn test_select_gateway_for_job_id_started_before_epoch() {
+        let contracts_client = generate_contracts_client().await;
+
+        let job = generate_generic_job(None, contracts_client.epoch - 1).await;
+
+        add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
+
+        let gateway_address = contracts_client
+            .select_gateway_for_job_id(job.clone(), job.starttime.as_u64(), job.sequence_number)
+            .await;
+
+        assert_eq!(
+            gateway_address.err().unwrap(),
+            ServerlessError::JobStartedBeforeEpoch(contracts_client.epoch - 1)
+        );
+    }
+
     #[tokio::test]
     async fn test_select_gateway_for_job_id_no_cycle_state() {
         let contracts_client = generate_contracts_client().await;
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
 
         let gateway_address = contracts_client
             .select_gateway_for_job_id(job.clone(), job.starttime.as_u64(), job.sequence_number)
@@ -1601,7 +2145,7 @@ mod common_chain_interaction_tests {
     async fn test_select_gateway_for_job_id_multiple_gateways() {
         let contracts_client = generate_contracts_client().await;
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
 
         add_gateway_epoch_state(contracts_client.clone(), Some(5), None, None).await;
 
@@ -1639,7 +2183,7 @@ mod common_chain_interaction_tests {
     async fn test_select_gateway_for_job_id_multiple_gateways_seq_number() {
         let contracts_client = generate_contracts_client().await;
 
-        let mut job = generate_generic_job(None, None).await;
+        let mut job = generate_generic_job(None, contracts_client.clock.now()).await;
         job.sequence_number = 5;
 
         add_gateway_epoch_state(contracts_client.clone(), Some(5), None, None).await;
@@ -1680,7 +2224,7 @@ mod common_chain_interaction_tests {
     async fn test_job_relayed_handler() {
         let contracts_client = generate_contracts_client().await;
 
-        let mut job = generate_generic_job(None, None).await;
+        let mut job = generate_generic_job(None, contracts_client.clock.now()).await;
 
         add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
 
@@ -1700,11 +2244,7 @@ mod common_chain_interaction_tests {
 
             assert_eq!(contracts_client.active_jobs.read().unwrap().len(), 1);
             assert_eq!(
-                contracts_client
-                    .active_jobs
-                    .read()
-                    .unwrap()
-                    .get(&job.job_id),
+                contracts_client.active_jobs.read().unwrap().get(&job.key()),
                 Some(&rx_job)
             );
 
@@ -1727,7 +2267,7 @@ mod common_chain_interaction_tests {
     async fn test_job_relayed_handler_selected_gateway_not_self() {
         let contracts_client = generate_contracts_client().await;
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
 
         add_gateway_epoch_state(contracts_client.clone(), Some(4), Some(false), None).await;
 
@@ -1741,16 +2281,12 @@ mod common_chain_interaction_tests {
             .await;
 
         assert_eq!(
-            contracts_client
-                .active_jobs
-                .read()
-                .unwrap()
-                .get(&job.job_id),
+            contracts_client.active_jobs.read().unwrap().get(&job.key()),
             None
         );
 
         let current_jobs_guard = contracts_client.current_jobs.read().unwrap();
-        let current_job = current_jobs_guard.get(&job.job_id);
+        let current_job = current_jobs_guard.get(&job.key());
 
         assert!(current_job.is_some());
 
@@ -1781,7 +2317,7 @@ mod common_chain_interaction_tests {
     async fn test_job_relayed_handler_no_cycle_state() {
         let contracts_client = generate_contracts_client().await;
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
 
         let (req_chain_tx, _) = channel::<Job>(100);
 
@@ -1803,11 +2339,7 @@ mod common_chain_interaction_tests {
         assert_eq!(waitlisted_jobs[0][0], job);
 
         assert_eq!(
-            contracts_client
-                .active_jobs
-                .read()
-                .unwrap()
-                .get(&job.job_id),
+            contracts_client.active_jobs.read().unwrap().get(&job.key()),
             None
         );
     }
@@ -1816,7 +2348,7 @@ mod common_chain_interaction_tests {
     async fn test_job_relayed_slash_timer_txn_success() {
         let contracts_client = generate_contracts_client().await;
 
-        let mut job = generate_generic_job(None, None).await;
+        let mut job = generate_generic_job(None, contracts_client.clock.now()).await;
 
         add_gateway_epoch_state(contracts_client.clone(), Some(5), None, None).await;
         job.gateway_address = Some(
@@ -1833,11 +2365,23 @@ mod common_chain_interaction_tests {
                 .address,
         );
 
+        contracts_client
+            .current_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+
         let (req_chain_tx, mut com_chain_rx) = channel::<Job>(100);
 
         let mock_provider = MockHttpProvider::new(Some(job.clone()));
         contracts_client
-            .job_relayed_slash_timer(job.clone(), Some(1), req_chain_tx, &mock_provider)
+            .job_relayed_slash_timer(
+                job.clone(),
+                Some(1),
+                req_chain_tx,
+                &mock_provider,
+                &mock_provider,
+            )
             .await;
 
         assert!(com_chain_rx.recv().await.is_none());
@@ -1847,7 +2391,7 @@ mod common_chain_interaction_tests {
     async fn test_job_relayed_slash_timer_txn_fail_retry() {
         let contracts_client = generate_contracts_client().await;
 
-        let mut job = generate_generic_job(Some(U256::from(2)), None).await;
+        let mut job = generate_generic_job(Some(2), contracts_client.clock.now()).await;
 
         add_gateway_epoch_state(contracts_client.clone(), Some(5), None, None).await;
         job.gateway_address = Some(
@@ -1864,13 +2408,25 @@ mod common_chain_interaction_tests {
                 .address,
         );
 
+        contracts_client
+            .current_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+
         let (req_chain_tx, mut com_chain_rx) = channel::<Job>(100);
 
         let mock_provider = MockHttpProvider::new(Some(job.clone()));
 
         contracts_client
             .clone()
-            .job_relayed_slash_timer(job.clone(), Some(1 as u64), req_chain_tx, &mock_provider)
+            .job_relayed_slash_timer(
+                job.clone(),
+                Some(1 as u64),
+                req_chain_tx,
+                &mock_provider,
+                &mock_provider,
+            )
             .await;
 
         if let Some(rx_job) = com_chain_rx.recv().await {
@@ -1887,7 +2443,7 @@ mod common_chain_interaction_tests {
     async fn test_job_relayed_slash_timer_txn_fail_max_retry() {
         let contracts_client = generate_contracts_client().await;
 
-        let mut job = generate_generic_job(Some(U256::from(2)), None).await;
+        let mut job = generate_generic_job(Some(2), contracts_client.clock.now()).await;
 
         add_gateway_epoch_state(contracts_client.clone(), Some(5), None, None).await;
         job.gateway_address = Some(
@@ -1905,12 +2461,24 @@ mod common_chain_interaction_tests {
         );
         job.sequence_number = MAX_GATEWAY_RETRIES;
 
+        contracts_client
+            .current_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+
         let (req_chain_tx, mut com_chain_rx) = channel::<Job>(100);
 
         let mock_provider = MockHttpProvider::new(Some(job.clone()));
         contracts_client
             .clone()
-            .job_relayed_slash_timer(job.clone(), Some(1 as u64), req_chain_tx, &mock_provider)
+            .job_relayed_slash_timer(
+                job.clone(),
+                Some(1 as u64),
+                req_chain_tx,
+                &mock_provider,
+                &mock_provider,
+            )
             .await;
 
         if let Some(rx_job) = com_chain_rx.recv().await {
@@ -1923,22 +2491,149 @@ mod common_chain_interaction_tests {
         assert!(com_chain_rx.recv().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_job_relayed_slash_timer_relay_state_unavailable() {
+        let contracts_client = generate_contracts_client().await;
+
+        let mut job = generate_generic_job(Some(3), contracts_client.clock.now()).await;
+        job.gateway_address = Some(Address::random());
+
+        contracts_client
+            .current_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+
+        let (req_chain_tx, mut com_chain_rx) = channel::<Job>(100);
+
+        // The relay state call fails, the JobRelayed logs are used instead
+        let mock_provider = MockHttpProvider::new(Some(job.clone()));
+        contracts_client
+            .clone()
+            .job_relayed_slash_timer(
+                job.clone(),
+                Some(1),
+                req_chain_tx,
+                &mock_provider,
+                &mock_provider,
+            )
+            .await;
+
+        assert!(com_chain_rx.recv().await.is_none());
+        assert!(contracts_client.current_jobs.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_job_relayed_slash_timer_resolved_by_event() {
+        let contracts_client = generate_contracts_client().await;
+
+        let mut job = generate_generic_job(Some(2), contracts_client.clock.now()).await;
+        job.gateway_address = Some(Address::random());
+
+        contracts_client
+            .current_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+
+        let job_relayed_log = Log {
+            address: H160::from_str(GATEWAY_JOBS_CONTRACT_ADDR).unwrap(),
+            topics: vec![
+                CommonChainJobRelayedFilter::signature(),
+                H256::from_uint(&job.key().common_chain_job_id()),
+            ],
+            data: encode(&[
+                Token::Uint(U256::from(100)),
+                Token::Uint(U256::from(job.env)),
+                Token::Address(job.job_owner),
+                Token::Address(job.gateway_address.unwrap()),
+            ])
+            .into(),
+            ..Default::default()
+        };
+        contracts_client
+            .clone()
+            .job_relayed_event_handler(job_relayed_log)
+            .await;
+
+        assert!(contracts_client.current_jobs.read().unwrap().is_empty());
+
+        let (req_chain_tx, mut com_chain_rx) = channel::<Job>(100);
+
+        // Job ID 2 is not relayed as per the mocked contract state
+        let mock_provider = MockHttpProvider::new(Some(job.clone()));
+        contracts_client
+            .job_relayed_slash_timer(
+                job.clone(),
+                Some(1),
+                req_chain_tx,
+                &mock_provider,
+                &mock_provider,
+            )
+            .await;
+
+        assert!(com_chain_rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_job_relayed_event_handler_other_gateway() {
+        let contracts_client = generate_contracts_client().await;
+
+        let mut job = generate_generic_job(None, contracts_client.clock.now()).await;
+        job.gateway_address = Some(Address::random());
+
+        contracts_client
+            .current_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+
+        let job_relayed_log = Log {
+            address: H160::from_str(GATEWAY_JOBS_CONTRACT_ADDR).unwrap(),
+            topics: vec![
+                CommonChainJobRelayedFilter::signature(),
+                H256::from_uint(&job.key().common_chain_job_id()),
+            ],
+            data: encode(&[
+                Token::Uint(U256::from(100)),
+                Token::Uint(U256::from(job.env)),
+                Token::Address(job.job_owner),
+                Token::Address(Address::random()),
+            ])
+            .into(),
+            ..Default::default()
+        };
+        contracts_client
+            .clone()
+            .job_relayed_event_handler(job_relayed_log)
+            .await;
+
+        assert_eq!(
+            contracts_client
+                .current_jobs
+                .read()
+                .unwrap()
+                .get(&job.key()),
+            Some(&job)
+        );
+    }
+
     #[tokio::test]
     async fn test_cancel_job_with_job_id_single_active_job() {
         let contracts_client = generate_contracts_client().await;
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
         contracts_client
             .active_jobs
             .write()
             .unwrap()
-            .insert(job.job_id, job.clone());
+            .insert(job.key(), job.clone());
 
         assert_eq!(contracts_client.active_jobs.read().unwrap().len(), 1);
 
         contracts_client
             .clone()
-            .cancel_job_with_job_id(job.job_id)
+            .cancel_job_with_job_id(job.key())
             .await;
 
         assert_eq!(contracts_client.active_jobs.read().unwrap().len(), 0);
@@ -1948,25 +2643,25 @@ mod common_chain_interaction_tests {
     async fn test_cancel_job_with_job_id_multiple_active_jobs() {
         let contracts_client = generate_contracts_client().await;
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
         contracts_client
             .active_jobs
             .write()
             .unwrap()
-            .insert(job.job_id, job.clone());
+            .insert(job.key(), job.clone());
 
-        let job2 = generate_generic_job(Some(U256::from(2)), None).await;
+        let job2 = generate_generic_job(Some(2), contracts_client.clock.now()).await;
         contracts_client
             .active_jobs
             .write()
             .unwrap()
-            .insert(job2.job_id, job2.clone());
+            .insert(job2.key(), job2.clone());
 
         assert_eq!(contracts_client.active_jobs.read().unwrap().len(), 2);
 
         contracts_client
             .clone()
-            .cancel_job_with_job_id(job.job_id)
+            .cancel_job_with_job_id(job.key())
             .await;
 
         assert_eq!(contracts_client.active_jobs.read().unwrap().len(), 1);
@@ -1975,7 +2670,7 @@ mod common_chain_interaction_tests {
                 .active_jobs
                 .read()
                 .unwrap()
-                .get(&job2.job_id),
+                .get(&job2.key()),
             Some(&job2)
         );
     }
@@ -1984,13 +2679,13 @@ mod common_chain_interaction_tests {
     async fn test_cancel_job_with_job_id_no_active_jobs() {
         let contracts_client = generate_contracts_client().await;
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
 
         assert_eq!(contracts_client.active_jobs.read().unwrap().len(), 0);
 
         contracts_client
             .clone()
-            .cancel_job_with_job_id(job.job_id)
+            .cancel_job_with_job_id(job.key())
             .await;
 
         assert_eq!(contracts_client.active_jobs.read().unwrap().len(), 0);
@@ -2019,13 +2714,13 @@ mod common_chain_interaction_tests {
 
         let log = generate_job_responded_log(None).await;
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
         let expected_job = generate_generic_response_job(None).await;
         contracts_client
             .active_jobs
             .write()
             .unwrap()
-            .insert(job.job_id, job.clone());
+            .insert(job.key(), job.clone());
 
         let job = contracts_client.get_job_from_job_responded_event(log).await;
 
@@ -2041,19 +2736,19 @@ mod common_chain_interaction_tests {
         let log = Log {
             address: H160::from_str(GATEWAY_JOBS_CONTRACT_ADDR).unwrap(),
             topics: vec![
-                keccak256(COMMON_CHAIN_JOB_RESPONDED_EVENT).into(),
+                CommonChainJobRespondedFilter::signature(),
                 H256::from_low_u64_be(1),
             ],
             data: encode(&[Token::Bytes([].into())]).into(),
             ..Default::default()
         };
 
-        let job = generate_generic_job(None, None).await;
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
         contracts_client
             .active_jobs
             .write()
             .unwrap()
-            .insert(job.job_id, job.clone());
+            .insert(job.key(), job.clone());
 
         let job = contracts_client.get_job_from_job_responded_event(log).await;
 
@@ -2061,5 +2756,239 @@ mod common_chain_interaction_tests {
         assert_eq!(job.err().unwrap().to_string(), "Failed to decode log");
     }
 
+    #[tokio::test]
+    async fn test_get_job_from_job_responded_event_subscription_instance() {
+        let contracts_client = generate_contracts_client().await;
+
+        // Instance 1 of subscription 1 and instance 0 of subscription 2
+        let mut job = generate_generic_job(None, contracts_client.clock.now()).await;
+        job.job_mode = JobMode::Subscription;
+        job.job_id = job_subscription_id(CHAIN_ID, 1);
+        job.instance_index = 1;
+        let mut job2 = job.clone();
+        job2.job_id = job_subscription_id(CHAIN_ID, 2);
+        job2.instance_index = 0;
+        contracts_client
+            .active_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+        contracts_client
+            .active_jobs
+            .write()
+            .unwrap()
+            .insert(job2.key(), job2.clone());
+
+        let mut log = generate_job_responded_log(None).await;
+        log.topics[1] = H256::from_uint(&job2.key().common_chain_job_id());
+
+        let response_job = contracts_client
+            .get_job_from_job_responded_event(log)
+            .await
+            .unwrap();
+
+        assert_eq!(response_job.key(), job2.key());
+        assert_eq!(
+            response_job.key().request_chain_job_id(),
+            job_subscription_id(CHAIN_ID, 2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_job_with_job_id_same_job_count_on_other_request_chain() {
+        let contracts_client = generate_contracts_client().await;
+
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
+        let mut job2 = generate_generic_job(None, contracts_client.clock.now()).await;
+        job2.request_chain_id = OTHER_CHAIN_ID;
+        job2.job_id = relay_job_id(OTHER_CHAIN_ID, 1);
+        contracts_client
+            .active_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+        contracts_client
+            .active_jobs
+            .write()
+            .unwrap()
+            .insert(job2.key(), job2.clone());
+
+        assert_eq!(contracts_client.active_jobs.read().unwrap().len(), 2);
+
+        // JobCancelled for the job 1 of the other request chain
+        contracts_client
+            .clone()
+            .cancel_job_with_job_id(JobKey::once(
+                OTHER_CHAIN_ID,
+                relay_job_id(OTHER_CHAIN_ID, 1),
+            ))
+            .await;
+
+        let active_jobs = contracts_client.active_jobs.read().unwrap();
+        assert_eq!(active_jobs.len(), 1);
+        assert_eq!(active_jobs.get(&job.key()), Some(&job));
+    }
+
+    #[tokio::test]
+    async fn test_get_job_from_job_responded_event_same_job_count_on_other_request_chain() {
+        let contracts_client = generate_contracts_client().await;
+
+        let job = generate_generic_job(None, contracts_client.clock.now()).await;
+        let mut job2 = generate_generic_job(None, contracts_client.clock.now()).await;
+        job2.request_chain_id = OTHER_CHAIN_ID;
+        job2.job_id = relay_job_id(OTHER_CHAIN_ID, 1);
+        contracts_client
+            .active_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+        contracts_client
+            .active_jobs
+            .write()
+            .unwrap()
+            .insert(job2.key(), job2.clone());
+
+        let mut log = generate_job_responded_log(None).await;
+        log.topics[1] = H256::from_uint(&job2.key().common_chain_job_id());
+
+        let response_job = contracts_client
+            .get_job_from_job_responded_event(log)
+            .await
+            .unwrap();
+
+        assert_eq!(response_job.job_id, relay_job_id(OTHER_CHAIN_ID, 1));
+        assert_eq!(response_job.request_chain_id, OTHER_CHAIN_ID);
+
+        // Only the job of the other request chain is responded to
+        let (com_chain_tx, mut com_chain_rx) = channel::<ResponseJob>(100);
+        contracts_client
+            .clone()
+            .job_responded_handler(response_job.clone(), com_chain_tx)
+            .await;
+
+        assert_eq!(com_chain_rx.recv().await, Some(response_job));
+        let active_jobs = contracts_client.active_jobs.read().unwrap();
+        assert_eq!(active_jobs.len(), 1);
+        assert_eq!(active_jobs.get(&job.key()), Some(&job));
+    }
+
+    #[tokio::test]
+    async fn test_gateway_reassigned_handler_same_job_count_on_other_request_chain() {
+        let contracts_client = generate_contracts_client().await;
+        let (req_chain_tx, _req_chain_rx) = channel::<Job>(100);
+
+        let mut job = generate_generic_job(None, contracts_client.clock.now()).await;
+        job.gateway_address = Some(Address::random());
+        let mut job2 = job.clone();
+        job2.request_chain_id = OTHER_CHAIN_ID;
+        job2.job_id = relay_job_id(OTHER_CHAIN_ID, 1);
+        contracts_client
+            .current_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+        contracts_client
+            .current_jobs
+            .write()
+            .unwrap()
+            .insert(job2.key(), job2.clone());
+
+        let log = Log {
+            address: H160::from_str(GATEWAY_JOBS_CONTRACT_ADDR).unwrap(),
+            topics: vec![
+                GatewayReassignedFilter::signature(),
+                H256::from_uint(&job2.key().common_chain_job_id()),
+            ],
+            data: encode(&[
+                Token::Address(job2.gateway_address.unwrap()),
+                Token::Address(Address::random()),
+                Token::Uint(job2.sequence_number.into()),
+            ])
+            .into(),
+            ..Default::default()
+        };
+
+        contracts_client
+            .clone()
+            .gateway_reassigned_handler(log, req_chain_tx)
+            .await;
+
+        let current_jobs = contracts_client.current_jobs.read().unwrap();
+        assert_eq!(current_jobs.len(), 1);
+        assert_eq!(current_jobs.get(&job.key()), Some(&job));
+    }
+
+    #[tokio::test]
+    async fn test_relay_txn_deadline() {
+        let mut job = generate_generic_job(None, 1000).await;
+
+        assert_eq!(job.relay_txn_deadline(), 1000 + 2 * REQUEST_RELAY_TIMEOUT);
+
+        job.job_type = GatewayJobType::SlashGatewayJob;
+        job.sequence_number = 2;
+
+        assert_eq!(job.relay_txn_deadline(), 1000 + 3 * REQUEST_RELAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_response_txn_deadline() {
+        let contracts_client = generate_contracts_client().await;
+        let job = generate_generic_job(None, 1000).await;
+        let response_job = generate_generic_response_job(None).await;
+
+        // Responses of the jobs no longer tracked are never dropped
+        assert_eq!(
+            contracts_client.response_txn_deadline(&response_job),
+            u64::MAX
+        );
+
+        contracts_client
+            .active_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+
+        // User timeout of 2000 ms
+        assert_eq!(
+            contracts_client.response_txn_deadline(&response_job),
+            1000 + 2 + RESPONSE_RELAY_TIMEOUT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_drop_expired_relay_txn() {
+        let contracts_client = generate_contracts_client().await;
+        let mut job = generate_generic_job(None, 1000).await;
+        job.gateway_address = Some(contracts_client.enclave_address);
+
+        contracts_client
+            .current_jobs
+            .write()
+            .unwrap()
+            .insert(job.key(), job.clone());
+        contracts_client
+            .common_chain_txn_queue
+            .push(job.clone(), job.relay_txn_deadline());
+
+        let DequeuedItem::Expired(expired_job) = contracts_client
+            .common_chain_txn_queue
+            .try_pop(job.relay_txn_deadline() + 1)
+            .unwrap()
+        else {
+            panic!("Relay transaction past its deadline is not dropped");
+        };
+        contracts_client.drop_expired_relay_txn(expired_job);
+
+        // The slash timer finds the job resolved
+        assert!(!contracts_client.is_job_pending_relay(&job));
+        assert_eq!(
+            contracts_client
+                .common_chain_txn_queue
+                .metrics()
+                .dropped_items,
+            1
+        );
+    }
+
     // TODO: tests for gateway_epoch_state_service
 }
@@ -11,6 +11,10 @@ impl ConfigManager {
         let settings = config::Config::builder()
             .add_source(File::with_name(self.path.as_str()))
             .build()?;
-        settings.try_deserialize()
+        let config: Config = settings.try_deserialize()?;
+        config.subscription_trigger.validate().map_err(|err| {
+            ConfigError::Message(format!("Invalid subscription_trigger config: {}", err))
+        })?;
+        Ok(config)
     }
 }
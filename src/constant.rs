@@ -3,42 +3,23 @@ use lazy_static::lazy_static;
 
 pub const REQUEST_RELAY_TIMEOUT: u64 = 40;
 
-// pub const RESPONSE_RELAY_TIMEOUT: u64 = 40;
+pub const RESPONSE_RELAY_TIMEOUT: u64 = 40;
 pub const MAX_GATEWAY_RETRIES: u8 = 2;
 pub const MAX_TX_RECEIPT_RETRIES: u8 = 5;
 pub const MAX_RETRY_ON_PROVIDER_ERROR: u8 = 5;
 
 pub const GATEWAY_BLOCK_STATES_TO_MAINTAIN: u64 = 5;
 pub const WAIT_BEFORE_CHECKING_BLOCK: u64 = 100;
+pub const COMMON_CHAIN_CLOCK_SYNC_INTERVAL: u64 = 10;
+pub const SUBSCRIPTION_INSTANCE_HISTORY_LEN: usize = 32;
+pub const DEFAULT_SUBSCRIPTION_PREVIEW_INSTANCES: usize = 10;
+pub const MAX_SUBSCRIPTION_PREVIEW_INSTANCES: usize = 100;
+pub const MUTABLE_CONFIG_SIGNATURE_VALIDITY: u64 = 300;
+pub const GAS_WALLET_ROTATION_DRAIN_TIMEOUT: u64 = 120;
+pub const REPLACEMENT_GAS_PRICE_BUMP_PERCENT: u64 = 25;
+pub const DEREGISTRATION_POLL_INTERVAL: u64 = 10;
 
 lazy_static! {
     pub static ref MIN_GATEWAY_STAKE: U256 = U256::from(111_111_111_111_111_110_000 as u128);
     pub static ref GATEWAY_STAKE_ADJUSTMENT_FACTOR: U256 = U256::from(1e18 as u128);
 }
-
-// Event signatures
-pub const COMMON_CHAIN_GATEWAY_REGISTERED_EVENT: &str =
-    "GatewayRegistered(address,address,uint256[])";
-pub const COMMON_CHAIN_GATEWAY_DEREGISTERED_EVENT: &str = "GatewayDeregistered(address)";
-pub const COMMON_CHAIN_GATEWAY_CHAIN_ADDED_EVENT: &str = "ChainAdded(address,uint256)";
-pub const COMMON_CHAIN_GATEWAY_CHAIN_REMOVED_EVENT: &str = "ChainRemoved(address,uint256)";
-
-pub const REQUEST_CHAIN_GATEWAY_REGISTERED_EVENT: &str = "GatewayRegistered(address,address)";
-
-pub const REQUEST_CHAIN_JOB_RELAYED_EVENT: &str =
-    "JobRelayed(uint256,uint8,bytes32,bytes,uint256,uint256,uint256,uint256,address,address,uint256,uint256)";
-pub const REQUEST_CHAIN_JOB_CANCELLED_EVENT: &str = "JobCancelled(uint256)";
-pub const REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT: &str =
-    "JobSubscriptionStarted(uint256,uint8,address,uint256,uint256,uint256,uint256,address,bytes32,bytes,uint256)";
-pub const REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT: &str =
-    "JobSubscriptionJobParamsUpdated(uint256,bytes32,bytes)";
-pub const REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT: &str =
-    "JobSubscriptionTerminationParamsUpdated(uint256,uint256)";
-
-pub const COMMON_CHAIN_JOB_RELAYED_EVENT: &str =
-    "JobRelayed(uint256,uint256,uint8,address,address)";
-pub const COMMON_CHAIN_JOB_RESPONDED_EVENT: &str = "JobResponded(uint256,bytes,uint256,uint8)";
-pub const COMMON_CHAIN_JOB_RESOURCE_UNAVAILABLE_EVENT: &str =
-    "JobResourceUnavailable(uint256,address)";
-pub const COMMON_CHAIN_GATEWAY_REASSIGNED_EVENT: &str =
-    "GatewayReassigned(uint256,address,address,uint8)";
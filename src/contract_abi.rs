@@ -4,3 +4,104 @@ abigen!(GatewaysContract, "./Gateways.json",);
 abigen!(RelayContract, "./Relay.json",);
 abigen!(GatewayJobsContract, "./GatewayJobs.json",);
 abigen!(RelaySubscriptionsContract, "./RelaySubscriptions.json");
+
+// Events emitted with the same name by more than one contract
+pub use gateway_jobs_contract::{
+    JobRelayedFilter as CommonChainJobRelayedFilter,
+    JobRespondedFilter as CommonChainJobRespondedFilter,
+};
+pub use gateways_contract::{
+    GatewayDeregisteredFilter as CommonChainGatewayDeregisteredFilter,
+    GatewayRegisteredFilter as CommonChainGatewayRegisteredFilter,
+};
+pub use relay_contract::{
+    GatewayRegisteredFilter as RequestChainGatewayRegisteredFilter,
+    JobRelayedFilter as RequestChainJobRelayedFilter,
+};
+
+#[cfg(test)]
+mod contract_abi_tests {
+    use ethers::contract::EthEvent;
+
+    use super::*;
+
+    // The handlers rely on the parameter layout of these events, any change to the ABI files
+    // must be reflected here and in the handlers.
+    #[test]
+    fn test_event_signatures_match_abi() {
+        let events = [
+            (
+                CommonChainGatewayRegisteredFilter::abi_signature(),
+                "GatewayRegistered(address,address,uint256[])",
+            ),
+            (
+                CommonChainGatewayDeregisteredFilter::abi_signature(),
+                "GatewayDeregistered(address)",
+            ),
+            (
+                ChainAddedFilter::abi_signature(),
+                "ChainAdded(address,uint256)",
+            ),
+            (
+                ChainRemovedFilter::abi_signature(),
+                "ChainRemoved(address,uint256)",
+            ),
+            (
+                RequestChainGatewayRegisteredFilter::abi_signature(),
+                "GatewayRegistered(address,address)",
+            ),
+            (
+                RequestChainJobRelayedFilter::abi_signature(),
+                "JobRelayed(uint256,uint8,bytes32,bytes,uint256,uint256,uint256,uint256,address,address,uint256,uint256)",
+            ),
+            (
+                JobCancelledFilter::abi_signature(),
+                "JobCancelled(uint256)",
+            ),
+            (
+                JobSubscriptionStartedFilter::abi_signature(),
+                "JobSubscriptionStarted(uint256,uint8,address,uint256,uint256,uint256,uint256,address,bytes32,bytes,uint256)",
+            ),
+            (
+                JobSubscriptionJobParamsUpdatedFilter::abi_signature(),
+                "JobSubscriptionJobParamsUpdated(uint256,bytes32,bytes)",
+            ),
+            (
+                JobSubscriptionTerminationParamsUpdatedFilter::abi_signature(),
+                "JobSubscriptionTerminationParamsUpdated(uint256,uint256)",
+            ),
+            (
+                JobSubscriptionTerminatedFilter::abi_signature(),
+                "JobSubscriptionTerminated(uint256)",
+            ),
+            (
+                JobSubscriptionFundsDepositedFilter::abi_signature(),
+                "JobSubscriptionFundsDeposited(uint256,address,uint256,uint256)",
+            ),
+            (
+                JobSubscriptionFundsWithdrawnFilter::abi_signature(),
+                "JobSubscriptionFundsWithdrawn(uint256,address,uint256,uint256,bool)",
+            ),
+            (
+                CommonChainJobRelayedFilter::abi_signature(),
+                "JobRelayed(uint256,uint256,uint8,address,address)",
+            ),
+            (
+                CommonChainJobRespondedFilter::abi_signature(),
+                "JobResponded(uint256,bytes,uint256,uint8)",
+            ),
+            (
+                JobResourceUnavailableFilter::abi_signature(),
+                "JobResourceUnavailable(uint256,address)",
+            ),
+            (
+                GatewayReassignedFilter::abi_signature(),
+                "GatewayReassigned(uint256,address,address,uint8)",
+            ),
+        ];
+
+        for (abi_signature, expected_abi_signature) in events {
+            assert_eq!(abi_signature, expected_abi_signature);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{self, AtomicU64};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::Notify;
+
+use crate::clock::Clock;
+use crate::constant::REQUEST_RELAY_TIMEOUT;
+use crate::model::{
+    DeadlineQueue, DeadlineQueueItem, DeadlineQueueMetrics, DequeuedItem, Job, SubscriptionJob,
+};
+
+// Reverse ordering to make the BinaryHeap a min-heap on the deadline, items with the same
+// deadline are taken in the order they were pushed
+impl<T> PartialEq for DeadlineQueueItem<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for DeadlineQueueItem<T> {}
+
+impl<T> PartialOrd for DeadlineQueueItem<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for DeadlineQueueItem<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.deadline, other.sequence).cmp(&(self.deadline, self.sequence))
+    }
+}
+
+impl<T> Default for DeadlineQueue<T> {
+    fn default() -> Self {
+        DeadlineQueue {
+            items: Mutex::new(BinaryHeap::new()),
+            next_sequence: AtomicU64::new(0),
+            dropped_items: AtomicU64::new(0),
+            notify: Notify::new(),
+        }
+    }
+}
+
+impl<T> DeadlineQueue<T> {
+    pub fn push(&self, item: T, deadline: u64) {
+        let sequence = self.next_sequence.fetch_add(1, atomic::Ordering::Relaxed);
+
+        // Scope for lock on items
+        {
+            let mut items = self.items.lock().unwrap();
+            items.push(DeadlineQueueItem {
+                deadline,
+                sequence,
+                enqueued_at: Instant::now(),
+                item,
+            });
+        }
+
+        self.notify.notify_one();
+    }
+
+    /// Takes the item with the earliest deadline, if any. Items whose deadline is before the
+    /// current timestamp are returned as expired, so that the caller can clean up after them
+    /// instead of sending a transaction bound to revert.
+    pub fn try_pop(&self, current_timestamp: u64) -> Option<DequeuedItem<T>> {
+        let queue_item = self.items.lock().unwrap().pop()?;
+
+        if queue_item.deadline < current_timestamp {
+            self.dropped_items.fetch_add(1, atomic::Ordering::Relaxed);
+            return Some(DequeuedItem::Expired(queue_item.item));
+        }
+        Some(DequeuedItem::Due(queue_item.item))
+    }
+
+    /// Waits for the item with the earliest deadline.
+    pub async fn pop(&self, clock: &dyn Clock) -> DequeuedItem<T> {
+        loop {
+            let notified = self.notify.notified();
+            if let Some(dequeued_item) = self.try_pop(clock.now()) {
+                return dequeued_item;
+            }
+            notified.await;
+        }
+    }
+
+    pub fn metrics(&self) -> DeadlineQueueMetrics {
+        let items = self.items.lock().unwrap();
+
+        DeadlineQueueMetrics {
+            depth: items.len(),
+            oldest_item_age_ms: items
+                .iter()
+                .map(|queue_item| queue_item.enqueued_at.elapsed().as_millis() as u64)
+                .max(),
+            dropped_items: self.dropped_items.load(atomic::Ordering::Relaxed),
+        }
+    }
+}
+
+/// Moves the items sent over the channel into the queue with their deadlines.
+pub async fn forward_to_deadline_queue<T>(
+    mut rx: Receiver<T>,
+    queue: Arc<DeadlineQueue<T>>,
+    deadline: impl Fn(&T) -> u64,
+) {
+    while let Some(item) = rx.recv().await {
+        let item_deadline = deadline(&item);
+        queue.push(item, item_deadline);
+    }
+}
+
+impl Job {
+    /// Deadline of the relay or reassign transaction of the job on the common chain.
+    ///
+    /// Each relay attempt is given `REQUEST_RELAY_TIMEOUT` seconds from the job start time,
+    /// with one more window of slack for the jobs picked up late.
+    pub fn relay_txn_deadline(&self) -> u64 {
+        relay_attempt_deadline(self.starttime.as_u64(), self.sequence_number)
+    }
+}
+
+impl SubscriptionJob {
+    /// Deadline to relay the instance triggered at the trigger time, the earlier of the end of
+    /// its user timeout and the deadline of its relay transaction on the common chain.
+    pub fn instance_deadline(&self, trigger_time: u64) -> u64 {
+        // User timeout is in milliseconds
+        let user_timeout_deadline =
+            trigger_time.saturating_add(self.user_timeout.as_u64().div_ceil(1000));
+        // Instances are relayed as the first attempt of a job started at their trigger time
+        user_timeout_deadline.min(relay_attempt_deadline(trigger_time, 1))
+    }
+}
+
+fn relay_attempt_deadline(starttime: u64, sequence_number: u8) -> u64 {
+    starttime.saturating_add(REQUEST_RELAY_TIMEOUT * (sequence_number as u64 + 1))
+}
+
+#[cfg(test)]
+mod deadline_queue_tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_util::MockClock;
+
+    fn pop_due(queue: &DeadlineQueue<u64>, current_timestamp: u64) -> Option<u64> {
+        match queue.try_pop(current_timestamp) {
+            Some(DequeuedItem::Due(item)) => Some(item),
+            Some(DequeuedItem::Expired(item)) => panic!("Item {} expired", item),
+            None => None,
+        }
+    }
+
+    #[test]
+    fn test_pop_by_deadline() {
+        let queue = DeadlineQueue::default();
+
+        queue.push(1, 1030);
+        queue.push(2, 1010);
+        queue.push(3, 1020);
+        queue.push(4, 1010);
+
+        assert_eq!(pop_due(&queue, 1000), Some(2));
+        assert_eq!(pop_due(&queue, 1000), Some(4));
+        assert_eq!(pop_due(&queue, 1000), Some(3));
+        assert_eq!(pop_due(&queue, 1000), Some(1));
+        assert_eq!(pop_due(&queue, 1000), None);
+    }
+
+    #[test]
+    fn test_pop_expired() {
+        let queue = DeadlineQueue::default();
+
+        queue.push(1, 1000);
+        queue.push(2, 1010);
+
+        // The deadline itself is still in time
+        assert!(matches!(
+            queue.try_pop(1001),
+            Some(DequeuedItem::Expired(1))
+        ));
+        assert_eq!(pop_due(&queue, 1010), Some(2));
+        assert_eq!(queue.metrics().dropped_items, 1);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let queue = DeadlineQueue::default();
+
+        assert_eq!(queue.metrics().depth, 0);
+        assert_eq!(queue.metrics().oldest_item_age_ms, None);
+
+        queue.push(1, 1030);
+        std::thread::sleep(Duration::from_millis(20));
+        queue.push(2, 1010);
+
+        let metrics = queue.metrics();
+        assert_eq!(metrics.depth, 2);
+        assert!(metrics.oldest_item_age_ms.unwrap() >= 20);
+        assert_eq!(metrics.dropped_items, 0);
+    }
+
+    #[tokio::test]
+    async fn test_forward_to_deadline_queue() {
+        let queue = Arc::new(DeadlineQueue::default());
+        let (tx, rx) = tokio::sync::mpsc::channel::<u64>(10);
+
+        tokio::spawn(forward_to_deadline_queue(
+            rx,
+            queue.clone(),
+            |item: &u64| 1000 + item,
+        ));
+
+        tx.send(20).await.unwrap();
+        tx.send(10).await.unwrap();
+
+        let clock = MockClock::new(1000);
+        assert!(matches!(
+            queue.pop(&clock).await,
+            DequeuedItem::Due(20) | DequeuedItem::Due(10)
+        ));
+
+        // Wait for the forwarded item once the queue is empty
+        let queue_clone = queue.clone();
+        let pop_handle = tokio::spawn(async move {
+            queue_clone.pop(&MockClock::new(1000)).await;
+            queue_clone.pop(&MockClock::new(1000)).await
+        });
+        tx.send(5).await.unwrap();
+
+        assert!(matches!(
+            tokio::time::timeout(Duration::from_secs(1), pop_handle)
+                .await
+                .unwrap()
+                .unwrap(),
+            DequeuedItem::Due(_)
+        ));
+        assert_eq!(queue.metrics().depth, 0);
+    }
+}
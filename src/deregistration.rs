@@ -0,0 +1,510 @@
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use ethers::contract::ContractCall;
+use ethers::prelude::*;
+use log::{error, info};
+use tokio::time::sleep;
+
+use crate::chain_util::ChainConnector;
+use crate::clock::{Clock, SystemClock};
+use crate::constant::DEREGISTRATION_POLL_INTERVAL;
+use crate::contract_abi::{GatewaysContract, RelayContract};
+use crate::event_decoder::uint_to_u64;
+use crate::gas_wallet_rotation::{drain_gas_wallet_txns, gas_wallet_chains};
+use crate::model::{
+    AppState, ConfigManager, ContractsClient, DeregisterGatewayBody, GasWallet,
+    GatewayDetailsResponse, RegistrationTarget,
+};
+use crate::sealed_state::persist_sealed_state;
+use crate::self_registration::{owner_http_rpc_client, OwnerProviderType, OwnerSignerArgs};
+
+/// Retires the registered gateway, `AppState::deregistering` is set by the caller beforehand.
+///
+/// With the signer of the owner, the gateway is drained on the common chain and deregistered on
+/// every chain once the draining period is over. Without it, the gateway keeps serving until the
+/// owner deregisters it on every chain. Either way the transactions in flight from the gas wallet
+/// are settled before the gateway is torn down, and the gateway keeps running on failure.
+pub async fn run_deregistration(
+    app_state: &AppState,
+    owner_signer: Option<GasWallet>,
+) -> anyhow::Result<()> {
+    let Some(contracts_client) = app_state.contracts_client.lock().unwrap().clone() else {
+        return Err(anyhow!("Contracts client not running"));
+    };
+    let Some(gas_wallet) = app_state.wallet.lock().unwrap().clone() else {
+        return Err(anyhow!("Mutable param wallet not configured yet!"));
+    };
+    let chains = gas_wallet_chains(app_state, Some(contracts_client.as_ref()));
+
+    // Keep new transactions from being sent with the gas wallet until the gateway is torn down
+    let _gas_wallet_guard = match owner_signer {
+        Some(owner_signer) => {
+            let target = RegistrationTarget::from(app_state);
+            drain_on_common_chain(
+                &target,
+                owner_signer.clone(),
+                contracts_client.clock.as_ref(),
+            )
+            .await?;
+            let gas_wallet_guard =
+                drain_gas_wallet_txns(&contracts_client, &gas_wallet, &chains).await?;
+            submit_deregistration(&target, owner_signer, contracts_client.request_chain_ids())
+                .await?;
+            gas_wallet_guard
+        }
+        None => {
+            wait_for_deregistration(&contracts_client).await;
+            drain_gas_wallet_txns(&contracts_client, &gas_wallet, &chains).await?
+        }
+    };
+
+    teardown_gateway(app_state).await;
+    Ok(())
+}
+
+/// Tears down the gateway deregistered by the owner without going through the admin server, once
+/// the transactions in flight from the gas wallet are settled. The gateway is of no use past the
+/// deregistration, so it is torn down even if they can't be settled.
+pub async fn teardown_deregistered_gateway(app_state: &AppState) {
+    let contracts_client = app_state.contracts_client.lock().unwrap().clone();
+    let gas_wallet = app_state.wallet.lock().unwrap().clone();
+
+    // Keep new transactions from being sent with the gas wallet until the gateway is torn down
+    let _gas_wallet_guard = match (contracts_client, gas_wallet) {
+        (Some(contracts_client), Some(gas_wallet)) => {
+            let chains = gas_wallet_chains(app_state, Some(contracts_client.as_ref()));
+            match drain_gas_wallet_txns(&contracts_client, &gas_wallet, &chains).await {
+                Ok(gas_wallet_guard) => Some(gas_wallet_guard),
+                Err(err) => {
+                    error!(
+                        "Failed to settle the transactions of the gas wallet: {:#}",
+                        err
+                    );
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    teardown_gateway(app_state).await;
+}
+
+/// Stops the contracts client and resets the registration status, the gateway can then be
+/// registered again with the same owner and gas wallet.
+pub async fn teardown_gateway(app_state: &AppState) {
+    let contracts_client = app_state.contracts_client.lock().unwrap().take();
+    if let Some(contracts_client) = contracts_client {
+        contracts_client.stop();
+    }
+
+    app_state.registered.store(false, Ordering::SeqCst);
+    *app_state
+        .registration_events_listener_active
+        .lock()
+        .unwrap() = false;
+    app_state.request_chain_ids.lock().unwrap().clear();
+    persist_sealed_state(app_state).await;
+
+    app_state.deregistering.store(false, Ordering::SeqCst);
+    info!("Gateway torn down, ready to be registered again");
+}
+
+// Drains the gateway on the common chain so that it is no longer selected for new jobs, and waits
+// for the draining period to be over
+async fn drain_on_common_chain(
+    target: &RegistrationTarget,
+    owner_signer: GasWallet,
+    clock: &dyn Clock,
+) -> anyhow::Result<()> {
+    let chain_id = target.common_chain_id;
+    let gateways_contract = GatewaysContract::new(
+        target.gateways_contract_addr,
+        owner_http_rpc_client(&target.common_chain_http_url, owner_signer, chain_id)?,
+    );
+
+    let (owner, _, draining, mut drain_start_time) = gateways_contract
+        .gateways(target.enclave_address)
+        .await
+        .context("Failed to fetch the gateway from the common chain")?;
+    if owner.is_zero() {
+        info!("Gateway already deregistered on the chain {}", chain_id);
+        return Ok(());
+    }
+
+    if !draining {
+        send_owner_txn(
+            chain_id,
+            gateways_contract.drain_gateway(target.enclave_address),
+        )
+        .await
+        .context("Failed to drain the gateway")?;
+        (_, _, _, drain_start_time) = gateways_contract
+            .gateways(target.enclave_address)
+            .await
+            .context("Failed to fetch the gateway from the common chain")?;
+    }
+
+    let draining_time_duration = gateways_contract
+        .draining_time_duration()
+        .await
+        .context("Failed to fetch the draining time duration from the common chain")?;
+    let drain_end_time = drain_start_time
+        .checked_add(draining_time_duration)
+        .and_then(|drain_end_time| u64::try_from(drain_end_time).ok())
+        .ok_or(anyhow!("Drain end time of the gateway out of range"))?;
+    info!(
+        "Gateway draining on the chain {} until {}",
+        chain_id, drain_end_time
+    );
+
+    loop {
+        let now = clock.now();
+        if now > drain_end_time {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(drain_end_time + 1 - now)).await;
+    }
+}
+
+// Deregisters the gateway with the transactions of the owner on the common chain and then on the
+// given request chains it is still registered on
+async fn submit_deregistration(
+    target: &RegistrationTarget,
+    owner_signer: GasWallet,
+    request_chain_ids: impl IntoIterator<Item = u64>,
+) -> anyhow::Result<()> {
+    let enclave_address = target.enclave_address;
+
+    let chain_id = target.common_chain_id;
+    let gateways_contract = GatewaysContract::new(
+        target.gateways_contract_addr,
+        owner_http_rpc_client(
+            &target.common_chain_http_url,
+            owner_signer.clone(),
+            chain_id,
+        )?,
+    );
+    let (owner, _, _, _) = gateways_contract
+        .gateways(enclave_address)
+        .await
+        .context("Failed to fetch the gateway from the common chain")?;
+    if !owner.is_zero() {
+        send_owner_txn(
+            chain_id,
+            gateways_contract.deregister_gateway(enclave_address),
+        )
+        .await
+        .context("Failed to deregister the gateway on the common chain")?;
+    }
+    info!("Gateway deregistered on the chain {}", chain_id);
+
+    // The gateway is of no use past the common chain deregistration, so the failures on the
+    // request chains are left for the owner to deregister there
+    for chain_id in request_chain_ids {
+        match deregister_on_request_chain(
+            target,
+            &gateways_contract,
+            owner_signer.clone(),
+            chain_id,
+        )
+        .await
+        {
+            Ok(()) => info!("Gateway deregistered on the chain {}", chain_id),
+            Err(err) => error!(
+                "Failed to deregister the gateway on the chain {}: {:#}",
+                chain_id, err
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+async fn deregister_on_request_chain(
+    target: &RegistrationTarget,
+    gateways_contract: &GatewaysContract<OwnerProviderType>,
+    owner_signer: GasWallet,
+    chain_id: u64,
+) -> anyhow::Result<()> {
+    let (relay_address, _, http_rpc_url, _) = gateways_contract
+        .request_chains(U256::from(chain_id))
+        .await
+        .context(format!(
+            "Failed to fetch the request chain data for chain id {}",
+            chain_id
+        ))?;
+
+    let relay_contract = RelayContract::new(
+        relay_address,
+        owner_http_rpc_client(&http_rpc_url, owner_signer, chain_id)?,
+    );
+    let owner = relay_contract
+        .gateway_owners(target.enclave_address)
+        .await
+        .context("Failed to fetch the gateway owner from the request chain")?;
+    if owner.is_zero() {
+        return Ok(());
+    }
+
+    send_owner_txn(
+        chain_id,
+        relay_contract.deregister_gateway(target.enclave_address),
+    )
+    .await?;
+    Ok(())
+}
+
+// Waits for the owner to deregister the gateway on the common chain and every request chain
+async fn wait_for_deregistration<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: &ContractsClient<M, C>,
+) {
+    info!("Waiting for the deregistration on the Common Chain and all Request Chains...");
+    loop {
+        match contracts_client.registration_status().await {
+            Ok((false, request_chain_ids_registered))
+                if request_chain_ids_registered.is_empty() =>
+            {
+                info!("Gateway deregistered on the Common Chain and all Request Chains");
+                return;
+            }
+            Ok(_) => {}
+            Err(err) => error!(
+                "Failed to fetch the registration status on chain: {:?}",
+                err
+            ),
+        }
+
+        sleep(Duration::from_secs(DEREGISTRATION_POLL_INTERVAL)).await;
+    }
+}
+
+// Sends the transaction of the owner and waits for its receipt
+async fn send_owner_txn(
+    chain_id: u64,
+    call: ContractCall<OwnerProviderType, ()>,
+) -> anyhow::Result<H256> {
+    let pending_txn = call
+        .send()
+        .await
+        .map_err(|err| anyhow!("Failed to send the transaction: {}", err))?;
+    let txn_hash = pending_txn.tx_hash();
+    info!("Transaction {:?} sent on the chain {}", txn_hash, chain_id);
+
+    match pending_txn.await {
+        Ok(Some(receipt)) if receipt.status == Some(U64::one()) => Ok(txn_hash),
+        Ok(Some(_)) => Err(anyhow!("Transaction {:?} reverted", txn_hash)),
+        Ok(None) => Err(anyhow!(
+            "Transaction {:?} dropped from the mempool",
+            txn_hash
+        )),
+        Err(err) => Err(anyhow!(
+            "Failed to confirm the transaction {:?}: {}",
+            txn_hash,
+            err
+        )),
+    }
+}
+
+// Arguments of the 'deregister' subcommand, deregistering a running gateway with the transactions
+// of its owner signed on this machine
+#[derive(Args)]
+pub struct DeregisterArgs {
+    // Url of the public server of the gateway
+    #[clap(long, value_parser, default_value = "http://127.0.0.1:6001")]
+    gateway_url: String,
+    // Url of the admin server of the gateway
+    #[clap(long, value_parser, default_value = "http://127.0.0.1:6002")]
+    admin_url: String,
+    #[clap(long, value_parser)]
+    admin_auth_token: Option<String>,
+    #[clap(flatten)]
+    owner_signer: OwnerSignerArgs,
+}
+
+impl DeregisterArgs {
+    /// Has the gateway wait for its deregistration, then drains it on the common chain and
+    /// deregisters it on every chain with the transactions of the owner signed here. The gateway
+    /// tears itself down once deregistered, the owner key never leaves this machine.
+    pub async fn run(self, config_file: &String) -> anyhow::Result<()> {
+        let config = ConfigManager::new(config_file)
+            .load_config()
+            .context("Failed to load the gateway config")?;
+        let owner_signer = self.owner_signer.connect().await?;
+        let client = reqwest::Client::new();
+
+        let gateway_details: GatewayDetailsResponse = client
+            .get(format!(
+                "{}/gateway-details",
+                self.gateway_url.trim_end_matches('/')
+            ))
+            .send()
+            .await
+            .context("Failed to fetch the gateway details from the gateway")?
+            .json()
+            .await
+            .context("Invalid gateway details")?;
+        if owner_signer.address() != gateway_details.owner_address {
+            return Err(anyhow!(
+                "Owner signer address {:?} does not match the enclave owner {:?}",
+                owner_signer.address(),
+                gateway_details.owner_address
+            ));
+        }
+
+        // Without the owner signer, the gateway keeps serving until it is deregistered on every
+        // chain and then tears itself down
+        let mut request = client
+            .post(format!(
+                "{}/deregister",
+                self.admin_url.trim_end_matches('/')
+            ))
+            .json(&DeregisterGatewayBody { owner_signer: None });
+        if let Some(admin_auth_token) = self.admin_auth_token {
+            request = request.bearer_auth(admin_auth_token);
+        }
+        let response = request
+            .send()
+            .await
+            .context("Failed to request the deregistration from the gateway")?;
+        let status = response.status();
+        let body = response.text().await?;
+        // A deregistration already in progress is resumed from here
+        if !status.is_success() && body != "Deregistration already in progress!" {
+            return Err(anyhow!(
+                "Deregistration request failed ({}): {}",
+                status,
+                body
+            ));
+        }
+
+        let target = RegistrationTarget {
+            enclave_address: gateway_details.enclave_address,
+            common_chain_id: config.common_chain_id,
+            common_chain_http_url: config.common_chain_http_url,
+            gateways_contract_addr: config.gateways_contract_addr,
+        };
+        // The request chains are no longer listed once deregistered on the common chain
+        let request_chain_ids = GatewaysContract::new(
+            target.gateways_contract_addr,
+            owner_http_rpc_client(
+                &target.common_chain_http_url,
+                owner_signer.clone(),
+                target.common_chain_id,
+            )?,
+        )
+        .get_gateway_chain_ids(target.enclave_address)
+        .await
+        .context("Failed to fetch the request chains of the gateway from the common chain")?
+        .into_iter()
+        .map(uint_to_u64)
+        .collect::<Result<Vec<u64>, _>>()
+        .context("Request chain id of the gateway on the common chain out of range")?;
+
+        drain_on_common_chain(&target, owner_signer.clone(), &SystemClock).await?;
+        submit_deregistration(&target, owner_signer, request_chain_ids).await?;
+
+        println!("Gateway {:?} deregistered", target.enclave_address);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod deregistration_tests {
+    use std::future::pending;
+
+    use tokio::time::timeout;
+
+    use super::*;
+    use crate::test_util::{
+        generate_app_state, generate_app_state_with_contracts_client, generate_contracts_client,
+        CHAIN_ID,
+    };
+
+    #[tokio::test]
+    async fn test_teardown_gateway() {
+        let app_state = generate_app_state().await;
+        let contracts_client = generate_contracts_client().await;
+
+        let service = tokio::spawn(pending::<()>());
+        contracts_client
+            .service_tasks
+            .lock()
+            .unwrap()
+            .push(service.abort_handle());
+        let listener = tokio::spawn(pending::<()>());
+        contracts_client
+            .request_chain_listeners
+            .lock()
+            .unwrap()
+            .insert(CHAIN_ID, listener.abort_handle());
+
+        *app_state.contracts_client.lock().unwrap() = Some(contracts_client.clone());
+        app_state.registered.store(true, Ordering::SeqCst);
+        *app_state
+            .registration_events_listener_active
+            .lock()
+            .unwrap() = true;
+        app_state.request_chain_ids.lock().unwrap().insert(CHAIN_ID);
+        app_state.deregistering.store(true, Ordering::SeqCst);
+
+        teardown_gateway(&app_state).await;
+
+        assert!(service.await.unwrap_err().is_cancelled());
+        assert!(listener.await.unwrap_err().is_cancelled());
+        assert!(contracts_client.service_tasks.lock().unwrap().is_empty());
+        assert!(contracts_client
+            .request_chain_listeners
+            .lock()
+            .unwrap()
+            .is_empty());
+        assert!(contracts_client
+            .request_chain_senders
+            .lock()
+            .unwrap()
+            .is_none());
+
+        assert!(app_state.contracts_client.lock().unwrap().is_none());
+        assert!(!app_state.registered.load(Ordering::SeqCst));
+        assert!(!*app_state
+            .registration_events_listener_active
+            .lock()
+            .unwrap());
+        assert!(app_state.request_chain_ids.lock().unwrap().is_empty());
+        assert!(!app_state.deregistering.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_run_deregistration_without_owner_signer() {
+        let (app_state, contracts_client) = generate_app_state_with_contracts_client().await;
+        app_state.registered.store(true, Ordering::SeqCst);
+        app_state.deregistering.store(true, Ordering::SeqCst);
+
+        // A transaction in flight from the gas wallet
+        let txn_permit = contracts_client.gas_wallet_lock.clone().read_owned().await;
+
+        // The test enclave is not registered on chain, so the deregistration is observed right
+        // away and the teardown waits for the transaction to be settled
+        let app_state_clone = app_state.clone();
+        let mut deregistration =
+            tokio::spawn(async move { run_deregistration(&app_state_clone, None).await });
+        assert!(timeout(Duration::from_millis(500), &mut deregistration)
+            .await
+            .is_err());
+        assert!(app_state.contracts_client.lock().unwrap().is_some());
+        assert!(app_state.registered.load(Ordering::SeqCst));
+
+        drop(txn_permit);
+        deregistration.await.unwrap().unwrap();
+
+        assert!(app_state.contracts_client.lock().unwrap().is_none());
+        assert!(!app_state.registered.load(Ordering::SeqCst));
+        assert!(app_state.request_chain_ids.lock().unwrap().is_empty());
+        assert!(!app_state.deregistering.load(Ordering::SeqCst));
+        // The gas wallet is released along with the teardown
+        assert!(contracts_client.gas_wallet_lock.try_write().is_ok());
+    }
+}
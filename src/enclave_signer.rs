@@ -0,0 +1,190 @@
+use anyhow::Context;
+use ethers::types::Address;
+use ethers::utils::{keccak256, public_key_to_address};
+use k256::ecdsa::{Error, SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+use std::fmt::Debug;
+use tokio::fs;
+
+/// Key of the enclave signing the EIP-712 messages verified by the contracts (job relays,
+/// gateway reassignments, job responses and the registration).
+pub trait EnclaveSigner: Debug + Send + Sync {
+    fn verifying_key(&self) -> VerifyingKey;
+
+    /// Signs the prehashed digest, returning the 65 bytes (r, s, v) signature with `v` being
+    /// 27 or 28 as expected by `ecrecover`.
+    fn sign_digest(&self, digest: &[u8; 32]) -> Result<Vec<u8>, Error>;
+
+    /// Key sealing the state persisted by the gateway, derived from the enclave secret so that
+    /// the state can only be unsealed with the same enclave key.
+    fn sealing_key(&self) -> [u8; 32];
+
+    fn address(&self) -> Address {
+        public_key_to_address(&self.verifying_key())
+    }
+
+    /// Uncompressed public key without the `0x04` prefix, as registered on the contracts.
+    fn public_key_hex(&self) -> String {
+        "0x".to_string()
+            + &hex::encode(&self.verifying_key().to_encoded_point(false).as_bytes()[1..])
+    }
+}
+
+fn derive_sealing_key(signing_key: &SigningKey) -> [u8; 32] {
+    let mut key_material = b"marlin.oyster.ServerlessGateway.sealing".to_vec();
+    key_material.extend_from_slice(&signing_key.to_bytes());
+    keccak256(key_material)
+}
+
+fn sign_digest_with_key(signing_key: &SigningKey, digest: &[u8; 32]) -> Result<Vec<u8>, Error> {
+    let (rs, v) = signing_key.sign_prehash_recoverable(digest)?;
+
+    let mut signature = rs.to_bytes().to_vec();
+    signature.push(27 + v.to_byte());
+    Ok(signature)
+}
+
+/// Enclave key read from the secret key file on the host.
+#[derive(Debug)]
+pub struct FileEnclaveSigner {
+    signing_key: SigningKey,
+}
+
+impl FileEnclaveSigner {
+    pub async fn from_file(path: &str) -> anyhow::Result<Self> {
+        let signing_key = SigningKey::from_slice(
+            fs::read(path)
+                .await
+                .context("Failed to read the enclave signer key")?
+                .as_slice(),
+        )
+        .context("Invalid enclave signer key")?;
+
+        Ok(Self { signing_key })
+    }
+}
+
+impl EnclaveSigner for FileEnclaveSigner {
+    fn verifying_key(&self) -> VerifyingKey {
+        *self.signing_key.verifying_key()
+    }
+
+    fn sign_digest(&self, digest: &[u8; 32]) -> Result<Vec<u8>, Error> {
+        sign_digest_with_key(&self.signing_key, digest)
+    }
+
+    fn sealing_key(&self) -> [u8; 32] {
+        derive_sealing_key(&self.signing_key)
+    }
+}
+
+/// Enclave key generated on startup, it never leaves the memory of the enclave and only its
+/// public key is exported (through `/gateway-details`).
+#[derive(Debug)]
+pub struct InMemoryEnclaveSigner {
+    signing_key: SigningKey,
+}
+
+impl InMemoryEnclaveSigner {
+    pub fn generate() -> Self {
+        Self {
+            signing_key: SigningKey::random(&mut OsRng),
+        }
+    }
+}
+
+impl EnclaveSigner for InMemoryEnclaveSigner {
+    fn verifying_key(&self) -> VerifyingKey {
+        *self.signing_key.verifying_key()
+    }
+
+    fn sign_digest(&self, digest: &[u8; 32]) -> Result<Vec<u8>, Error> {
+        sign_digest_with_key(&self.signing_key, digest)
+    }
+
+    fn sealing_key(&self) -> [u8; 32] {
+        derive_sealing_key(&self.signing_key)
+    }
+}
+
+#[cfg(test)]
+mod enclave_signer_tests {
+    use k256::ecdsa::{RecoveryId, Signature};
+
+    use super::*;
+
+    fn recover_address(digest: &[u8; 32], signature: &[u8]) -> Address {
+        let recovered_key = VerifyingKey::recover_from_prehash(
+            digest,
+            &Signature::from_slice(&signature[0..64]).unwrap(),
+            RecoveryId::try_from(signature[64] - 27).unwrap(),
+        )
+        .unwrap();
+        public_key_to_address(&recovered_key)
+    }
+
+    #[test]
+    fn test_in_memory_enclave_signer() {
+        let enclave_signer = InMemoryEnclaveSigner::generate();
+        let digest = keccak256("message");
+
+        let signature = enclave_signer.sign_digest(&digest).unwrap();
+
+        assert_eq!(signature.len(), 65);
+        assert!(signature[64] == 27 || signature[64] == 28);
+        assert_eq!(
+            recover_address(&digest, &signature),
+            enclave_signer.address()
+        );
+        // Every enclave generates a key of its own
+        assert_ne!(
+            InMemoryEnclaveSigner::generate().address(),
+            enclave_signer.address()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_enclave_signer() {
+        let signing_key = SigningKey::random(&mut OsRng);
+        let key_file = std::env::temp_dir().join(format!("enclave_key_{}", rand::random::<u64>()));
+        std::fs::write(&key_file, signing_key.to_bytes()).unwrap();
+
+        let enclave_signer = FileEnclaveSigner::from_file(key_file.to_str().unwrap())
+            .await
+            .unwrap();
+        std::fs::remove_file(&key_file).unwrap();
+
+        assert_eq!(enclave_signer.verifying_key(), *signing_key.verifying_key());
+        assert_eq!(
+            enclave_signer.public_key_hex(),
+            "0x".to_string()
+                + &hex::encode(
+                    &signing_key
+                        .verifying_key()
+                        .to_encoded_point(false)
+                        .as_bytes()[1..]
+                )
+        );
+
+        let digest = keccak256("message");
+        let signature = enclave_signer.sign_digest(&digest).unwrap();
+        assert_eq!(
+            recover_address(&digest, &signature),
+            enclave_signer.address()
+        );
+
+        // The sealing key is tied to the enclave key
+        assert_eq!(
+            enclave_signer.sealing_key(),
+            derive_sealing_key(&signing_key)
+        );
+        assert_ne!(
+            enclave_signer.sealing_key(),
+            InMemoryEnclaveSigner::generate().sealing_key()
+        );
+
+        assert!(FileEnclaveSigner::from_file(key_file.to_str().unwrap())
+            .await
+            .is_err());
+    }
+}
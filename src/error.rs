@@ -1,3 +1,5 @@
+use ethers::providers::ProviderError;
+use ethers::signers::WalletError;
 use ethers::types::U256;
 use thiserror::Error;
 
@@ -9,12 +11,22 @@ pub enum ServerlessError {
     JobDoesNotBelongToEnclave,
     #[error("Job is older than the maintained block states")]
     JobOlderThanMaintainedBlockStates,
+    #[error("Job start time: {0} is before the first cycle of the epoch")]
+    JobStartedBeforeEpoch(u64),
     #[error("No Gateways Registered in cycle: {0}")]
     NoGatewaysRegisteredInCycle(u64),
     #[error("No Gateways avaialble in cycle: {0} for the Request Chain: {1}")]
     NoValidGatewaysForChain(u64, u64),
-    #[error("No Subscription Job found for the subscription id: {0}")]
-    NoSubscriptionJobFound(U256),
+    #[error("No Subscription Job found for the subscription id: {1} of the Request Chain: {0}")]
+    NoSubscriptionJobFound(u64, U256),
+    #[error("Job ID: {1} is not laid out as the job ids of the Request Chain: {0}")]
+    JobIdNotOfRequestChain(u64, U256),
+    #[error("Common Chain Job ID: {0} does not map to a job of a Request Chain")]
+    InvalidCommonChainJobId(U256),
+    #[error("Invalid schedule: {0}")]
+    InvalidSchedule(String),
+    #[error("Provider error: {0}")]
+    ProviderError(String),
 
     #[cfg(test)]
     #[error("Invalid topic")]
@@ -28,3 +40,15 @@ pub enum ServerlessError {
     #[error("Empty Topic 0")]
     EmptyTopic0,
 }
+
+#[derive(Debug, Error)]
+pub enum GasWalletError {
+    #[error("Local wallet error: {0}")]
+    LocalWalletError(#[from] WalletError),
+    #[error("Remote signer error: {0}")]
+    RemoteSignerError(#[from] ProviderError),
+    #[error("Invalid signature returned by the remote signer: {0}")]
+    InvalidRemoteSignature(String),
+    #[error("{0} is not supported by the remote signer")]
+    UnsupportedByRemoteSigner(&'static str),
+}
@@ -0,0 +1,607 @@
+use ethers::abi::RawLog;
+use ethers::contract::EthEvent;
+use ethers::types::{Log, H256, U256};
+
+use crate::error::ServerlessError;
+
+// Fallible decoding of the event logs into the abigen generated event types of `contract_abi`.
+// Nothing here indexes into the topics or unwraps the decoded tokens, so a malformed log
+// (from any contract address) results in a `ServerlessError::LogDecodeFailure` instead of a panic.
+
+/// Decodes the log into the event type `E`, checking the event signature in topic 0.
+pub fn decode_event<E: EthEvent>(log: &Log) -> Result<E, ServerlessError> {
+    E::decode_log(&RawLog {
+        topics: log.topics.clone(),
+        data: log.data.to_vec(),
+    })
+    .map_err(|_| ServerlessError::LogDecodeFailure)
+}
+
+/// Returns the topic at the given index of the log, if present.
+pub fn topic(log: &Log, index: usize) -> Result<H256, ServerlessError> {
+    log.topics
+        .get(index)
+        .copied()
+        .ok_or(ServerlessError::LogDecodeFailure)
+}
+
+/// Converts a uint field of a decoded event into a u64, a value out of its range is treated as a
+/// malformed log.
+pub fn uint_to_u64(value: U256) -> Result<u64, ServerlessError> {
+    u64::try_from(value).map_err(|_| ServerlessError::LogDecodeFailure)
+}
+
+#[cfg(test)]
+mod event_decoder_tests {
+    use ethers::abi::{encode, Token};
+    use ethers::types::{Address, Bytes};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::contract_abi::{
+        ChainAddedFilter, ChainRemovedFilter, CommonChainGatewayDeregisteredFilter,
+        CommonChainGatewayRegisteredFilter, CommonChainJobRelayedFilter,
+        CommonChainJobRespondedFilter, GatewayReassignedFilter, JobCancelledFilter,
+        JobResourceUnavailableFilter, JobSubscriptionFundsDepositedFilter,
+        JobSubscriptionFundsWithdrawnFilter, JobSubscriptionJobParamsUpdatedFilter,
+        JobSubscriptionStartedFilter, JobSubscriptionTerminatedFilter,
+        JobSubscriptionTerminationParamsUpdatedFilter, RequestChainGatewayRegisteredFilter,
+        RequestChainJobRelayedFilter,
+    };
+
+    fn event_signatures() -> [H256; 17] {
+        [
+            CommonChainGatewayRegisteredFilter::signature(),
+            CommonChainGatewayDeregisteredFilter::signature(),
+            ChainAddedFilter::signature(),
+            ChainRemovedFilter::signature(),
+            RequestChainGatewayRegisteredFilter::signature(),
+            RequestChainJobRelayedFilter::signature(),
+            JobCancelledFilter::signature(),
+            JobSubscriptionStartedFilter::signature(),
+            JobSubscriptionJobParamsUpdatedFilter::signature(),
+            JobSubscriptionTerminationParamsUpdatedFilter::signature(),
+            JobSubscriptionTerminatedFilter::signature(),
+            JobSubscriptionFundsDepositedFilter::signature(),
+            JobSubscriptionFundsWithdrawnFilter::signature(),
+            CommonChainJobRelayedFilter::signature(),
+            CommonChainJobRespondedFilter::signature(),
+            JobResourceUnavailableFilter::signature(),
+            GatewayReassignedFilter::signature(),
+        ]
+    }
+
+    fn decode_all(log: &Log) {
+        let _ = decode_event::<CommonChainGatewayRegisteredFilter>(log);
+        let _ = decode_event::<CommonChainGatewayDeregisteredFilter>(log);
+        let _ = decode_event::<ChainAddedFilter>(log);
+        let _ = decode_event::<ChainRemovedFilter>(log);
+        let _ = decode_event::<RequestChainGatewayRegisteredFilter>(log);
+        let _ = decode_event::<RequestChainJobRelayedFilter>(log);
+        let _ = decode_event::<JobCancelledFilter>(log);
+        let _ = decode_event::<JobSubscriptionStartedFilter>(log);
+        let _ = decode_event::<JobSubscriptionJobParamsUpdatedFilter>(log);
+        let _ = decode_event::<JobSubscriptionTerminationParamsUpdatedFilter>(log);
+        let _ = decode_event::<JobSubscriptionTerminatedFilter>(log);
+        let _ = decode_event::<JobSubscriptionFundsDepositedFilter>(log);
+        let _ = decode_event::<JobSubscriptionFundsWithdrawnFilter>(log);
+        let _ = decode_event::<CommonChainJobRelayedFilter>(log);
+        let _ = decode_event::<CommonChainJobRespondedFilter>(log);
+        let _ = decode_event::<JobResourceUnavailableFilter>(log);
+        let _ = decode_event::<GatewayReassignedFilter>(log);
+    }
+
+    fn event_log(event_signature: H256, topics: Vec<H256>, data: Vec<Token>) -> Log {
+        let mut log_topics = vec![event_signature];
+        log_topics.extend(topics);
+        Log {
+            topics: log_topics,
+            data: encode(&data).into(),
+            ..Default::default()
+        }
+    }
+
+    fn address_topic(address: Address) -> H256 {
+        H256::from(address)
+    }
+
+    fn uint_topic(value: U256) -> H256 {
+        let mut topic = [0u8; 32];
+        value.to_big_endian(&mut topic);
+        H256::from(topic)
+    }
+
+    fn arb_u256() -> impl Strategy<Value = U256> {
+        any::<[u64; 4]>().prop_map(U256)
+    }
+
+    fn arb_address() -> impl Strategy<Value = Address> {
+        any::<[u8; 20]>().prop_map(Address::from)
+    }
+
+    fn arb_bytes() -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(any::<u8>(), 0..256)
+    }
+
+    fn arb_topics() -> impl Strategy<Value = Vec<H256>> {
+        prop::collection::vec(any::<[u8; 32]>().prop_map(H256::from), 0..5)
+    }
+
+    #[test]
+    fn test_decode_empty_log() {
+        let log = Log::default();
+
+        assert_eq!(
+            decode_event::<RequestChainJobRelayedFilter>(&log),
+            Err(ServerlessError::LogDecodeFailure)
+        );
+        assert_eq!(
+            decode_event::<CommonChainJobRespondedFilter>(&log),
+            Err(ServerlessError::LogDecodeFailure)
+        );
+        assert_eq!(topic(&log, 0), Err(ServerlessError::LogDecodeFailure));
+        decode_all(&log);
+    }
+
+    #[test]
+    fn test_decode_missing_topics() {
+        let log = event_log(
+            GatewayReassignedFilter::signature(),
+            vec![],
+            vec![
+                Token::Address(Address::random()),
+                Token::Address(Address::random()),
+                Token::Uint(1.into()),
+            ],
+        );
+
+        assert_eq!(
+            decode_event::<GatewayReassignedFilter>(&log),
+            Err(ServerlessError::LogDecodeFailure)
+        );
+    }
+
+    #[test]
+    fn test_decode_wrong_event_signature() {
+        let log = event_log(
+            JobCancelledFilter::signature(),
+            vec![uint_topic(1.into())],
+            vec![],
+        );
+
+        assert_eq!(
+            decode_event::<JobCancelledFilter>(&log),
+            Ok(JobCancelledFilter { job_id: 1.into() })
+        );
+        assert_eq!(
+            decode_event::<JobResourceUnavailableFilter>(&log),
+            Err(ServerlessError::LogDecodeFailure)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_decode_arbitrary_log_never_panics(
+            topics in arb_topics(),
+            data in prop::collection::vec(any::<u8>(), 0..1024),
+        ) {
+            decode_all(&Log {
+                topics,
+                data: data.into(),
+                ..Default::default()
+            });
+        }
+
+        #[test]
+        fn test_decode_arbitrary_data_with_known_signature_never_panics(
+            signature_index in 0..event_signatures().len(),
+            topics in arb_topics(),
+            data in prop::collection::vec(any::<u8>(), 0..1024),
+        ) {
+            let mut log_topics = vec![event_signatures()[signature_index]];
+            log_topics.extend(topics);
+            decode_all(&Log {
+                topics: log_topics,
+                data: data.into(),
+                ..Default::default()
+            });
+        }
+
+        #[test]
+        fn test_gateway_registered_event_round_trip(
+            enclave_address in arb_address(),
+            owner in arb_address(),
+            chain_ids in prop::collection::vec(arb_u256(), 0..8),
+        ) {
+            let log = event_log(
+                CommonChainGatewayRegisteredFilter::signature(),
+                vec![address_topic(enclave_address), address_topic(owner)],
+                vec![Token::Array(chain_ids.iter().cloned().map(Token::Uint).collect())],
+            );
+
+            prop_assert_eq!(
+                decode_event::<CommonChainGatewayRegisteredFilter>(&log),
+                Ok(CommonChainGatewayRegisteredFilter { enclave_address, owner, chain_ids })
+            );
+        }
+
+        #[test]
+        fn test_gateway_deregistered_event_round_trip(enclave_address in arb_address()) {
+            let log = event_log(
+                CommonChainGatewayDeregisteredFilter::signature(),
+                vec![address_topic(enclave_address)],
+                vec![],
+            );
+
+            prop_assert_eq!(
+                decode_event::<CommonChainGatewayDeregisteredFilter>(&log),
+                Ok(CommonChainGatewayDeregisteredFilter { enclave_address })
+            );
+        }
+
+        #[test]
+        fn test_gateway_chain_events_round_trip(
+            enclave_address in arb_address(),
+            chain_id in arb_u256(),
+        ) {
+            let log = event_log(
+                ChainAddedFilter::signature(),
+                vec![address_topic(enclave_address)],
+                vec![Token::Uint(chain_id)],
+            );
+            prop_assert_eq!(
+                decode_event::<ChainAddedFilter>(&log),
+                Ok(ChainAddedFilter { enclave_address, chain_id })
+            );
+
+            let log = event_log(
+                ChainRemovedFilter::signature(),
+                vec![address_topic(enclave_address)],
+                vec![Token::Uint(chain_id)],
+            );
+            prop_assert_eq!(
+                decode_event::<ChainRemovedFilter>(&log),
+                Ok(ChainRemovedFilter { enclave_address, chain_id })
+            );
+        }
+
+        #[test]
+        fn test_request_chain_gateway_registered_event_round_trip(
+            owner in arb_address(),
+            enclave_address in arb_address(),
+        ) {
+            let log = event_log(
+                RequestChainGatewayRegisteredFilter::signature(),
+                vec![address_topic(owner), address_topic(enclave_address)],
+                vec![],
+            );
+
+            prop_assert_eq!(
+                decode_event::<RequestChainGatewayRegisteredFilter>(&log),
+                Ok(RequestChainGatewayRegisteredFilter { owner, enclave_address })
+            );
+        }
+
+        #[test]
+        fn test_request_chain_job_relayed_event_round_trip(
+            job_id in arb_u256(),
+            env in any::<u8>(),
+            codehash in any::<[u8; 32]>(),
+            code_inputs in arb_bytes(),
+            uints in any::<[[u64; 4]; 6]>(),
+            refund_account in arb_address(),
+            callback_contract in arb_address(),
+        ) {
+            let uints = uints.map(U256);
+            let log = event_log(
+                RequestChainJobRelayedFilter::signature(),
+                vec![uint_topic(job_id), uint_topic(env.into())],
+                vec![
+                    Token::FixedBytes(codehash.to_vec()),
+                    Token::Bytes(code_inputs.clone()),
+                    Token::Uint(uints[0]),
+                    Token::Uint(uints[1]),
+                    Token::Uint(uints[2]),
+                    Token::Uint(uints[3]),
+                    Token::Address(refund_account),
+                    Token::Address(callback_contract),
+                    Token::Uint(uints[4]),
+                    Token::Uint(uints[5]),
+                ],
+            );
+
+            prop_assert_eq!(
+                decode_event::<RequestChainJobRelayedFilter>(&log),
+                Ok(RequestChainJobRelayedFilter {
+                    job_id,
+                    env,
+                    codehash,
+                    code_inputs: Bytes::from(code_inputs),
+                    user_timeout: uints[0],
+                    max_gas_price: uints[1],
+                    usdc_deposit: uints[2],
+                    callback_deposit: uints[3],
+                    refund_account,
+                    callback_contract,
+                    start_time: uints[4],
+                    callback_gas_limit: uints[5],
+                })
+            );
+        }
+
+        #[test]
+        fn test_job_cancelled_event_round_trip(job_id in arb_u256()) {
+            let log = event_log(JobCancelledFilter::signature(), vec![uint_topic(job_id)], vec![]);
+
+            prop_assert_eq!(
+                decode_event::<JobCancelledFilter>(&log),
+                Ok(JobCancelledFilter { job_id })
+            );
+        }
+
+        #[test]
+        fn test_job_subscription_started_event_round_trip(
+            job_subs_id in arb_u256(),
+            env in any::<u8>(),
+            job_subscriber in arb_address(),
+            uints in any::<[[u64; 4]; 5]>(),
+            refund_account in arb_address(),
+            codehash in any::<[u8; 32]>(),
+            code_inputs in arb_bytes(),
+        ) {
+            let uints = uints.map(U256);
+            let log = event_log(
+                JobSubscriptionStartedFilter::signature(),
+                vec![
+                    uint_topic(job_subs_id),
+                    uint_topic(env.into()),
+                    address_topic(job_subscriber),
+                ],
+                vec![
+                    Token::Uint(uints[0]),
+                    Token::Uint(uints[1]),
+                    Token::Uint(uints[2]),
+                    Token::Uint(uints[3]),
+                    Token::Address(refund_account),
+                    Token::FixedBytes(codehash.to_vec()),
+                    Token::Bytes(code_inputs.clone()),
+                    Token::Uint(uints[4]),
+                ],
+            );
+
+            prop_assert_eq!(
+                decode_event::<JobSubscriptionStartedFilter>(&log),
+                Ok(JobSubscriptionStartedFilter {
+                    job_subs_id,
+                    env,
+                    job_subscriber,
+                    periodic_gap: uints[0],
+                    usdc_deposit: uints[1],
+                    termination_timestamp: uints[2],
+                    user_timeout: uints[3],
+                    refund_account,
+                    codehash,
+                    code_inputs: Bytes::from(code_inputs),
+                    start_time: uints[4],
+                })
+            );
+        }
+
+        #[test]
+        fn test_job_subscription_job_params_updated_event_round_trip(
+            job_subs_id in arb_u256(),
+            codehash in any::<[u8; 32]>(),
+            code_inputs in arb_bytes(),
+        ) {
+            let log = event_log(
+                JobSubscriptionJobParamsUpdatedFilter::signature(),
+                vec![uint_topic(job_subs_id)],
+                vec![
+                    Token::FixedBytes(codehash.to_vec()),
+                    Token::Bytes(code_inputs.clone()),
+                ],
+            );
+
+            prop_assert_eq!(
+                decode_event::<JobSubscriptionJobParamsUpdatedFilter>(&log),
+                Ok(JobSubscriptionJobParamsUpdatedFilter {
+                    job_subs_id,
+                    codehash,
+                    code_inputs: Bytes::from(code_inputs),
+                })
+            );
+        }
+
+        #[test]
+        fn test_job_subscription_termination_params_updated_event_round_trip(
+            job_subs_id in arb_u256(),
+            termination_timestamp in arb_u256(),
+        ) {
+            let log = event_log(
+                JobSubscriptionTerminationParamsUpdatedFilter::signature(),
+                vec![uint_topic(job_subs_id)],
+                vec![Token::Uint(termination_timestamp)],
+            );
+
+            prop_assert_eq!(
+                decode_event::<JobSubscriptionTerminationParamsUpdatedFilter>(&log),
+                Ok(JobSubscriptionTerminationParamsUpdatedFilter {
+                    job_subs_id,
+                    termination_timestamp,
+                })
+            );
+        }
+
+        #[test]
+        fn test_job_subscription_terminated_event_round_trip(job_subs_id in arb_u256()) {
+            let log = event_log(
+                JobSubscriptionTerminatedFilter::signature(),
+                vec![uint_topic(job_subs_id)],
+                vec![],
+            );
+
+            prop_assert_eq!(
+                decode_event::<JobSubscriptionTerminatedFilter>(&log),
+                Ok(JobSubscriptionTerminatedFilter { job_subs_id })
+            );
+        }
+
+        #[test]
+        fn test_job_subscription_funds_deposited_event_round_trip(
+            job_subs_id in arb_u256(),
+            depositor in arb_address(),
+            usdc_deposit in arb_u256(),
+            callback_deposit in arb_u256(),
+        ) {
+            let log = event_log(
+                JobSubscriptionFundsDepositedFilter::signature(),
+                vec![uint_topic(job_subs_id), address_topic(depositor)],
+                vec![Token::Uint(usdc_deposit), Token::Uint(callback_deposit)],
+            );
+
+            prop_assert_eq!(
+                decode_event::<JobSubscriptionFundsDepositedFilter>(&log),
+                Ok(JobSubscriptionFundsDepositedFilter {
+                    job_subs_id,
+                    depositor,
+                    usdc_deposit,
+                    callback_deposit,
+                })
+            );
+        }
+
+        #[test]
+        fn test_job_subscription_funds_withdrawn_event_round_trip(
+            job_subs_id in arb_u256(),
+            withdrawer in arb_address(),
+            usdc_amount_withdrawn in arb_u256(),
+            callback_amount_withdrawn in arb_u256(),
+            success in any::<bool>(),
+        ) {
+            let log = event_log(
+                JobSubscriptionFundsWithdrawnFilter::signature(),
+                vec![uint_topic(job_subs_id), address_topic(withdrawer)],
+                vec![
+                    Token::Uint(usdc_amount_withdrawn),
+                    Token::Uint(callback_amount_withdrawn),
+                    Token::Bool(success),
+                ],
+            );
+
+            prop_assert_eq!(
+                decode_event::<JobSubscriptionFundsWithdrawnFilter>(&log),
+                Ok(JobSubscriptionFundsWithdrawnFilter {
+                    job_subs_id,
+                    withdrawer,
+                    usdc_amount_withdrawn,
+                    callback_amount_withdrawn,
+                    success,
+                })
+            );
+        }
+
+        #[test]
+        fn test_common_chain_job_relayed_event_round_trip(
+            job_id in arb_u256(),
+            exec_job_id in arb_u256(),
+            env in any::<u8>(),
+            job_owner in arb_address(),
+            gateway in arb_address(),
+        ) {
+            let log = event_log(
+                CommonChainJobRelayedFilter::signature(),
+                vec![uint_topic(job_id)],
+                vec![
+                    Token::Uint(exec_job_id),
+                    Token::Uint(env.into()),
+                    Token::Address(job_owner),
+                    Token::Address(gateway),
+                ],
+            );
+
+            prop_assert_eq!(
+                decode_event::<CommonChainJobRelayedFilter>(&log),
+                Ok(CommonChainJobRelayedFilter { job_id, exec_job_id, env, job_owner, gateway })
+            );
+        }
+
+        #[test]
+        fn test_job_responded_event_round_trip(
+            job_id in arb_u256(),
+            output in arb_bytes(),
+            total_time in arb_u256(),
+            error_code in any::<u8>(),
+        ) {
+            let log = event_log(
+                CommonChainJobRespondedFilter::signature(),
+                vec![uint_topic(job_id)],
+                vec![
+                    Token::Bytes(output.clone()),
+                    Token::Uint(total_time),
+                    Token::Uint(error_code.into()),
+                ],
+            );
+
+            prop_assert_eq!(
+                decode_event::<CommonChainJobRespondedFilter>(&log),
+                Ok(CommonChainJobRespondedFilter {
+                    job_id,
+                    output: Bytes::from(output),
+                    total_time,
+                    error_code,
+                })
+            );
+        }
+
+        #[test]
+        fn test_job_resource_unavailable_event_round_trip(
+            job_id in arb_u256(),
+            gateway in arb_address(),
+        ) {
+            let log = event_log(
+                JobResourceUnavailableFilter::signature(),
+                vec![uint_topic(job_id), address_topic(gateway)],
+                vec![],
+            );
+
+            prop_assert_eq!(
+                decode_event::<JobResourceUnavailableFilter>(&log),
+                Ok(JobResourceUnavailableFilter { job_id, gateway })
+            );
+        }
+
+        #[test]
+        fn test_gateway_reassigned_event_round_trip(
+            job_id in arb_u256(),
+            prev_gateway in arb_address(),
+            reporter_gateway in arb_address(),
+            sequence_id in any::<u8>(),
+        ) {
+            let log = event_log(
+                GatewayReassignedFilter::signature(),
+                vec![uint_topic(job_id)],
+                vec![
+                    Token::Address(prev_gateway),
+                    Token::Address(reporter_gateway),
+                    Token::Uint(sequence_id.into()),
+                ],
+            );
+
+            prop_assert_eq!(
+                decode_event::<GatewayReassignedFilter>(&log),
+                Ok(GatewayReassignedFilter { job_id, prev_gateway, reporter_gateway, sequence_id })
+            );
+        }
+
+        #[test]
+        fn test_uint_to_u64(value in prop_oneof![any::<u64>().prop_map(U256::from), arb_u256()]) {
+            match uint_to_u64(value) {
+                Ok(converted) => prop_assert_eq!(U256::from(converted), value),
+                Err(err) => {
+                    prop_assert!(value > U256::from(u64::MAX));
+                    prop_assert_eq!(err, ServerlessError::LogDecodeFailure);
+                }
+            }
+        }
+    }
+}
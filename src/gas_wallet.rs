@@ -0,0 +1,387 @@
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::{Address, Bytes, Signature};
+use ethers::utils::rlp::Rlp;
+use tokio::fs;
+
+use crate::error::GasWalletError;
+use crate::model::{GasWallet, GasWalletConfig, RemoteSigner};
+
+impl GasWalletConfig {
+    /// Address of the configured gas wallet, known without unlocking the keystore or connecting
+    /// to the remote signer.
+    pub fn address(&self) -> anyhow::Result<Address> {
+        match self {
+            GasWalletConfig::Local { gas_key_hex } => Ok(local_gas_wallet(gas_key_hex)?.address()),
+            GasWalletConfig::Keystore { address, .. } | GasWalletConfig::Remote { address, .. } => {
+                Ok(*address)
+            }
+        }
+    }
+}
+
+impl GasWallet {
+    /// Sets up the configured gas wallet, decoding the gas key, unlocking the keystore or
+    /// connecting to the remote signer.
+    pub async fn from_config(
+        gas_wallet_config: &GasWalletConfig,
+        chain_id: u64,
+    ) -> anyhow::Result<Self> {
+        match gas_wallet_config {
+            GasWalletConfig::Local { gas_key_hex } => Ok(GasWallet::Local(
+                local_gas_wallet(gas_key_hex)?.with_chain_id(chain_id),
+            )),
+            GasWalletConfig::Keystore {
+                keystore_file,
+                password_file,
+                address,
+            } => {
+                let password = fs::read_to_string(password_file)
+                    .await
+                    .context("Failed to read the gas keystore password")?;
+                let wallet = LocalWallet::decrypt_keystore(
+                    keystore_file,
+                    password.trim_end_matches(['\r', '\n']),
+                )
+                .context("Failed to decrypt the gas keystore")?;
+                if wallet.address() != *address {
+                    return Err(anyhow!(
+                        "Gas keystore doesn't hold the key of the address {:?}",
+                        address
+                    ));
+                }
+
+                Ok(GasWallet::Local(wallet.with_chain_id(chain_id)))
+            }
+            GasWalletConfig::Remote { url, address } => Ok(GasWallet::Remote(
+                RemoteSigner::connect(url, *address)
+                    .await?
+                    .with_chain_id(chain_id),
+            )),
+        }
+    }
+}
+
+fn local_gas_wallet(gas_key_hex: &str) -> anyhow::Result<LocalWallet> {
+    let mut bytes32_gas_key = [0u8; 32];
+    hex::decode_to_slice(gas_key_hex, &mut bytes32_gas_key).map_err(|err| {
+        anyhow!(
+            "Failed to hex decode the gas private key into 32 bytes: {:?}",
+            err
+        )
+    })?;
+    LocalWallet::from_bytes(&bytes32_gas_key)
+        .map_err(|err| anyhow!("Invalid gas private key provided: {:?}", err))
+}
+
+#[async_trait]
+impl Signer for GasWallet {
+    type Error = GasWalletError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            GasWallet::Local(wallet) => Ok(wallet.sign_message(message).await?),
+            GasWallet::Remote(remote_signer) => remote_signer.sign_message(message).await,
+        }
+    }
+
+    async fn sign_transaction(&self, message: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            GasWallet::Local(wallet) => Ok(wallet.sign_transaction(message).await?),
+            GasWallet::Remote(remote_signer) => remote_signer.sign_transaction(message).await,
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            GasWallet::Local(wallet) => Ok(wallet.sign_typed_data(payload).await?),
+            GasWallet::Remote(remote_signer) => remote_signer.sign_typed_data(payload).await,
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            GasWallet::Local(wallet) => wallet.address(),
+            GasWallet::Remote(remote_signer) => remote_signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            GasWallet::Local(wallet) => wallet.chain_id(),
+            GasWallet::Remote(remote_signer) => remote_signer.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            GasWallet::Local(wallet) => GasWallet::Local(wallet.with_chain_id(chain_id)),
+            GasWallet::Remote(remote_signer) => {
+                GasWallet::Remote(remote_signer.with_chain_id(chain_id))
+            }
+        }
+    }
+}
+
+impl RemoteSigner {
+    /// Connects to the remote signer and checks that it holds the key of the address.
+    pub async fn connect(url: &str, address: Address) -> anyhow::Result<Self> {
+        let http_rpc_client = Provider::<Http>::try_from(url)
+            .context(format!("Invalid remote signer url {}", url))?;
+
+        let accounts = http_rpc_client
+            .get_accounts()
+            .await
+            .context("Failed to fetch the accounts of the remote signer")?;
+        if !accounts.contains(&address) {
+            return Err(anyhow!(
+                "Remote signer {} does not hold the key of the address {:?}",
+                url,
+                address
+            ));
+        }
+
+        Ok(RemoteSigner {
+            url: url.to_owned(),
+            http_rpc_client,
+            address,
+            chain_id: 1,
+        })
+    }
+}
+
+// The rpc client holds no state of its own, the signer is identified by its url and address
+impl PartialEq for RemoteSigner {
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url && self.address == other.address && self.chain_id == other.chain_id
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    type Error = GasWalletError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        let signature: Bytes = self
+            .http_rpc_client
+            .request(
+                "eth_sign",
+                (self.address, Bytes::from(message.as_ref().to_vec())),
+            )
+            .await?;
+
+        Signature::try_from(signature.as_ref())
+            .map_err(|err| GasWalletError::InvalidRemoteSignature(err.to_string()))
+    }
+
+    async fn sign_transaction(&self, message: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let mut tx = message.clone();
+        tx.set_from(self.address);
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+
+        // The remote signer returns the rlp encoded signed transaction
+        let signed_tx: Bytes = self
+            .http_rpc_client
+            .request("eth_signTransaction", [&tx])
+            .await?;
+        let (signed_tx, signature) = TypedTransaction::decode_signed(&Rlp::new(&signed_tx))
+            .map_err(|err| GasWalletError::InvalidRemoteSignature(err.to_string()))?;
+
+        // Make sure the transaction asked for was signed, and by the expected key
+        if signed_tx.sighash() != tx.sighash() {
+            return Err(GasWalletError::InvalidRemoteSignature(
+                "Signed transaction differs from the requested one".to_owned(),
+            ));
+        }
+        signature
+            .verify(tx.sighash(), self.address)
+            .map_err(|err| GasWalletError::InvalidRemoteSignature(err.to_string()))?;
+
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Err(GasWalletError::UnsupportedByRemoteSigner(
+            "Signing typed data",
+        ))
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        RemoteSigner {
+            chain_id: chain_id.into(),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod gas_wallet_tests {
+    use actix_web::web::{self, Data, Json};
+    use actix_web::{App, HttpResponse, HttpServer, Responder};
+    use ethers::types::TransactionRequest;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_util::{CHAIN_ID, GAS_WALLET_KEY, GAS_WALLET_PUBLIC_ADDRESS};
+
+    // JSON-RPC handler signing with a local wallet, standing in for the remote signer
+    async fn sign_with_local_wallet(
+        wallet: Data<LocalWallet>,
+        Json(request): Json<Value>,
+    ) -> impl Responder {
+        let result = match request["method"].as_str().unwrap() {
+            "eth_accounts" => json!([wallet.address()]),
+            "eth_signTransaction" => {
+                let tx: TypedTransaction =
+                    serde_json::from_value(request["params"][0].clone()).unwrap();
+                let signature = wallet.sign_transaction(&tx).await.unwrap();
+                json!(tx.rlp_signed(&signature))
+            }
+            method => panic!("Unexpected method {}", method),
+        };
+
+        HttpResponse::Ok().json(json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": result,
+        }))
+    }
+
+    fn start_mock_remote_signer(wallet: LocalWallet) -> String {
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(Data::new(wallet.clone()))
+                .route("/", web::post().to(sign_with_local_wallet))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}", server.addrs()[0]);
+
+        actix_web::rt::spawn(server.run());
+        url
+    }
+
+    #[tokio::test]
+    async fn test_keystore_gas_wallet() {
+        let keystore_dir =
+            std::env::temp_dir().join(format!("gas_keystore_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&keystore_dir).unwrap();
+        LocalWallet::encrypt_keystore(
+            &keystore_dir,
+            &mut rand::thread_rng(),
+            hex::decode(GAS_WALLET_KEY).unwrap(),
+            "password",
+            Some("keystore"),
+        )
+        .unwrap();
+        std::fs::write(keystore_dir.join("password"), "password\n").unwrap();
+
+        let gas_wallet_config = GasWalletConfig::Keystore {
+            keystore_file: keystore_dir.join("keystore").to_string_lossy().into_owned(),
+            password_file: keystore_dir.join("password").to_string_lossy().into_owned(),
+            address: GAS_WALLET_PUBLIC_ADDRESS.parse::<Address>().unwrap(),
+        };
+        let gas_wallet = GasWallet::from_config(&gas_wallet_config, CHAIN_ID)
+            .await
+            .unwrap();
+
+        assert!(matches!(gas_wallet, GasWallet::Local(_)));
+        assert_eq!(
+            gas_wallet.address(),
+            GAS_WALLET_PUBLIC_ADDRESS.parse::<Address>().unwrap()
+        );
+        assert_eq!(gas_wallet.chain_id(), CHAIN_ID);
+
+        // Unlock a keystore not holding the key of the configured address
+        let mismatched_gas_wallet_config = GasWalletConfig::Keystore {
+            keystore_file: keystore_dir.join("keystore").to_string_lossy().into_owned(),
+            password_file: keystore_dir.join("password").to_string_lossy().into_owned(),
+            address: Address::random(),
+        };
+        assert!(
+            GasWallet::from_config(&mismatched_gas_wallet_config, CHAIN_ID)
+                .await
+                .is_err()
+        );
+
+        // Unlock the keystore with the wrong password
+        std::fs::write(keystore_dir.join("password"), "wrong password").unwrap();
+        assert!(GasWallet::from_config(&gas_wallet_config, CHAIN_ID)
+            .await
+            .is_err());
+
+        std::fs::remove_dir_all(&keystore_dir).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_remote_gas_wallet() {
+        let local_wallet = GAS_WALLET_KEY
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(CHAIN_ID);
+        let url = start_mock_remote_signer(local_wallet.clone());
+
+        // Connect to the remote signer for an address it doesn't hold the key of
+        assert!(GasWallet::from_config(
+            &GasWalletConfig::Remote {
+                url: url.clone(),
+                address: Address::random(),
+            },
+            CHAIN_ID,
+        )
+        .await
+        .is_err());
+
+        let gas_wallet = GasWallet::from_config(
+            &GasWalletConfig::Remote {
+                url,
+                address: local_wallet.address(),
+            },
+            CHAIN_ID,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(gas_wallet, GasWallet::Remote(_)));
+        assert_eq!(gas_wallet.address(), local_wallet.address());
+        assert_eq!(gas_wallet.chain_id(), CHAIN_ID);
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::random())
+            .value(1000)
+            .gas(21000)
+            .gas_price(100)
+            .nonce(7)
+            .chain_id(CHAIN_ID)
+            .into();
+
+        let signature = gas_wallet.sign_transaction(&tx).await.unwrap();
+        assert_eq!(signature, local_wallet.sign_transaction(&tx).await.unwrap());
+    }
+}
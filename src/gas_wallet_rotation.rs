@@ -0,0 +1,486 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use log::{error, info};
+use tokio::sync::OwnedRwLockWriteGuard;
+use tokio::time::timeout;
+
+use crate::chain_util::{ChainConnector, WsHttpConnector};
+use crate::constant::{GAS_WALLET_ROTATION_DRAIN_TIMEOUT, REPLACEMENT_GAS_PRICE_BUMP_PERCENT};
+use crate::contract_abi::{GatewayJobsContract, RelayContract, RelaySubscriptionsContract};
+use crate::model::{AppState, ContractsClient, GasWallet};
+
+/// Rotates the gas wallet of the enclave to the staged one.
+///
+/// The transactions in flight from the old wallet are given time to confirm, and are replaced by
+/// no-op transactions if they don't, before the contracts switch over to the new wallet with nonce
+/// managers synced from the chain. The funds left on the old wallet are optionally swept to the
+/// new one on the common chain and every request chain.
+pub async fn rotate_gas_wallet(
+    app_state: &AppState,
+    gas_wallet: GasWallet,
+    sweep_funds: bool,
+) -> anyhow::Result<()> {
+    let old_wallet = app_state.wallet.lock().unwrap().clone();
+    let contracts_client = app_state.contracts_client.lock().unwrap().clone();
+    let chains = gas_wallet_chains(app_state, contracts_client.as_deref());
+    let chain_connector = contracts_client.as_ref().map_or_else(
+        || Arc::new(WsHttpConnector),
+        |contracts_client| contracts_client.chain_connector.clone(),
+    );
+
+    // Keep new transactions from being sent with the old wallet until the rotation is over
+    let _gas_wallet_guard = match (contracts_client.as_ref(), old_wallet.as_ref()) {
+        (Some(contracts_client), Some(old_wallet)) => {
+            Some(drain_gas_wallet_txns(contracts_client, old_wallet, &chains).await?)
+        }
+        _ => None,
+    };
+
+    if let (true, Some(old_wallet)) = (sweep_funds, old_wallet.as_ref()) {
+        for (&chain_id, http_rpc_url) in chains.iter() {
+            sweep_gas_wallet_funds(
+                chain_connector.as_ref(),
+                http_rpc_url,
+                old_wallet.clone().with_chain_id(chain_id),
+                gas_wallet.address(),
+            )
+            .await
+            .context(format!(
+                "Failed to sweep the funds on the chain {}",
+                chain_id
+            ))?;
+        }
+    }
+
+    if let Some(contracts_client) = contracts_client.as_ref() {
+        info!("Updating Contracts Client with the new wallet address");
+        update_contracts_client_wallet(app_state, contracts_client, &gas_wallet).await?;
+    }
+
+    *app_state.wallet.lock().unwrap() = Some(gas_wallet);
+    Ok(())
+}
+
+/// Http rpc urls of the chains the gas wallet sends transactions on, by chain id.
+pub fn gas_wallet_chains<M, C>(
+    app_state: &AppState,
+    contracts_client: Option<&ContractsClient<M, C>>,
+) -> HashMap<u64, String> {
+    let mut chains = HashMap::from([(
+        app_state.common_chain_id,
+        app_state.common_chain_http_url.clone(),
+    )]);
+    if let Some(contracts_client) = contracts_client {
+        for (&chain_id, request_chain_client) in contracts_client
+            .request_chain_clients
+            .read()
+            .unwrap()
+            .iter()
+        {
+            chains.insert(chain_id, request_chain_client.http_rpc_url.clone());
+        }
+    }
+    chains
+}
+
+/// Waits for the transactions in flight from the old wallet to be confirmed, replacing the ones
+/// still pending on the chains after the drain timeout. No transaction is sent from the wallet
+/// until the returned guard is dropped.
+pub async fn drain_gas_wallet_txns<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: &ContractsClient<M, C>,
+    old_wallet: &GasWallet,
+    chains: &HashMap<u64, String>,
+) -> anyhow::Result<OwnedRwLockWriteGuard<()>> {
+    let drain_timeout = Duration::from_secs(GAS_WALLET_ROTATION_DRAIN_TIMEOUT);
+
+    // The write request is queued right away so that no transaction starts in the meantime
+    let gas_wallet_write = contracts_client.gas_wallet_lock.clone().write_owned();
+    tokio::pin!(gas_wallet_write);
+    if let Ok(gas_wallet_guard) = timeout(drain_timeout, &mut gas_wallet_write).await {
+        return Ok(gas_wallet_guard);
+    }
+
+    info!("Replacing the pending transactions of the old gas wallet");
+    for (&chain_id, http_rpc_url) in chains.iter() {
+        replace_pending_txns(
+            contracts_client.chain_connector.as_ref(),
+            http_rpc_url,
+            old_wallet.clone().with_chain_id(chain_id),
+        )
+        .await
+        .context(format!(
+            "Failed to replace the pending transactions on the chain {}",
+            chain_id
+        ))?;
+    }
+
+    timeout(drain_timeout, gas_wallet_write)
+        .await
+        .map_err(|_| anyhow!("Transactions in flight from the old gas wallet did not settle"))
+}
+
+// Takes over the nonces of the pending transactions with zero value transfers to self at a
+// higher gas price, the senders of the replaced transactions see them dropped
+async fn replace_pending_txns<M, C: ChainConnector<M>>(
+    chain_connector: &C,
+    http_rpc_url: &str,
+    old_wallet: GasWallet,
+) -> anyhow::Result<()> {
+    let http_rpc_client = SignerMiddleware::new(
+        chain_connector.connect_http(http_rpc_url).await?,
+        old_wallet,
+    );
+    let address = http_rpc_client.address();
+
+    let confirmed_nonce = http_rpc_client
+        .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+        .await?;
+    let pending_nonce = http_rpc_client
+        .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+        .await?;
+    if pending_nonce <= confirmed_nonce {
+        return Ok(());
+    }
+
+    let gas_price = http_rpc_client.get_gas_price().await?;
+    let pending_txn_gas_prices = pending_txn_gas_prices(&http_rpc_client, address).await;
+
+    let mut replacement_txns = Vec::new();
+    for nonce in confirmed_nonce.as_u64()..pending_nonce.as_u64() {
+        let mut txn: TypedTransaction = TransactionRequest::new()
+            .from(address)
+            .to(address)
+            .value(0)
+            .gas_price(replacement_gas_price(
+                gas_price,
+                pending_txn_gas_prices.get(&U256::from(nonce)).copied(),
+            ))
+            .nonce(nonce)
+            .into();
+        let gas = http_rpc_client.estimate_gas(&txn, None).await?;
+        txn.set_gas(gas);
+
+        let pending_txn = http_rpc_client.send_transaction(txn, None).await?;
+        info!(
+            "Replacement transaction {} sent for the nonce {}",
+            pending_txn.tx_hash(),
+            nonce
+        );
+        replacement_txns.push(pending_txn);
+    }
+
+    for pending_txn in replacement_txns {
+        let txn_hash = pending_txn.tx_hash();
+        confirm_gas_wallet_txn(pending_txn).await.context(format!(
+            "Replacement transaction {} not confirmed",
+            txn_hash
+        ))?;
+    }
+
+    Ok(())
+}
+
+// Gas prices paid by the transactions of the address waiting in the txpool of the node, by nonce.
+// Empty if the node doesn't expose its txpool, the replacements are then priced off the current
+// gas price alone.
+async fn pending_txn_gas_prices<M: Middleware>(
+    http_rpc_client: &M,
+    address: Address,
+) -> HashMap<U256, U256> {
+    let txpool_content = match http_rpc_client.txpool_content().await {
+        Ok(txpool_content) => txpool_content,
+        Err(err) => {
+            error!(
+                "Failed to fetch the pending transactions of the old gas wallet: {:?}",
+                err
+            );
+            return HashMap::new();
+        }
+    };
+
+    [&txpool_content.pending, &txpool_content.queued]
+        .into_iter()
+        .filter_map(|txns| txns.get(&address))
+        .flat_map(|txns| txns.values())
+        .filter_map(|txn| Some((txn.nonce, txn.max_fee_per_gas.or(txn.gas_price)?)))
+        .collect()
+}
+
+// Gas price of the replacement transaction, bumped over both the replaced transaction and the
+// current gas price, as nodes reject the replacements not paying more than what they replace
+fn replacement_gas_price(gas_price: U256, pending_txn_gas_price: Option<U256>) -> U256 {
+    gas_price.max(pending_txn_gas_price.unwrap_or_default())
+        * (100 + REPLACEMENT_GAS_PRICE_BUMP_PERCENT)
+        / 100
+}
+
+// Transfers the balance of the old wallet to the new one, leaving out the transfer fee
+async fn sweep_gas_wallet_funds<M, C: ChainConnector<M>>(
+    chain_connector: &C,
+    http_rpc_url: &str,
+    old_wallet: GasWallet,
+    new_address: Address,
+) -> anyhow::Result<()> {
+    let http_rpc_client = SignerMiddleware::new(
+        chain_connector.connect_http(http_rpc_url).await?,
+        old_wallet,
+    );
+    let address = http_rpc_client.address();
+
+    let balance = http_rpc_client.get_balance(address, None).await?;
+    let gas_price = http_rpc_client.get_gas_price().await?;
+    let mut txn: TypedTransaction = TransactionRequest::new()
+        .from(address)
+        .to(new_address)
+        .value(0)
+        .gas_price(gas_price)
+        .into();
+    let gas = http_rpc_client.estimate_gas(&txn, None).await?;
+
+    let fee = gas * gas_price;
+    if balance <= fee {
+        info!(
+            "Nothing to sweep from the old gas wallet {:?}, balance {}",
+            address, balance
+        );
+        return Ok(());
+    }
+    txn.set_gas(gas);
+    txn.set_value(balance - fee);
+
+    let pending_txn = http_rpc_client.send_transaction(txn, None).await?;
+    let txn_hash = pending_txn.tx_hash();
+    confirm_gas_wallet_txn(pending_txn)
+        .await
+        .context(format!("Sweep transaction {} not confirmed", txn_hash))?;
+
+    info!(
+        "Swept {} from the old gas wallet {:?} to {:?} in the transaction {}",
+        balance - fee,
+        address,
+        new_address,
+        txn_hash
+    );
+    Ok(())
+}
+
+async fn confirm_gas_wallet_txn<F, E>(pending_txn: F) -> anyhow::Result<()>
+where
+    F: Future<Output = Result<Option<TransactionReceipt>, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    match timeout(
+        Duration::from_secs(GAS_WALLET_ROTATION_DRAIN_TIMEOUT),
+        pending_txn,
+    )
+    .await
+    {
+        Ok(Ok(Some(_))) => Ok(()),
+        Ok(Ok(None)) => Err(anyhow!("Transaction dropped from the mempool")),
+        Ok(Err(err)) => Err(err.into()),
+        Err(_) => Err(anyhow!("Timed out waiting for the confirmation")),
+    }
+}
+
+// Switches the contracts of the common chain and the request chains to the new wallet, with nonce
+// managers initialized from the pending transaction count of the new address
+async fn update_contracts_client_wallet<M: Middleware + 'static, C: ChainConnector<M>>(
+    app_state: &AppState,
+    contracts_client: &ContractsClient<M, C>,
+    gas_wallet: &GasWallet,
+) -> anyhow::Result<()> {
+    let common_chain_http_rpc_client = contracts_client
+        .chain_connector
+        .connect_gas_wallet(&app_state.common_chain_http_url, gas_wallet.clone())
+        .await?;
+    let gateway_jobs_contract = GatewayJobsContract::new(
+        app_state.gateway_jobs_contract_addr,
+        common_chain_http_rpc_client,
+    );
+
+    let request_chain_clients = contracts_client
+        .request_chain_clients
+        .read()
+        .unwrap()
+        .clone();
+    let mut request_chain_contracts = HashMap::new();
+    for (&chain_id, request_chain_client) in request_chain_clients.iter() {
+        let request_chain_http_rpc_client = contracts_client
+            .chain_connector
+            .connect_gas_wallet(
+                &request_chain_client.http_rpc_url,
+                gas_wallet.clone().with_chain_id(chain_id),
+            )
+            .await?;
+
+        let relay_contract = RelayContract::new(
+            request_chain_client.relay_address,
+            request_chain_http_rpc_client.clone(),
+        );
+        let relay_subs_contract = RelaySubscriptionsContract::new(
+            request_chain_client.relay_subscriptions_address,
+            request_chain_http_rpc_client,
+        );
+        request_chain_contracts.insert(chain_id, (relay_contract, relay_subs_contract));
+    }
+
+    *contracts_client.gateway_jobs_contract.write().unwrap() = gateway_jobs_contract;
+    for (chain_id, (relay_contract, relay_subs_contract)) in request_chain_contracts {
+        let request_chain_client = &request_chain_clients[&chain_id];
+        *request_chain_client.relay_contract.write().unwrap() = relay_contract;
+        *request_chain_client
+            .relay_subscriptions_contract
+            .write()
+            .unwrap() = relay_subs_contract;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod gas_wallet_rotation_tests {
+    use actix_web::{body::MessageBody, http};
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{
+        generate_app_state, new_app, owner_signed_mutable_config, CHAIN_ID, GAS_WALLET_KEY,
+        OWNER_ADDRESS,
+    };
+
+    const GAS_WALLET_KEY_2: &str =
+        "5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a";
+
+    fn wallet_from_hex(hex: &str) -> GasWallet {
+        GasWallet::Local(hex.parse::<LocalWallet>().unwrap().with_chain_id(CHAIN_ID))
+    }
+
+    #[test]
+    fn test_replacement_gas_price() {
+        // Bumped over the current gas price if the replaced transaction is unknown or cheaper
+        assert_eq!(
+            replacement_gas_price(U256::from(100), None),
+            U256::from(125)
+        );
+        assert_eq!(
+            replacement_gas_price(U256::from(100), Some(U256::from(80))),
+            U256::from(125)
+        );
+        // Bumped over the replaced transaction if it pays more
+        assert_eq!(
+            replacement_gas_price(U256::from(100), Some(U256::from(200))),
+            U256::from(250)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rotation_waits_for_txns_in_flight() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/immutable-config")
+            .set_json(&json!({
+                "owner_address_hex": OWNER_ADDRESS
+            }))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/signed-registration-message")
+            .set_json(&json!({
+                "chain_ids": [CHAIN_ID]
+            }))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+        let contracts_client = app_state.contracts_client.lock().unwrap().clone().unwrap();
+
+        // Transaction in flight from the old wallet
+        let gas_wallet_permit = contracts_client.gas_wallet_lock.clone().read_owned().await;
+
+        let rotation = rotate_gas_wallet(&app_state, wallet_from_hex(GAS_WALLET_KEY_2), false);
+        tokio::pin!(rotation);
+        assert!(timeout(Duration::from_millis(200), &mut rotation)
+            .await
+            .is_err());
+        assert_eq!(
+            *app_state.wallet.lock().unwrap(),
+            Some(wallet_from_hex(GAS_WALLET_KEY))
+        );
+        // New transactions wait for the rotation to be over
+        assert!(contracts_client.gas_wallet_lock.try_read().is_err());
+
+        drop(gas_wallet_permit);
+        rotation.await.unwrap();
+
+        assert_eq!(
+            *app_state.wallet.lock().unwrap(),
+            Some(wallet_from_hex(GAS_WALLET_KEY_2))
+        );
+        assert_eq!(
+            contracts_client
+                .gateway_jobs_contract
+                .read()
+                .unwrap()
+                .client()
+                .inner()
+                .signer()
+                .address(),
+            wallet_from_hex(GAS_WALLET_KEY_2).address()
+        );
+        assert_eq!(
+            contracts_client
+                .request_chain_client(CHAIN_ID)
+                .unwrap()
+                .relay_contract
+                .read()
+                .unwrap()
+                .client()
+                .inner()
+                .signer()
+                .address(),
+            wallet_from_hex(GAS_WALLET_KEY_2).address()
+        );
+        assert!(contracts_client.gas_wallet_lock.try_read().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rotation_already_in_progress() {
+        let app_state = generate_app_state().await;
+        let app = actix_web::test::init_service(new_app(app_state.clone())).await;
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/immutable-config")
+            .set_json(&json!({
+                "owner_address_hex": OWNER_ADDRESS
+            }))
+            .to_request();
+        actix_web::test::call_service(&app, req).await;
+
+        *app_state.staged_wallet.lock().unwrap() = Some(wallet_from_hex(GAS_WALLET_KEY_2));
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/mutable-config")
+            .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+        assert_eq!(
+            resp.into_body().try_into_bytes().unwrap(),
+            "A gas wallet rotation is already in progress!"
+        );
+        assert_eq!(*app_state.wallet.lock().unwrap(), None);
+        assert_eq!(*app_state.mutable_config_nonce.lock().unwrap(), 0);
+    }
+}
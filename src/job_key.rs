@@ -0,0 +1,306 @@
+use std::fmt;
+
+use ethers::types::U256;
+
+use crate::error::ServerlessError;
+use crate::model::{Job, JobKey, JobMode, ResponseJob, SubscriptionJob, SubscriptionKey};
+
+// Layout of the job ids derived by the contracts of the request chains, from the most
+// significant bit:
+//
+// | request chain id (64 bits) | job mode (1 bit) | job count (191 bits)                          |
+// | request chain id (64 bits) | job mode (1 bit) | subscription count (64 bits) | instance (127 bits) |
+//
+// The Relay contract starts its job count at `block.chainid << 192` and wraps it around before
+// reaching the job mode bit. The RelaySubscriptions contract starts its subscription count at
+// `block.chainid << 192 | 1 << 191` and steps it by `1 << 127`, leaving the low 127 bits of the
+// subscription ids for the index of the instance, `jobSubsResponse` takes the subscription id
+// plus the instance index. (See `initialize` and `relayJob` of Relay.json and `initialize` and
+// `startJobSubscription` of RelaySubscriptions.json.)
+//
+// The job ids of the request chains thus already differ across the request chains, the job modes
+// and the instances, and are relayed to the GatewayJobs contract as they are.
+const REQUEST_CHAIN_ID_SHIFT: usize = 192;
+const JOB_MODE_BIT: usize = 191;
+const INSTANCE_INDEX_BITS: usize = 127;
+
+fn instance_index_mask() -> U256 {
+    (U256::one() << INSTANCE_INDEX_BITS) - 1
+}
+
+impl JobKey {
+    pub fn once(request_chain_id: u64, job_id: U256) -> Self {
+        JobKey {
+            request_chain_id,
+            job_mode: JobMode::Once,
+            job_id,
+            instance_index: 0,
+        }
+    }
+
+    pub fn subscription_instance(
+        request_chain_id: u64,
+        subscription_id: U256,
+        instance_index: u64,
+    ) -> Self {
+        JobKey {
+            request_chain_id,
+            job_mode: JobMode::Subscription,
+            job_id: subscription_id,
+            instance_index,
+        }
+    }
+
+    /// Checks that the job id is laid out as the contracts of the request chain derive it for the
+    /// job mode, any job id emitted by them passes.
+    pub fn validate(&self) -> Result<(), ServerlessError> {
+        let is_subscription = self.job_mode == JobMode::Subscription;
+        if self.job_id >> REQUEST_CHAIN_ID_SHIFT != U256::from(self.request_chain_id)
+            || self.job_id.bit(JOB_MODE_BIT) != is_subscription
+            || (is_subscription && !(self.job_id & instance_index_mask()).is_zero())
+        {
+            return Err(ServerlessError::JobIdNotOfRequestChain(
+                self.request_chain_id,
+                self.job_id,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Job id of the job on the common chain, used in the relay and reassign signatures and
+    /// in the calls and events of the GatewayJobs contract. Same as the job id on the request
+    /// chain.
+    pub fn common_chain_job_id(&self) -> U256 {
+        self.request_chain_job_id()
+    }
+
+    /// Key of the job id in the GatewayJobs events, the job ids with an instance index out of
+    /// the u64 range are not relayed by the gateways.
+    pub fn from_common_chain_job_id(common_chain_job_id: U256) -> Result<Self, ServerlessError> {
+        let request_chain_id = (common_chain_job_id >> REQUEST_CHAIN_ID_SHIFT).as_u64();
+        if !common_chain_job_id.bit(JOB_MODE_BIT) {
+            return Ok(JobKey::once(request_chain_id, common_chain_job_id));
+        }
+
+        let instance_index = u64::try_from(common_chain_job_id & instance_index_mask())
+            .map_err(|_| ServerlessError::InvalidCommonChainJobId(common_chain_job_id))?;
+        Ok(JobKey::subscription_instance(
+            request_chain_id,
+            common_chain_job_id & !instance_index_mask(),
+            instance_index,
+        ))
+    }
+
+    /// Subscription of the instance, the one time jobs are keyed the same way by their job id.
+    pub fn subscription_key(&self) -> SubscriptionKey {
+        SubscriptionKey::new(self.request_chain_id, self.job_id)
+    }
+
+    /// Job id expected by the request chain contracts in the job responses. The Relay contract
+    /// takes its own job id, while the RelaySubscriptions contract identifies an instance by
+    /// the subscription id offset by the instance index.
+    pub fn request_chain_job_id(&self) -> U256 {
+        match self.job_mode {
+            JobMode::Once => self.job_id,
+            JobMode::Subscription => self.job_id + self.instance_index,
+        }
+    }
+}
+
+impl SubscriptionKey {
+    pub fn new(request_chain_id: u64, subscription_id: U256) -> Self {
+        SubscriptionKey {
+            request_chain_id,
+            subscription_id,
+        }
+    }
+}
+
+impl fmt::Display for SubscriptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (Request Chain ID: {})",
+            self.subscription_id, self.request_chain_id
+        )
+    }
+}
+
+impl SubscriptionJob {
+    pub fn key(&self) -> SubscriptionKey {
+        SubscriptionKey::new(self.request_chain_id, self.subscription_id)
+    }
+}
+
+impl Job {
+    pub fn key(&self) -> JobKey {
+        JobKey {
+            request_chain_id: self.request_chain_id,
+            job_mode: self.job_mode,
+            job_id: self.job_id,
+            instance_index: self.instance_index,
+        }
+    }
+}
+
+impl ResponseJob {
+    pub fn key(&self) -> JobKey {
+        JobKey {
+            request_chain_id: self.request_chain_id,
+            job_mode: self.job_mode,
+            job_id: self.job_id,
+            instance_index: self.instance_index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod job_key_tests {
+    use proptest::prelude::*;
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::test_util::{job_subscription_id, relay_job_id};
+
+    // Job ids of the first job and subscription of the contracts deployed on the chain 31337
+    // (Hardhat), as set to `jobCount` and `jobSubsCount` by `initialize`
+    #[test]
+    fn test_contract_job_ids() {
+        assert_eq!(
+            relay_job_id(31337, 0),
+            U256::from_str("0x7a69000000000000000000000000000000000000000000000000").unwrap()
+        );
+        assert_eq!(
+            job_subscription_id(31337, 0),
+            U256::from_str("0x7a69800000000000000000000000000000000000000000000000").unwrap()
+        );
+        assert_eq!(
+            job_subscription_id(31337, 1),
+            U256::from_str("0x7a69800000000000000080000000000000000000000000000000").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_common_chain_job_id_encoding() {
+        assert_eq!(
+            JobKey::once(31337, relay_job_id(31337, 42)).common_chain_job_id(),
+            U256::from_str("0x7a6900000000000000000000000000000000000000000000002a").unwrap()
+        );
+        assert_eq!(
+            JobKey::subscription_instance(31337, job_subscription_id(31337, 5), 3)
+                .common_chain_job_id(),
+            U256::from_str("0x7a69800000000000000280000000000000000000000000000003").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_overlapping_subscription_instances_do_not_collide() {
+        let first = JobKey::subscription_instance(31337, job_subscription_id(31337, 1), 1);
+        let second = JobKey::subscription_instance(31337, job_subscription_id(31337, 2), 0);
+
+        assert_ne!(first, second);
+        assert_ne!(first.common_chain_job_id(), second.common_chain_job_id());
+        assert_ne!(first.request_chain_job_id(), second.request_chain_job_id());
+    }
+
+    #[test]
+    fn test_one_time_and_subscription_jobs_do_not_collide() {
+        let once = JobKey::once(31337, relay_job_id(31337, 1));
+        let subscription = JobKey::subscription_instance(31337, job_subscription_id(31337, 0), 1);
+
+        assert_ne!(once, subscription);
+        assert_ne!(
+            once.common_chain_job_id(),
+            subscription.common_chain_job_id()
+        );
+    }
+
+    #[test]
+    fn test_same_job_count_on_different_request_chains_do_not_collide() {
+        let first = JobKey::once(1, relay_job_id(1, 42));
+        let second = JobKey::once(2, relay_job_id(2, 42));
+
+        assert_ne!(first, second);
+        assert_ne!(first.common_chain_job_id(), second.common_chain_job_id());
+    }
+
+    #[test]
+    fn test_validate_job_id_not_of_request_chain() {
+        // Job id of another request chain
+        assert_eq!(
+            JobKey::once(1, relay_job_id(2, 1)).validate(),
+            Err(ServerlessError::JobIdNotOfRequestChain(
+                1,
+                relay_job_id(2, 1)
+            ))
+        );
+        // Subscription id relayed as a one time job
+        assert_eq!(
+            JobKey::once(1, job_subscription_id(1, 1)).validate(),
+            Err(ServerlessError::JobIdNotOfRequestChain(
+                1,
+                job_subscription_id(1, 1)
+            ))
+        );
+        // Job id of an instance taken as a subscription id
+        assert_eq!(
+            JobKey::subscription_instance(1, job_subscription_id(1, 1) + 1, 0).validate(),
+            Err(ServerlessError::JobIdNotOfRequestChain(
+                1,
+                job_subscription_id(1, 1) + 1
+            ))
+        );
+        // The largest ids of the contracts
+        assert_eq!(
+            JobKey::once(u64::MAX, relay_job_id(u64::MAX, 0) | (U256::MAX >> 65)).validate(),
+            Ok(())
+        );
+        assert_eq!(
+            JobKey::subscription_instance(u64::MAX, job_subscription_id(u64::MAX, u64::MAX), 0)
+                .validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_from_common_chain_job_id_instance_out_of_range() {
+        let common_chain_job_id = job_subscription_id(1, 1) + (U256::one() << 64);
+
+        assert_eq!(
+            JobKey::from_common_chain_job_id(common_chain_job_id),
+            Err(ServerlessError::InvalidCommonChainJobId(
+                common_chain_job_id
+            ))
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_common_chain_job_id_round_trip(
+            request_chain_id in any::<u64>(),
+            job_count in any::<u128>(),
+            subscription_count in any::<u64>(),
+            instance_index in any::<u64>(),
+            is_subscription in any::<bool>(),
+        ) {
+            let job_key = if is_subscription {
+                JobKey::subscription_instance(
+                    request_chain_id,
+                    job_subscription_id(request_chain_id, subscription_count),
+                    instance_index,
+                )
+            } else {
+                JobKey::once(
+                    request_chain_id,
+                    relay_job_id(request_chain_id, 0) + U256::from(job_count),
+                )
+            };
+
+            prop_assert_eq!(job_key.validate(), Ok(()));
+            prop_assert_eq!(
+                JobKey::from_common_chain_job_id(job_key.common_chain_job_id()),
+                Ok(job_key)
+            );
+        }
+    }
+}
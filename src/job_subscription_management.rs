@@ -1,31 +1,39 @@
+use anyhow::{anyhow, Context};
 use ethers::{
-    abi::{decode, ParamType},
-    types::{BigEndianHash, Bytes, Log, U256},
-    utils::keccak256,
+    contract::EthEvent,
+    providers::Middleware,
+    types::{Address, Log, U256},
 };
 use log::{error, info};
+use rand::Rng;
 use std::{
-    sync::Arc,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
 };
 use tokio::{
     sync::mpsc::{Receiver, Sender},
-    time::{sleep_until, Instant},
+    time::{sleep, sleep_until, Instant},
 };
 
 use crate::{
-    chain_util::{HttpProvider, HttpProviderLogs, LogsProvider},
-    constant::{
-        GATEWAY_BLOCK_STATES_TO_MAINTAIN, REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT,
-        REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT,
-        REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT,
+    chain_util::{ChainConnector, HttpProviderLogs, LogsProvider, RelayFundsView},
+    common_chain_interaction::select_gateway,
+    constant::{GATEWAY_BLOCK_STATES_TO_MAINTAIN, SUBSCRIPTION_INSTANCE_HISTORY_LEN},
+    contract_abi::{
+        JobSubscriptionFundsDepositedFilter, JobSubscriptionFundsWithdrawnFilter,
+        JobSubscriptionJobParamsUpdatedFilter, JobSubscriptionStartedFilter,
+        JobSubscriptionTerminatedFilter, JobSubscriptionTerminationParamsUpdatedFilter,
     },
     error::ServerlessError,
+    event_decoder::{decode_event, topic, uint_to_u64},
     model::{
-        ContractsClient, GatewayJobType, Job, JobMode, JobSubscriptionAction,
-        JobSubscriptionChannelType, RequestChainClient, SubscriptionJob,
-        SubscriptionJobInstanceHeap,
+        ContractsClient, GatewayData, GatewayJobType, Job, JobKey, JobMode, JobSubscriptionAction,
+        JobSubscriptionChannelType, RequestChainClient, SubscriptionCatchUpPolicy,
+        SubscriptionInstanceRecord, SubscriptionInstanceStatus, SubscriptionJob,
+        SubscriptionJobInstanceHeap, SubscriptionJobScheduler, SubscriptionKey,
+        SubscriptionPreviewResponse, UpcomingSubscriptionInstance,
     },
+    schedule::Schedule,
 };
 
 /// Converts a Unix timestamp to an `Instant`.
@@ -36,22 +44,18 @@ use crate::{
 /// # Arguments
 ///
 /// * `timestamp` - A Unix timestamp represented as a `u64`.
+/// * `now` - The current Unix timestamp as reported by the client's clock.
 ///
 /// # Returns
 ///
 /// * An `Instant` representing the given timestamp or the current time if the timestamp is in the past.
-fn unix_timestamp_to_instant(timestamp: u64) -> Instant {
-    let duration = Duration::from_secs(timestamp);
-    let timestamp_in_system_time = UNIX_EPOCH + duration;
-    Instant::now()
-        + timestamp_in_system_time
-            .duration_since(SystemTime::now())
-            .unwrap_or_default()
+fn unix_timestamp_to_instant(timestamp: u64, now: u64) -> Instant {
+    Instant::now() + Duration::from_secs(timestamp.saturating_sub(now))
 }
 
 impl PartialEq for SubscriptionJobInstanceHeap {
     fn eq(&self, other: &Self) -> bool {
-        self.next_trigger_time == other.next_trigger_time
+        self.cmp(other) == std::cmp::Ordering::Equal
     }
 }
 
@@ -59,126 +63,302 @@ impl Eq for SubscriptionJobInstanceHeap {}
 
 impl PartialOrd for SubscriptionJobInstanceHeap {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(
-            self.next_trigger_time
-                .cmp(&other.next_trigger_time)
-                .reverse(),
-        )
+        Some(self.cmp(other))
     }
 }
 
+// Reversed so that the BinaryHeap pops the earliest trigger time first
 impl Ord for SubscriptionJobInstanceHeap {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.next_trigger_time
             .cmp(&other.next_trigger_time)
+            .then_with(|| self.subscription_key.cmp(&other.subscription_key))
+            .then_with(|| self.generation.cmp(&other.generation))
             .reverse()
     }
 }
 
-pub async fn process_historic_job_subscriptions(
-    contracts_client: &Arc<ContractsClient>,
+impl SubscriptionJobScheduler {
+    /// Schedules the next instance of the subscription, replacing any instance already queued
+    /// for it.
+    pub fn schedule(&mut self, subscription_key: SubscriptionKey, next_trigger_time: u64) {
+        self.next_generation += 1;
+        let generation = self.next_generation;
+
+        self.scheduled
+            .insert(subscription_key, (generation, next_trigger_time));
+        self.instances.push(SubscriptionJobInstanceHeap {
+            subscription_key,
+            next_trigger_time,
+            generation,
+        });
+        self.compact();
+    }
+
+    /// Cancels the instance queued for the subscription, returns whether one was queued.
+    pub fn cancel(&mut self, subscription_key: &SubscriptionKey) -> bool {
+        let cancelled = self.scheduled.remove(subscription_key).is_some();
+        self.compact();
+        cancelled
+    }
+
+    pub fn scheduled_trigger_time(&self, subscription_key: &SubscriptionKey) -> Option<u64> {
+        self.scheduled
+            .get(subscription_key)
+            .map(|(_, next_trigger_time)| *next_trigger_time)
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty()
+    }
+
+    /// Returns the trigger time of the earliest live instance.
+    pub fn next_trigger_time(&mut self) -> Option<u64> {
+        self.discard_stale();
+        self.instances
+            .peek()
+            .map(|instance| instance.next_trigger_time)
+    }
+
+    /// Removes and returns the earliest live instance if it is due by now, the subscription is no
+    /// longer scheduled until its next instance is added. The instances not due yet are left
+    /// queued, as the earliest one may have been cancelled or rescheduled while waiting for it.
+    pub fn pop_due(&mut self, now: u64) -> Option<SubscriptionJobInstanceHeap> {
+        if self.next_trigger_time()? > now {
+            return None;
+        }
+        let instance = self.instances.pop()?;
+        self.scheduled.remove(&instance.subscription_key);
+        Some(instance)
+    }
+
+    fn is_live(&self, instance: &SubscriptionJobInstanceHeap) -> bool {
+        self.scheduled
+            .get(&instance.subscription_key)
+            .is_some_and(|(generation, _)| *generation == instance.generation)
+    }
+
+    fn discard_stale(&mut self) {
+        while let Some(instance) = self.instances.peek() {
+            if self.is_live(instance) {
+                break;
+            }
+            self.instances.pop();
+        }
+    }
+
+    // Drops the invalidated instances once they outnumber the live ones
+    fn compact(&mut self) {
+        if self.instances.len() <= 2 * self.scheduled.len() + 16 {
+            return;
+        }
+        let scheduled = &self.scheduled;
+        self.instances.retain(|instance| {
+            scheduled
+                .get(&instance.subscription_key)
+                .is_some_and(|(generation, _)| *generation == instance.generation)
+        });
+    }
+}
+
+pub async fn process_historic_job_subscriptions<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
     req_chain_tx: Sender<Job>,
     job_sub_tx: Sender<JobSubscriptionChannelType>,
 ) {
     info!("Processing Historic Job Subscriptions on Request Chains");
 
-    for request_chain_id in contracts_client.request_chain_ids.clone() {
-        let contracts_client_clone = contracts_client.clone();
-        let req_chain_tx_clone = req_chain_tx.clone();
+    for request_chain_id in contracts_client.request_chain_ids() {
+        contracts_client.spawn_service(process_historic_subscription_jobs_of_request_chain(
+            contracts_client.clone(),
+            request_chain_id,
+            req_chain_tx.clone(),
+            job_sub_tx.clone(),
+        ));
+    }
+}
 
-        let job_sub_tx_clone = job_sub_tx.clone();
-        tokio::spawn(async move {
-            let request_chain_client = contracts_client_clone
-                .request_chain_clients
-                .get(&request_chain_id)
-                .unwrap();
-            let http_provider = HttpProvider::new(request_chain_client.http_rpc_url.clone());
-            process_historic_subscription_jobs_on_request_chain(
-                &contracts_client_clone,
-                request_chain_client,
-                req_chain_tx_clone,
-                job_sub_tx_clone,
-                http_provider,
-            )
-            .await;
-        });
+/// Processes the subscriptions started on the request chain before it was listened to, retrying
+/// until their logs are fetched or the request chain is no longer served.
+pub async fn process_historic_subscription_jobs_of_request_chain<
+    M: Middleware + 'static,
+    C: ChainConnector<M>,
+>(
+    contracts_client: Arc<ContractsClient<M, C>>,
+    request_chain_id: u64,
+    req_chain_tx: Sender<Job>,
+    job_sub_tx: Sender<JobSubscriptionChannelType>,
+) {
+    loop {
+        let Some(request_chain_client) = contracts_client.request_chain_client(request_chain_id)
+        else {
+            info!("Request Chain ID: {:?}, no longer served", request_chain_id);
+            return;
+        };
+        let http_provider = request_chain_client.http_rpc_client.clone();
+        match process_historic_subscription_jobs_on_request_chain(
+            &contracts_client,
+            &request_chain_client,
+            req_chain_tx.clone(),
+            job_sub_tx.clone(),
+            http_provider,
+        )
+        .await
+        {
+            Ok(()) => return,
+            Err(err) => {
+                error!(
+                    "Request Chain ID: {:?}, failed to process the historic subscription jobs: {:#}",
+                    request_chain_id, err
+                );
+                sleep(Duration::from_secs(1)).await;
+            }
+        }
     }
 }
 
-pub async fn process_historic_subscription_jobs_on_request_chain<'a, P: HttpProviderLogs>(
-    contracts_client: &Arc<ContractsClient>,
-    request_chain_client: &Arc<RequestChainClient>,
+pub async fn process_historic_subscription_jobs_on_request_chain<
+    'a,
+    M: Middleware + 'static,
+    C: ChainConnector<M>,
+    P: HttpProviderLogs,
+>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    request_chain_client: &Arc<RequestChainClient<M>>,
     req_chain_tx: Sender<Job>,
     job_sub_tx: Sender<JobSubscriptionChannelType>,
     http_provider: P,
-) {
+) -> anyhow::Result<()> {
     let logs = contracts_client
         .request_chain_historic_subscription_jobs(&request_chain_client, &http_provider)
         .await
-        .unwrap();
+        .context("Failed to fetch the historic subscription logs")?;
 
     let request_chain_id = request_chain_client.chain_id;
+    let mut added_subscription_keys = Vec::new();
 
     for log in logs {
-        if log.topics[0] == keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT).into() {
+        let Ok(topic0) = topic(&log, 0) else {
+            continue;
+        };
+
+        if topic0 == JobSubscriptionStartedFilter::signature() {
             info!(
-                "Processing Historic Job Subscription - Subscription Started - Subscription ID: {}",
-                log.topics[1]
+                "Processing Historic Job Subscription - Subscription Started - Subscription ID: {:?}",
+                topic(&log, 1).ok()
             );
-            let sub_id = add_subscription_job(
+            let Ok(Some(subscription_key)) = add_subscription_job(
                 contracts_client,
                 log,
                 request_chain_id,
                 req_chain_tx.clone(),
                 true,
-            )
-            .unwrap();
-            if sub_id == U256::zero() {
+            ) else {
                 continue;
-            }
+            };
+            added_subscription_keys.push(subscription_key);
+        } else if topic0 == JobSubscriptionJobParamsUpdatedFilter::signature() {
+            info!(
+                "Processing Historic Job Subscription - Subscription Params Updated - Subscription ID: {:?}",
+                topic(&log, 1).ok()
+            );
+            let _ = update_subscription_job_params(contracts_client, log, request_chain_id);
+        } else if topic0 == JobSubscriptionTerminationParamsUpdatedFilter::signature() {
+            info!(
+                "Processing Historic Job Subscription - Subscription Termination Params Updated - Subscription ID: {:?}",
+                topic(&log, 1).ok()
+            );
+            let _ =
+                update_subscription_job_termination_params(contracts_client, log, request_chain_id);
+        } else if topic0 == JobSubscriptionTerminatedFilter::signature() {
+            info!(
+                "Processing Historic Job Subscription - Subscription Terminated - Subscription ID: {:?}",
+                topic(&log, 1).ok()
+            );
+            let Ok(subscription_key) =
+                remove_subscription_job(contracts_client, log, request_chain_id)
+            else {
+                continue;
+            };
             job_sub_tx
                 .send(JobSubscriptionChannelType {
-                    subscription_action: JobSubscriptionAction::Add,
-                    subscription_id: sub_id,
+                    subscription_action: JobSubscriptionAction::Remove,
+                    subscription_key,
                 })
                 .await
-                .unwrap();
-        } else if log.topics[0]
-            == keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT).into()
-        {
+                .map_err(|_| anyhow!("Job subscription channel closed"))?;
+        } else if topic0 == JobSubscriptionFundsDepositedFilter::signature() {
             info!(
-                "Processing Historic Job Subscription - Subscription Params Updated - Subscription ID: {}",
-                log.topics[1]
+                "Processing Historic Job Subscription - Subscription Funds Deposited - Subscription ID: {:?}",
+                topic(&log, 1).ok()
             );
-            let _ = update_subscription_job_params(contracts_client, log);
-        } else if log.topics[0]
-            == keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT).into()
-        {
+            let _ =
+                update_subscription_job_funds_deposited(contracts_client, log, request_chain_id);
+        } else if topic0 == JobSubscriptionFundsWithdrawnFilter::signature() {
             info!(
-                "Processing Historic Job Subscription - Subscription Termination Params Updated - Subscription ID: {}",
-                log.topics[1]
+                "Processing Historic Job Subscription - Subscription Funds Withdrawn - Subscription ID: {:?}",
+                topic(&log, 1).ok()
             );
-            let _ = update_subscription_job_termination_params(contracts_client, log);
+            let _ =
+                update_subscription_job_funds_withdrawn(contracts_client, log, request_chain_id);
+        }
+    }
+
+    // Instances are caught up once all the updates to the subscriptions have been applied
+    for subscription_key in added_subscription_keys {
+        if !contracts_client
+            .subscription_jobs
+            .read()
+            .unwrap()
+            .contains_key(&subscription_key)
+        {
+            continue;
         }
+
+        catch_up_subscription_job(contracts_client, subscription_key, req_chain_tx.clone());
+
+        job_sub_tx
+            .send(JobSubscriptionChannelType {
+                subscription_action: JobSubscriptionAction::Add,
+                subscription_key,
+            })
+            .await
+            .map_err(|_| anyhow!("Job subscription channel closed"))?;
     }
+
+    info!(
+        "Request Chain ID: {}, Subscription instances caught up: {}, skipped: {}",
+        request_chain_id,
+        contracts_client
+            .subscription_catch_up_metrics
+            .caught_up_instances
+            .load(Ordering::Relaxed),
+        contracts_client
+            .subscription_catch_up_metrics
+            .skipped_instances
+            .load(Ordering::Relaxed)
+    );
+    Ok(())
 }
 
-pub async fn job_subscription_manager(
-    contracts_client: Arc<ContractsClient>,
+pub async fn job_subscription_manager<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: Arc<ContractsClient<M, C>>,
     mut rx: Receiver<JobSubscriptionChannelType>,
     req_chain_tx: Sender<Job>,
 ) {
     loop {
         let next_trigger_time: Option<u64>;
-        // Scope for read lock on subscription_job_instance_heap
+        // Scope for write lock on subscription_job_scheduler
         {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            next_trigger_time = subscription_job_instance_heap_guard
-                .peek()
-                .map(|t| t.next_trigger_time);
+            let mut subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.write().unwrap();
+            next_trigger_time = subscription_job_scheduler_guard.next_trigger_time();
         }
 
         tokio::select! {
@@ -187,28 +367,40 @@ pub async fn job_subscription_manager(
                     JobSubscriptionAction::Add => {
                         info!(
                             "Added new subscription JobSubscriptionId: {}",
-                            job_subscription_channel_data.subscription_id
+                            job_subscription_channel_data.subscription_key
+                        );
+                    }
+                    JobSubscriptionAction::Remove => {
+                        info!(
+                            "Removed subscription JobSubscriptionId: {}",
+                            job_subscription_channel_data.subscription_key
+                        );
+                    }
+                    JobSubscriptionAction::Update => {
+                        info!(
+                            "Updated subscription JobSubscriptionId: {}",
+                            job_subscription_channel_data.subscription_key
                         );
                     }
                 }
             }
             _ = sleep_until(next_trigger_time.map(|t|
-                unix_timestamp_to_instant(t)
+                unix_timestamp_to_instant(t, contracts_client.clock.now())
             ).unwrap_or_else(Instant::now)), if next_trigger_time.is_some() => {
-                let contracts_client_clone = contracts_client.clone();
                 let subscription_job_instance: Option<SubscriptionJobInstanceHeap>;
+                // Scope for write lock on subscription_job_scheduler
                 {
-                    let mut subscription_job_instance_heap_guard = contracts_client.subscription_job_instance_heap
+                    let mut subscription_job_scheduler_guard = contracts_client.subscription_job_scheduler
                         .write()
                         .unwrap();
-                    subscription_job_instance = subscription_job_instance_heap_guard.pop();
+                    subscription_job_instance =
+                        subscription_job_scheduler_guard.pop_due(contracts_client.clock.now());
                 }
 
-                if subscription_job_instance.is_none() {
-                    error!("Subscription Job Triggered but no subscription found");
+                // The instance waited for was cancelled or rescheduled, wait for the next one
+                let Some(subscription_job_instance) = subscription_job_instance else {
                     continue;
-                }
-                let subscription_job_instance = subscription_job_instance.unwrap();
+                };
 
                 let req_chain_tx_clone = req_chain_tx.clone();
 
@@ -217,31 +409,42 @@ pub async fn job_subscription_manager(
                 {
                     let subscription_jobs_guard = contracts_client.subscription_jobs.read().unwrap();
                     subscription_job = subscription_jobs_guard
-                        .get(&subscription_job_instance.subscription_id)
+                        .get(&subscription_job_instance.subscription_key)
                         .cloned();
                 }
 
                 if subscription_job.is_none() {
                     info!(
                         "Job No longer active for Subscription - Subscription ID: {}",
-                        subscription_job_instance.subscription_id
+                        subscription_job_instance.subscription_key
                     );
-                    return;
+                    continue;
+                }
+
+                if U256::from(subscription_job_instance.next_trigger_time)
+                    > subscription_job.as_ref().unwrap().termination_time
+                {
+                    info!(
+                        "Subscription Job has reached termination time - Subscription ID: {}",
+                        subscription_job_instance.subscription_key
+                    );
+                    drop_subscription_job(
+                        &contracts_client,
+                        &subscription_job_instance.subscription_key,
+                    );
+                    continue;
                 }
 
-                tokio::spawn(async move {
-                    trigger_subscription_job(
-                        subscription_job.unwrap(),
-                        subscription_job_instance.next_trigger_time,
-                        contracts_client_clone,
-                        req_chain_tx_clone
-                    ).await;
-                });
+                dispatch_subscription_instance(
+                    &contracts_client,
+                    subscription_job.unwrap(),
+                    subscription_job_instance.next_trigger_time,
+                    req_chain_tx_clone,
+                );
                 add_next_trigger_time_to_heap(
                     &contracts_client,
-                    subscription_job_instance.subscription_id.clone(),
+                    subscription_job_instance.subscription_key,
                     subscription_job_instance.next_trigger_time,
-                    false,
                 );
             }
             else => {
@@ -252,121 +455,316 @@ pub async fn job_subscription_manager(
     }
 }
 
-pub fn add_subscription_job(
-    contracts_client: &Arc<ContractsClient>,
+/// Queues the instance triggered at the trigger time behind the rate limit of its request chain,
+/// or relays it right away when the request chain is not rate limited.
+pub fn dispatch_subscription_instance<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_job: SubscriptionJob,
+    trigger_time: u64,
+    req_chain_tx: Sender<Job>,
+) {
+    if contracts_client
+        .subscription_trigger_limiter
+        .is_rate_limited(subscription_job.request_chain_id)
+    {
+        contracts_client
+            .subscription_trigger_limiter
+            .enqueue(subscription_job, trigger_time);
+        return;
+    }
+
+    let contracts_client_clone = contracts_client.clone();
+    tokio::spawn(async move {
+        relay_subscription_instance(
+            contracts_client_clone,
+            subscription_job,
+            trigger_time,
+            req_chain_tx,
+        )
+        .await
+    });
+}
+
+/// Relays the instance of the subscription job triggered at the trigger time, after a random
+/// jitter to spread out the instances of the subscriptions sharing the same schedule.
+pub async fn relay_subscription_instance<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: Arc<ContractsClient<M, C>>,
+    subscription_job: SubscriptionJob,
+    trigger_time: u64,
+    req_chain_tx: Sender<Job>,
+) {
+    let jitter_ms = contracts_client
+        .subscription_trigger_limiter
+        .config
+        .jitter_ms;
+    if jitter_ms > 0 {
+        let jitter = rand::thread_rng().gen_range(0..=jitter_ms);
+        sleep(Duration::from_millis(jitter)).await;
+    }
+
+    let Some(request_chain_client) =
+        contracts_client.request_chain_client(subscription_job.request_chain_id)
+    else {
+        error!(
+            "Request Chain Client not found for Chain ID: {}",
+            subscription_job.request_chain_id
+        );
+        return;
+    };
+
+    if !is_subscription_instance_affordable(
+        &contracts_client,
+        &subscription_job,
+        request_chain_client.as_ref(),
+    )
+    .await
+    {
+        record_subscription_instance(
+            &contracts_client,
+            &JobKey::subscription_instance(
+                subscription_job.request_chain_id,
+                subscription_job.subscription_id,
+                subscription_job.schedule().instance_index(trigger_time),
+            ),
+            SubscriptionInstanceStatus::Skipped {
+                reason: "Insufficient funds".to_owned(),
+            },
+        );
+        return;
+    }
+
+    trigger_subscription_job(
+        subscription_job,
+        trigger_time,
+        contracts_client,
+        req_chain_tx,
+    )
+    .await;
+}
+
+pub fn add_subscription_job<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
     subscription_log: Log,
     request_chain_id: u64,
     req_chain_tx: Sender<Job>,
     is_historic_log: bool,
-) -> Result<U256, ServerlessError> {
-    let types = vec![
-        ParamType::Uint(256),
-        ParamType::Uint(256),
-        ParamType::Uint(256),
-        ParamType::Uint(256),
-        ParamType::Address,
-        ParamType::FixedBytes(32),
-        ParamType::Bytes,
-        ParamType::Uint(256),
-    ];
-
-    let decoded = decode(&types, &subscription_log.data.0);
-    let decoded = match decoded {
-        Ok(decoded) => decoded,
-        Err(e) => {
-            error!("Failed to decode subscription log: {}", e);
-            return Err(ServerlessError::LogDecodeFailure);
-        }
-    };
+) -> Result<Option<SubscriptionKey>, ServerlessError> {
+    let job_subscription_started_event =
+        match decode_event::<JobSubscriptionStartedFilter>(&subscription_log) {
+            Ok(job_subscription_started_event) => job_subscription_started_event,
+            Err(e) => {
+                error!("Failed to decode subscription log: {}", e);
+                return Err(e);
+            }
+        };
+
+    // The instances of the subscription are scheduled on u64 timestamps
+    let (starttime, termination_time) =
+        match decode_subscription_times(&job_subscription_started_event) {
+            Ok(subscription_times) => subscription_times,
+            Err(e) => {
+                error!(
+                    "Subscription times out of range in subscription log: {:?}",
+                    subscription_log
+                );
+                return Err(e);
+            }
+        };
 
     let subscription_job = SubscriptionJob {
-        subscription_id: subscription_log.topics[1].into_uint(),
+        subscription_id: job_subscription_started_event.job_subs_id,
         request_chain_id,
-        subscriber: subscription_log.topics[3].into(),
-        interval: decoded[0].clone().into_uint().unwrap().into(),
-        termination_time: decoded[2].clone().into_uint().unwrap().into(),
-        user_timeout: decoded[3].clone().into_uint().unwrap().into(),
-        tx_hash: decoded[5].clone().into_fixed_bytes().unwrap(),
-        code_input: decoded[6].clone().into_bytes().unwrap().into(),
-        starttime: decoded[7].clone().into_uint().unwrap().into(),
-        env: subscription_log.topics[2].into_uint().as_u64() as u8,
+        subscriber: job_subscription_started_event.job_subscriber,
+        interval: job_subscription_started_event.periodic_gap,
+        termination_time: job_subscription_started_event.termination_timestamp,
+        user_timeout: job_subscription_started_event.user_timeout,
+        tx_hash: job_subscription_started_event.codehash.to_vec(),
+        code_input: job_subscription_started_event.code_inputs,
+        starttime: job_subscription_started_event.start_time,
+        env: job_subscription_started_event.env,
+        usdc_balance: job_subscription_started_event.usdc_deposit,
     };
 
-    let current_timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    // The instances of the subscription are relayed with the subscription id in their job keys
+    if let Err(err) =
+        JobKey::subscription_instance(request_chain_id, subscription_job.subscription_id, 0)
+            .validate()
+    {
+        error!("{}", err);
+        return Err(err);
+    }
 
-    if is_historic_log && subscription_job.termination_time.as_u64() < current_timestamp {
+    let subscription_key = subscription_job.key();
+    let current_timestamp = contracts_client.clock.now();
+
+    if is_historic_log && termination_time < current_timestamp {
         info!(
             "Subscription Job is complete - Subscription ID: {}",
-            subscription_job.subscription_id
+            subscription_key
         );
-        return Ok(0.into());
+        return Ok(None);
     }
 
     // Scope for write lock on subscription_jobs
     {
         let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-        subscription_jobs.insert(subscription_job.subscription_id, subscription_job.clone());
+        subscription_jobs.insert(subscription_key, subscription_job.clone());
     }
 
-    let mut to_trigger_first_instance = true;
+    // Instances of historic subscriptions are scheduled by `catch_up_subscription_job`
     if is_historic_log {
-        let minimum_timestamp_for_job = get_minimum_timestamp_for_job(contracts_client);
+        return Ok(Some(subscription_key));
+    }
 
-        if subscription_job.starttime.as_u64() < minimum_timestamp_for_job {
-            to_trigger_first_instance = false;
-        }
+    if starttime > current_timestamp {
+        info!(
+            "Subscription Job is scheduled for future - Subscription ID: {}",
+            subscription_key
+        );
+
+        add_first_trigger_time_to_heap(contracts_client, subscription_key);
+        return Ok(Some(subscription_key));
     }
 
-    let current_timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
+    dispatch_subscription_instance(
+        contracts_client,
+        subscription_job.clone(),
+        starttime,
+        req_chain_tx,
+    );
+
+    add_next_trigger_time_to_heap(contracts_client, subscription_key, starttime);
+    Ok(Some(subscription_key))
+}
+
+// Start and termination times of the started subscription, checking that every time of it fits
+// in u64
+fn decode_subscription_times(
+    job_subscription_started_event: &JobSubscriptionStartedFilter,
+) -> Result<(u64, u64), ServerlessError> {
+    uint_to_u64(job_subscription_started_event.periodic_gap)?;
+    uint_to_u64(job_subscription_started_event.user_timeout)?;
+    Ok((
+        uint_to_u64(job_subscription_started_event.start_time)?,
+        uint_to_u64(job_subscription_started_event.termination_timestamp)?,
+    ))
+}
+
+/// Applies the catch up policy to the instances of a historic subscription that fell within
+/// the maintained block states while the gateway was down, and schedules its next instance.
+///
+/// Instances past their deadline, the end of their user timeout or of the window to relay them on
+/// the common chain, are skipped under every policy.
+pub fn catch_up_subscription_job<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_key: SubscriptionKey,
+    req_chain_tx: Sender<Job>,
+) {
+    let subscription_job = contracts_client
+        .subscription_jobs
+        .read()
         .unwrap()
-        .as_secs();
+        .get(&subscription_key)
+        .cloned();
+
+    let Some(subscription_job) = subscription_job else {
+        error!(
+            "Subscription Job not found for Subscription ID: {}",
+            subscription_key
+        );
+        return;
+    };
 
-    let mut is_starttime_in_future = false;
-    if subscription_job.starttime.as_u64() > current_timestamp {
+    let current_timestamp = contracts_client.clock.now();
+    let schedule = subscription_job.schedule();
+
+    if schedule.starttime() > current_timestamp {
         info!(
             "Subscription Job is scheduled for future - Subscription ID: {}",
-            subscription_job.subscription_id
+            subscription_key
         );
+        add_first_trigger_time_to_heap(contracts_client, subscription_key);
+        return;
+    }
 
-        to_trigger_first_instance = false;
+    // Instances from the minimum timestamp up to the current time, before the termination time
+    let missed_instances = schedule.trigger_times_between(
+        get_minimum_timestamp_for_job(contracts_client),
+        current_timestamp,
+    );
 
-        add_next_trigger_time_to_heap(
-            &contracts_client,
-            subscription_job.subscription_id,
-            subscription_job.starttime.as_u64() - subscription_job.interval.as_u64(),
-            is_historic_log,
+    // Instances past their deadline would only be dropped by the relay pipeline
+    let relayable_instances: Vec<u64> = missed_instances
+        .iter()
+        .copied()
+        .filter(|&instance_time| {
+            subscription_job.instance_deadline(instance_time) > current_timestamp
+        })
+        .collect();
+
+    let instances_to_relay = match contracts_client.subscription_catch_up_policy {
+        SubscriptionCatchUpPolicy::SkipAll => Vec::new(),
+        SubscriptionCatchUpPolicy::RelayAll => relayable_instances,
+        SubscriptionCatchUpPolicy::RelayLatest => {
+            relayable_instances.last().copied().into_iter().collect()
+        }
+    };
+
+    info!(
+        "Subscription ID: {}, missed instances: {}, catching up: {}",
+        subscription_key,
+        missed_instances.len(),
+        instances_to_relay.len()
+    );
+    contracts_client
+        .subscription_catch_up_metrics
+        .caught_up_instances
+        .fetch_add(instances_to_relay.len() as u64, Ordering::Relaxed);
+    contracts_client
+        .subscription_catch_up_metrics
+        .skipped_instances
+        .fetch_add(
+            (missed_instances.len() - instances_to_relay.len()) as u64,
+            Ordering::Relaxed,
         );
 
-        is_starttime_in_future = true;
+    for &instance_time in missed_instances.iter() {
+        if instances_to_relay.contains(&instance_time) {
+            continue;
+        }
+        let reason = if subscription_job.instance_deadline(instance_time) > current_timestamp {
+            "Catch up policy"
+        } else {
+            "Deadline passed during downtime"
+        };
+        record_subscription_instance(
+            contracts_client,
+            &JobKey::subscription_instance(
+                subscription_job.request_chain_id,
+                subscription_job.subscription_id,
+                schedule.instance_index(instance_time),
+            ),
+            SubscriptionInstanceStatus::Skipped {
+                reason: reason.to_owned(),
+            },
+        );
     }
 
-    if to_trigger_first_instance {
-        let contracts_client_clone = contracts_client.clone();
-        let subscription_job_clone = subscription_job.clone();
-        tokio::spawn(async move {
-            trigger_subscription_job(
-                subscription_job_clone,
-                subscription_job.starttime.as_u64(),
-                contracts_client_clone,
-                req_chain_tx,
-            )
-            .await
-        });
+    for instance_time in instances_to_relay {
+        dispatch_subscription_instance(
+            contracts_client,
+            subscription_job.clone(),
+            instance_time,
+            req_chain_tx.clone(),
+        );
     }
 
-    if !is_starttime_in_future {
-        add_next_trigger_time_to_heap(
-            &contracts_client,
-            subscription_job.subscription_id,
-            subscription_job.starttime.as_u64(),
-            is_historic_log,
-        );
+    match schedule.latest_trigger_time_until(current_timestamp) {
+        Some(latest_instance_time) => {
+            add_next_trigger_time_to_heap(contracts_client, subscription_key, latest_instance_time)
+        }
+        None => add_first_trigger_time_to_heap(contracts_client, subscription_key),
     }
-    Ok(subscription_job.subscription_id)
 }
 
 /// Returns the minimum timestamp for a job to be triggered w.r.t to the current time
@@ -379,112 +777,371 @@ pub fn add_subscription_job(
 /// # Returns
 ///
 /// * A `u64` representing the minimum timestamp for a job to be triggered.
-fn get_minimum_timestamp_for_job(contracts_client: &Arc<ContractsClient>) -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
+fn get_minimum_timestamp_for_job<M, C>(contracts_client: &Arc<ContractsClient<M, C>>) -> u64 {
+    contracts_client.clock.now()
         - ((GATEWAY_BLOCK_STATES_TO_MAINTAIN) * contracts_client.time_interval)
         - contracts_client.offset_for_epoch
 }
 
-fn add_next_trigger_time_to_heap(
-    contracts_client: &Arc<ContractsClient>,
-    subscription_id: U256,
+fn add_next_trigger_time_to_heap<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_key: SubscriptionKey,
     previous_trigger_time: u64,
-    is_historic_log: bool,
+) {
+    schedule_subscription_instance(contracts_client, subscription_key, |schedule| {
+        schedule.next_trigger_time(previous_trigger_time)
+    });
+}
+
+fn add_first_trigger_time_to_heap<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_key: SubscriptionKey,
+) {
+    schedule_subscription_instance(contracts_client, subscription_key, |schedule| {
+        schedule.first_trigger_time()
+    });
+}
+
+// Queues the instance of the subscription job picked from its schedule in the scheduler, or
+// drops the subscription job if its schedule has no instances left
+fn schedule_subscription_instance<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_key: SubscriptionKey,
+    pick_trigger_time: impl FnOnce(&dyn Schedule) -> Option<u64>,
 ) {
     let subscription_job = contracts_client
         .subscription_jobs
         .read()
         .unwrap()
-        .get(&subscription_id)
+        .get(&subscription_key)
         .cloned();
 
     if subscription_job.is_none() {
         error!(
             "Subscription Job not found for Subscription ID: {}",
-            subscription_id
+            subscription_key
         );
         return;
     }
 
     let subscription_job = subscription_job.unwrap();
 
-    let mut next_trigger_time = previous_trigger_time + subscription_job.interval.as_u64();
-
-    if next_trigger_time > subscription_job.termination_time.as_u64() {
+    let Some(next_trigger_time) = pick_trigger_time(&subscription_job.schedule()) else {
         info!(
             "Subscription Job has reached termination time - Subscription ID: {}",
-            subscription_job.subscription_id
+            subscription_key
         );
-        // Scope for write lock on subscription_jobs
-        {
-            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.remove(&subscription_id);
-        }
+        drop_subscription_job(contracts_client, &subscription_key);
         return;
-    }
-
-    if is_historic_log {
-        let minimum_timestamp_for_job = get_minimum_timestamp_for_job(contracts_client);
+    };
 
-        if next_trigger_time < minimum_timestamp_for_job {
-            let instance_count = ((minimum_timestamp_for_job
-                - subscription_job.starttime.as_u64())
-                / subscription_job.interval.as_u64())
-                + 1;
+    // Scope for write lock on subscription_job_scheduler
+    {
+        let mut subscription_job_scheduler_guard =
+            contracts_client.subscription_job_scheduler.write().unwrap();
+        subscription_job_scheduler_guard.schedule(subscription_key, next_trigger_time);
+    }
+}
 
-            next_trigger_time = subscription_job.starttime.as_u64()
-                + instance_count * subscription_job.interval.as_u64();
-        }
+// Removes the subscription job along with the history of its instances
+fn drop_subscription_job<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_key: &SubscriptionKey,
+) -> Option<SubscriptionJob> {
+    let subscription_job: Option<SubscriptionJob>;
+    // Scope for write lock on subscription_jobs
+    {
+        let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+        subscription_job = subscription_jobs.remove(subscription_key);
     }
 
-    // Scope for write lock on subscription_job_instance_heap
+    // Scope for write lock on subscription_instance_history
     {
-        let mut subscription_job_instance_heap_guard = contracts_client
-            .subscription_job_instance_heap
+        let mut subscription_instance_history = contracts_client
+            .subscription_instance_history
             .write()
             .unwrap();
-        subscription_job_instance_heap_guard.push(SubscriptionJobInstanceHeap {
-            subscription_id: subscription_job.subscription_id,
-            next_trigger_time,
-        });
+        subscription_instance_history.remove(subscription_key);
     }
+
+    subscription_job
 }
 
-async fn trigger_subscription_job(
-    subscription_job: SubscriptionJob,
-    trigger_timestamp: u64,
-    contracts_client: Arc<ContractsClient>,
-    req_chain_tx: Sender<Job>,
+/// Records the latest status of an instance of a subscription job, shown by the subscription
+/// preview endpoint. Only the last `SUBSCRIPTION_INSTANCE_HISTORY_LEN` instances are kept.
+pub fn record_subscription_instance<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    job_key: &JobKey,
+    status: SubscriptionInstanceStatus,
 ) {
-    info!(
-        "Triggering subscription job with ID: {}",
-        subscription_job.subscription_id
-    );
+    if job_key.job_mode != JobMode::Subscription {
+        return;
+    }
 
-    let job = subscription_job_to_relay_job(subscription_job, trigger_timestamp);
+    let subscription_key = job_key.subscription_key();
+    let subscription_job = contracts_client
+        .subscription_jobs
+        .read()
+        .unwrap()
+        .get(&subscription_key)
+        .cloned();
+    let Some(subscription_job) = subscription_job else {
+        return;
+    };
 
-    contracts_client
-        .job_relayed_handler(job, req_chain_tx)
-        .await;
-}
+    let Some(trigger_time) = subscription_job
+        .schedule()
+        .trigger_time_of_instance(job_key.instance_index)
+    else {
+        return;
+    };
 
-fn subscription_job_to_relay_job(subscription_job: SubscriptionJob, trigger_timestamp: u64) -> Job {
-    let instance_count =
-        (U256::from(trigger_timestamp) - subscription_job.starttime) / subscription_job.interval;
-    let job_id = subscription_job.subscription_id + instance_count;
-    let instance_starttime =
-        subscription_job.starttime + instance_count * subscription_job.interval;
+    // Scope for write lock on subscription_instance_history
+    {
+        let mut subscription_instance_history = contracts_client
+            .subscription_instance_history
+            .write()
+            .unwrap();
+        let instances = subscription_instance_history
+            .entry(subscription_key)
+            .or_default();
+        instances.insert(
+            job_key.instance_index,
+            SubscriptionInstanceRecord {
+                instance_index: job_key.instance_index,
+                trigger_time,
+                status,
+                updated_at: contracts_client.clock.now(),
+            },
+        );
+        while instances.len() > SUBSCRIPTION_INSTANCE_HISTORY_LEN {
+            instances.pop_first();
+        }
+    }
+}
 
-    Job {
-        job_id,
-        request_chain_id: subscription_job.request_chain_id,
-        tx_hash: subscription_job.tx_hash,
+/// Returns the subscription job with its next `num_instances` instances, from the one queued
+/// in the scheduler, along with the gateway each of them would be assigned to by the current
+/// gateway epoch state, and the recorded statuses of its past instances.
+pub fn preview_subscription_job<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_key: SubscriptionKey,
+    num_instances: usize,
+) -> Result<SubscriptionPreviewResponse, ServerlessError> {
+    let subscription_job = contracts_client
+        .subscription_jobs
+        .read()
+        .unwrap()
+        .get(&subscription_key)
+        .cloned();
+    let Some(subscription_job) = subscription_job else {
+        return Err(ServerlessError::NoSubscriptionJobFound(
+            subscription_key.request_chain_id,
+            subscription_key.subscription_id,
+        ));
+    };
+
+    let schedule = subscription_job.schedule();
+    let mut trigger_time = contracts_client
+        .subscription_job_scheduler
+        .read()
+        .unwrap()
+        .scheduled_trigger_time(&subscription_key);
+
+    let mut upcoming_instances = Vec::new();
+    while let Some(instance_trigger_time) = trigger_time {
+        if upcoming_instances.len() >= num_instances {
+            break;
+        }
+
+        let job = subscription_job_to_relay_job(subscription_job.clone(), instance_trigger_time);
+        let (gateway, gateway_error) = match preview_gateway_for_job(contracts_client, &job) {
+            Ok(gateway) => (Some(gateway), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        upcoming_instances.push(UpcomingSubscriptionInstance {
+            instance_index: job.instance_index,
+            trigger_time: instance_trigger_time,
+            gateway,
+            gateway_error,
+        });
+
+        trigger_time = schedule.next_trigger_time(instance_trigger_time);
+    }
+
+    let past_instances = contracts_client
+        .subscription_instance_history
+        .read()
+        .unwrap()
+        .get(&subscription_key)
+        .map(|instances| instances.values().cloned().collect())
+        .unwrap_or_default();
+
+    Ok(SubscriptionPreviewResponse {
+        subscription_id: subscription_job.subscription_id,
+        request_chain_id: subscription_job.request_chain_id,
+        subscriber: subscription_job.subscriber,
+        interval: subscription_job.interval,
+        termination_time: subscription_job.termination_time,
+        user_timeout: subscription_job.user_timeout,
+        tx_hash: format!("0x{}", hex::encode(&subscription_job.tx_hash)),
+        code_input: subscription_job.code_input,
+        starttime: subscription_job.starttime,
+        env: subscription_job.env,
+        usdc_balance: subscription_job.usdc_balance,
+        upcoming_instances,
+        past_instances,
+    })
+}
+
+// Gateway selected for the job with the gateway epoch state of its cycle. The states of the
+// upcoming cycles are not known yet, the latest known state is used for them instead.
+fn preview_gateway_for_job<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    job: &Job,
+) -> Result<Address, ServerlessError> {
+    let job_cycle = job
+        .starttime
+        .as_u64()
+        .saturating_sub(contracts_client.epoch + contracts_client.offset_for_epoch)
+        / contracts_client.time_interval;
+
+    let all_gateways_data: Vec<GatewayData>;
+    // Scope for read lock on gateway_epoch_state
+    {
+        let gateway_epoch_state = contracts_client.gateway_epoch_state.read().unwrap();
+        all_gateways_data = gateway_epoch_state
+            .get(&job_cycle)
+            .or_else(|| {
+                gateway_epoch_state
+                    .last_key_value()
+                    .map(|(_, gateways)| gateways)
+            })
+            .map(|gateways| gateways.values().cloned().collect())
+            .unwrap_or_default();
+    }
+
+    select_gateway(
+        &all_gateways_data,
+        job.request_chain_id,
+        job_cycle,
+        job.starttime.as_u64(),
+        job.sequence_number,
+    )
+}
+
+/// Checks whether the subscriber can pay for one more instance of the subscription job and
+/// reserves the fee for it from the tracked balance.
+///
+/// The tracked balance is charged the maximum fee for every relayed instance, so it is
+/// refreshed from the `RelaySubscriptions` contract before an instance is skipped.
+/// If the fee can not be fetched the instance is relayed as before.
+async fn is_subscription_instance_affordable<M, C, V: RelayFundsView>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_job: &SubscriptionJob,
+    relay_funds_view: &V,
+) -> bool {
+    let subscription_key = subscription_job.key();
+
+    let fee = match relay_funds_view
+        .job_fee(subscription_job.env, subscription_job.user_timeout)
+        .await
+    {
+        Ok(fee) => fee,
+        Err(err) => {
+            error!(
+                "Failed to fetch the job fee for Subscription ID: {}, Error: {}",
+                subscription_key, err
+            );
+            return true;
+        }
+    };
+
+    if reserve_subscription_instance_fee(contracts_client, &subscription_key, fee) {
+        return true;
+    }
+
+    match relay_funds_view
+        .job_subscription_balance(subscription_key.subscription_id)
+        .await
+    {
+        Ok(usdc_balance) => {
+            // Scope for write lock on subscription_jobs
+            {
+                let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+                if let Some(subscription_job) = subscription_jobs.get_mut(&subscription_key) {
+                    subscription_job.usdc_balance = usdc_balance;
+                }
+            }
+            if reserve_subscription_instance_fee(contracts_client, &subscription_key, fee) {
+                return true;
+            }
+        }
+        Err(err) => {
+            error!(
+                "Failed to fetch the balance for Subscription ID: {}, Error: {}",
+                subscription_key, err
+            );
+        }
+    }
+
+    error!(
+        "Insufficient funds for Subscription ID: {}, skipping the instance with fee: {}",
+        subscription_key, fee
+    );
+    false
+}
+
+fn reserve_subscription_instance_fee<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_key: &SubscriptionKey,
+    fee: U256,
+) -> bool {
+    let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+    let Some(subscription_job) = subscription_jobs.get_mut(subscription_key) else {
+        return false;
+    };
+
+    if subscription_job.usdc_balance < fee {
+        return false;
+    }
+    subscription_job.usdc_balance -= fee;
+    true
+}
+
+async fn trigger_subscription_job<M: Middleware + 'static, C: ChainConnector<M>>(
+    subscription_job: SubscriptionJob,
+    trigger_timestamp: u64,
+    contracts_client: Arc<ContractsClient<M, C>>,
+    req_chain_tx: Sender<Job>,
+) {
+    info!(
+        "Triggering subscription job with ID: {}",
+        subscription_job.key()
+    );
+
+    let job = subscription_job_to_relay_job(subscription_job, trigger_timestamp);
+
+    contracts_client
+        .job_relayed_handler(job, req_chain_tx)
+        .await;
+}
+
+fn subscription_job_to_relay_job(subscription_job: SubscriptionJob, trigger_timestamp: u64) -> Job {
+    let schedule = subscription_job.schedule();
+    let instance_index = schedule.instance_index(trigger_timestamp);
+    let instance_starttime = schedule
+        .trigger_time_of_instance(instance_index)
+        .unwrap_or(trigger_timestamp);
+
+    Job {
+        job_id: subscription_job.subscription_id,
+        instance_index,
+        request_chain_id: subscription_job.request_chain_id,
+        tx_hash: subscription_job.tx_hash,
         code_input: subscription_job.code_input,
         user_timeout: subscription_job.user_timeout,
-        starttime: instance_starttime,
+        starttime: U256::from(instance_starttime),
         job_owner: subscription_job.subscriber,
         job_type: GatewayJobType::JobRelay,
         sequence_number: 1,
@@ -494,46 +1151,49 @@ fn subscription_job_to_relay_job(subscription_job: SubscriptionJob, trigger_time
     }
 }
 
-pub fn update_subscription_job_params(
-    contracts_client: &Arc<ContractsClient>,
+pub fn update_subscription_job_params<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
     subscription_log: Log,
+    request_chain_id: u64,
 ) -> Result<(), ServerlessError> {
-    let types = vec![ParamType::FixedBytes(32), ParamType::Bytes];
-
-    let decoded = decode(&types, &subscription_log.data.0);
-    let decoded = match decoded {
-        Ok(decoded) => decoded,
-        Err(e) => {
-            error!("Failed to decode subscription log: {}", e);
-            return Err(ServerlessError::LogDecodeFailure);
-        }
-    };
+    let job_params_updated_event =
+        match decode_event::<JobSubscriptionJobParamsUpdatedFilter>(&subscription_log) {
+            Ok(job_params_updated_event) => job_params_updated_event,
+            Err(e) => {
+                error!("Failed to decode subscription log: {}", e);
+                return Err(e);
+            }
+        };
 
-    let subscription_id = subscription_log.topics[1].into_uint();
+    let subscription_key =
+        SubscriptionKey::new(request_chain_id, job_params_updated_event.job_subs_id);
 
     let subscription_job = contracts_client
         .subscription_jobs
         .read()
         .unwrap()
-        .get(&subscription_id)
+        .get(&subscription_key)
         .cloned();
 
     if subscription_job.is_none() {
         error!(
             "Subscription Job not found for Subscription ID: {}",
-            subscription_id
+            subscription_key
         );
-        return Err(ServerlessError::NoSubscriptionJobFound(subscription_id));
+        return Err(ServerlessError::NoSubscriptionJobFound(
+            request_chain_id,
+            subscription_key.subscription_id,
+        ));
     }
 
-    let new_tx_hash = decoded[0].clone().into_fixed_bytes().unwrap();
-    let new_code_input: Bytes = decoded[1].clone().into_bytes().unwrap().into();
+    let new_tx_hash = job_params_updated_event.codehash.to_vec();
+    let new_code_input = job_params_updated_event.code_inputs;
 
     // Update the subscription job
     // Scope for write lock on subscription_jobs
     {
         let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-        let subscription_job = subscription_jobs.get_mut(&subscription_id).unwrap();
+        let subscription_job = subscription_jobs.get_mut(&subscription_key).unwrap();
         subscription_job.tx_hash = new_tx_hash;
         subscription_job.code_input = new_code_input;
     }
@@ -541,98 +1201,292 @@ pub fn update_subscription_job_params(
     Ok(())
 }
 
-pub fn update_subscription_job_termination_params(
-    contracts_client: &Arc<ContractsClient>,
+pub fn update_subscription_job_termination_params<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
     subscription_log: Log,
+    request_chain_id: u64,
 ) -> Result<(), ServerlessError> {
-    let types = vec![ParamType::Uint(256)];
-
-    let decoded = decode(&types, &subscription_log.data.0);
-    let decoded = match decoded {
-        Ok(decoded) => decoded,
-        Err(e) => {
-            error!("Failed to decode subscription log: {}", e);
-            return Err(ServerlessError::LogDecodeFailure);
-        }
-    };
+    let termination_params_updated_event =
+        match decode_event::<JobSubscriptionTerminationParamsUpdatedFilter>(&subscription_log) {
+            Ok(termination_params_updated_event) => termination_params_updated_event,
+            Err(e) => {
+                error!("Failed to decode subscription log: {}", e);
+                return Err(e);
+            }
+        };
 
-    let subscription_id = subscription_log.topics[1].into_uint();
+    let subscription_key = SubscriptionKey::new(
+        request_chain_id,
+        termination_params_updated_event.job_subs_id,
+    );
 
     let subscription_job = contracts_client
         .subscription_jobs
         .read()
         .unwrap()
-        .get(&subscription_id)
+        .get(&subscription_key)
         .cloned();
 
     if subscription_job.is_none() {
         error!(
             "Subscription Job not found for Subscription ID: {}",
-            subscription_id
+            subscription_key
         );
-        return Err(ServerlessError::NoSubscriptionJobFound(subscription_id));
+        return Err(ServerlessError::NoSubscriptionJobFound(
+            request_chain_id,
+            subscription_key.subscription_id,
+        ));
     }
 
-    let new_termination_time = decoded[0].clone().into_uint().unwrap();
+    let new_termination_time = termination_params_updated_event.termination_timestamp;
+    if let Err(err) = uint_to_u64(new_termination_time) {
+        error!(
+            "Termination time out of range in subscription log: {:?}",
+            subscription_log
+        );
+        return Err(err);
+    }
 
     // Update the subscription job
     // Scope for write lock on subscription_jobs
     {
         let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-        let subscription_job = subscription_jobs.get_mut(&subscription_id).unwrap();
+        let subscription_job = subscription_jobs.get_mut(&subscription_key).unwrap();
         subscription_job.termination_time = new_termination_time;
     }
 
+    // Scope for write lock on subscription_job_scheduler
+    {
+        let mut subscription_job_scheduler_guard =
+            contracts_client.subscription_job_scheduler.write().unwrap();
+        let is_past_termination = subscription_job_scheduler_guard
+            .scheduled_trigger_time(&subscription_key)
+            .is_some_and(|next_trigger_time| U256::from(next_trigger_time) > new_termination_time);
+        if is_past_termination {
+            info!(
+                "Subscription Job has reached termination time - Subscription ID: {}",
+                subscription_key
+            );
+            subscription_job_scheduler_guard.cancel(&subscription_key);
+            drop_subscription_job(contracts_client, &subscription_key);
+        }
+    }
+
     Ok(())
 }
 
+pub fn remove_subscription_job<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_log: Log,
+    request_chain_id: u64,
+) -> Result<SubscriptionKey, ServerlessError> {
+    let terminated_event = match decode_event::<JobSubscriptionTerminatedFilter>(&subscription_log)
+    {
+        Ok(terminated_event) => terminated_event,
+        Err(e) => {
+            error!("Failed to decode subscription log: {}", e);
+            return Err(e);
+        }
+    };
+
+    let subscription_key = SubscriptionKey::new(request_chain_id, terminated_event.job_subs_id);
+
+    let subscription_job = drop_subscription_job(contracts_client, &subscription_key);
+
+    // Scope for write lock on subscription_job_scheduler
+    {
+        let mut subscription_job_scheduler_guard =
+            contracts_client.subscription_job_scheduler.write().unwrap();
+        subscription_job_scheduler_guard.cancel(&subscription_key);
+    }
+
+    if subscription_job.is_none() {
+        error!(
+            "Subscription Job not found for Subscription ID: {}",
+            subscription_key
+        );
+        return Err(ServerlessError::NoSubscriptionJobFound(
+            request_chain_id,
+            subscription_key.subscription_id,
+        ));
+    }
+
+    info!(
+        "Subscription Job terminated - Subscription ID: {}",
+        subscription_key
+    );
+
+    Ok(subscription_key)
+}
+
+/// Drops the subscription jobs started on the request chain once the gateway stops serving it,
+/// returns the keys of the dropped subscriptions.
+pub fn remove_request_chain_subscription_jobs<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    request_chain_id: u64,
+) -> Vec<SubscriptionKey> {
+    let subscription_keys: Vec<SubscriptionKey> = contracts_client
+        .subscription_jobs
+        .read()
+        .unwrap()
+        .keys()
+        .filter(|subscription_key| subscription_key.request_chain_id == request_chain_id)
+        .copied()
+        .collect();
+
+    for subscription_key in subscription_keys.iter() {
+        drop_subscription_job(contracts_client, subscription_key);
+        contracts_client
+            .subscription_job_scheduler
+            .write()
+            .unwrap()
+            .cancel(subscription_key);
+    }
+
+    info!(
+        "Dropped {} subscription jobs of the Request Chain ID: {}",
+        subscription_keys.len(),
+        request_chain_id
+    );
+    subscription_keys
+}
+
+pub fn update_subscription_job_funds_deposited<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_log: Log,
+    request_chain_id: u64,
+) -> Result<SubscriptionKey, ServerlessError> {
+    let funds_deposited_event =
+        match decode_event::<JobSubscriptionFundsDepositedFilter>(&subscription_log) {
+            Ok(funds_deposited_event) => funds_deposited_event,
+            Err(e) => {
+                error!("Failed to decode subscription log: {}", e);
+                return Err(e);
+            }
+        };
+
+    let subscription_key =
+        SubscriptionKey::new(request_chain_id, funds_deposited_event.job_subs_id);
+
+    // Scope for write lock on subscription_jobs
+    {
+        let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+        let Some(subscription_job) = subscription_jobs.get_mut(&subscription_key) else {
+            error!(
+                "Subscription Job not found for Subscription ID: {}",
+                subscription_key
+            );
+            return Err(ServerlessError::NoSubscriptionJobFound(
+                request_chain_id,
+                subscription_key.subscription_id,
+            ));
+        };
+        subscription_job.usdc_balance = subscription_job
+            .usdc_balance
+            .saturating_add(funds_deposited_event.usdc_deposit);
+    }
+
+    info!(
+        "Subscription Job funds deposited - Subscription ID: {}, USDC: {}, Callback: {}",
+        subscription_key,
+        funds_deposited_event.usdc_deposit,
+        funds_deposited_event.callback_deposit
+    );
+
+    Ok(subscription_key)
+}
+
+pub fn update_subscription_job_funds_withdrawn<M, C>(
+    contracts_client: &Arc<ContractsClient<M, C>>,
+    subscription_log: Log,
+    request_chain_id: u64,
+) -> Result<SubscriptionKey, ServerlessError> {
+    let funds_withdrawn_event =
+        match decode_event::<JobSubscriptionFundsWithdrawnFilter>(&subscription_log) {
+            Ok(funds_withdrawn_event) => funds_withdrawn_event,
+            Err(e) => {
+                error!("Failed to decode subscription log: {}", e);
+                return Err(e);
+            }
+        };
+
+    let subscription_key =
+        SubscriptionKey::new(request_chain_id, funds_withdrawn_event.job_subs_id);
+
+    // Scope for write lock on subscription_jobs
+    {
+        let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+        let Some(subscription_job) = subscription_jobs.get_mut(&subscription_key) else {
+            error!(
+                "Subscription Job not found for Subscription ID: {}",
+                subscription_key
+            );
+            return Err(ServerlessError::NoSubscriptionJobFound(
+                request_chain_id,
+                subscription_key.subscription_id,
+            ));
+        };
+        if funds_withdrawn_event.success {
+            subscription_job.usdc_balance = subscription_job
+                .usdc_balance
+                .saturating_sub(funds_withdrawn_event.usdc_amount_withdrawn);
+        }
+    }
+
+    info!(
+        "Subscription Job funds withdrawn - Subscription ID: {}, USDC: {}, Callback: {}",
+        subscription_key,
+        funds_withdrawn_event.usdc_amount_withdrawn,
+        funds_withdrawn_event.callback_amount_withdrawn
+    );
+
+    Ok(subscription_key)
+}
+
 #[cfg(test)]
 mod job_subscription_management_tests {
     use ethers::{
         abi::{encode, Token},
-        types::{Address, H256},
+        types::{Address, BigEndianHash, Bytes, Filter, H256},
     };
+    use proptest::prelude::*;
     use serde_json::json;
+    use std::collections::HashSet;
 
     use super::*;
 
+    use crate::constant::REQUEST_RELAY_TIMEOUT;
+    use crate::model::{SubscriptionTriggerConfig, SubscriptionTriggerLimiter, TriggerRateLimit};
     use crate::test_util::{
-        add_gateway_epoch_state, generate_contracts_client, generate_generic_subscription_job,
-        generate_job_subscription_job_params_updated, generate_job_subscription_started_log,
-        generate_job_subscription_termination_params_updated, MockHttpProvider, CHAIN_ID,
+        add_gateway_epoch_state, generate_contracts_client, generate_contracts_client_with_clock,
+        generate_generic_subscription_job, generate_job_subscription_funds_deposited,
+        generate_job_subscription_funds_withdrawn, generate_job_subscription_job_params_updated,
+        generate_job_subscription_started_log, generate_job_subscription_terminated,
+        generate_job_subscription_termination_params_updated, job_subscription_id, relay_job_id,
+        MockClock, MockHttpProvider, CHAIN_ID,
     };
 
     #[test]
     fn test_unix_timestamp_to_instant() {
+        let now = 1_700_000_000;
+
         // Future time stays in future
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + 60;
-        let instant = unix_timestamp_to_instant(timestamp);
+        let timestamp = now + 60;
+        let instant = unix_timestamp_to_instant(timestamp, now);
         let instant_now = Instant::now();
         assert!(instant < instant_now + Duration::from_secs(60));
         assert!(instant > instant_now + Duration::from_secs(59));
 
         // Future time stays in future
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + 1000;
-        let instant = unix_timestamp_to_instant(timestamp);
+        let timestamp = now + 1000;
+        let instant = unix_timestamp_to_instant(timestamp, now);
         let instant_now = Instant::now();
         assert!(instant < instant_now + Duration::from_secs(1000));
         assert!(instant > instant_now + Duration::from_secs(999));
 
         // Past time becomes instance now time
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            - 100;
-        let instant = unix_timestamp_to_instant(timestamp);
+        let timestamp = now - 100;
+        let instant = unix_timestamp_to_instant(timestamp, now);
         let instant_now = Instant::now();
         assert!(instant < instant_now + Duration::from_secs(1));
         assert!(instant > instant_now - Duration::from_secs(1));
@@ -650,8 +1504,8 @@ mod job_subscription_management_tests {
         let log = Log {
             address: Address::default(),
             topics: vec![
-                keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT).into(),
-                H256::from_uint(&U256::one()),
+                JobSubscriptionStartedFilter::signature(),
+                H256::from_uint(&job_subscription_id(CHAIN_ID, 1)),
                 H256::from_uint(&U256::one()),
             ],
             data: encode(&[
@@ -680,40 +1534,98 @@ mod job_subscription_management_tests {
             is_history_log,
         );
 
-        // Sleep for 1s to allow all async tasks to complete
-        tokio::time::sleep(Duration::from_secs(1)).await;
-
         assert!(res.is_err());
         assert_eq!(res.err().unwrap(), ServerlessError::LogDecodeFailure);
 
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let subscription_job = subscription_jobs.get(&U256::one());
+            let subscription_job = subscription_jobs.get(&SubscriptionKey::new(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+            ));
 
             assert!(subscription_job.is_none());
         }
 
-        // Scope for read lock on subscription_job_instance_heap
+        // Scope for read lock on subscription_job_scheduler
         {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek();
-
-            assert!(subscription_job_instance.is_none());
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
+            assert!(subscription_job_scheduler_guard.is_empty());
         }
 
         // Scope for read lock on active_jobs
         {
             let active_jobs = contracts_client.active_jobs.read().unwrap();
-            let active_job = active_jobs.get(&U256::one());
+            let active_job = active_jobs.get(&JobKey::subscription_instance(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+                0,
+            ));
 
             assert!(active_job.is_none());
         }
     }
 
+    #[tokio::test]
+    async fn test_add_subscription_job_out_of_range_uint() {
+        let contracts_client = generate_contracts_client().await;
+        let (req_chain_tx, _) = tokio::sync::mpsc::channel::<Job>(100);
+        let now = contracts_client.clock.now();
+
+        // Uint fields of the data scheduled on u64 timestamps: periodic gap, termination
+        // timestamp, user timeout and start time
+        proptest!(|(
+            field_index in prop::sample::select(vec![0usize, 2, 3, 7]),
+            value in prop_oneof![
+                Just(U256::from(u64::MAX) + 1),
+                Just(U256::MAX),
+                (any::<[u64; 3]>(), 1..=u64::MAX)
+                    .prop_map(|(low, high)| U256([low[0], low[1], low[2], high])),
+            ],
+        )| {
+            let mut data = vec![
+                Token::Uint(U256::from(10)),
+                Token::Uint(U256::from(1000)),
+                Token::Uint(U256::from(now + 1000)),
+                Token::Uint(U256::from(100)),
+                Token::Address(Address::random()),
+                Token::FixedBytes(vec![0u8; 32]),
+                Token::Bytes(vec![]),
+                Token::Uint(U256::from(now)),
+            ];
+            data[field_index] = Token::Uint(value);
+            let log = Log {
+                address: Address::default(),
+                topics: vec![
+                    JobSubscriptionStartedFilter::signature(),
+                    H256::from_uint(&job_subscription_id(CHAIN_ID, 1)),
+                    H256::from_uint(&U256::one()),
+                    H256::from(Address::random()),
+                ],
+                data: encode(&data).into(),
+                ..Default::default()
+            };
+
+            let res = add_subscription_job(
+                &contracts_client,
+                log,
+                CHAIN_ID,
+                req_chain_tx.clone(),
+                false,
+            );
+
+            prop_assert_eq!(res, Err(ServerlessError::LogDecodeFailure));
+            prop_assert!(contracts_client.subscription_jobs.read().unwrap().is_empty());
+            prop_assert!(contracts_client
+                .subscription_job_scheduler
+                .read()
+                .unwrap()
+                .is_empty());
+        });
+    }
+
     #[tokio::test]
     async fn test_add_subscription_job_historic_inactive_job() {
         let contracts_client = generate_contracts_client().await;
@@ -731,51 +1643,69 @@ mod job_subscription_management_tests {
             is_history_log,
         );
 
-        // Sleep for 1s to allow all async tasks to complete
-        tokio::time::sleep(Duration::from_secs(1)).await;
-
         assert!(res.is_ok());
-        assert_eq!(res.unwrap(), U256::zero());
+        assert_eq!(res.unwrap(), None);
 
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let subscription_job = subscription_jobs.get(&U256::one());
+            let subscription_job = subscription_jobs.get(&SubscriptionKey::new(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+            ));
 
             assert!(subscription_job.is_none());
         }
 
-        // Scope for read lock on subscription_job_instance_heap
+        // Scope for read lock on subscription_job_scheduler
         {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek();
-
-            assert!(subscription_job_instance.is_none());
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
+            assert!(subscription_job_scheduler_guard.is_empty());
         }
 
         // Scope for read lock on active_jobs
         {
             let active_jobs = contracts_client.active_jobs.read().unwrap();
-            let active_job = active_jobs.get(&U256::one());
+            let active_job = active_jobs.get(&JobKey::subscription_instance(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+                0,
+            ));
 
             assert!(active_job.is_none());
         }
     }
 
     #[tokio::test]
-    async fn test_add_subscription_job_historic_active_job_with_first_instance_trigger() {
+    async fn test_get_minimum_timestamp_for_job() {
+        let clock = Arc::new(MockClock::new(1_700_000_000));
+        let contracts_client = generate_contracts_client_with_clock(clock.clone()).await;
+        let maintained_duration = GATEWAY_BLOCK_STATES_TO_MAINTAIN * contracts_client.time_interval
+            + contracts_client.offset_for_epoch;
+
+        assert_eq!(
+            get_minimum_timestamp_for_job(&contracts_client),
+            1_700_000_000 - maintained_duration
+        );
+
+        // Moves along with the clock
+        clock.advance(contracts_client.time_interval);
+        assert_eq!(
+            get_minimum_timestamp_for_job(&contracts_client),
+            1_700_000_000 + contracts_client.time_interval - maintained_duration
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_subscription_job_historic_active_job() {
         let contracts_client = generate_contracts_client().await;
-        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-2)).await;
-        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-3)).await;
-        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-4)).await;
+        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-5)).await;
         let request_chain_id = CHAIN_ID;
-        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+        let (req_chain_tx, _) = tokio::sync::mpsc::channel::<Job>(100);
         let is_history_log = true;
 
-        let log = generate_job_subscription_started_log(None, Some(-50));
+        let log = generate_job_subscription_started_log(None, Some(-500));
 
         let res = add_subscription_job(
             &contracts_client.clone(),
@@ -785,147 +1715,58 @@ mod job_subscription_management_tests {
             is_history_log,
         );
 
-        // Sleep for 1s to allow all async tasks to complete
-        tokio::time::sleep(Duration::from_secs(1)).await;
-
         assert!(res.is_ok());
-        assert_eq!(res.unwrap(), U256::one());
+        assert_eq!(
+            res.unwrap(),
+            Some(SubscriptionKey::new(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1)
+            ))
+        );
 
-        let result_subscription_job: SubscriptionJob;
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let subscription_job = subscription_jobs.get(&U256::one()).cloned();
+            let subscription_job = subscription_jobs.get(&SubscriptionKey::new(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+            ));
 
             assert!(subscription_job.is_some());
-
-            result_subscription_job = subscription_job.unwrap();
-
-            assert_eq!(result_subscription_job.subscription_id, U256::one());
-        }
-
-        // Scope for read lock on subscription_job_instance_heap
-        {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek().unwrap();
-
-            assert_eq!(subscription_job_instance.subscription_id, U256::one());
             assert_eq!(
-                subscription_job_instance.next_trigger_time,
-                result_subscription_job.starttime.as_u64()
-                    + result_subscription_job.interval.as_u64()
+                subscription_job.unwrap().subscription_id,
+                job_subscription_id(CHAIN_ID, 1)
             );
         }
 
-        let mut expected_job = subscription_job_to_relay_job(
-            result_subscription_job.clone(),
-            result_subscription_job.starttime.as_u64(),
-        );
-        expected_job.gateway_address = Some(contracts_client.enclave_address);
+        // Instances are scheduled by the catch up once all the historic logs are processed
+        // Scope for read lock on subscription_job_scheduler
+        {
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
+            assert!(subscription_job_scheduler_guard.is_empty());
+        }
 
         // Scope for read lock on active_jobs
         {
             let active_jobs = contracts_client.active_jobs.read().unwrap();
-            let active_job = active_jobs.get(&U256::one());
+            let active_job = active_jobs.get(&JobKey::subscription_instance(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+                0,
+            ));
 
-            assert!(active_job.is_some());
-            assert_eq!(active_job.unwrap(), &expected_job);
+            assert!(active_job.is_none());
         }
     }
 
     #[tokio::test]
-    async fn test_get_minimum_timestamp_for_job() {
+    async fn test_add_subscription_job_active_job() {
         let contracts_client = generate_contracts_client().await;
-        let minimum_timestamp = get_minimum_timestamp_for_job(&contracts_client);
-        let current_timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let expected_timestamp = current_timestamp
-            - (GATEWAY_BLOCK_STATES_TO_MAINTAIN * contracts_client.time_interval)
-            - contracts_client.offset_for_epoch;
-        assert_eq!(minimum_timestamp, expected_timestamp);
-    }
-
-    #[tokio::test]
-    async fn test_add_subscription_job_historic_active_job() {
-        let contracts_client = generate_contracts_client().await;
-        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-5)).await;
-        let request_chain_id = CHAIN_ID;
-        let (req_chain_tx, _) = tokio::sync::mpsc::channel::<Job>(100);
-        let is_history_log = true;
-
-        let log = generate_job_subscription_started_log(None, Some(-500));
-
-        let res = add_subscription_job(
-            &contracts_client.clone(),
-            log,
-            request_chain_id,
-            req_chain_tx,
-            is_history_log,
-        );
-
-        // Sleep for 1s to allow all async tasks to complete
-        tokio::time::sleep(Duration::from_secs(1)).await;
-
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), U256::one());
-
-        let result_subscription_job: SubscriptionJob;
-        // Scope for read lock on subscription_jobs
-        {
-            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let subscription_job = subscription_jobs.get(&U256::one()).cloned();
-
-            assert!(subscription_job.is_some());
-
-            result_subscription_job = subscription_job.unwrap();
-
-            assert_eq!(result_subscription_job.subscription_id, U256::one());
-        }
-
-        let expected_next_trigger_time = (result_subscription_job.starttime
-            + result_subscription_job.interval
-                * (((get_minimum_timestamp_for_job(&contracts_client)
-                    - result_subscription_job.starttime.as_u64())
-                    / result_subscription_job.interval.as_u64())
-                    + 1))
-            .as_u64();
-
-        // Scope for read lock on subscription_job_instance_heap
-        {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek().unwrap();
-
-            assert_eq!(subscription_job_instance.subscription_id, U256::one());
-            assert_eq!(
-                subscription_job_instance.next_trigger_time,
-                expected_next_trigger_time
-            );
-        }
-
-        // Scope for read lock on active_jobs
-        {
-            let active_jobs = contracts_client.active_jobs.read().unwrap();
-            let active_job = active_jobs.get(&U256::one());
-
-            assert!(active_job.is_none());
-        }
-    }
-
-    #[tokio::test]
-    async fn test_add_subscription_job_active_job() {
-        let contracts_client = generate_contracts_client().await;
-        add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
-        let request_chain_id = CHAIN_ID;
-        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
-        let is_history_log = false;
+        add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
+        let request_chain_id = CHAIN_ID;
+        let (req_chain_tx, mut com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+        let is_history_log = false;
 
         let log = generate_job_subscription_started_log(None, None);
 
@@ -939,36 +1780,48 @@ mod job_subscription_management_tests {
             is_history_log,
         );
 
-        // Sleep for 1s to allow all async tasks to complete
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        // The first instance is sent out once it is in the active jobs
+        assert!(com_chain_rx.recv().await.is_some());
 
         assert!(res.is_ok());
 
         let res = res.unwrap();
-        assert_eq!(res, U256::one());
+        assert_eq!(
+            res,
+            Some(SubscriptionKey::new(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1)
+            ))
+        );
 
         let subscription_job: SubscriptionJob;
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            subscription_job = subscription_jobs.get(&U256::one()).cloned().unwrap();
+            subscription_job = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .cloned()
+                .unwrap();
 
             assert_eq!(subscription_job, expected_subscription_job);
         }
 
-        // Scope for read lock on subscription_job_instance_heap
+        // Scope for read lock on subscription_job_scheduler
         {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek().unwrap();
-
-            assert_eq!(subscription_job_instance.subscription_id, U256::one());
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
             assert_eq!(
-                subscription_job_instance.next_trigger_time,
-                expected_subscription_job.starttime.as_u64()
-                    + expected_subscription_job.interval.as_u64()
+                subscription_job_scheduler_guard.scheduled_trigger_time(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1)
+                )),
+                Some(
+                    expected_subscription_job.starttime.as_u64()
+                        + expected_subscription_job.interval.as_u64()
+                )
             );
         }
 
@@ -981,7 +1834,11 @@ mod job_subscription_management_tests {
         // Scope for read lock on active_jobs
         {
             let active_jobs = contracts_client.active_jobs.read().unwrap();
-            let active_job = active_jobs.get(&U256::one());
+            let active_job = active_jobs.get(&JobKey::subscription_instance(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+                0,
+            ));
 
             assert!(active_job.is_some());
             assert_eq!(active_job.unwrap(), &expected_job);
@@ -989,153 +1846,86 @@ mod job_subscription_management_tests {
     }
 
     #[tokio::test]
-    async fn test_add_next_trigger_time_to_heap_historic_active_job_with_first_instance_trigger() {
+    async fn test_add_subscription_job_rate_limited() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
-
-        let subscription_job = generate_generic_subscription_job(None, Some(-100));
-
-        // when is_historic_log is true, previous_trigger_time is the starttime of the job
-        let subscription_job_starttime = subscription_job.starttime.as_u64();
-        let is_historic_log = true;
+        let contracts_client = Arc::new(ContractsClient {
+            subscription_trigger_limiter: Arc::new(SubscriptionTriggerLimiter::new(
+                SubscriptionTriggerConfig {
+                    jitter_ms: 0,
+                    default_rate_limit: Some(TriggerRateLimit {
+                        instances_per_second: 1.0,
+                        burst: 1,
+                    }),
+                    rate_limits: vec![],
+                },
+            )),
+            ..(*contracts_client).clone()
+        });
+        add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
 
-        // Scope for write lock on subscription_jobs
-        {
-            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
-        }
+        let log = generate_job_subscription_started_log(None, None);
+        let res = add_subscription_job(&contracts_client, log, CHAIN_ID, req_chain_tx, false);
 
-        add_next_trigger_time_to_heap(
-            &contracts_client,
-            subscription_id,
-            subscription_job_starttime,
-            is_historic_log,
+        assert_eq!(
+            res.unwrap(),
+            Some(SubscriptionKey::new(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1)
+            ))
         );
 
-        let expected_next_trigger_time = subscription_job.starttime + subscription_job.interval;
-
-        // Scope for read lock on subscription_job_instance_heap
-        {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek().unwrap();
-
-            assert_eq!(subscription_job_instance.subscription_id, U256::one());
-            assert_eq!(
-                subscription_job_instance.next_trigger_time,
-                expected_next_trigger_time.as_u64()
-            );
-        }
-
-        // Scope for read lock on subscription_jobs
-        {
-            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
-
-            assert_eq!(*result_subscription_job, subscription_job);
-        }
-    }
-
-    #[tokio::test]
-    async fn test_add_next_trigger_time_to_heap_historic_active_job() {
-        let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
-
-        let subscription_job = generate_generic_subscription_job(None, Some(-500));
-
-        // when is_historic_log is true, previous_trigger_time is the starttime of the job
-        let subscription_job_starttime = subscription_job.starttime.as_u64();
-        let is_historic_log = true;
-
-        // Scope for write lock on subscription_jobs
-        {
-            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
-        }
-
-        add_next_trigger_time_to_heap(
-            &contracts_client,
-            subscription_id,
-            subscription_job_starttime,
-            is_historic_log,
+        // The first instance waits for the tokens of the request chain like the scheduled ones
+        assert_eq!(
+            contracts_client.subscription_trigger_limiter.pending_len(),
+            1
         );
-
-        let expected_next_trigger_time = (subscription_job.starttime
-            + subscription_job.interval
-                * (((get_minimum_timestamp_for_job(&contracts_client)
-                    - subscription_job.starttime.as_u64())
-                    / subscription_job.interval.as_u64())
-                    + 1))
-            .as_u64();
-
-        // Scope for read lock on subscription_job_instance_heap
-        {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek().unwrap();
-
-            assert_eq!(subscription_job_instance.subscription_id, U256::one());
-            assert_eq!(
-                subscription_job_instance.next_trigger_time,
-                expected_next_trigger_time
-            );
-        }
-
-        // Scope for read lock on subscription_jobs
-        {
-            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
-
-            assert_eq!(*result_subscription_job, subscription_job);
-        }
+        assert!(contracts_client.active_jobs.read().unwrap().is_empty());
     }
 
     #[tokio::test]
     async fn test_add_next_trigger_time_to_heap_job_just_added() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
+        let subscription_id = job_subscription_id(CHAIN_ID, 1);
         let subscription_job = generate_generic_subscription_job(None, None);
 
         // when job is just added, previous_trigger_time is job starttime
         let subscription_job_starttime = subscription_job.starttime.as_u64();
-        let is_historic_log = false;
 
         // Scope for write lock on subscription_jobs
         {
             let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
         }
 
         add_next_trigger_time_to_heap(
             &contracts_client,
-            subscription_id,
+            SubscriptionKey::new(CHAIN_ID, subscription_id),
             subscription_job_starttime,
-            is_historic_log,
         );
 
-        // Scope for read lock on subscription_job_instance_heap
+        // Scope for read lock on subscription_job_scheduler
         {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek().unwrap();
-
-            assert_eq!(subscription_job_instance.subscription_id, U256::one());
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
             assert_eq!(
-                subscription_job_instance.next_trigger_time,
-                subscription_job_starttime + subscription_job.interval.as_u64()
+                subscription_job_scheduler_guard.scheduled_trigger_time(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1)
+                )),
+                Some(subscription_job_starttime + subscription_job.interval.as_u64())
             );
         }
 
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
+            let result_subscription_job = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
 
             assert_eq!(*result_subscription_job, subscription_job);
         }
@@ -1143,50 +1933,50 @@ mod job_subscription_management_tests {
 
     #[tokio::test]
     async fn test_add_next_trigger_time_to_heap_active_job() {
-        let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
-        let system_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        // when is_historic_log is false, previous_trigger_time is the closest time to the current time
-        let previous_trigger_time = system_time;
-        let is_historic_log = false;
+        let now = 1_700_000_000;
+        let contracts_client =
+            generate_contracts_client_with_clock(Arc::new(MockClock::new(now))).await;
+        let subscription_id = job_subscription_id(CHAIN_ID, 1);
+        let previous_trigger_time = now;
 
-        let subscription_job = generate_generic_subscription_job(None, Some(-500));
+        let mut subscription_job = generate_generic_subscription_job(None, None);
+        subscription_job.starttime = U256::from(now - 500);
+        subscription_job.termination_time = subscription_job.starttime + 1000;
 
         // Scope for write lock on subscription_jobs
         {
             let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
         }
 
         add_next_trigger_time_to_heap(
             &contracts_client,
-            subscription_id,
+            SubscriptionKey::new(CHAIN_ID, subscription_id),
             previous_trigger_time,
-            is_historic_log,
         );
 
-        // Scope for read lock on subscription_job_instance_heap
+        // Scope for read lock on subscription_job_scheduler
         {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek().unwrap();
-
-            assert_eq!(subscription_job_instance.subscription_id, U256::one());
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
             assert_eq!(
-                subscription_job_instance.next_trigger_time,
-                previous_trigger_time + subscription_job.interval.as_u64()
+                subscription_job_scheduler_guard.scheduled_trigger_time(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1)
+                )),
+                Some(previous_trigger_time + subscription_job.interval.as_u64())
             );
         }
 
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
+            let result_subscription_job = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
 
             assert_eq!(*result_subscription_job, subscription_job);
         }
@@ -1194,46 +1984,42 @@ mod job_subscription_management_tests {
 
     #[tokio::test]
     async fn test_add_next_trigger_time_to_heap_at_job_termination_time() {
-        let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
-        let system_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        // when is_historic_log is true, previous_trigger_time is the starttime of the job
-        let previous_trigger_time = system_time;
-        let is_historic_log = false;
+        let now = 1_700_000_000;
+        let contracts_client =
+            generate_contracts_client_with_clock(Arc::new(MockClock::new(now))).await;
+        let subscription_id = job_subscription_id(CHAIN_ID, 1);
+        let previous_trigger_time = now;
 
-        let subscription_job = generate_generic_subscription_job(None, Some(-1000));
+        let mut subscription_job = generate_generic_subscription_job(None, None);
+        subscription_job.starttime = U256::from(now - 1000);
+        subscription_job.termination_time = subscription_job.starttime + 1000;
 
         // Scope for write lock on subscription_jobs
         {
             let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
         }
 
         add_next_trigger_time_to_heap(
             &contracts_client,
-            subscription_id,
+            SubscriptionKey::new(CHAIN_ID, subscription_id),
             previous_trigger_time,
-            is_historic_log,
         );
 
-        // Scope for read lock on subscription_job_instance_heap
+        // Scope for read lock on subscription_job_scheduler
         {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek();
-
-            assert!(subscription_job_instance.is_none());
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
+            assert!(subscription_job_scheduler_guard.is_empty());
         }
 
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let subscription_job = subscription_jobs.get(&U256::one());
+            let subscription_job = subscription_jobs.get(&SubscriptionKey::new(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+            ));
 
             assert!(subscription_job.is_none());
         }
@@ -1247,6 +2033,7 @@ mod job_subscription_management_tests {
         let job = subscription_job_to_relay_job(subscription_job.clone(), trigger_timestamp);
 
         assert_eq!(job.job_id, subscription_job.subscription_id);
+        assert_eq!(job.instance_index, 0);
         assert_eq!(job.request_chain_id, subscription_job.request_chain_id);
         assert_eq!(job.tx_hash, subscription_job.tx_hash);
         assert_eq!(job.code_input, subscription_job.code_input);
@@ -1262,7 +2049,8 @@ mod job_subscription_management_tests {
 
         let job = subscription_job_to_relay_job(subscription_job.clone(), trigger_timestamp);
 
-        assert_eq!(job.job_id, subscription_job.subscription_id + 5);
+        assert_eq!(job.job_id, subscription_job.subscription_id);
+        assert_eq!(job.instance_index, 5);
         assert_eq!(job.request_chain_id, subscription_job.request_chain_id);
         assert_eq!(job.tx_hash, subscription_job.tx_hash);
         assert_eq!(job.code_input, subscription_job.code_input);
@@ -1278,7 +2066,8 @@ mod job_subscription_management_tests {
 
         let job = subscription_job_to_relay_job(subscription_job.clone(), trigger_timestamp);
 
-        assert_eq!(job.job_id, subscription_job.subscription_id + 40);
+        assert_eq!(job.job_id, subscription_job.subscription_id);
+        assert_eq!(job.instance_index, 40);
         assert_eq!(job.request_chain_id, subscription_job.request_chain_id);
         assert_eq!(job.tx_hash, subscription_job.tx_hash);
         assert_eq!(job.code_input, subscription_job.code_input);
@@ -1294,27 +2083,26 @@ mod job_subscription_management_tests {
     #[tokio::test]
     async fn test_update_subscription_job_params_invalid_log() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
         let subscription_job = generate_generic_subscription_job(None, None);
 
         // Scope for write lock on subscription_jobs
         {
             let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
         }
 
         // data is empty
         let log = Log {
             address: Address::default(),
             topics: vec![
-                keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT).into(),
-                H256::from_uint(&U256::one()),
+                JobSubscriptionJobParamsUpdatedFilter::signature(),
+                H256::from_uint(&job_subscription_id(CHAIN_ID, 1)),
             ],
             data: encode(&[]).into(),
             ..Default::default()
         };
 
-        let res = update_subscription_job_params(&contracts_client, log);
+        let res = update_subscription_job_params(&contracts_client, log, CHAIN_ID);
 
         assert!(res.is_err());
         assert_eq!(res.err().unwrap(), ServerlessError::LogDecodeFailure);
@@ -1322,7 +2110,12 @@ mod job_subscription_management_tests {
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
+            let result_subscription_job = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
 
             assert_eq!(*result_subscription_job, subscription_job);
         }
@@ -1331,35 +2124,34 @@ mod job_subscription_management_tests {
     #[tokio::test]
     async fn test_update_subscription_job_params_subscription_job_not_found() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
+        let subscription_id = job_subscription_id(CHAIN_ID, 1);
 
         let log = generate_job_subscription_job_params_updated(None, None, Some(104));
 
-        let res = update_subscription_job_params(&contracts_client, log);
+        let res = update_subscription_job_params(&contracts_client, log, CHAIN_ID);
 
         assert!(res.is_err());
         assert_eq!(
             res.err().unwrap(),
-            ServerlessError::NoSubscriptionJobFound(subscription_id)
+            ServerlessError::NoSubscriptionJobFound(CHAIN_ID, subscription_id)
         );
     }
 
     #[tokio::test]
     async fn test_update_subscription_job_params_active_job_tx_hash_update() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
         let subscription_job = generate_generic_subscription_job(None, None);
 
         // Scope for write lock on subscription_jobs
         {
             let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
         }
 
         let new_code_hash = "9468bb6a8e85ed11e292c8cac0c1539df691c8d8ec62e7dbfa9f1bd7f504e7d8";
         let log = generate_job_subscription_job_params_updated(None, Some(new_code_hash), None);
 
-        let res = update_subscription_job_params(&contracts_client, log);
+        let res = update_subscription_job_params(&contracts_client, log, CHAIN_ID);
 
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), ());
@@ -1367,7 +2159,12 @@ mod job_subscription_management_tests {
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
+            let result_subscription_job = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
 
             assert_eq!(
                 result_subscription_job.tx_hash,
@@ -1383,18 +2180,17 @@ mod job_subscription_management_tests {
     #[tokio::test]
     async fn test_update_subscription_job_params_active_job_code_input_update() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
         let subscription_job = generate_generic_subscription_job(None, None);
 
         // Scope for write lock on subscription_jobs
         {
             let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
         }
 
         let log = generate_job_subscription_job_params_updated(None, None, Some(188));
 
-        let res = update_subscription_job_params(&contracts_client, log);
+        let res = update_subscription_job_params(&contracts_client, log, CHAIN_ID);
 
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), ());
@@ -1402,7 +2198,12 @@ mod job_subscription_management_tests {
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
+            let result_subscription_job = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
 
             assert_eq!(result_subscription_job.tx_hash, subscription_job.tx_hash,);
             assert_eq!(
@@ -1420,13 +2221,12 @@ mod job_subscription_management_tests {
     #[tokio::test]
     async fn test_update_subscription_job_params_active_job_all_update() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
         let subscription_job = generate_generic_subscription_job(None, None);
 
         // Scope for write lock on subscription_jobs
         {
             let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
         }
 
         let new_code_hash = "9468bb6a8e85ed11e292c8cac0c1539df691c8d8ec62e7dbfa9f1bd7f504e7d8";
@@ -1436,7 +2236,7 @@ mod job_subscription_management_tests {
             Some(188),
         );
 
-        let res = update_subscription_job_params(&contracts_client, log);
+        let res = update_subscription_job_params(&contracts_client, log, CHAIN_ID);
 
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), ());
@@ -1444,7 +2244,12 @@ mod job_subscription_management_tests {
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
+            let result_subscription_job = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
 
             assert_eq!(
                 result_subscription_job.tx_hash,
@@ -1465,27 +2270,26 @@ mod job_subscription_management_tests {
     #[tokio::test]
     async fn test_update_subscription_job_termination_params_invalid_log() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
         let subscription_job = generate_generic_subscription_job(None, None);
 
         // Scope for write lock on subscription_jobs
         {
             let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
         }
 
         // data is empty
         let log = Log {
             address: Address::default(),
             topics: vec![
-                keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT).into(),
-                H256::from_uint(&U256::one()),
+                JobSubscriptionTerminationParamsUpdatedFilter::signature(),
+                H256::from_uint(&job_subscription_id(CHAIN_ID, 1)),
             ],
             data: encode(&[]).into(),
             ..Default::default()
         };
 
-        let res = update_subscription_job_termination_params(&contracts_client, log);
+        let res = update_subscription_job_termination_params(&contracts_client, log, CHAIN_ID);
 
         assert!(res.is_err());
         assert_eq!(res.err().unwrap(), ServerlessError::LogDecodeFailure);
@@ -1493,7 +2297,12 @@ mod job_subscription_management_tests {
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
+            let result_subscription_job = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
 
             assert_eq!(*result_subscription_job, subscription_job);
         }
@@ -1502,40 +2311,35 @@ mod job_subscription_management_tests {
     #[tokio::test]
     async fn test_update_subscription_job_termination_params_subscription_job_not_found() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
+        let subscription_id = job_subscription_id(CHAIN_ID, 1);
 
         let log = generate_job_subscription_termination_params_updated(None, None);
 
-        let res = update_subscription_job_termination_params(&contracts_client, log);
+        let res = update_subscription_job_termination_params(&contracts_client, log, CHAIN_ID);
 
         assert!(res.is_err());
         assert_eq!(
             res.err().unwrap(),
-            ServerlessError::NoSubscriptionJobFound(subscription_id)
+            ServerlessError::NoSubscriptionJobFound(CHAIN_ID, subscription_id)
         );
     }
 
     #[tokio::test]
     async fn test_update_subscription_job_termination_params_active_job() {
         let contracts_client = generate_contracts_client().await;
-        let subscription_id = U256::one();
         let subscription_job = generate_generic_subscription_job(None, None);
 
         // Scope for write lock on subscription_jobs
         {
             let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
-            subscription_jobs.insert(subscription_id, subscription_job.clone());
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
         }
 
-        let new_termination_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + 5000;
+        let new_termination_time = subscription_job.starttime.as_u64() + 5000;
         let log =
             generate_job_subscription_termination_params_updated(None, Some(new_termination_time));
 
-        let res = update_subscription_job_termination_params(&contracts_client, log);
+        let res = update_subscription_job_termination_params(&contracts_client, log, CHAIN_ID);
 
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), ());
@@ -1543,7 +2347,12 @@ mod job_subscription_management_tests {
         // Scope for read lock on subscription_jobs
         {
             let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let result_subscription_job = subscription_jobs.get(&U256::one()).unwrap();
+            let result_subscription_job = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
 
             assert_eq!(
                 result_subscription_job.termination_time.as_u64(),
@@ -1553,154 +2362,1064 @@ mod job_subscription_management_tests {
     }
 
     #[tokio::test]
-    async fn test_process_historic_subscription_jobs_on_request_chain() {
+    async fn test_remove_subscription_job_subscription_job_not_found() {
         let contracts_client = generate_contracts_client().await;
-        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-2)).await;
-        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-3)).await;
-        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-4)).await;
-        let request_chain_client = contracts_client
-            .request_chain_clients
-            .get(&CHAIN_ID)
-            .unwrap();
-        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
-        let (job_sub_tx, mut job_sub_rx) =
-            tokio::sync::mpsc::channel::<JobSubscriptionChannelType>(100);
-        let mock_http_provider = MockHttpProvider::new(None);
 
-        process_historic_subscription_jobs_on_request_chain(
-            &contracts_client,
-            request_chain_client,
-            req_chain_tx,
-            job_sub_tx,
-            mock_http_provider,
-        )
-        .await;
+        let log = generate_job_subscription_terminated(None);
 
-        // Sleep for 2s to allow all async tasks to complete
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        let res = remove_subscription_job(&contracts_client, log, CHAIN_ID);
 
-        if let Some(rx_job) = job_sub_rx.recv().await {
-            if rx_job.subscription_action == JobSubscriptionAction::Add {
-                assert_eq!(rx_job.subscription_id, U256::one());
-            } else {
-                assert!(false);
-            }
-        } else {
-            assert!(false);
-        }
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap(),
+            ServerlessError::NoSubscriptionJobFound(CHAIN_ID, job_subscription_id(CHAIN_ID, 1))
+        );
+    }
 
-        if let Some(rx_job) = job_sub_rx.recv().await {
-            if rx_job.subscription_action == JobSubscriptionAction::Add {
-                assert_eq!(rx_job.subscription_id, U256::from(3));
-            } else {
-                assert!(false);
-            }
-        } else {
-            assert!(false);
-        }
+    #[tokio::test]
+    async fn test_remove_subscription_job_active_job() {
+        let contracts_client = generate_contracts_client().await;
+        let subscription_job = generate_generic_subscription_job(None, None);
+        let other_subscription_job = generate_generic_subscription_job(Some(2), None);
 
-        let subscription_job_one: Option<SubscriptionJob>;
-        // Scope for read lock on subscription_jobs
+        // Scope for write lock on subscription_jobs
         {
-            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            subscription_job_one = subscription_jobs.get(&U256::one()).cloned();
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+            subscription_jobs.insert(other_subscription_job.key(), other_subscription_job.clone());
         }
 
-        assert!(subscription_job_one.is_some());
-
-        let subscription_job_one = subscription_job_one.unwrap();
-        assert_eq!(
-            subscription_job_one.tx_hash,
-            hex::decode(
-                "9468bb6a8e85ed11e292c8cac0c1539df691c8d8ec62e7dbfa9f1bd7f504e7d8".to_owned(),
-            )
-            .unwrap(),
+        add_next_trigger_time_to_heap(
+            &contracts_client,
+            subscription_job.key(),
+            subscription_job.starttime.as_u64(),
         );
-        assert_eq!(
-            subscription_job_one.code_input,
-            Bytes::from(
-                serde_json::to_vec(&json!({
-                    "num": 108
-                }))
-                .unwrap(),
-            )
+        add_next_trigger_time_to_heap(
+            &contracts_client,
+            other_subscription_job.key(),
+            other_subscription_job.starttime.as_u64(),
         );
 
-        let system_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        assert!(subscription_job_one.termination_time.as_u64() >= system_time + 1490);
-        assert!(subscription_job_one.termination_time.as_u64() <= system_time + 1510);
-
-        {
-            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
-            let subscription_job_two = subscription_jobs.get(&U256::from(2));
+        let log = generate_job_subscription_terminated(None);
 
-            assert!(subscription_job_two.is_none());
-        }
+        let res = remove_subscription_job(&contracts_client, log, CHAIN_ID);
 
-        assert!(job_sub_rx.recv().await.is_none());
+        assert_eq!(res.unwrap(), subscription_job.key());
 
-        // Scope for read lock on subscription_job_instance_heap
+        // Scope for read lock on subscription_jobs
         {
-            let subscription_job_instance_heap_guard = contracts_client
-                .subscription_job_instance_heap
-                .read()
-                .unwrap();
-            let subscription_job_instance = subscription_job_instance_heap_guard.peek().unwrap();
-
-            assert_eq!(subscription_job_instance.subscription_id, U256::one());
-            assert_eq!(
-                subscription_job_instance.next_trigger_time,
-                subscription_job_one.starttime.as_u64() + subscription_job_one.interval.as_u64()
-            );
+            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
+            assert!(subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1)
+                ))
+                .is_none());
+            assert!(subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 2)
+                ))
+                .is_some());
         }
 
-        let mut expected_job = subscription_job_to_relay_job(
-            subscription_job_one.clone(),
-            subscription_job_one.starttime.as_u64(),
-        );
-        expected_job.gateway_address = Some(contracts_client.enclave_address);
-        expected_job.tx_hash = hex::decode(
-            "9468bb6a8e85ed11e292c8cac0c1539df691c8d8ec62e7dbfa9f1bd7f504e46e".to_owned(),
-        )
-        .unwrap();
-        expected_job.code_input = Bytes::from(
-            serde_json::to_vec(&json!({
-                "num": 10
-            }))
-            .unwrap(),
-        );
-
-        // Scope for read lock on active_jobs
+        // Scope for read lock on subscription_job_scheduler
         {
-            let active_jobs = contracts_client.active_jobs.read().unwrap();
-            let active_job = active_jobs.get(&U256::one());
-
-            assert!(active_job.is_some());
-            assert_eq!(active_job.unwrap(), &expected_job);
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
+            assert_eq!(subscription_job_scheduler_guard.len(), 1);
+            assert!(subscription_job_scheduler_guard
+                .scheduled_trigger_time(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 2)
+                ))
+                .is_some());
         }
     }
 
     #[tokio::test]
-    async fn test_trigger_subscription_job() {
+    async fn test_subscription_jobs_with_same_id_on_two_request_chains() {
         let contracts_client = generate_contracts_client().await;
-        add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
         let subscription_job = generate_generic_subscription_job(None, None);
-        let trigger_timestamp = subscription_job.starttime.as_u64();
-        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+        let mut other_chain_subscription_job = generate_generic_subscription_job(None, None);
+        other_chain_subscription_job.request_chain_id = CHAIN_ID + 1;
 
-        trigger_subscription_job(
-            subscription_job.clone(),
-            trigger_timestamp,
-            contracts_client.clone(),
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+            subscription_jobs.insert(
+                other_chain_subscription_job.key(),
+                other_chain_subscription_job.clone(),
+            );
+        }
+        add_next_trigger_time_to_heap(
+            &contracts_client,
+            subscription_job.key(),
+            subscription_job.starttime.as_u64(),
+        );
+        add_next_trigger_time_to_heap(
+            &contracts_client,
+            other_chain_subscription_job.key(),
+            other_chain_subscription_job.starttime.as_u64(),
+        );
+        record_subscription_instance(
+            &contracts_client,
+            &JobKey::subscription_instance(CHAIN_ID, job_subscription_id(CHAIN_ID, 1), 0),
+            SubscriptionInstanceStatus::Responded,
+        );
+        record_subscription_instance(
+            &contracts_client,
+            &JobKey::subscription_instance(CHAIN_ID + 1, job_subscription_id(CHAIN_ID + 1, 1), 0),
+            SubscriptionInstanceStatus::Responded,
+        );
+
+        // Funds deposited on the other chain only
+        let log = generate_job_subscription_funds_deposited(None, Some(500));
+        let res = update_subscription_job_funds_deposited(&contracts_client, log, CHAIN_ID + 1);
+        assert_eq!(res.unwrap(), other_chain_subscription_job.key());
+
+        // Terminated on the first chain only
+        let log = generate_job_subscription_terminated(None);
+        let res = remove_subscription_job(&contracts_client, log, CHAIN_ID);
+        assert_eq!(res.unwrap(), subscription_job.key());
+
+        // Scope for read lock on subscription_jobs
+        {
+            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
+            assert_eq!(subscription_jobs.len(), 1);
+            assert_eq!(
+                subscription_jobs[&other_chain_subscription_job.key()].usdc_balance,
+                other_chain_subscription_job.usdc_balance + 500
+            );
+        }
+
+        // Scope for read lock on subscription_job_scheduler
+        {
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
+            assert_eq!(subscription_job_scheduler_guard.len(), 1);
+            assert_eq!(
+                subscription_job_scheduler_guard
+                    .scheduled_trigger_time(&other_chain_subscription_job.key()),
+                Some(
+                    other_chain_subscription_job.starttime.as_u64()
+                        + other_chain_subscription_job.interval.as_u64()
+                )
+            );
+        }
+
+        // Scope for read lock on subscription_instance_history
+        {
+            let subscription_instance_history = contracts_client
+                .subscription_instance_history
+                .read()
+                .unwrap();
+            assert!(subscription_instance_history
+                .get(&subscription_job.key())
+                .is_none());
+            assert_eq!(
+                subscription_instance_history[&other_chain_subscription_job.key()].len(),
+                1
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remove_request_chain_subscription_jobs() {
+        let contracts_client = generate_contracts_client().await;
+        let subscription_job = generate_generic_subscription_job(None, None);
+        let mut other_chain_subscription_job = generate_generic_subscription_job(Some(2), None);
+        other_chain_subscription_job.request_chain_id = CHAIN_ID + 1;
+
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+            subscription_jobs.insert(
+                other_chain_subscription_job.key(),
+                other_chain_subscription_job.clone(),
+            );
+        }
+
+        add_next_trigger_time_to_heap(
+            &contracts_client,
+            subscription_job.key(),
+            subscription_job.starttime.as_u64(),
+        );
+        add_next_trigger_time_to_heap(
+            &contracts_client,
+            other_chain_subscription_job.key(),
+            other_chain_subscription_job.starttime.as_u64(),
+        );
+
+        assert_eq!(
+            remove_request_chain_subscription_jobs(&contracts_client, CHAIN_ID + 1),
+            vec![other_chain_subscription_job.key()]
+        );
+
+        // Scope for read lock on subscription_jobs
+        {
+            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
+            assert!(subscription_jobs.get(&subscription_job.key()).is_some());
+            assert!(subscription_jobs
+                .get(&other_chain_subscription_job.key())
+                .is_none());
+        }
+
+        // Scope for read lock on subscription_job_scheduler
+        {
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
+            assert_eq!(subscription_job_scheduler_guard.len(), 1);
+            assert!(subscription_job_scheduler_guard
+                .scheduled_trigger_time(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1)
+                ))
+                .is_some());
+        }
+
+        assert!(remove_request_chain_subscription_jobs(&contracts_client, CHAIN_ID + 1).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_subscription_job_funds_subscription_job_not_found() {
+        let contracts_client = generate_contracts_client().await;
+
+        let log = generate_job_subscription_funds_deposited(None, None);
+        let res = update_subscription_job_funds_deposited(&contracts_client, log, CHAIN_ID);
+        assert_eq!(
+            res.err().unwrap(),
+            ServerlessError::NoSubscriptionJobFound(CHAIN_ID, job_subscription_id(CHAIN_ID, 1))
+        );
+
+        let log = generate_job_subscription_funds_withdrawn(None, None);
+        let res = update_subscription_job_funds_withdrawn(&contracts_client, log, CHAIN_ID);
+        assert_eq!(
+            res.err().unwrap(),
+            ServerlessError::NoSubscriptionJobFound(CHAIN_ID, job_subscription_id(CHAIN_ID, 1))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_subscription_job_funds_active_job() {
+        let contracts_client = generate_contracts_client().await;
+        let subscription_job = generate_generic_subscription_job(None, None);
+
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+        }
+
+        let log = generate_job_subscription_funds_deposited(None, Some(500));
+        let res = update_subscription_job_funds_deposited(&contracts_client, log, CHAIN_ID);
+        assert_eq!(res.unwrap(), subscription_job.key());
+        assert_eq!(
+            contracts_client.subscription_jobs.read().unwrap()[&subscription_job.key()]
+                .usdc_balance,
+            U256::from(1500)
+        );
+
+        let log = generate_job_subscription_funds_withdrawn(None, Some(200));
+        let res = update_subscription_job_funds_withdrawn(&contracts_client, log, CHAIN_ID);
+        assert_eq!(res.unwrap(), subscription_job.key());
+        assert_eq!(
+            contracts_client.subscription_jobs.read().unwrap()[&subscription_job.key()]
+                .usdc_balance,
+            U256::from(1300)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_is_subscription_instance_affordable() {
+        let contracts_client = generate_contracts_client().await;
+        let subscription_job = generate_generic_subscription_job(None, None);
+        let mock_provider = MockHttpProvider::new(None);
+
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+        }
+
+        // Fee of 200 per instance against a balance of 1000
+        for _ in 0..5 {
+            assert!(
+                is_subscription_instance_affordable(
+                    &contracts_client,
+                    &subscription_job,
+                    &mock_provider
+                )
+                .await
+            );
+        }
+        assert_eq!(
+            contracts_client.subscription_jobs.read().unwrap()
+                [&SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1))]
+                .usdc_balance,
+            U256::zero()
+        );
+
+        assert!(
+            !is_subscription_instance_affordable(
+                &contracts_client,
+                &subscription_job,
+                &mock_provider
+            )
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reserve_subscription_instance_fee_subscription_job_not_found() {
+        let contracts_client = generate_contracts_client().await;
+
+        assert!(!reserve_subscription_instance_fee(
+            &contracts_client,
+            &SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1)),
+            U256::zero()
+        ));
+    }
+
+    #[test]
+    fn test_subscription_job_scheduler_reschedule_and_cancel() {
+        let mut scheduler = SubscriptionJobScheduler::default();
+        let subscription_key =
+            |subscription_id: u64| SubscriptionKey::new(CHAIN_ID, U256::from(subscription_id));
+
+        scheduler.schedule(subscription_key(1), 100);
+        scheduler.schedule(subscription_key(2), 50);
+        scheduler.schedule(subscription_key(3), 150);
+
+        // Reschedule replaces the queued instance
+        scheduler.schedule(subscription_key(2), 200);
+        assert_eq!(scheduler.len(), 3);
+        assert_eq!(
+            scheduler.scheduled_trigger_time(&subscription_key(2)),
+            Some(200)
+        );
+        assert_eq!(scheduler.next_trigger_time(), Some(100));
+
+        // Not due yet
+        assert!(scheduler.pop_due(99).is_none());
+        assert_eq!(scheduler.len(), 3);
+
+        let instance = scheduler.pop_due(100).unwrap();
+        assert_eq!(instance.subscription_key, subscription_key(1));
+        assert_eq!(instance.next_trigger_time, 100);
+        assert_eq!(scheduler.scheduled_trigger_time(&subscription_key(1)), None);
+
+        assert!(scheduler.cancel(&subscription_key(3)));
+        assert!(!scheduler.cancel(&subscription_key(3)));
+        assert_eq!(scheduler.next_trigger_time(), Some(200));
+
+        // The cancelled instance is not popped in place of the next one
+        assert!(scheduler.pop_due(150).is_none());
+
+        let instance = scheduler.pop_due(250).unwrap();
+        assert_eq!(instance.subscription_key, subscription_key(2));
+        assert_eq!(instance.next_trigger_time, 200);
+
+        assert!(scheduler.is_empty());
+        assert_eq!(scheduler.next_trigger_time(), None);
+        assert!(scheduler.pop_due(u64::MAX).is_none());
+    }
+
+    #[test]
+    fn test_subscription_job_scheduler_compacts_stale_instances() {
+        let mut scheduler = SubscriptionJobScheduler::default();
+
+        for trigger_time in 0..100 {
+            scheduler.schedule(
+                SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1)),
+                trigger_time,
+            );
+        }
+
+        assert_eq!(scheduler.len(), 1);
+        assert!(scheduler.instances.len() <= 2 * scheduler.len() + 16);
+        assert_eq!(scheduler.next_trigger_time(), Some(99));
+    }
+
+    #[tokio::test]
+    async fn test_update_subscription_job_termination_params_before_next_instance() {
+        let contracts_client = generate_contracts_client().await;
+        let subscription_id = job_subscription_id(CHAIN_ID, 1);
+        let subscription_job = generate_generic_subscription_job(None, None);
+
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+        }
+
+        add_next_trigger_time_to_heap(
+            &contracts_client,
+            subscription_job.key(),
+            subscription_job.starttime.as_u64(),
+        );
+
+        // Terminate before the queued instance
+        let log = generate_job_subscription_termination_params_updated(
+            None,
+            Some(subscription_job.starttime.as_u64() + 1),
+        );
+
+        let res = update_subscription_job_termination_params(&contracts_client, log, CHAIN_ID);
+
+        assert!(res.is_ok());
+        assert!(contracts_client
+            .subscription_job_scheduler
+            .read()
+            .unwrap()
+            .is_empty());
+        assert!(contracts_client
+            .subscription_jobs
+            .read()
+            .unwrap()
+            .get(&SubscriptionKey::new(CHAIN_ID, subscription_id))
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_job_subscription_manager_cancelled_next_instance() {
+        let contracts_client = generate_contracts_client().await;
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+        let (_job_sub_tx, job_sub_rx) =
+            tokio::sync::mpsc::channel::<JobSubscriptionChannelType>(100);
+
+        let cancelled_subscription_job = generate_generic_subscription_job(Some(1), Some(1));
+        let next_subscription_job = generate_generic_subscription_job(Some(2), Some(100));
+        // Scope for write locks on subscription_jobs and subscription_job_scheduler
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            let mut subscription_job_scheduler =
+                contracts_client.subscription_job_scheduler.write().unwrap();
+            for subscription_job in [&cancelled_subscription_job, &next_subscription_job] {
+                subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+                subscription_job_scheduler
+                    .schedule(subscription_job.key(), subscription_job.starttime.as_u64());
+            }
+        }
+
+        let manager = tokio::spawn(job_subscription_manager(
+            contracts_client.clone(),
+            job_sub_rx,
+            req_chain_tx,
+        ));
+        sleep(Duration::from_millis(200)).await;
+
+        // Terminated before the instance the manager is waiting for
+        let log = generate_job_subscription_termination_params_updated(
+            Some(1),
+            Some(cancelled_subscription_job.starttime.as_u64() - 1),
+        );
+        update_subscription_job_termination_params(&contracts_client, log, CHAIN_ID).unwrap();
+
+        // The manager wakes up at the cancelled instance without triggering the next one early
+        sleep(Duration::from_secs(2)).await;
+        manager.abort();
+
+        // Still queued rather than triggered and followed by its next instance
+        assert_eq!(
+            contracts_client
+                .subscription_job_scheduler
+                .read()
+                .unwrap()
+                .scheduled_trigger_time(&next_subscription_job.key()),
+            Some(next_subscription_job.starttime.as_u64())
+        );
+    }
+
+    #[test]
+    fn test_subscription_job_schedule_missed_instances() {
+        let mut subscription_job = generate_generic_subscription_job(None, None);
+        subscription_job.starttime = U256::from(1000);
+        subscription_job.interval = U256::from(10);
+        subscription_job.termination_time = U256::from(1045);
+        let schedule = subscription_job.schedule();
+
+        // Starts before the maintained window
+        assert_eq!(
+            schedule.trigger_times_between(1015, 1040),
+            vec![1020, 1030, 1040]
+        );
+        // Stops at the termination time
+        assert_eq!(
+            schedule.trigger_times_between(1015, 1100),
+            vec![1020, 1030, 1040]
+        );
+        // Window starting before the subscription
+        assert_eq!(schedule.trigger_times_between(900, 1015), vec![1000, 1010]);
+        assert!(schedule.trigger_times_between(1041, 1045).is_empty());
+    }
+
+    async fn catch_up_with_policy(policy: SubscriptionCatchUpPolicy) -> Arc<ContractsClient> {
+        catch_up_with_trigger_config(policy, SubscriptionTriggerConfig::default()).await
+    }
+
+    async fn catch_up_with_trigger_config(
+        policy: SubscriptionCatchUpPolicy,
+        subscription_trigger: SubscriptionTriggerConfig,
+    ) -> Arc<ContractsClient> {
+        let now = 1_700_000_000;
+        let contracts_client = generate_contracts_client().await;
+        let contracts_client = Arc::new(ContractsClient {
+            clock: Arc::new(MockClock::new(now)),
+            subscription_catch_up_policy: policy,
+            subscription_trigger_limiter: Arc::new(SubscriptionTriggerLimiter::new(
+                subscription_trigger,
+            )),
+            ..(*contracts_client).clone()
+        });
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+
+        // Missed instances at now - 50, ..., now, the user timeout covers the last three
+        let mut subscription_job = generate_generic_subscription_job(None, None);
+        subscription_job.starttime = U256::from(now - 50);
+        subscription_job.termination_time = U256::from(now + 1000);
+        subscription_job.user_timeout = U256::from(25_000);
+
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job);
+        }
+
+        catch_up_subscription_job(
+            &contracts_client,
+            SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1)),
+            req_chain_tx,
+        );
+
+        assert_eq!(
+            contracts_client
+                .subscription_job_scheduler
+                .read()
+                .unwrap()
+                .scheduled_trigger_time(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1)
+                )),
+            Some(now + 10)
+        );
+
+        contracts_client
+    }
+
+    fn catch_up_metrics(contracts_client: &Arc<ContractsClient>) -> (u64, u64) {
+        (
+            contracts_client
+                .subscription_catch_up_metrics
+                .caught_up_instances
+                .load(Ordering::Relaxed),
+            contracts_client
+                .subscription_catch_up_metrics
+                .skipped_instances
+                .load(Ordering::Relaxed),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_subscription_job_skip_all() {
+        let contracts_client = catch_up_with_policy(SubscriptionCatchUpPolicy::SkipAll).await;
+
+        assert_eq!(catch_up_metrics(&contracts_client), (0, 6));
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_subscription_job_relay_all() {
+        let contracts_client = catch_up_with_policy(SubscriptionCatchUpPolicy::RelayAll).await;
+
+        assert_eq!(catch_up_metrics(&contracts_client), (3, 3));
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_subscription_job_relay_latest() {
+        let contracts_client = catch_up_with_policy(SubscriptionCatchUpPolicy::RelayLatest).await;
+
+        assert_eq!(catch_up_metrics(&contracts_client), (1, 5));
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_subscription_job_rate_limited() {
+        let contracts_client = catch_up_with_trigger_config(
+            SubscriptionCatchUpPolicy::RelayAll,
+            SubscriptionTriggerConfig {
+                jitter_ms: 0,
+                default_rate_limit: Some(TriggerRateLimit {
+                    instances_per_second: 1.0,
+                    burst: 1,
+                }),
+                rate_limits: vec![],
+            },
+        )
+        .await;
+
+        // Caught up instances wait for the tokens of the request chain
+        assert_eq!(catch_up_metrics(&contracts_client), (3, 3));
+        assert_eq!(
+            contracts_client.subscription_trigger_limiter.pending_len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_subscription_job_past_relay_deadline() {
+        let now = 1_700_000_000;
+        let contracts_client =
+            generate_contracts_client_with_clock(Arc::new(MockClock::new(now))).await;
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+
+        // Missed instances at now - 100, ..., now, all within the user timeout
+        let mut subscription_job = generate_generic_subscription_job(None, None);
+        subscription_job.starttime = U256::from(now - 100);
+        subscription_job.termination_time = U256::from(now + 1000);
+        subscription_job.user_timeout = U256::from(200_000);
+
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+        }
+
+        catch_up_subscription_job(&contracts_client, subscription_job.key(), req_chain_tx);
+
+        // The instances at now - 100, now - 90 and now - 80 can no longer be relayed on the
+        // common chain
+        assert_eq!(catch_up_metrics(&contracts_client), (8, 3));
+        assert_eq!(
+            subscription_job.instance_deadline(now - 80),
+            now - 80 + 2 * REQUEST_RELAY_TIMEOUT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_subscription_job_starttime_in_future() {
+        let contracts_client = generate_contracts_client().await;
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+        let subscription_job = generate_generic_subscription_job(None, Some(100));
+
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+        }
+
+        catch_up_subscription_job(
+            &contracts_client,
+            SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1)),
+            req_chain_tx,
+        );
+
+        assert_eq!(
+            contracts_client
+                .subscription_job_scheduler
+                .read()
+                .unwrap()
+                .scheduled_trigger_time(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1)
+                )),
+            Some(subscription_job.starttime.as_u64())
+        );
+        assert_eq!(catch_up_metrics(&contracts_client), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_record_subscription_instance() {
+        let contracts_client = generate_contracts_client().await;
+        let subscription_job = generate_generic_subscription_job(None, None);
+        let starttime = subscription_job.starttime.as_u64();
+
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job);
+        }
+
+        let gateway = Address::random();
+        record_subscription_instance(
+            &contracts_client,
+            &JobKey::subscription_instance(CHAIN_ID, job_subscription_id(CHAIN_ID, 1), 0),
+            SubscriptionInstanceStatus::Assigned { gateway },
+        );
+        record_subscription_instance(
+            &contracts_client,
+            &JobKey::subscription_instance(CHAIN_ID, job_subscription_id(CHAIN_ID, 1), 0),
+            SubscriptionInstanceStatus::Relayed { gateway },
+        );
+        record_subscription_instance(
+            &contracts_client,
+            &JobKey::subscription_instance(CHAIN_ID, job_subscription_id(CHAIN_ID, 1), 1),
+            SubscriptionInstanceStatus::Skipped {
+                reason: "Insufficient funds".to_owned(),
+            },
+        );
+        // Not instances of the subscription
+        record_subscription_instance(
+            &contracts_client,
+            &JobKey::once(CHAIN_ID, relay_job_id(CHAIN_ID, 1)),
+            SubscriptionInstanceStatus::Responded,
+        );
+        record_subscription_instance(
+            &contracts_client,
+            &JobKey::subscription_instance(CHAIN_ID + 1, job_subscription_id(CHAIN_ID + 1, 1), 2),
+            SubscriptionInstanceStatus::Responded,
+        );
+
+        // Scope for read lock on subscription_instance_history
+        {
+            let subscription_instance_history = contracts_client
+                .subscription_instance_history
+                .read()
+                .unwrap();
+            let instances = subscription_instance_history
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
+
+            assert_eq!(instances.len(), 2);
+            assert_eq!(instances[&0].trigger_time, starttime);
+            assert_eq!(
+                instances[&0].status,
+                SubscriptionInstanceStatus::Relayed { gateway }
+            );
+            assert_eq!(instances[&1].trigger_time, starttime + 10);
+            assert_eq!(
+                instances[&1].status,
+                SubscriptionInstanceStatus::Skipped {
+                    reason: "Insufficient funds".to_owned()
+                }
+            );
+        }
+
+        // Only the latest instances are kept
+        for instance_index in 0..(SUBSCRIPTION_INSTANCE_HISTORY_LEN as u64 + 5) {
+            record_subscription_instance(
+                &contracts_client,
+                &JobKey::subscription_instance(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                    instance_index,
+                ),
+                SubscriptionInstanceStatus::Responded,
+            );
+        }
+
+        // Scope for read lock on subscription_instance_history
+        {
+            let subscription_instance_history = contracts_client
+                .subscription_instance_history
+                .read()
+                .unwrap();
+            let instances = subscription_instance_history
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
+
+            assert_eq!(instances.len(), SUBSCRIPTION_INSTANCE_HISTORY_LEN);
+            assert_eq!(instances.first_key_value().unwrap().0, &5);
+        }
+
+        drop_subscription_job(
+            &contracts_client,
+            &SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1)),
+        );
+
+        assert!(contracts_client
+            .subscription_instance_history
+            .read()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_preview_subscription_job() {
+        let contracts_client = generate_contracts_client().await;
+        let subscription_job = generate_generic_subscription_job(None, Some(100));
+        let starttime = subscription_job.starttime.as_u64();
+
+        assert_eq!(
+            preview_subscription_job(
+                &contracts_client,
+                SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1)),
+                10
+            )
+            .unwrap_err(),
+            ServerlessError::NoSubscriptionJobFound(CHAIN_ID, job_subscription_id(CHAIN_ID, 1))
+        );
+
+        // Scope for write lock on subscription_jobs
+        {
+            let mut subscription_jobs = contracts_client.subscription_jobs.write().unwrap();
+            subscription_jobs.insert(subscription_job.key(), subscription_job.clone());
+        }
+        add_next_trigger_time_to_heap(
+            &contracts_client,
+            subscription_job.key(),
+            starttime - subscription_job.interval.as_u64(),
+        );
+        record_subscription_instance(
+            &contracts_client,
+            &JobKey::subscription_instance(CHAIN_ID, job_subscription_id(CHAIN_ID, 1), 0),
+            SubscriptionInstanceStatus::Skipped {
+                reason: "Insufficient funds".to_owned(),
+            },
+        );
+
+        // No gateway epoch states are known
+        let preview = preview_subscription_job(
+            &contracts_client,
+            SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1)),
+            2,
+        )
+        .unwrap();
+
+        assert_eq!(preview.subscription_id, job_subscription_id(CHAIN_ID, 1));
+        assert_eq!(preview.starttime, subscription_job.starttime);
+        assert_eq!(preview.upcoming_instances.len(), 2);
+        assert_eq!(preview.upcoming_instances[0].trigger_time, starttime);
+        assert_eq!(preview.upcoming_instances[1].trigger_time, starttime + 10);
+        assert_eq!(preview.upcoming_instances[1].instance_index, 1);
+        assert_eq!(preview.upcoming_instances[0].gateway, None);
+        assert!(preview.upcoming_instances[0].gateway_error.is_some());
+        assert_eq!(preview.past_instances.len(), 1);
+        assert_eq!(preview.past_instances[0].instance_index, 0);
+
+        // Upcoming instances stop at the termination time
+        add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
+        let preview = preview_subscription_job(
+            &contracts_client,
+            SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1)),
+            1000,
+        )
+        .unwrap();
+
+        assert_eq!(preview.upcoming_instances.len(), 101);
+        assert_eq!(
+            preview.upcoming_instances.last().unwrap().trigger_time,
+            subscription_job.termination_time.as_u64()
+        );
+        assert!(preview
+            .upcoming_instances
+            .iter()
+            .all(|instance| instance.gateway == Some(contracts_client.enclave_address)));
+    }
+
+    #[tokio::test]
+    async fn test_process_historic_subscription_jobs_on_request_chain() {
+        let contracts_client = generate_contracts_client().await;
+        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-2)).await;
+        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-3)).await;
+        add_gateway_epoch_state(contracts_client.clone(), None, None, Some(-4)).await;
+        let request_chain_client = contracts_client.request_chain_client(CHAIN_ID).unwrap();
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+        let (job_sub_tx, mut job_sub_rx) =
+            tokio::sync::mpsc::channel::<JobSubscriptionChannelType>(100);
+        let mock_http_provider = MockHttpProvider::new(None);
+
+        process_historic_subscription_jobs_on_request_chain(
+            &contracts_client,
+            &request_chain_client,
+            req_chain_tx,
+            job_sub_tx,
+            mock_http_provider,
+        )
+        .await
+        .unwrap();
+
+        if let Some(rx_job) = job_sub_rx.recv().await {
+            if rx_job.subscription_action == JobSubscriptionAction::Add {
+                assert_eq!(
+                    rx_job.subscription_key,
+                    SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 1))
+                );
+            } else {
+                assert!(false);
+            }
+        } else {
+            assert!(false);
+        }
+
+        if let Some(rx_job) = job_sub_rx.recv().await {
+            if rx_job.subscription_action == JobSubscriptionAction::Add {
+                assert_eq!(
+                    rx_job.subscription_key,
+                    SubscriptionKey::new(CHAIN_ID, job_subscription_id(CHAIN_ID, 3))
+                );
+            } else {
+                assert!(false);
+            }
+        } else {
+            assert!(false);
+        }
+
+        let subscription_job_one: Option<SubscriptionJob>;
+        // Scope for read lock on subscription_jobs
+        {
+            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
+            subscription_job_one = subscription_jobs
+                .get(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .cloned();
+        }
+
+        assert!(subscription_job_one.is_some());
+
+        let subscription_job_one = subscription_job_one.unwrap();
+        assert_eq!(
+            subscription_job_one.tx_hash,
+            hex::decode(
+                "9468bb6a8e85ed11e292c8cac0c1539df691c8d8ec62e7dbfa9f1bd7f504e7d8".to_owned(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            subscription_job_one.code_input,
+            Bytes::from(
+                serde_json::to_vec(&json!({
+                    "num": 108
+                }))
+                .unwrap(),
+            )
+        );
+
+        let system_time = contracts_client.clock.now();
+        assert!(subscription_job_one.termination_time.as_u64() >= system_time + 1490);
+        assert!(subscription_job_one.termination_time.as_u64() <= system_time + 1510);
+
+        {
+            let subscription_jobs = contracts_client.subscription_jobs.read().unwrap();
+            let subscription_job_two = subscription_jobs.get(&SubscriptionKey::new(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 2),
+            ));
+
+            assert!(subscription_job_two.is_none());
+        }
+
+        assert!(job_sub_rx.recv().await.is_none());
+
+        // Scope for read lock on subscription_job_scheduler
+        {
+            let subscription_job_scheduler_guard =
+                contracts_client.subscription_job_scheduler.read().unwrap();
+            // Next instance after the time the historic logs were processed at
+            let next_trigger_time = subscription_job_scheduler_guard
+                .scheduled_trigger_time(&SubscriptionKey::new(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                ))
+                .unwrap();
+            assert!(next_trigger_time + subscription_job_one.interval.as_u64() > system_time);
+            assert_eq!(
+                (next_trigger_time - subscription_job_one.starttime.as_u64())
+                    % subscription_job_one.interval.as_u64(),
+                0
+            );
+        }
+
+        // The user timeout of the first instance has elapsed, so it is not caught up
+        // Scope for read lock on active_jobs
+        {
+            let active_jobs = contracts_client.active_jobs.read().unwrap();
+            let active_job = active_jobs.get(&JobKey::subscription_instance(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+                0,
+            ));
+
+            assert!(active_job.is_none());
+        }
+    }
+
+    // Request chain provider failing to fetch the logs
+    struct FailingLogsProvider;
+
+    impl HttpProviderLogs for FailingLogsProvider {
+        async fn get_logs(&self, _filter: &Filter) -> Result<Vec<Log>, ServerlessError> {
+            Err(ServerlessError::ProviderError(
+                "eth_getLogs failed".to_owned(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_historic_subscription_jobs_on_request_chain_logs_failure() {
+        let contracts_client = generate_contracts_client().await;
+        let request_chain_client = contracts_client.request_chain_client(CHAIN_ID).unwrap();
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+        let (job_sub_tx, mut job_sub_rx) =
+            tokio::sync::mpsc::channel::<JobSubscriptionChannelType>(100);
+
+        let res = process_historic_subscription_jobs_on_request_chain(
+            &contracts_client,
+            &request_chain_client,
             req_chain_tx,
+            job_sub_tx,
+            FailingLogsProvider,
         )
         .await;
 
-        // Sleep for 1s to allow all async tasks to complete
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(res.is_err());
+        assert!(job_sub_rx.try_recv().is_err());
+        assert!(contracts_client
+            .subscription_jobs
+            .read()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_process_historic_subscription_jobs_of_request_chain_not_served() {
+        let contracts_client = generate_contracts_client().await;
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+        let (job_sub_tx, _job_sub_rx) =
+            tokio::sync::mpsc::channel::<JobSubscriptionChannelType>(100);
+
+        // Gives up without retrying once the request chain is no longer served
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            process_historic_subscription_jobs_of_request_chain(
+                contracts_client.clone(),
+                CHAIN_ID + 1,
+                req_chain_tx,
+                job_sub_tx,
+            ),
+        )
+        .await
+        .unwrap();
+        assert!(contracts_client
+            .subscription_jobs
+            .read()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trigger_subscription_job() {
+        let contracts_client = generate_contracts_client().await;
+        add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
+        let subscription_job = generate_generic_subscription_job(None, None);
+        let trigger_timestamp = subscription_job.starttime.as_u64();
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+
+        trigger_subscription_job(
+            subscription_job.clone(),
+            trigger_timestamp,
+            contracts_client.clone(),
+            req_chain_tx,
+        )
+        .await;
 
         let mut expected_job = subscription_job_to_relay_job(
             subscription_job.clone(),
@@ -1711,10 +3430,220 @@ mod job_subscription_management_tests {
         // Scope for read lock on active_jobs
         {
             let active_jobs = contracts_client.active_jobs.read().unwrap();
-            let active_job = active_jobs.get(&U256::one());
+            let active_job = active_jobs.get(&JobKey::subscription_instance(
+                CHAIN_ID,
+                job_subscription_id(CHAIN_ID, 1),
+                0,
+            ));
 
             assert!(active_job.is_some());
             assert_eq!(active_job.unwrap(), &expected_job);
         }
     }
+
+    #[tokio::test]
+    async fn test_trigger_overlapping_subscription_jobs() {
+        let contracts_client = generate_contracts_client().await;
+        add_gateway_epoch_state(contracts_client.clone(), None, None, None).await;
+        let (req_chain_tx, _com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+
+        // The second instance of subscription 1 and the first instance of subscription 2 are
+        // triggered together, with the job ids derived by the contract from their subscription ids
+        let subscription_job_one = generate_generic_subscription_job(Some(1), Some(-10));
+        let subscription_job_two = generate_generic_subscription_job(Some(2), None);
+        let trigger_timestamp = subscription_job_two.starttime.as_u64();
+
+        trigger_subscription_job(
+            subscription_job_one.clone(),
+            trigger_timestamp,
+            contracts_client.clone(),
+            req_chain_tx.clone(),
+        )
+        .await;
+        trigger_subscription_job(
+            subscription_job_two.clone(),
+            trigger_timestamp,
+            contracts_client.clone(),
+            req_chain_tx,
+        )
+        .await;
+
+        let job_one = subscription_job_to_relay_job(subscription_job_one, trigger_timestamp);
+        let job_two = subscription_job_to_relay_job(subscription_job_two, trigger_timestamp);
+        assert_eq!(
+            job_one.key().request_chain_job_id(),
+            job_subscription_id(CHAIN_ID, 1) + 1
+        );
+        assert_eq!(
+            job_two.key().request_chain_job_id(),
+            job_subscription_id(CHAIN_ID, 2)
+        );
+        assert_ne!(
+            job_one.key().common_chain_job_id(),
+            job_two.key().common_chain_job_id()
+        );
+
+        // Scope for read lock on active_jobs
+        {
+            let active_jobs = contracts_client.active_jobs.read().unwrap();
+            assert_eq!(active_jobs.len(), 2);
+
+            let active_job_one = active_jobs
+                .get(&JobKey::subscription_instance(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 1),
+                    1,
+                ))
+                .unwrap();
+            assert_eq!(active_job_one.job_id, job_subscription_id(CHAIN_ID, 1));
+            assert_eq!(active_job_one.instance_index, 1);
+
+            let active_job_two = active_jobs
+                .get(&JobKey::subscription_instance(
+                    CHAIN_ID,
+                    job_subscription_id(CHAIN_ID, 2),
+                    0,
+                ))
+                .unwrap();
+            assert_eq!(active_job_two.job_id, job_subscription_id(CHAIN_ID, 2));
+            assert_eq!(active_job_two.instance_index, 0);
+        }
+    }
+
+    // Adds subscription 1 an interval before subscription 2, so that the second instance of
+    // subscription 1 and the first instance of subscription 2 are due together, and relays the
+    // three instances to the gateway selected for them
+    async fn add_overlapping_subscription_jobs(
+        add_self: bool,
+    ) -> (Arc<ContractsClient>, Receiver<Job>, Vec<SubscriptionKey>) {
+        let contracts_client = generate_contracts_client().await;
+        add_gateway_epoch_state(contracts_client.clone(), None, Some(add_self), Some(-1)).await;
+        add_gateway_epoch_state(contracts_client.clone(), None, Some(add_self), None).await;
+        let (req_chain_tx, com_chain_rx) = tokio::sync::mpsc::channel::<Job>(100);
+
+        let mut subscription_keys = Vec::new();
+        for (subscription_id, starttime_delta) in [(1, -10), (2, 0)] {
+            let log =
+                generate_job_subscription_started_log(Some(subscription_id), Some(starttime_delta));
+            let res = add_subscription_job(
+                &contracts_client,
+                log,
+                CHAIN_ID,
+                req_chain_tx.clone(),
+                false,
+            );
+            subscription_keys.push(res.unwrap().unwrap());
+        }
+
+        let subscription_jobs = contracts_client.subscription_jobs.read().unwrap().clone();
+        trigger_subscription_job(
+            subscription_jobs[&subscription_keys[0]].clone(),
+            subscription_jobs[&subscription_keys[1]].starttime.as_u64(),
+            contracts_client.clone(),
+            req_chain_tx,
+        )
+        .await;
+
+        (contracts_client, com_chain_rx, subscription_keys)
+    }
+
+    fn assert_overlapping_subscription_instances(
+        contracts_client: &Arc<ContractsClient>,
+        job_keys: Vec<JobKey>,
+        subscription_keys: &[SubscriptionKey],
+    ) {
+        let expected_job_keys = HashSet::from([
+            JobKey::subscription_instance(CHAIN_ID, job_subscription_id(CHAIN_ID, 1), 0),
+            JobKey::subscription_instance(CHAIN_ID, job_subscription_id(CHAIN_ID, 1), 1),
+            JobKey::subscription_instance(CHAIN_ID, job_subscription_id(CHAIN_ID, 2), 0),
+        ]);
+        assert_eq!(
+            job_keys.into_iter().collect::<HashSet<_>>(),
+            expected_job_keys
+        );
+
+        // Distinct job ids on the request chain and the common chain
+        let request_chain_job_ids: HashSet<U256> = expected_job_keys
+            .iter()
+            .map(|job_key| job_key.request_chain_job_id())
+            .collect();
+        assert_eq!(request_chain_job_ids.len(), 3);
+        let common_chain_job_ids: HashSet<U256> = expected_job_keys
+            .iter()
+            .map(|job_key| job_key.common_chain_job_id())
+            .collect();
+        assert_eq!(common_chain_job_ids.len(), 3);
+
+        // Scope for read lock on subscription_instance_history
+        {
+            let subscription_instance_history = contracts_client
+                .subscription_instance_history
+                .read()
+                .unwrap();
+            assert_eq!(
+                subscription_instance_history[&subscription_keys[0]]
+                    .keys()
+                    .copied()
+                    .collect::<Vec<u64>>(),
+                vec![0, 1]
+            );
+            assert_eq!(
+                subscription_instance_history[&subscription_keys[1]]
+                    .keys()
+                    .copied()
+                    .collect::<Vec<u64>>(),
+                vec![0]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_overlapping_subscription_jobs_active_jobs() {
+        let (contracts_client, mut com_chain_rx, subscription_keys) =
+            add_overlapping_subscription_jobs(true).await;
+
+        // The instances assigned to the gateway are sent out once they are in the active jobs
+        let mut job_keys = Vec::new();
+        for _ in 0..3 {
+            job_keys.push(com_chain_rx.recv().await.unwrap().key());
+        }
+
+        assert_eq!(contracts_client.active_jobs.read().unwrap().len(), 3);
+        assert!(contracts_client.current_jobs.read().unwrap().is_empty());
+        for job_key in job_keys.iter() {
+            let active_jobs = contracts_client.active_jobs.read().unwrap();
+            let active_job = active_jobs.get(job_key).unwrap();
+            assert_eq!(active_job.job_id, job_key.job_id);
+            assert_eq!(active_job.instance_index, job_key.instance_index);
+        }
+        assert_overlapping_subscription_instances(&contracts_client, job_keys, &subscription_keys);
+    }
+
+    #[tokio::test]
+    async fn test_add_overlapping_subscription_jobs_current_jobs() {
+        let (contracts_client, _com_chain_rx, subscription_keys) =
+            add_overlapping_subscription_jobs(false).await;
+
+        // The first instances are relayed by the tasks spawned in `add_subscription_job`
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while contracts_client.current_jobs.read().unwrap().len() < 3 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert!(contracts_client.active_jobs.read().unwrap().is_empty());
+        let job_keys: Vec<JobKey> = contracts_client
+            .current_jobs
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(job_key, current_job)| {
+                assert_eq!(current_job.key(), *job_key);
+                *job_key
+            })
+            .collect();
+        assert_overlapping_subscription_instances(&contracts_client, job_keys, &subscription_keys);
+    }
 }
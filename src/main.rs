@@ -1,13 +1,28 @@
+mod admin_auth;
 mod api_impl;
 mod chain_util;
+mod clock;
 mod common_chain_gateway_state_service;
 mod common_chain_interaction;
 mod config;
 mod constant;
 mod contract_abi;
+mod deadline_queue;
+mod deregistration;
+mod enclave_signer;
 mod error;
+mod event_decoder;
+mod gas_wallet;
+mod gas_wallet_rotation;
+mod job_key;
 mod job_subscription_management;
 mod model;
+mod request_chain_management;
+mod schedule;
+mod sealed_state;
+mod self_registration;
+mod subscription_trigger_limiter;
+mod typed_data;
 
 #[cfg(test)]
 mod test_util;
@@ -15,25 +30,31 @@ mod test_util;
 use actix_web::web::Data;
 use actix_web::{App, HttpServer};
 use anyhow::Context;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use env_logger::Env;
 use ethers::prelude::*;
 use ethers::providers::Provider;
-use ethers::utils::public_key_to_address;
-use k256::ecdsa::SigningKey;
+use log::{error, info};
 use std::collections::HashSet;
 use std::error::Error;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
-use tokio::fs;
 
 use crate::api_impl::{
-    export_signed_registration_message, get_gateway_details, index, inject_immutable_config,
-    inject_mutable_config,
+    deregister_gateway, detect_registration, export_signed_add_chains_message,
+    export_signed_registration_message, export_signed_remove_chains_message, get_gateway_details,
+    get_schedule_preview, get_subscription_preview, get_txn_queue_metrics, index,
+    inject_immutable_config, inject_mutable_config, register_gateway,
 };
-use crate::model::{AppState, ConfigManager};
+use crate::chain_util::{ChainConnector, WsHttpConnector};
+use crate::clock::{BlockTimestampClock, Clock, SystemClock};
+use crate::deregistration::DeregisterArgs;
+use crate::enclave_signer::{EnclaveSigner, FileEnclaveSigner, InMemoryEnclaveSigner};
+use crate::model::{AppState, ConfigManager, GasWallet, SealedState};
+use crate::sealed_state::restore_sealed_state;
+use crate::self_registration::RegisterArgs;
 
-type HttpProviderType = NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>;
+type HttpProviderType = NonceManagerMiddleware<SignerMiddleware<Provider<Http>, GasWallet>>;
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -44,8 +65,25 @@ struct Cli {
         default_value = "./oyster_serverless_gateway_config.json"
     )]
     config_file: String,
+    #[clap(long, value_parser, default_value = "0.0.0.0")]
+    address: String,
     #[clap(long, value_parser, default_value = "6001")]
     port: u16,
+    // The config endpoints are only reachable from the enclave itself unless bound elsewhere
+    #[clap(long, value_parser, default_value = "127.0.0.1")]
+    admin_address: String,
+    #[clap(long, value_parser, default_value = "6002")]
+    admin_port: u16,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    // Registers a running gateway on the common chain and its request chains with the owner key
+    Register(RegisterArgs),
+    // Deregisters a running gateway on the common chain and its request chains with the owner key
+    Deregister(DeregisterArgs),
 }
 
 #[tokio::main]
@@ -53,24 +91,73 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
     // Load the configuration file
     let args = Cli::parse();
+    match args.command {
+        Some(Command::Register(register_args)) => {
+            register_args.run(&args.config_file).await?;
+            return Ok(());
+        }
+        Some(Command::Deregister(deregister_args)) => {
+            deregister_args.run(&args.config_file).await?;
+            return Ok(());
+        }
+        None => {}
+    }
+
     let config_manager = ConfigManager::new(&args.config_file);
     let config = config_manager.load_config().unwrap();
 
-    let enclave_signer_key = SigningKey::from_slice(
-        fs::read(config.enclave_secret_key)
-            .await
-            .context("Failed to read the enclave signer key")?
-            .as_slice(),
-    )
-    .context("Invalid enclave signer key")?;
+    // Use the enclave key from the secret key file if configured, else generate one that never
+    // leaves the enclave memory
+    let enclave_signer: Arc<dyn EnclaveSigner> = match &config.enclave_secret_key {
+        Some(enclave_secret_key) => {
+            Arc::new(FileEnclaveSigner::from_file(enclave_secret_key).await?)
+        }
+        None => {
+            info!("No enclave secret key file configured, generating the enclave key in memory");
+            if config.sealed_state_file.is_some() {
+                info!("The sealed state can only be restored with the same enclave key, it won't survive a restart");
+            }
+            Arc::new(InMemoryEnclaveSigner::generate())
+        }
+    };
+
+    let enclave_address = enclave_signer.address();
 
-    let enclave_address = public_key_to_address(&enclave_signer_key.verifying_key());
+    // Derive the current time from the common chain blocks if configured, else use the system time
+    let clock: Arc<dyn Clock> = if config.use_common_chain_time {
+        let common_chain_http_rpc_client = WsHttpConnector
+            .connect_http(&config.common_chain_http_url)
+            .await?;
+        let block_timestamp_clock = Arc::new(BlockTimestampClock::new());
+        let block_timestamp_clock_clone = block_timestamp_clock.clone();
+        tokio::spawn(async move {
+            block_timestamp_clock_clone
+                .sync_with_chain(Arc::new(common_chain_http_rpc_client))
+                .await;
+        });
+        block_timestamp_clock
+    } else {
+        Arc::new(SystemClock)
+    };
+
+    // Set up the gas wallet from the config, if configured, else wait for the gas key to be
+    // injected through the mutable config
+    let gas_wallet = match &config.gas_wallet {
+        Some(gas_wallet_config) => Some(
+            GasWallet::from_config(gas_wallet_config, config.common_chain_id)
+                .await
+                .context("Failed to set up the gas wallet")?,
+        ),
+        None => None,
+    };
+    let gas_wallet_configured = gas_wallet.is_some();
 
     // Create a Appstate
     let app_data = Data::new(AppState {
-        enclave_signer_key,
+        enclave_signer,
         enclave_address,
-        wallet: None.into(),
+        wallet: gas_wallet.into(),
+        staged_wallet: Mutex::new(None),
         common_chain_id: config.common_chain_id,
         common_chain_http_url: config.common_chain_http_url,
         common_chain_ws_url: config.common_chain_ws_url,
@@ -83,27 +170,84 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
         offset_for_epoch: config.offset_for_epoch,
         enclave_owner: H160::zero().into(),
         immutable_params_injected: Mutex::new(false),
-        mutable_params_injected: Arc::new(AtomicBool::new(false)),
+        mutable_params_injected: Arc::new(AtomicBool::new(gas_wallet_configured)),
         registration_events_listener_active: false.into(),
+        deregistering: false.into(),
         contracts_client: Mutex::new(None),
+        clock,
+        subscription_catch_up_policy: config.subscription_catch_up_policy,
+        subscription_trigger: config.subscription_trigger,
+        admin_auth_token: config.admin_auth_token,
+        mutable_config_nonce: Mutex::new(0),
+        eip712_domains: config.eip712_domains,
+        sealed_state_file: config.sealed_state_file,
     });
-    // Start a http server
+    // Resume from the state persisted before the restart, if any
+    if let Some(sealed_state_file) = app_data.sealed_state_file.clone() {
+        match SealedState::load(&sealed_state_file, app_data.enclave_signer.as_ref()).await {
+            Ok(Some(sealed_state)) => {
+                info!("Restoring the sealed state from {}", sealed_state_file);
+                if let Err(err) = restore_sealed_state(app_data.clone(), sealed_state).await {
+                    error!("Failed to restore the sealed state: {:?}", err);
+                }
+            }
+            Ok(None) => {}
+            Err(err) => error!("Failed to load the sealed state: {:?}", err),
+        }
+    }
+
+    // Pick up the registration done before the restart, if any
+    if let Err(err) = detect_registration(&app_data).await {
+        error!(
+            "Failed to detect the registration of the gateway on chain: {:?}",
+            err
+        );
+    }
+
+    // Start the http server for the public endpoints
+    let public_app_data = app_data.clone();
     let server = HttpServer::new(move || {
+        App::new()
+            .app_data(public_app_data.clone())
+            .service(index)
+            .service(get_gateway_details)
+            .service(get_schedule_preview)
+            .service(get_txn_queue_metrics)
+    })
+    .bind((args.address.as_str(), args.port))
+    .context(format!(
+        "could not bind to address {}:{}",
+        args.address, args.port
+    ))?
+    .run();
+
+    // Start a separate http server for the endpoints configuring the enclave
+    let admin_server = HttpServer::new(move || {
         App::new()
             .app_data(app_data.clone())
             .service(index)
             .service(inject_immutable_config)
             .service(inject_mutable_config)
             .service(export_signed_registration_message)
-            .service(get_gateway_details)
+            .service(register_gateway)
+            .service(deregister_gateway)
+            .service(export_signed_add_chains_message)
+            .service(export_signed_remove_chains_message)
+            .service(get_subscription_preview)
     })
-    .bind(("0.0.0.0", args.port))
-    .context(format!("could not bind to port {}", args.port))?
+    .bind((args.admin_address.as_str(), args.admin_port))
+    .context(format!(
+        "could not bind to admin address {}:{}",
+        args.admin_address, args.admin_port
+    ))?
     .run();
 
-    println!("Node server started on port {}", args.port);
+    println!(
+        "Node server started on {}:{}, admin server on {}:{}",
+        args.address, args.port, args.admin_address, args.admin_port
+    );
 
-    server.await?;
+    tokio::try_join!(server, admin_server)?;
 
     Ok(())
 }
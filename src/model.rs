@@ -1,20 +1,29 @@
 use ethers::abi::FixedBytes;
+use ethers::providers::{Http, Provider};
 use ethers::signers::LocalWallet;
-use ethers::types::{Address, Bytes, H160, U256};
-use k256::ecdsa::SigningKey;
+use ethers::types::transaction::eip712::EIP712Domain;
+use ethers::types::{Address, Bytes, H160, H256, U256};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Notify;
+use tokio::task::AbortHandle;
 
+use crate::chain_util::WsHttpConnector;
+use crate::clock::Clock;
 use crate::contract_abi::{GatewayJobsContract, RelayContract, RelaySubscriptionsContract};
+use crate::enclave_signer::EnclaveSigner;
 use crate::HttpProviderType;
 
 #[derive(Debug)]
 pub struct AppState {
-    pub enclave_signer_key: SigningKey,
+    pub enclave_signer: Arc<dyn EnclaveSigner>,
     pub enclave_address: H160,
-    pub wallet: Mutex<Option<LocalWallet>>,
+    pub wallet: Mutex<Option<GasWallet>>,
+    pub staged_wallet: Mutex<Option<GasWallet>>,
     pub common_chain_id: u64,
     pub common_chain_http_url: String,
     pub common_chain_ws_url: String,
@@ -29,7 +38,16 @@ pub struct AppState {
     pub immutable_params_injected: Mutex<bool>,
     pub mutable_params_injected: Arc<AtomicBool>,
     pub registration_events_listener_active: Mutex<bool>,
+    // Set while the gateway is being deregistered, until it is torn down or the deregistration fails
+    pub deregistering: AtomicBool,
     pub contracts_client: Mutex<Option<Arc<ContractsClient>>>,
+    pub clock: Arc<dyn Clock>,
+    pub subscription_catch_up_policy: SubscriptionCatchUpPolicy,
+    pub subscription_trigger: SubscriptionTriggerConfig,
+    pub admin_auth_token: Option<String>,
+    pub mutable_config_nonce: Mutex<u64>,
+    pub eip712_domains: Eip712Domains,
+    pub sealed_state_file: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,7 +57,30 @@ pub struct ImmutableConfig {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MutableConfig {
-    pub gas_key_hex: String,
+    pub gas_wallet: GasWalletConfig,
+    pub nonce: u64,
+    pub sign_timestamp: u64,
+    pub owner_signature_hex: String,
+    #[serde(default)]
+    pub sweep_funds: bool,
+}
+
+// Configuration and registration status of the gateway persisted across restarts, sealed with a
+// key derived from the enclave secret
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SealedState {
+    pub enclave_owner: Option<H160>,
+    pub gas_wallet: Option<SealedGasWallet>,
+    pub mutable_config_nonce: u64,
+    pub request_chain_ids: BTreeSet<u64>,
+    pub registered: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SealedGasWallet {
+    Local { gas_key_hex: String },
+    Remote { url: String, address: H160 },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,6 +90,7 @@ pub struct SignedRegistrationBody {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SignedRegistrationResponse {
+    pub enclave_address: H160,
     pub owner: H160,
     pub sign_timestamp: usize,
     pub chain_ids: Vec<u64>,
@@ -56,6 +98,90 @@ pub struct SignedRegistrationResponse {
     pub request_chain_signature: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignedChainsUpdateBody {
+    pub chain_ids: Vec<u64>,
+}
+
+// Signature of the enclave for the 'addChains' and 'removeChains' functions of the Gateways
+// contract
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignedChainsUpdateResponse {
+    pub enclave_address: H160,
+    pub sign_timestamp: usize,
+    pub chain_ids: Vec<u64>,
+    pub signature: String,
+}
+
+// Signer of the registration and deregistration transactions, holding the key of the enclave
+// owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OwnerSignerConfig {
+    Local { owner_key_hex: String },
+    Remote { url: String, address: H160 },
+}
+
+// Attestation of the enclave signed by the attestation verifier, as expected by the
+// 'registerGateway' functions of the contracts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistrationAttestation {
+    pub attestation_signature: Bytes,
+    pub enclave_pub_key: Bytes,
+    pub pcr0: Bytes,
+    pub pcr1: Bytes,
+    pub pcr2: Bytes,
+    pub timestamp_in_milliseconds: u64,
+}
+
+// Common chain and contract the registration transactions of the owner are sent to, along with
+// the request chains it lists
+#[derive(Debug, Clone)]
+pub struct RegistrationTarget {
+    pub enclave_address: H160,
+    pub common_chain_id: u64,
+    pub common_chain_http_url: String,
+    pub gateways_contract_addr: H160,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterGatewayBody {
+    pub chain_ids: Vec<u64>,
+    pub owner_signer: OwnerSignerConfig,
+    pub attestation: RegistrationAttestation,
+    #[serde(default)]
+    pub stake_amount: U256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistrationTxnStatus {
+    AlreadyRegistered,
+    Confirmed,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistrationTxnResult {
+    pub chain_id: u64,
+    pub status: RegistrationTxnStatus,
+    pub txn_hash: Option<H256>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterGatewayResponse {
+    pub common_chain: RegistrationTxnResult,
+    pub request_chains: Vec<RegistrationTxnResult>,
+}
+
+// The owner signer is optional, the gateway waits for the owner to deregister it without one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeregisterGatewayBody {
+    #[serde(default)]
+    pub owner_signer: Option<OwnerSignerConfig>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GatewayDetailsResponse {
     pub enclave_public_key: String,
@@ -85,10 +211,118 @@ pub struct Config {
     pub common_chain_ws_url: String,
     pub gateways_contract_addr: H160,
     pub gateway_jobs_contract_addr: H160,
-    pub enclave_secret_key: String,
+    #[serde(default)]
+    pub enclave_secret_key: Option<String>,
     pub epoch: u64,
     pub time_interval: u64,
     pub offset_for_epoch: u64,
+    #[serde(default)]
+    pub use_common_chain_time: bool,
+    #[serde(default)]
+    pub subscription_catch_up_policy: SubscriptionCatchUpPolicy,
+    #[serde(default)]
+    pub subscription_trigger: SubscriptionTriggerConfig,
+    #[serde(default)]
+    pub admin_auth_token: Option<String>,
+    #[serde(default)]
+    pub gas_wallet: Option<GasWalletConfig>,
+    #[serde(default)]
+    pub eip712_domains: Eip712Domains,
+    #[serde(default)]
+    pub sealed_state_file: Option<String>,
+}
+
+// EIP-712 domains of the contracts verifying the enclave signatures and of the messages the
+// enclave owner signs for the gateway, each one defaults to the contract name with the version "1"
+// and a configured domain replaces it as a whole
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Eip712Domains {
+    pub gateways: EIP712Domain,
+    pub gateway_jobs: EIP712Domain,
+    pub relay: EIP712Domain,
+    pub relay_subscriptions: EIP712Domain,
+    pub serverless_gateway: EIP712Domain,
+}
+
+// Pacing of the subscription instances handed over to the relay pipeline, instances of the
+// request chains without a rate limit are relayed as soon as they are due
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SubscriptionTriggerConfig {
+    #[serde(default)]
+    pub jitter_ms: u64,
+    #[serde(default)]
+    pub default_rate_limit: Option<TriggerRateLimit>,
+    #[serde(default)]
+    pub rate_limits: Vec<ChainTriggerRateLimit>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct TriggerRateLimit {
+    pub instances_per_second: f64,
+    pub burst: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ChainTriggerRateLimit {
+    pub chain_id: u64,
+    pub instances_per_second: f64,
+    pub burst: u64,
+}
+
+// Instances of the subscriptions missed while the gateway was down, that are still within
+// the maintained block states, to relay on startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionCatchUpPolicy {
+    SkipAll,
+    #[default]
+    RelayAll,
+    RelayLatest,
+}
+
+#[derive(Debug, Default)]
+pub struct SubscriptionCatchUpMetrics {
+    pub caught_up_instances: AtomicU64,
+    pub skipped_instances: AtomicU64,
+}
+
+// Gas wallet injected through the mutable config or set up on startup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GasWalletConfig {
+    // Hex encoded gas private key
+    Local {
+        gas_key_hex: String,
+    },
+    // Encrypted JSON (V3) keystore holding the key of the address, unlocked with the password
+    // read from the password file
+    Keystore {
+        keystore_file: String,
+        password_file: String,
+        address: H160,
+    },
+    // Signer holding the key outside the gateway, serving the 'eth_accounts' and
+    // 'eth_signTransaction' JSON-RPC methods (Web3Signer-style)
+    Remote {
+        url: String,
+        address: H160,
+    },
+}
+
+// Signer of the transactions sent by the gateway to the common chain and request chains
+#[derive(Debug, Clone, PartialEq)]
+pub enum GasWallet {
+    Local(LocalWallet),
+    Remote(RemoteSigner),
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    pub url: String,
+    pub http_rpc_client: Provider<Http>,
+    pub address: Address,
+    pub chain_id: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -100,27 +334,94 @@ pub struct GatewayData {
     pub draining: bool,
 }
 
-#[derive(Debug, Clone)]
-pub struct ContractsClient {
+#[derive(Debug)]
+pub struct ContractsClient<M = HttpProviderType, C = WsHttpConnector> {
     pub enclave_owner: Address,
-    pub enclave_signer_key: SigningKey,
+    pub enclave_signer: Arc<dyn EnclaveSigner>,
     pub enclave_address: Address,
     pub common_chain_ws_url: String,
     pub common_chain_http_url: String,
+    pub common_chain_client: Arc<M>,
     pub gateways_contract_address: Address,
-    pub gateway_jobs_contract: Arc<RwLock<GatewayJobsContract<HttpProviderType>>>,
-    pub request_chain_clients: HashMap<u64, Arc<RequestChainClient>>,
+    pub gateway_jobs_contract: Arc<RwLock<GatewayJobsContract<M>>>,
+    // Request chains served by the gateway, chains are added and removed while it is running
+    pub request_chain_clients: Arc<RwLock<HashMap<u64, Arc<RequestChainClient<M>>>>>,
+    // Listeners of the request chain events, by chain
+    pub request_chain_listeners: Arc<Mutex<HashMap<u64, AbortHandle>>>,
+    // Senders of the request chain listeners, set once the client runs so that the listeners of
+    // the chains added later feed the same services
+    pub request_chain_senders: Arc<Mutex<Option<RequestChainSenders>>>,
+    // Long running services of the client, aborted when it is stopped
+    pub service_tasks: Arc<Mutex<Vec<AbortHandle>>>,
     pub gateway_epoch_state: Arc<RwLock<BTreeMap<u64, BTreeMap<Address, GatewayData>>>>,
-    pub request_chain_ids: HashSet<u64>,
-    pub active_jobs: Arc<RwLock<HashMap<U256, Job>>>,
-    pub current_jobs: Arc<RwLock<HashMap<U256, Job>>>,
+    pub active_jobs: Arc<RwLock<HashMap<JobKey, Job>>>,
+    pub current_jobs: Arc<RwLock<HashMap<JobKey, Job>>>,
     pub epoch: u64,
     pub time_interval: u64,
     pub offset_for_epoch: u64,
     pub gateway_epoch_state_waitlist: Arc<RwLock<HashMap<u64, Vec<Job>>>>,
     pub common_chain_start_block_number: Arc<Mutex<u64>>,
-    pub subscription_job_instance_heap: Arc<RwLock<BinaryHeap<SubscriptionJobInstanceHeap>>>,
-    pub subscription_jobs: Arc<RwLock<HashMap<U256, SubscriptionJob>>>,
+    pub subscription_job_scheduler: Arc<RwLock<SubscriptionJobScheduler>>,
+    pub subscription_jobs: Arc<RwLock<HashMap<SubscriptionKey, SubscriptionJob>>>,
+    pub clock: Arc<dyn Clock>,
+    pub subscription_catch_up_policy: SubscriptionCatchUpPolicy,
+    pub subscription_catch_up_metrics: Arc<SubscriptionCatchUpMetrics>,
+    pub subscription_instance_history:
+        Arc<RwLock<HashMap<SubscriptionKey, BTreeMap<u64, SubscriptionInstanceRecord>>>>,
+    pub subscription_trigger_limiter: Arc<SubscriptionTriggerLimiter>,
+    pub common_chain_txn_queue: Arc<DeadlineQueue<Job>>,
+    pub request_chain_txn_queue: Arc<DeadlineQueue<ResponseJob>>,
+    pub eip712_domains: Eip712Domains,
+    // Held shared by the transactions in flight from the gas wallet and exclusively by the
+    // rotation of the gas wallet
+    pub gas_wallet_lock: Arc<tokio::sync::RwLock<()>>,
+    pub chain_connector: Arc<C>,
+}
+
+// Cloned field by field as the middleware is only held behind an `Arc` and need not be `Clone`
+impl<M, C> Clone for ContractsClient<M, C> {
+    fn clone(&self) -> Self {
+        ContractsClient {
+            enclave_owner: self.enclave_owner,
+            enclave_signer: self.enclave_signer.clone(),
+            enclave_address: self.enclave_address,
+            common_chain_ws_url: self.common_chain_ws_url.clone(),
+            common_chain_http_url: self.common_chain_http_url.clone(),
+            common_chain_client: self.common_chain_client.clone(),
+            gateways_contract_address: self.gateways_contract_address,
+            gateway_jobs_contract: self.gateway_jobs_contract.clone(),
+            request_chain_clients: self.request_chain_clients.clone(),
+            request_chain_listeners: self.request_chain_listeners.clone(),
+            request_chain_senders: self.request_chain_senders.clone(),
+            service_tasks: self.service_tasks.clone(),
+            gateway_epoch_state: self.gateway_epoch_state.clone(),
+            active_jobs: self.active_jobs.clone(),
+            current_jobs: self.current_jobs.clone(),
+            epoch: self.epoch,
+            time_interval: self.time_interval,
+            offset_for_epoch: self.offset_for_epoch,
+            gateway_epoch_state_waitlist: self.gateway_epoch_state_waitlist.clone(),
+            common_chain_start_block_number: self.common_chain_start_block_number.clone(),
+            subscription_job_scheduler: self.subscription_job_scheduler.clone(),
+            subscription_jobs: self.subscription_jobs.clone(),
+            clock: self.clock.clone(),
+            subscription_catch_up_policy: self.subscription_catch_up_policy,
+            subscription_catch_up_metrics: self.subscription_catch_up_metrics.clone(),
+            subscription_instance_history: self.subscription_instance_history.clone(),
+            subscription_trigger_limiter: self.subscription_trigger_limiter.clone(),
+            common_chain_txn_queue: self.common_chain_txn_queue.clone(),
+            request_chain_txn_queue: self.request_chain_txn_queue.clone(),
+            eip712_domains: self.eip712_domains.clone(),
+            gas_wallet_lock: self.gas_wallet_lock.clone(),
+            chain_connector: self.chain_connector.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestChainSenders {
+    pub req_chain_tx: Sender<Job>,
+    pub job_subscription_tx: Sender<JobSubscriptionChannelType>,
 }
 
 #[derive(Debug, Clone)]
@@ -133,14 +434,15 @@ pub struct RequestChainData {
 }
 
 #[derive(Debug, Clone)]
-pub struct RequestChainClient {
+pub struct RequestChainClient<M = HttpProviderType> {
     pub chain_id: u64,
     pub relay_address: Address,
     pub relay_subscriptions_address: Address,
     pub ws_rpc_url: String,
     pub http_rpc_url: String,
-    pub relay_contract: Arc<RwLock<RelayContract<HttpProviderType>>>,
-    pub relay_subscriptions_contract: Arc<RwLock<RelaySubscriptionsContract<HttpProviderType>>>,
+    pub http_rpc_client: Arc<M>,
+    pub relay_contract: Arc<RwLock<RelayContract<M>>>,
+    pub relay_subscriptions_contract: Arc<RwLock<RelaySubscriptionsContract<M>>>,
     pub request_chain_start_block_number: u64,
     pub confirmation_blocks: u64,
     pub last_seen_block: Arc<AtomicU64>,
@@ -154,15 +456,34 @@ pub enum GatewayJobType {
     // SlashGatewayResponse,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum JobMode {
     Once,
     Subscription,
 }
 
+// Identity of a job across the request chains, with the job id on the request chain for one
+// time jobs and the subscription id with the index of the instance for subscription jobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JobKey {
+    pub request_chain_id: u64,
+    pub job_mode: JobMode,
+    pub job_id: U256,
+    pub instance_index: u64,
+}
+
+// Identity of a subscription across the request chains, the subscription ids of the
+// RelaySubscriptions contracts are only unique on their own chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionKey {
+    pub request_chain_id: u64,
+    pub subscription_id: U256,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Job {
     pub job_id: U256,
+    pub instance_index: u64,
     pub request_chain_id: u64,
     pub tx_hash: FixedBytes,
     pub code_input: Bytes,
@@ -179,6 +500,7 @@ pub struct Job {
 #[derive(Debug, Clone, PartialEq)]
 pub struct ResponseJob {
     pub job_id: U256,
+    pub instance_index: u64,
     pub request_chain_id: u64,
     pub output: Bytes,
     pub total_time: U256,
@@ -192,14 +514,18 @@ pub struct ResponseJob {
 #[derive(Debug, Clone, PartialEq)]
 pub enum JobSubscriptionAction {
     Add,
+    Remove,
+    Update,
 }
 
 #[derive(Debug, Clone)]
 pub struct JobSubscriptionChannelType {
     pub subscription_action: JobSubscriptionAction,
-    pub subscription_id: U256,
+    pub subscription_key: SubscriptionKey,
 }
 
+// The times and the user timeout of the subscription fit in u64, the subscription logs carrying
+// larger values are rejected when decoded
 #[derive(Debug, Clone, PartialEq)]
 pub struct SubscriptionJob {
     pub subscription_id: U256,
@@ -212,10 +538,184 @@ pub struct SubscriptionJob {
     pub code_input: Bytes,
     pub starttime: U256,
     pub env: u8,
+    pub usdc_balance: U256,
+}
+
+// Latest known state of an instance of a subscription job, by its instance index
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SubscriptionInstanceStatus {
+    Assigned { gateway: Address },
+    Relayed { gateway: Address },
+    Responded,
+    Skipped { reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubscriptionInstanceRecord {
+    pub instance_index: u64,
+    pub trigger_time: u64,
+    #[serde(flatten)]
+    pub status: SubscriptionInstanceStatus,
+    pub updated_at: u64,
+}
+
+// Alternative schedules of the subscription instances, only previewed off-chain until the
+// contracts support them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleSpec {
+    FixedInterval {
+        interval: u64,
+    },
+    Cron {
+        expression: String,
+    },
+    Calendar {
+        every_months: u64,
+        day_of_month: u64,
+        #[serde(default)]
+        hour: u64,
+        #[serde(default)]
+        minute: u64,
+        #[serde(default)]
+        second: u64,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchedulePreviewBody {
+    pub schedule: ScheduleSpec,
+    pub starttime: u64,
+    pub termination_time: u64,
+    pub from: Option<u64>,
+    pub num_instances: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchedulePreviewResponse {
+    pub first_trigger_time: Option<u64>,
+    pub trigger_times: Vec<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscriptionPreviewBody {
+    pub request_chain_id: u64,
+    pub subscription_id: U256,
+    pub num_instances: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpcomingSubscriptionInstance {
+    pub instance_index: u64,
+    pub trigger_time: u64,
+    pub gateway: Option<Address>,
+    pub gateway_error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubscriptionPreviewResponse {
+    pub subscription_id: U256,
+    pub request_chain_id: u64,
+    pub subscriber: Address,
+    pub interval: U256,
+    pub termination_time: U256,
+    pub user_timeout: U256,
+    pub tx_hash: String,
+    pub code_input: Bytes,
+    pub starttime: U256,
+    pub env: u8,
+    pub usdc_balance: U256,
+    pub upcoming_instances: Vec<UpcomingSubscriptionInstance>,
+    pub past_instances: Vec<SubscriptionInstanceRecord>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    pub capacity: f64,
+    pub tokens: f64,
+    pub refill_per_second: f64,
+    pub last_refill: Instant,
+}
+
+// Instance of a subscription job waiting for a token of its request chain, ordered by its
+// deadline (`trigger_time + user_timeout`)
+#[derive(Debug, Clone)]
+pub struct PendingSubscriptionInstance {
+    pub deadline: u64,
+    pub trigger_time: u64,
+    pub subscription_job: SubscriptionJob,
+}
+
+#[derive(Debug)]
+pub struct SubscriptionTriggerQueue {
+    pub bucket: TokenBucket,
+    pub pending: BinaryHeap<PendingSubscriptionInstance>,
+}
+
+#[derive(Debug, Default)]
+pub struct SubscriptionTriggerBatch {
+    pub ready: Vec<PendingSubscriptionInstance>,
+    pub expired: Vec<PendingSubscriptionInstance>,
+    pub wait: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+pub struct SubscriptionTriggerLimiter {
+    pub config: SubscriptionTriggerConfig,
+    pub queues: Mutex<HashMap<u64, SubscriptionTriggerQueue>>,
+    pub notify: Notify,
+}
+
+// Item of an outgoing transaction queue, ordered by the deadline (unix timestamp) after which
+// its transaction is bound to revert
+#[derive(Debug)]
+pub struct DeadlineQueueItem<T> {
+    pub deadline: u64,
+    pub sequence: u64,
+    pub enqueued_at: Instant,
+    pub item: T,
+}
+
+#[derive(Debug)]
+pub struct DeadlineQueue<T> {
+    pub items: Mutex<BinaryHeap<DeadlineQueueItem<T>>>,
+    pub next_sequence: AtomicU64,
+    pub dropped_items: AtomicU64,
+    pub notify: Notify,
+}
+
+#[derive(Debug)]
+pub enum DequeuedItem<T> {
+    Due(T),
+    Expired(T),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadlineQueueMetrics {
+    pub depth: usize,
+    pub oldest_item_age_ms: Option<u64>,
+    pub dropped_items: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TxnQueueMetricsResponse {
+    pub common_chain: DeadlineQueueMetrics,
+    pub request_chain: DeadlineQueueMetrics,
 }
 
 #[derive(Debug, Clone)]
 pub struct SubscriptionJobInstanceHeap {
-    pub subscription_id: U256,
+    pub subscription_key: SubscriptionKey,
     pub next_trigger_time: u64,
+    pub generation: u64,
+}
+
+// Instances in the heap are invalidated lazily, only the entry with the generation recorded
+// for a subscription in `scheduled` is live
+#[derive(Debug, Default)]
+pub struct SubscriptionJobScheduler {
+    pub instances: BinaryHeap<SubscriptionJobInstanceHeap>,
+    pub scheduled: HashMap<SubscriptionKey, (u64, u64)>,
+    pub next_generation: u64,
 }
@@ -0,0 +1,700 @@
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::web::Data;
+use anyhow::{anyhow, Context};
+use ethers::prelude::*;
+use log::{error, info};
+use tokio::time::sleep;
+
+use crate::chain_util::{build_request_chain_client, ChainConnector, GatewaysView};
+use crate::contract_abi::{
+    ChainAddedFilter, ChainRemovedFilter, CommonChainGatewayDeregisteredFilter,
+};
+use crate::deregistration::teardown_deregistered_gateway;
+use crate::event_decoder::{decode_event, topic, uint_to_u64};
+use crate::job_subscription_management::{
+    process_historic_subscription_jobs_of_request_chain, remove_request_chain_subscription_jobs,
+};
+use crate::model::{
+    AppState, ContractsClient, JobSubscriptionAction, JobSubscriptionChannelType,
+    RequestChainClient,
+};
+use crate::sealed_state::persist_sealed_state;
+
+impl<M: Middleware + 'static, C: ChainConnector<M>> ContractsClient<M, C> {
+    pub fn request_chain_client(&self, chain_id: u64) -> Option<Arc<RequestChainClient<M>>> {
+        self.request_chain_clients
+            .read()
+            .unwrap()
+            .get(&chain_id)
+            .cloned()
+    }
+
+    pub fn request_chain_ids(&self) -> HashSet<u64> {
+        self.request_chain_clients
+            .read()
+            .unwrap()
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    /// Stops serving the request chain, dropping its listener and the subscriptions started on it.
+    /// Returns whether the chain was served.
+    pub async fn remove_request_chain(self: &Arc<Self>, chain_id: u64) -> bool {
+        if self
+            .request_chain_clients
+            .write()
+            .unwrap()
+            .remove(&chain_id)
+            .is_none()
+        {
+            return false;
+        }
+        self.stop_request_chain_listener(chain_id);
+
+        // Drop the jobs of the chain awaiting their execution or their relay on the common chain
+        self.active_jobs
+            .write()
+            .unwrap()
+            .retain(|job_key, _| job_key.request_chain_id != chain_id);
+        self.current_jobs
+            .write()
+            .unwrap()
+            .retain(|job_key, _| job_key.request_chain_id != chain_id);
+
+        let subscription_keys = remove_request_chain_subscription_jobs(self, chain_id);
+        let request_chain_senders = self.request_chain_senders.lock().unwrap().clone();
+        if let Some(request_chain_senders) = request_chain_senders {
+            for subscription_key in subscription_keys {
+                let _ = request_chain_senders
+                    .job_subscription_tx
+                    .send(JobSubscriptionChannelType {
+                        subscription_action: JobSubscriptionAction::Remove,
+                        subscription_key,
+                    })
+                    .await;
+            }
+        }
+
+        info!("Request Chain ID: {:?} removed", chain_id);
+        true
+    }
+
+    /// Starts serving the request chain, along with the subscriptions started on it before it was
+    /// added. Returns whether the chain was not served yet.
+    pub async fn add_request_chain<V: GatewaysView>(
+        self: &Arc<Self>,
+        app_state: &AppState,
+        chain_id: u64,
+        gateways_view: &V,
+    ) -> anyhow::Result<bool> {
+        if self.request_chain_client(chain_id).is_some() {
+            return Ok(false);
+        }
+
+        let request_chain_data = gateways_view.request_chain_data(chain_id).await?;
+
+        {
+            // Keep the gas wallet from being rotated until the chain is in the map of the clients
+            let _gas_wallet_permit = self.gas_wallet_lock.read().await;
+            let Some(gas_wallet) = app_state.wallet.lock().unwrap().clone() else {
+                return Err(anyhow!("Mutable param wallet not configured yet!"));
+            };
+
+            let request_chain_client = build_request_chain_client(
+                self.chain_connector.as_ref(),
+                &gas_wallet,
+                chain_id,
+                &request_chain_data,
+            )
+            .await?;
+            self.request_chain_clients
+                .write()
+                .unwrap()
+                .insert(chain_id, request_chain_client);
+        }
+        self.start_request_chain_listener(chain_id);
+
+        let request_chain_senders = self.request_chain_senders.lock().unwrap().clone();
+        if let Some(request_chain_senders) = request_chain_senders {
+            self.spawn_service(process_historic_subscription_jobs_of_request_chain(
+                Arc::clone(self),
+                chain_id,
+                request_chain_senders.req_chain_tx,
+                request_chain_senders.job_subscription_tx,
+            ));
+        }
+
+        info!("Request Chain ID: {:?} added", chain_id);
+        Ok(true)
+    }
+
+    /// Runs the client along with the listener of the request chains added to and removed from
+    /// the gateway and of its deregistration.
+    pub fn start(self: Arc<Self>, app_state: Data<AppState>) {
+        let self_clone = Arc::clone(&self);
+        self.spawn_service(async move {
+            self_clone.gateway_updates_listener(app_state).await;
+        });
+        let self_clone = Arc::clone(&self);
+        self.spawn_service(async move {
+            let _ = self_clone.run().await;
+        });
+    }
+
+    // Listens for the ChainAdded, ChainRemoved and GatewayDeregistered events of the enclave on
+    // the common chain
+    async fn gateway_updates_listener(self: Arc<Self>, app_state: Data<AppState>) {
+        let gateway_updates_filter = Filter::new()
+            .address(self.gateways_contract_address)
+            .topic0(vec![
+                ChainAddedFilter::signature(),
+                ChainRemovedFilter::signature(),
+                CommonChainGatewayDeregisteredFilter::signature(),
+            ])
+            .topic1(self.enclave_address);
+
+        loop {
+            let common_chain_ws_provider = match self
+                .chain_connector
+                .connect_pubsub(&self.common_chain_ws_url)
+                .await
+            {
+                Ok(common_chain_ws_provider) => common_chain_ws_provider,
+                Err(err) => {
+                    error!(
+                        "Failed to connect to the common chain websocket provider: {:#}",
+                        err
+                    );
+                    sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            let mut stream = match common_chain_ws_provider
+                .subscribe_logs(&gateway_updates_filter)
+                .await
+            {
+                Ok(stream) => stream,
+                Err(err) => {
+                    error!(
+                        "Failed to subscribe to the gateway updates on the Common Chain: {}",
+                        err
+                    );
+                    sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            // Catch up with the updates missed before subscribing
+            if let Err(err) = self.sync_request_chains(&app_state, self.as_ref()).await {
+                error!(
+                    "Failed to sync the request chains of the gateway: {:?}",
+                    err
+                );
+            }
+
+            while let Some(log) = stream.next().await {
+                if log.removed.unwrap_or(true) {
+                    continue;
+                }
+
+                self.handle_gateway_update(&app_state, log, self.as_ref())
+                    .await;
+            }
+        }
+    }
+
+    async fn handle_gateway_update<V: GatewaysView>(
+        self: &Arc<Self>,
+        app_state: &Data<AppState>,
+        log: Log,
+        gateways_view: &V,
+    ) {
+        let Ok(topic0) = topic(&log, 0) else {
+            error!("Log without topics: {:?}", log);
+            return;
+        };
+
+        if topic0 == ChainAddedFilter::signature() {
+            let chain_added_event = match decode_event::<ChainAddedFilter>(&log) {
+                Ok(chain_added_event) => chain_added_event,
+                Err(err) => {
+                    error!("Error while decoding ChainAdded event: {}", err);
+                    return;
+                }
+            };
+            if chain_added_event.enclave_address != self.enclave_address {
+                return;
+            }
+
+            let Ok(chain_id) = uint_to_u64(chain_added_event.chain_id) else {
+                error!(
+                    "Request Chain ID out of range in ChainAdded event: {:?}",
+                    log
+                );
+                return;
+            };
+            info!("ChainAdded Request Chain ID: {:?}", chain_id);
+            match self
+                .add_request_chain(app_state, chain_id, gateways_view)
+                .await
+            {
+                Ok(true) => {
+                    app_state.request_chain_ids.lock().unwrap().insert(chain_id);
+                    persist_sealed_state(app_state).await;
+                }
+                Ok(false) => {}
+                Err(err) => error!(
+                    "Failed to add the Request Chain ID: {:?}: {:?}",
+                    chain_id, err
+                ),
+            }
+        } else if topic0 == ChainRemovedFilter::signature() {
+            let chain_removed_event = match decode_event::<ChainRemovedFilter>(&log) {
+                Ok(chain_removed_event) => chain_removed_event,
+                Err(err) => {
+                    error!("Error while decoding ChainRemoved event: {}", err);
+                    return;
+                }
+            };
+            if chain_removed_event.enclave_address != self.enclave_address {
+                return;
+            }
+
+            let Ok(chain_id) = uint_to_u64(chain_removed_event.chain_id) else {
+                error!(
+                    "Request Chain ID out of range in ChainRemoved event: {:?}",
+                    log
+                );
+                return;
+            };
+            info!("ChainRemoved Request Chain ID: {:?}", chain_id);
+            if self.remove_request_chain(chain_id).await {
+                app_state
+                    .request_chain_ids
+                    .lock()
+                    .unwrap()
+                    .remove(&chain_id);
+                persist_sealed_state(app_state).await;
+            }
+        } else if topic0 == CommonChainGatewayDeregisteredFilter::signature() {
+            match decode_event::<CommonChainGatewayDeregisteredFilter>(&log) {
+                Ok(gateway_deregistered_event)
+                    if gateway_deregistered_event.enclave_address == self.enclave_address => {}
+                _ => {
+                    error!("Invalid GatewayDeregistered event: {:?}", log);
+                    return;
+                }
+            }
+
+            // The deregistration requested through the admin server tears the gateway down itself
+            if app_state.deregistering.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            info!("Gateway deregistered on the Common Chain by the owner");
+            // Torn down from another task as the teardown aborts this listener
+            let app_state = app_state.clone();
+            tokio::spawn(async move {
+                teardown_deregistered_gateway(&app_state).await;
+            });
+        } else {
+            error!("Unknown event: {:?}", log);
+        }
+    }
+
+    // Serves the request chains the gateway is registered with on the common chain
+    async fn sync_request_chains<V: GatewaysView>(
+        self: &Arc<Self>,
+        app_state: &AppState,
+        gateways_view: &V,
+    ) -> anyhow::Result<()> {
+        let chain_ids = gateways_view
+            .gateway_chain_ids(self.enclave_address)
+            .await
+            .context("Failed to fetch the request chains of the gateway from the common chain")?;
+        let served_chain_ids = self.request_chain_ids();
+
+        // A request chain failing to be added doesn't hold off the others, it is added again by
+        // the next sync
+        for &chain_id in chain_ids.difference(&served_chain_ids) {
+            if let Err(err) = self
+                .add_request_chain(app_state, chain_id, gateways_view)
+                .await
+            {
+                error!(
+                    "Failed to add the Request Chain ID: {:?}: {:?}",
+                    chain_id, err
+                );
+            }
+        }
+        for &chain_id in served_chain_ids.difference(&chain_ids) {
+            self.remove_request_chain(chain_id).await;
+        }
+
+        *app_state.request_chain_ids.lock().unwrap() = self.request_chain_ids();
+        persist_sealed_state(app_state).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod request_chain_management_tests {
+    use ethers::abi::{encode, Token};
+    use tokio::task::yield_now;
+    use tokio::time::timeout;
+
+    use super::*;
+    use crate::model::{GatewayJobType, Job, JobKey, JobMode};
+    use crate::test_util::{
+        generate_app_state_with_contracts_client, generate_contracts_client, relay_job_id,
+        MockGatewaysView, CHAIN_ID, GAS_WALLET_PUBLIC_ADDRESS, RELAY_CONTRACT_ADDR,
+    };
+
+    fn generate_chain_update_log(signature: H256, enclave_address: Address, chain_id: u64) -> Log {
+        Log {
+            topics: vec![signature, H256::from(enclave_address)],
+            data: encode(&[Token::Uint(U256::from(chain_id))]).into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_request_chain() {
+        let (app_state, contracts_client) = generate_app_state_with_contracts_client().await;
+        let gateways_view = MockGatewaysView {
+            chain_ids: [CHAIN_ID, CHAIN_ID + 1].into(),
+            ..Default::default()
+        };
+
+        // The chain is already served
+        assert!(!contracts_client
+            .add_request_chain(&app_state, CHAIN_ID, &gateways_view)
+            .await
+            .unwrap());
+
+        // The chain is not known to the common chain
+        assert!(contracts_client
+            .add_request_chain(&app_state, CHAIN_ID + 2, &gateways_view)
+            .await
+            .is_err());
+        assert!(contracts_client
+            .request_chain_client(CHAIN_ID + 2)
+            .is_none());
+
+        // The gas wallet is not configured
+        let gas_wallet = app_state.wallet.lock().unwrap().take();
+        assert!(contracts_client
+            .add_request_chain(&app_state, CHAIN_ID + 1, &gateways_view)
+            .await
+            .is_err());
+        assert!(contracts_client
+            .request_chain_client(CHAIN_ID + 1)
+            .is_none());
+        *app_state.wallet.lock().unwrap() = gas_wallet;
+
+        assert!(contracts_client
+            .add_request_chain(&app_state, CHAIN_ID + 1, &gateways_view)
+            .await
+            .unwrap());
+        assert_eq!(
+            contracts_client.request_chain_ids(),
+            [CHAIN_ID, CHAIN_ID + 1].into()
+        );
+
+        let request_chain_client = contracts_client.request_chain_client(CHAIN_ID + 1).unwrap();
+        assert_eq!(request_chain_client.chain_id, CHAIN_ID + 1);
+        assert_eq!(
+            request_chain_client.relay_address,
+            RELAY_CONTRACT_ADDR.parse::<Address>().unwrap()
+        );
+        let signer = request_chain_client.http_rpc_client.inner().signer();
+        assert_eq!(signer.chain_id(), CHAIN_ID + 1);
+        assert_eq!(
+            signer.address(),
+            GAS_WALLET_PUBLIC_ADDRESS.parse::<Address>().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_gateway_update_chain_added_and_removed() {
+        let (app_state, contracts_client) = generate_app_state_with_contracts_client().await;
+        let gateways_view = MockGatewaysView {
+            chain_ids: [CHAIN_ID, CHAIN_ID + 1].into(),
+            ..Default::default()
+        };
+        let enclave_address = contracts_client.enclave_address;
+
+        // Chain added to another gateway
+        contracts_client
+            .handle_gateway_update(
+                &app_state,
+                generate_chain_update_log(
+                    ChainAddedFilter::signature(),
+                    Address::random(),
+                    CHAIN_ID + 1,
+                ),
+                &gateways_view,
+            )
+            .await;
+        assert_eq!(contracts_client.request_chain_ids(), [CHAIN_ID].into());
+
+        contracts_client
+            .handle_gateway_update(
+                &app_state,
+                generate_chain_update_log(
+                    ChainAddedFilter::signature(),
+                    enclave_address,
+                    CHAIN_ID + 1,
+                ),
+                &gateways_view,
+            )
+            .await;
+        assert_eq!(
+            contracts_client.request_chain_ids(),
+            [CHAIN_ID, CHAIN_ID + 1].into()
+        );
+        assert!(app_state
+            .request_chain_ids
+            .lock()
+            .unwrap()
+            .contains(&(CHAIN_ID + 1)));
+
+        // Chain removed from another gateway
+        contracts_client
+            .handle_gateway_update(
+                &app_state,
+                generate_chain_update_log(
+                    ChainRemovedFilter::signature(),
+                    Address::random(),
+                    CHAIN_ID + 1,
+                ),
+                &gateways_view,
+            )
+            .await;
+        assert_eq!(
+            contracts_client.request_chain_ids(),
+            [CHAIN_ID, CHAIN_ID + 1].into()
+        );
+
+        contracts_client
+            .handle_gateway_update(
+                &app_state,
+                generate_chain_update_log(
+                    ChainRemovedFilter::signature(),
+                    enclave_address,
+                    CHAIN_ID + 1,
+                ),
+                &gateways_view,
+            )
+            .await;
+        assert_eq!(contracts_client.request_chain_ids(), [CHAIN_ID].into());
+        assert!(!app_state
+            .request_chain_ids
+            .lock()
+            .unwrap()
+            .contains(&(CHAIN_ID + 1)));
+
+        // Malformed ChainAdded event
+        contracts_client
+            .handle_gateway_update(
+                &app_state,
+                Log {
+                    topics: vec![ChainAddedFilter::signature(), H256::from(enclave_address)],
+                    ..Default::default()
+                },
+                &gateways_view,
+            )
+            .await;
+        assert_eq!(contracts_client.request_chain_ids(), [CHAIN_ID].into());
+    }
+
+    #[tokio::test]
+    async fn test_handle_gateway_update_gateway_deregistered() {
+        let (app_state, contracts_client) = generate_app_state_with_contracts_client().await;
+        let gateways_view = MockGatewaysView {
+            chain_ids: [CHAIN_ID].into(),
+            ..Default::default()
+        };
+        app_state.registered.store(true, Ordering::SeqCst);
+
+        // Another gateway deregistered
+        contracts_client
+            .handle_gateway_update(
+                &app_state,
+                Log {
+                    topics: vec![
+                        CommonChainGatewayDeregisteredFilter::signature(),
+                        H256::from(Address::random()),
+                    ],
+                    ..Default::default()
+                },
+                &gateways_view,
+            )
+            .await;
+        assert!(!app_state.deregistering.load(Ordering::SeqCst));
+
+        // A transaction in flight from the gas wallet
+        let txn_permit = contracts_client.gas_wallet_lock.clone().read_owned().await;
+
+        contracts_client
+            .handle_gateway_update(
+                &app_state,
+                Log {
+                    topics: vec![
+                        CommonChainGatewayDeregisteredFilter::signature(),
+                        H256::from(contracts_client.enclave_address),
+                    ],
+                    ..Default::default()
+                },
+                &gateways_view,
+            )
+            .await;
+        assert!(app_state.deregistering.load(Ordering::SeqCst));
+
+        // The gateway is only torn down once the transaction is settled
+        let torn_down = || app_state.contracts_client.lock().unwrap().is_none();
+        assert!(timeout(Duration::from_millis(500), async {
+            while !torn_down() {
+                yield_now().await;
+            }
+        })
+        .await
+        .is_err());
+        assert!(app_state.registered.load(Ordering::SeqCst));
+
+        drop(txn_permit);
+        timeout(Duration::from_secs(5), async {
+            while !torn_down() {
+                yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(!app_state.registered.load(Ordering::SeqCst));
+        assert!(!app_state.deregistering.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_sync_request_chains() {
+        let (app_state, contracts_client) = generate_app_state_with_contracts_client().await;
+
+        // The gateway got the request chain swapped while not listening
+        let gateways_view = MockGatewaysView {
+            chain_ids: [CHAIN_ID + 1].into(),
+            ..Default::default()
+        };
+        contracts_client
+            .sync_request_chains(&app_state, &gateways_view)
+            .await
+            .unwrap();
+        assert_eq!(contracts_client.request_chain_ids(), [CHAIN_ID + 1].into());
+        assert_eq!(
+            *app_state.request_chain_ids.lock().unwrap(),
+            [CHAIN_ID + 1].into()
+        );
+
+        // Already in sync
+        contracts_client
+            .sync_request_chains(&app_state, &gateways_view)
+            .await
+            .unwrap();
+        assert_eq!(contracts_client.request_chain_ids(), [CHAIN_ID + 1].into());
+
+        // The request chain that failed to be added is left for the next sync, the others are
+        // still reconciled
+        let gateways_view = MockGatewaysView {
+            chain_ids: [CHAIN_ID, CHAIN_ID + 2].into(),
+            unavailable_chain_ids: [CHAIN_ID + 2].into(),
+        };
+        contracts_client
+            .sync_request_chains(&app_state, &gateways_view)
+            .await
+            .unwrap();
+        assert_eq!(contracts_client.request_chain_ids(), [CHAIN_ID].into());
+        assert_eq!(
+            *app_state.request_chain_ids.lock().unwrap(),
+            [CHAIN_ID].into()
+        );
+
+        let gateways_view = MockGatewaysView {
+            chain_ids: [CHAIN_ID, CHAIN_ID + 1].into(),
+            ..Default::default()
+        };
+        contracts_client
+            .sync_request_chains(&app_state, &gateways_view)
+            .await
+            .unwrap();
+        assert_eq!(
+            contracts_client.request_chain_ids(),
+            [CHAIN_ID, CHAIN_ID + 1].into()
+        );
+        assert_eq!(
+            *app_state.request_chain_ids.lock().unwrap(),
+            [CHAIN_ID, CHAIN_ID + 1].into()
+        );
+    }
+
+    fn generate_job(request_chain_id: u64) -> Job {
+        Job {
+            job_id: relay_job_id(request_chain_id, 1),
+            instance_index: 0,
+            request_chain_id,
+            tx_hash: vec![0u8; 32],
+            code_input: Bytes::default(),
+            user_timeout: U256::from(2000),
+            starttime: U256::from(1700000000),
+            job_owner: Address::random(),
+            job_type: GatewayJobType::JobRelay,
+            sequence_number: 1,
+            gateway_address: None,
+            job_mode: JobMode::Once,
+            env: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remove_request_chain() {
+        let contracts_client = generate_contracts_client().await;
+
+        assert_eq!(contracts_client.request_chain_ids(), [CHAIN_ID].into());
+        assert!(contracts_client.request_chain_client(CHAIN_ID).is_some());
+
+        // Jobs in progress on the removed chain and on another chain
+        let job = generate_job(CHAIN_ID);
+        let other_chain_job = generate_job(CHAIN_ID + 1);
+        for jobs in [
+            &contracts_client.active_jobs,
+            &contracts_client.current_jobs,
+        ] {
+            let mut jobs = jobs.write().unwrap();
+            jobs.insert(job.key(), job.clone());
+            jobs.insert(other_chain_job.key(), other_chain_job.clone());
+        }
+
+        assert!(contracts_client.remove_request_chain(CHAIN_ID).await);
+        assert!(contracts_client.request_chain_ids().is_empty());
+        assert!(contracts_client.request_chain_client(CHAIN_ID).is_none());
+        assert!(contracts_client
+            .request_chain_listeners
+            .lock()
+            .unwrap()
+            .is_empty());
+        for jobs in [
+            &contracts_client.active_jobs,
+            &contracts_client.current_jobs,
+        ] {
+            let jobs = jobs.read().unwrap();
+            assert_eq!(
+                jobs.keys().copied().collect::<Vec<JobKey>>(),
+                vec![other_chain_job.key()]
+            );
+        }
+
+        assert!(!contracts_client.remove_request_chain(CHAIN_ID).await);
+    }
+}
@@ -0,0 +1,702 @@
+use crate::error::ServerlessError;
+use crate::model::{ScheduleSpec, SubscriptionJob};
+
+const SECONDS_PER_MINUTE: u64 = 60;
+const SECONDS_PER_HOUR: u64 = 3600;
+const SECONDS_PER_DAY: u64 = 86400;
+
+// The weekdays of the calendar repeat every 28 years between the century years, so a cron
+// expression that matches no day within it never matches (e.g. `0 0 30 2 *`)
+const MAX_CRON_SEARCH_DAYS: u64 = 28 * 366;
+
+/// Trigger times of the instances of a subscription, from its start time up to its
+/// termination time (both inclusive).
+pub trait Schedule: Send + Sync {
+    fn starttime(&self) -> u64;
+
+    fn termination_time(&self) -> u64;
+
+    /// Earliest trigger time at or after the timestamp (and the start time), regardless of
+    /// the termination time.
+    fn earliest_trigger_time_from(&self, timestamp: u64) -> Option<u64>;
+
+    fn trigger_time_from(&self, timestamp: u64) -> Option<u64> {
+        self.earliest_trigger_time_from(timestamp)
+            .filter(|&trigger_time| trigger_time <= self.termination_time())
+    }
+
+    fn first_trigger_time(&self) -> Option<u64> {
+        self.trigger_time_from(self.starttime())
+    }
+
+    /// Trigger time of the instance following the one triggered at the previous trigger time.
+    fn next_trigger_time(&self, previous_trigger_time: u64) -> Option<u64> {
+        self.trigger_time_from(previous_trigger_time.checked_add(1)?)
+    }
+
+    /// Trigger times from the `from` timestamp up to the `until` timestamp (both inclusive).
+    fn trigger_times_between(&self, from: u64, until: u64) -> Vec<u64> {
+        let mut trigger_times = Vec::new();
+        let mut trigger_time = self.trigger_time_from(from);
+        while let Some(instance_trigger_time) = trigger_time {
+            if instance_trigger_time > until {
+                break;
+            }
+            trigger_times.push(instance_trigger_time);
+            trigger_time = self.next_trigger_time(instance_trigger_time);
+        }
+        trigger_times
+    }
+
+    /// Trigger times of the next `num_instances` instances from the timestamp.
+    fn upcoming_trigger_times(&self, from: u64, num_instances: usize) -> Vec<u64> {
+        let mut trigger_times = Vec::new();
+        let mut trigger_time = self.trigger_time_from(from);
+        while let Some(instance_trigger_time) = trigger_time {
+            if trigger_times.len() >= num_instances {
+                break;
+            }
+            trigger_times.push(instance_trigger_time);
+            trigger_time = self.next_trigger_time(instance_trigger_time);
+        }
+        trigger_times
+    }
+
+    /// Trigger time of the latest instance at or before the timestamp.
+    fn latest_trigger_time_until(&self, timestamp: u64) -> Option<u64> {
+        self.trigger_times_between(self.starttime(), timestamp)
+            .last()
+            .copied()
+    }
+
+    /// Index of the latest instance triggered at or before the trigger time.
+    fn instance_index(&self, trigger_time: u64) -> u64 {
+        (self
+            .trigger_times_between(self.starttime(), trigger_time)
+            .len() as u64)
+            .saturating_sub(1)
+    }
+
+    fn trigger_time_of_instance(&self, instance_index: u64) -> Option<u64> {
+        let mut trigger_time = self.first_trigger_time();
+        for _ in 0..instance_index {
+            trigger_time = self.next_trigger_time(trigger_time?);
+        }
+        trigger_time
+    }
+}
+
+/// Schedule of the subscriptions started on the request chains, an instance every `interval`
+/// seconds from the start time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedIntervalSchedule {
+    pub starttime: u64,
+    pub interval: u64,
+    pub termination_time: u64,
+}
+
+impl Schedule for FixedIntervalSchedule {
+    fn starttime(&self) -> u64 {
+        self.starttime
+    }
+
+    fn termination_time(&self) -> u64 {
+        self.termination_time
+    }
+
+    fn earliest_trigger_time_from(&self, timestamp: u64) -> Option<u64> {
+        if timestamp <= self.starttime {
+            return Some(self.starttime);
+        }
+        if self.interval == 0 {
+            return None;
+        }
+        self.starttime.checked_add(
+            (timestamp - self.starttime)
+                .div_ceil(self.interval)
+                .checked_mul(self.interval)?,
+        )
+    }
+
+    fn next_trigger_time(&self, previous_trigger_time: u64) -> Option<u64> {
+        if self.interval == 0 {
+            return None;
+        }
+        previous_trigger_time
+            .checked_add(self.interval)
+            .filter(|&trigger_time| trigger_time <= self.termination_time)
+    }
+
+    fn latest_trigger_time_until(&self, timestamp: u64) -> Option<u64> {
+        let until = timestamp.min(self.termination_time);
+        if until < self.starttime {
+            return None;
+        }
+        self.trigger_time_of_instance(self.instance_index(until))
+    }
+
+    fn instance_index(&self, trigger_time: u64) -> u64 {
+        if self.interval == 0 {
+            return 0;
+        }
+        trigger_time.saturating_sub(self.starttime) / self.interval
+    }
+
+    fn trigger_time_of_instance(&self, instance_index: u64) -> Option<u64> {
+        self.starttime
+            .checked_add(instance_index.checked_mul(self.interval)?)
+            .filter(|&trigger_time| trigger_time <= self.termination_time)
+    }
+}
+
+/// Schedule given by a cron expression of five fields (`minute hour day-of-month month
+/// day-of-week`) evaluated in UTC.
+///
+/// Each field takes `*`, values, ranges (`1-5`) and steps (`*/15`, `10-40/10`) separated by
+/// commas. As with cron, a day matches either of the day of month and the day of week fields
+/// when both of them are restricted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    pub starttime: u64,
+    pub termination_time: u64,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn new(
+        expression: &str,
+        starttime: u64,
+        termination_time: u64,
+    ) -> Result<Self, ServerlessError> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(ServerlessError::InvalidSchedule(format!(
+                "Cron expression must have 5 fields, found {}",
+                fields.len()
+            )));
+        };
+
+        // Sunday is both 0 and 7
+        let mut days_of_week_mask = parse_cron_field(days_of_week, 0, 7)?;
+        if days_of_week_mask & (1 << 7) != 0 {
+            days_of_week_mask = (days_of_week_mask | 1) & !(1 << 7);
+        }
+
+        Ok(CronSchedule {
+            starttime,
+            termination_time,
+            minutes: parse_cron_field(minutes, 0, 59)?,
+            hours: parse_cron_field(hours, 0, 23)?,
+            days_of_month: parse_cron_field(days_of_month, 1, 31)?,
+            months: parse_cron_field(months, 1, 12)?,
+            days_of_week: days_of_week_mask,
+            days_of_month_restricted: !days_of_month.starts_with('*'),
+            days_of_week_restricted: !days_of_week.starts_with('*'),
+        })
+    }
+
+    fn matches_day(&self, days: u64) -> bool {
+        let (_, month, day) = civil_from_days(days);
+        // 1970-01-01 was a Thursday
+        let day_of_week = (days + 4) % 7;
+
+        let day_of_month_matches = self.days_of_month & (1 << day) != 0;
+        let day_of_week_matches = self.days_of_week & (1 << day_of_week) != 0;
+        let day_matches = if self.days_of_month_restricted && self.days_of_week_restricted {
+            day_of_month_matches || day_of_week_matches
+        } else {
+            day_of_month_matches && day_of_week_matches
+        };
+
+        self.months & (1 << month) != 0 && day_matches
+    }
+
+    // Earliest matching second of the day at or after the given second of the day
+    fn time_of_day_from(&self, second_of_day: u64) -> Option<u64> {
+        let start_hour = second_of_day / SECONDS_PER_HOUR;
+        let start_minute = (second_of_day % SECONDS_PER_HOUR).div_ceil(SECONDS_PER_MINUTE);
+
+        for hour in start_hour..24 {
+            if self.hours & (1 << hour) == 0 {
+                continue;
+            }
+            let from_minute = if hour == start_hour { start_minute } else { 0 };
+            if let Some(minute) = (from_minute..60).find(|minute| self.minutes & (1 << minute) != 0)
+            {
+                return Some(hour * SECONDS_PER_HOUR + minute * SECONDS_PER_MINUTE);
+            }
+        }
+        None
+    }
+}
+
+impl Schedule for CronSchedule {
+    fn starttime(&self) -> u64 {
+        self.starttime
+    }
+
+    fn termination_time(&self) -> u64 {
+        self.termination_time
+    }
+
+    fn earliest_trigger_time_from(&self, timestamp: u64) -> Option<u64> {
+        let from = timestamp.max(self.starttime);
+        let from_day = from / SECONDS_PER_DAY;
+
+        for days in from_day..from_day + MAX_CRON_SEARCH_DAYS {
+            let day_start = days.checked_mul(SECONDS_PER_DAY)?;
+            if day_start > self.termination_time {
+                return None;
+            }
+            if !self.matches_day(days) {
+                continue;
+            }
+
+            let second_of_day = if days == from_day {
+                from % SECONDS_PER_DAY
+            } else {
+                0
+            };
+            if let Some(time_of_day) = self.time_of_day_from(second_of_day) {
+                return Some(day_start + time_of_day);
+            }
+        }
+        None
+    }
+}
+
+// Bit mask of the values of a cron field within the range `min..=max`
+fn parse_cron_field(field: &str, min: u64, max: u64) -> Result<u64, ServerlessError> {
+    let invalid_field =
+        || ServerlessError::InvalidSchedule(format!("Invalid cron field: {}", field));
+    let parse_value = |value: &str| -> Result<u64, ServerlessError> {
+        let value = value.parse::<u64>().map_err(|_| invalid_field())?;
+        if value < min || value > max {
+            return Err(ServerlessError::InvalidSchedule(format!(
+                "Cron field value {} is out of the range {}-{}",
+                value, min, max
+            )));
+        }
+        Ok(value)
+    };
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                Some(step.parse::<u64>().map_err(|_| invalid_field())?),
+            ),
+            None => (part, None),
+        };
+        if step == Some(0) {
+            return Err(invalid_field());
+        }
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start)?, parse_value(end)?)
+        } else {
+            let start = parse_value(range)?;
+            // `10/15` runs from 10 up to the maximum value
+            (start, if step.is_some() { max } else { start })
+        };
+        if start > end {
+            return Err(invalid_field());
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// Schedule of an instance every `every_months` months from the month of the start time, on
+/// the day of month at the time of day (UTC).
+///
+/// The days of month past the end of a shorter month fall on its last day, so that
+/// `day_of_month: 31` triggers on the last day of every month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarSchedule {
+    pub starttime: u64,
+    pub termination_time: u64,
+    pub every_months: u64,
+    pub day_of_month: u64,
+    pub second_of_day: u64,
+}
+
+impl CalendarSchedule {
+    fn trigger_time_in_month(&self, month_index: u64) -> Option<u64> {
+        let year = month_index / 12;
+        let month = month_index % 12 + 1;
+        let day = self.day_of_month.min(days_in_month(year, month));
+
+        days_from_civil(year, month, day)?
+            .checked_mul(SECONDS_PER_DAY)?
+            .checked_add(self.second_of_day)
+    }
+}
+
+impl Schedule for CalendarSchedule {
+    fn starttime(&self) -> u64 {
+        self.starttime
+    }
+
+    fn termination_time(&self) -> u64 {
+        self.termination_time
+    }
+
+    fn earliest_trigger_time_from(&self, timestamp: u64) -> Option<u64> {
+        let from = timestamp.max(self.starttime);
+        let start_month_index = month_index(self.starttime);
+        let elapsed_months = month_index(from) - start_month_index;
+
+        // The instance of the month of the timestamp may be before it
+        let mut period = elapsed_months.div_ceil(self.every_months);
+        for _ in 0..2 {
+            let trigger_time = self.trigger_time_in_month(
+                start_month_index.checked_add(period.checked_mul(self.every_months)?)?,
+            )?;
+            if trigger_time >= from {
+                return Some(trigger_time);
+            }
+            period += 1;
+        }
+        None
+    }
+}
+
+impl SubscriptionJob {
+    /// Schedule of the instances of the subscription job.
+    pub fn schedule(&self) -> FixedIntervalSchedule {
+        FixedIntervalSchedule {
+            starttime: self.starttime.as_u64(),
+            interval: self.interval.as_u64(),
+            termination_time: self.termination_time.as_u64(),
+        }
+    }
+}
+
+impl ScheduleSpec {
+    /// Builds the schedule between the start and termination times, checking that it has at
+    /// least one instance.
+    pub fn build(
+        &self,
+        starttime: u64,
+        termination_time: u64,
+    ) -> Result<Box<dyn Schedule>, ServerlessError> {
+        if termination_time < starttime {
+            return Err(ServerlessError::InvalidSchedule(
+                "Termination time is before the start time".to_owned(),
+            ));
+        }
+
+        let schedule: Box<dyn Schedule> = match self {
+            ScheduleSpec::FixedInterval { interval } => {
+                if *interval == 0 {
+                    return Err(ServerlessError::InvalidSchedule(
+                        "Interval must be greater than zero".to_owned(),
+                    ));
+                }
+                Box::new(FixedIntervalSchedule {
+                    starttime,
+                    interval: *interval,
+                    termination_time,
+                })
+            }
+            ScheduleSpec::Cron { expression } => {
+                Box::new(CronSchedule::new(expression, starttime, termination_time)?)
+            }
+            ScheduleSpec::Calendar {
+                every_months,
+                day_of_month,
+                hour,
+                minute,
+                second,
+            } => {
+                if *every_months == 0 {
+                    return Err(ServerlessError::InvalidSchedule(
+                        "Every months must be greater than zero".to_owned(),
+                    ));
+                }
+                if !(1..=31).contains(day_of_month) || *hour > 23 || *minute > 59 || *second > 59 {
+                    return Err(ServerlessError::InvalidSchedule(
+                        "Invalid day of month or time of day".to_owned(),
+                    ));
+                }
+                Box::new(CalendarSchedule {
+                    starttime,
+                    termination_time,
+                    every_months: *every_months,
+                    day_of_month: *day_of_month,
+                    second_of_day: hour * SECONDS_PER_HOUR + minute * SECONDS_PER_MINUTE + second,
+                })
+            }
+        };
+
+        if schedule.first_trigger_time().is_none() {
+            return Err(ServerlessError::InvalidSchedule(
+                "Schedule has no instances before the termination time".to_owned(),
+            ));
+        }
+        Ok(schedule)
+    }
+}
+
+// Months since year 0 of the month of the timestamp
+fn month_index(timestamp: u64) -> u64 {
+    let (year, month, _) = civil_from_days(timestamp / SECONDS_PER_DAY);
+    year * 12 + month - 1
+}
+
+fn is_leap_year(year: u64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+// Days since the unix epoch of the date in the proleptic Gregorian calendar, `None` before
+// the epoch
+fn days_from_civil(year: u64, month: u64, day: u64) -> Option<u64> {
+    // Years starting in March, so that the leap day is the last day of the year
+    let year = if month <= 2 {
+        year.checked_sub(1)?
+    } else {
+        year
+    };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    (era * 146097 + day_of_era).checked_sub(719468)
+}
+
+// Date in the proleptic Gregorian calendar of the days since the unix epoch
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod schedule_tests {
+    use super::*;
+
+    // 2024-01-01T00:00:00Z, a Monday
+    const JAN_1_2024: u64 = 1_704_067_200;
+
+    fn cron(expression: &str) -> CronSchedule {
+        CronSchedule::new(expression, JAN_1_2024, JAN_1_2024 + 400 * SECONDS_PER_DAY).unwrap()
+    }
+
+    #[test]
+    fn test_civil_date_conversion() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(days_from_civil(1970, 1, 1), Some(0));
+        assert_eq!(civil_from_days(JAN_1_2024 / SECONDS_PER_DAY), (2024, 1, 1));
+        assert_eq!(
+            days_from_civil(2024, 2, 29),
+            Some(JAN_1_2024 / SECONDS_PER_DAY + 59)
+        );
+        assert_eq!(days_from_civil(1969, 12, 31), None);
+
+        for days in (0..100_000).step_by(17) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), Some(days));
+        }
+    }
+
+    #[test]
+    fn test_fixed_interval_schedule() {
+        let schedule = FixedIntervalSchedule {
+            starttime: 1000,
+            interval: 10,
+            termination_time: 1040,
+        };
+
+        assert_eq!(schedule.first_trigger_time(), Some(1000));
+        assert_eq!(schedule.next_trigger_time(1030), Some(1040));
+        assert_eq!(schedule.next_trigger_time(1040), None);
+        assert_eq!(schedule.trigger_time_from(1015), Some(1020));
+        assert_eq!(schedule.trigger_time_from(1041), None);
+        assert_eq!(
+            schedule.trigger_times_between(900, 1025),
+            vec![1000, 1010, 1020]
+        );
+        assert_eq!(schedule.latest_trigger_time_until(1035), Some(1030));
+        assert_eq!(schedule.latest_trigger_time_until(2000), Some(1040));
+        assert_eq!(schedule.latest_trigger_time_until(999), None);
+        assert_eq!(schedule.instance_index(1038), 3);
+        assert_eq!(schedule.trigger_time_of_instance(3), Some(1030));
+        assert_eq!(schedule.trigger_time_of_instance(5), None);
+    }
+
+    #[test]
+    fn test_cron_schedule() {
+        // Every 15 minutes
+        assert_eq!(
+            cron("*/15 * * * *").upcoming_trigger_times(JAN_1_2024 + 1, 3),
+            vec![JAN_1_2024 + 900, JAN_1_2024 + 1800, JAN_1_2024 + 2700]
+        );
+
+        // 09:30 on weekdays, from Friday 2024-01-05
+        assert_eq!(
+            cron("30 9 * * 1-5").upcoming_trigger_times(JAN_1_2024 + 4 * SECONDS_PER_DAY, 2),
+            vec![
+                JAN_1_2024 + 4 * SECONDS_PER_DAY + 9 * SECONDS_PER_HOUR + 1800,
+                JAN_1_2024 + 7 * SECONDS_PER_DAY + 9 * SECONDS_PER_HOUR + 1800,
+            ]
+        );
+
+        // Midnight on the 29th of February
+        assert_eq!(
+            cron("0 0 29 2 *").first_trigger_time(),
+            Some(JAN_1_2024 + 59 * SECONDS_PER_DAY)
+        );
+    }
+
+    #[test]
+    fn test_cron_schedule_day_of_month_or_day_of_week() {
+        // Midnight on the 15th and on Sundays, 2024-01-07 is the first Sunday
+        assert_eq!(
+            cron("0 0 15 * 0").upcoming_trigger_times(JAN_1_2024, 3),
+            vec![
+                JAN_1_2024 + 6 * SECONDS_PER_DAY,
+                JAN_1_2024 + 13 * SECONDS_PER_DAY,
+                JAN_1_2024 + 14 * SECONDS_PER_DAY,
+            ]
+        );
+        // Sunday as 7
+        assert_eq!(
+            cron("0 0 * * 7").first_trigger_time(),
+            Some(JAN_1_2024 + 6 * SECONDS_PER_DAY)
+        );
+    }
+
+    #[test]
+    fn test_cron_schedule_invalid_expression() {
+        for expression in [
+            "* * * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(matches!(
+                CronSchedule::new(expression, JAN_1_2024, JAN_1_2024 + 1000),
+                Err(ServerlessError::InvalidSchedule(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_calendar_schedule() {
+        // Last day of every month at 12:00
+        let schedule = CalendarSchedule {
+            starttime: JAN_1_2024,
+            termination_time: JAN_1_2024 + 400 * SECONDS_PER_DAY,
+            every_months: 1,
+            day_of_month: 31,
+            second_of_day: 12 * SECONDS_PER_HOUR,
+        };
+
+        assert_eq!(
+            schedule.upcoming_trigger_times(JAN_1_2024, 3),
+            vec![
+                JAN_1_2024 + 30 * SECONDS_PER_DAY + 12 * SECONDS_PER_HOUR,
+                JAN_1_2024 + 59 * SECONDS_PER_DAY + 12 * SECONDS_PER_HOUR,
+                JAN_1_2024 + 90 * SECONDS_PER_DAY + 12 * SECONDS_PER_HOUR,
+            ]
+        );
+
+        // Quarterly on the 1st, the instance of the start month is before the start time
+        let schedule = CalendarSchedule {
+            starttime: JAN_1_2024 + SECONDS_PER_DAY,
+            termination_time: JAN_1_2024 + 400 * SECONDS_PER_DAY,
+            every_months: 3,
+            day_of_month: 1,
+            second_of_day: 0,
+        };
+
+        assert_eq!(
+            schedule.upcoming_trigger_times(0, 2),
+            vec![
+                days_from_civil(2024, 4, 1).unwrap() * SECONDS_PER_DAY,
+                days_from_civil(2024, 7, 1).unwrap() * SECONDS_PER_DAY,
+            ]
+        );
+        assert_eq!(
+            schedule.instance_index(JAN_1_2024 + 200 * SECONDS_PER_DAY),
+            1
+        );
+    }
+
+    #[test]
+    fn test_schedule_spec_build() {
+        let schedule = ScheduleSpec::FixedInterval { interval: 10 }
+            .build(1000, 1040)
+            .unwrap();
+        assert_eq!(
+            schedule.upcoming_trigger_times(0, 10),
+            vec![1000, 1010, 1020, 1030, 1040]
+        );
+
+        assert!(matches!(
+            ScheduleSpec::FixedInterval { interval: 0 }.build(1000, 1040),
+            Err(ServerlessError::InvalidSchedule(_))
+        ));
+        assert!(matches!(
+            ScheduleSpec::FixedInterval { interval: 10 }.build(1040, 1000),
+            Err(ServerlessError::InvalidSchedule(_))
+        ));
+        // The 30th of February never comes
+        assert!(matches!(
+            ScheduleSpec::Cron {
+                expression: "0 0 30 2 *".to_owned()
+            }
+            .build(JAN_1_2024, JAN_1_2024 + 3000 * SECONDS_PER_DAY),
+            Err(ServerlessError::InvalidSchedule(_))
+        ));
+        assert!(matches!(
+            ScheduleSpec::Calendar {
+                every_months: 1,
+                day_of_month: 32,
+                hour: 0,
+                minute: 0,
+                second: 0,
+            }
+            .build(JAN_1_2024, JAN_1_2024 + 1000),
+            Err(ServerlessError::InvalidSchedule(_))
+        ));
+    }
+}
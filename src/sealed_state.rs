@@ -0,0 +1,297 @@
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use actix_web::web::Data;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, Context};
+use ethers::signers::{LocalWallet, Signer};
+use log::{error, info};
+use tokio::fs;
+
+use crate::api_impl::build_contracts_client;
+use crate::enclave_signer::EnclaveSigner;
+use crate::model::{AppState, GasWallet, RemoteSigner, SealedGasWallet, SealedState};
+
+// Length of the AES-GCM nonce prepended to the sealed state
+const NONCE_LEN: usize = 12;
+
+impl SealedState {
+    /// Snapshot of the configuration and the registration status of the gateway.
+    pub fn from_app_state(app_state: &AppState) -> Self {
+        let enclave_owner = if *app_state.immutable_params_injected.lock().unwrap() {
+            Some(*app_state.enclave_owner.lock().unwrap())
+        } else {
+            None
+        };
+
+        let gas_wallet =
+            app_state
+                .wallet
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|gas_wallet| match gas_wallet {
+                    GasWallet::Local(wallet) => SealedGasWallet::Local {
+                        gas_key_hex: hex::encode(wallet.signer().to_bytes()),
+                    },
+                    GasWallet::Remote(remote_signer) => SealedGasWallet::Remote {
+                        url: remote_signer.url.clone(),
+                        address: remote_signer.address,
+                    },
+                });
+
+        SealedState {
+            enclave_owner,
+            gas_wallet,
+            mutable_config_nonce: *app_state.mutable_config_nonce.lock().unwrap(),
+            request_chain_ids: app_state
+                .request_chain_ids
+                .lock()
+                .unwrap()
+                .iter()
+                .copied()
+                .collect(),
+            registered: app_state.registered.load(Ordering::SeqCst),
+        }
+    }
+
+    pub fn seal(&self, enclave_signer: &dyn EnclaveSigner) -> anyhow::Result<Vec<u8>> {
+        let plaintext = serde_json::to_vec(self)?;
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&enclave_signer.sealing_key()));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|err| anyhow!("Failed to seal the state: {}", err))?;
+
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    pub fn unseal(sealed: &[u8], enclave_signer: &dyn EnclaveSigner) -> anyhow::Result<Self> {
+        if sealed.len() < NONCE_LEN {
+            return Err(anyhow!("Sealed state too short"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&enclave_signer.sealing_key()));
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                anyhow!("Failed to unseal the state, was it sealed by another enclave key?")
+            })?;
+
+        serde_json::from_slice(&plaintext).context("Invalid sealed state")
+    }
+
+    /// Reads the sealed state from the file, if any.
+    pub async fn load(
+        path: &str,
+        enclave_signer: &dyn EnclaveSigner,
+    ) -> anyhow::Result<Option<Self>> {
+        let sealed = match fs::read(path).await {
+            Ok(sealed) => sealed,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).context("Failed to read the sealed state"),
+        };
+
+        Self::unseal(&sealed, enclave_signer).map(Some)
+    }
+
+    pub async fn store(
+        &self,
+        path: &str,
+        enclave_signer: &dyn EnclaveSigner,
+    ) -> anyhow::Result<()> {
+        // Write to a temporary file first so that a crash never leaves a partial state behind
+        let temp_path = format!("{}.tmp", path);
+        fs::write(&temp_path, self.seal(enclave_signer)?)
+            .await
+            .context("Failed to write the sealed state")?;
+        fs::rename(&temp_path, path)
+            .await
+            .context("Failed to replace the sealed state")
+    }
+}
+
+/// Persists the current state of the gateway if a sealed state file is configured, failures are
+/// only logged as the gateway keeps running with its in-memory state.
+pub async fn persist_sealed_state(app_state: &AppState) {
+    let Some(sealed_state_file) = app_state.sealed_state_file.as_ref() else {
+        return;
+    };
+
+    let sealed_state = SealedState::from_app_state(app_state);
+    if let Err(err) = sealed_state
+        .store(sealed_state_file, app_state.enclave_signer.as_ref())
+        .await
+    {
+        error!("Failed to persist the sealed state: {:?}", err);
+    }
+}
+
+/// Restores the state persisted before the restart and resumes the gateway if it was registered.
+pub async fn restore_sealed_state(
+    app_state: Data<AppState>,
+    sealed_state: SealedState,
+) -> anyhow::Result<()> {
+    if let Some(enclave_owner) = sealed_state.enclave_owner {
+        *app_state.enclave_owner.lock().unwrap() = enclave_owner;
+        *app_state.immutable_params_injected.lock().unwrap() = true;
+    }
+    *app_state.mutable_config_nonce.lock().unwrap() = sealed_state.mutable_config_nonce;
+
+    // The gas wallet configured for startup takes precedence over the injected one
+    let gas_wallet_configured = app_state.wallet.lock().unwrap().is_some();
+    if let (false, Some(sealed_gas_wallet)) =
+        (gas_wallet_configured, sealed_state.gas_wallet.as_ref())
+    {
+        let gas_wallet = match sealed_gas_wallet {
+            SealedGasWallet::Local { gas_key_hex } => GasWallet::Local(
+                LocalWallet::from_bytes(&hex::decode(gas_key_hex)?)?
+                    .with_chain_id(app_state.common_chain_id),
+            ),
+            SealedGasWallet::Remote { url, address } => GasWallet::Remote(
+                RemoteSigner::connect(url, *address)
+                    .await?
+                    .with_chain_id(app_state.common_chain_id),
+            ),
+        };
+        *app_state.wallet.lock().unwrap() = Some(gas_wallet);
+        app_state
+            .mutable_params_injected
+            .store(true, Ordering::SeqCst);
+    }
+
+    let request_chain_ids: HashSet<u64> = sealed_state.request_chain_ids.iter().copied().collect();
+    *app_state.request_chain_ids.lock().unwrap() = request_chain_ids.clone();
+
+    if !sealed_state.registered {
+        return Ok(());
+    }
+    let Some(enclave_owner) = sealed_state.enclave_owner else {
+        return Err(anyhow!("Sealed state registered without an enclave owner"));
+    };
+
+    let contracts_client =
+        build_contracts_client(&app_state, enclave_owner, &request_chain_ids).await?;
+    *app_state.contracts_client.lock().unwrap() = Some(Arc::clone(&contracts_client));
+    *app_state
+        .registration_events_listener_active
+        .lock()
+        .unwrap() = true;
+
+    if contracts_client
+        .registered_on_chain()
+        .await
+        .context("Failed to check the registration on chain")?
+    {
+        info!("Gateway already registered, resuming the contracts client");
+        app_state.registered.store(true, Ordering::SeqCst);
+        contracts_client.start(app_state);
+    } else {
+        // Registered before the restart but not anymore, wait for the registration again
+        info!("Gateway no longer registered on chain, waiting for the registration");
+        app_state.registered.store(false, Ordering::SeqCst);
+        tokio::spawn(async move {
+            Arc::clone(&contracts_client)
+                .wait_for_registration(app_state.clone())
+                .await;
+            contracts_client.start(app_state);
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod sealed_state_tests {
+    use ethers::types::H160;
+
+    use super::*;
+    use crate::enclave_signer::InMemoryEnclaveSigner;
+    use crate::test_util::{generate_app_state, CHAIN_ID, GAS_WALLET_KEY, OWNER_ADDRESS};
+
+    fn sealed_state() -> SealedState {
+        SealedState {
+            enclave_owner: Some(OWNER_ADDRESS.parse().unwrap()),
+            gas_wallet: Some(SealedGasWallet::Local {
+                gas_key_hex: GAS_WALLET_KEY.to_owned(),
+            }),
+            mutable_config_nonce: 3,
+            request_chain_ids: [CHAIN_ID].into(),
+            registered: false,
+        }
+    }
+
+    #[test]
+    fn test_seal_and_unseal() {
+        let enclave_signer = InMemoryEnclaveSigner::generate();
+
+        let sealed = sealed_state().seal(&enclave_signer).unwrap();
+
+        assert_eq!(
+            SealedState::unseal(&sealed, &enclave_signer).unwrap(),
+            sealed_state()
+        );
+        // Nothing of the state is readable without the enclave key
+        assert!(!String::from_utf8_lossy(&sealed).contains(GAS_WALLET_KEY));
+        assert!(SealedState::unseal(&sealed, &InMemoryEnclaveSigner::generate()).is_err());
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(SealedState::unseal(&tampered, &enclave_signer).is_err());
+        assert!(SealedState::unseal(&sealed[0..NONCE_LEN - 1], &enclave_signer).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_store_and_load() {
+        let enclave_signer = InMemoryEnclaveSigner::generate();
+        let path = std::env::temp_dir().join(format!("sealed_state_{}", rand::random::<u64>()));
+        let path = path.to_str().unwrap();
+
+        assert_eq!(
+            SealedState::load(path, &enclave_signer).await.unwrap(),
+            None
+        );
+
+        sealed_state().store(path, &enclave_signer).await.unwrap();
+        assert_eq!(
+            SealedState::load(path, &enclave_signer).await.unwrap(),
+            Some(sealed_state())
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restore_sealed_state() {
+        let app_state = generate_app_state().await;
+
+        restore_sealed_state(app_state.clone(), sealed_state())
+            .await
+            .unwrap();
+
+        assert!(*app_state.immutable_params_injected.lock().unwrap());
+        assert_eq!(
+            *app_state.enclave_owner.lock().unwrap(),
+            OWNER_ADDRESS.parse::<H160>().unwrap()
+        );
+        assert!(app_state.mutable_params_injected.load(Ordering::SeqCst));
+        assert_eq!(
+            app_state.wallet.lock().unwrap().as_ref().unwrap().address(),
+            GAS_WALLET_KEY.parse::<LocalWallet>().unwrap().address()
+        );
+        assert_eq!(*app_state.mutable_config_nonce.lock().unwrap(), 3);
+        assert_eq!(
+            *app_state.request_chain_ids.lock().unwrap(),
+            [CHAIN_ID].into()
+        );
+        assert!(!app_state.registered.load(Ordering::SeqCst));
+        assert!(app_state.contracts_client.lock().unwrap().is_none());
+
+        // The snapshot of the restored state is the sealed state
+        assert_eq!(SealedState::from_app_state(&app_state), sealed_state());
+    }
+}
@@ -0,0 +1,614 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use ethers::contract::ContractCall;
+use ethers::prelude::*;
+use ethers::providers::Provider;
+use log::{error, info};
+use tokio::fs;
+
+use crate::contract_abi::{gateways_contract, relay_contract, GatewaysContract, RelayContract};
+use crate::model::{
+    AppState, ConfigManager, GasWallet, OwnerSignerConfig, RegisterGatewayResponse,
+    RegistrationAttestation, RegistrationTarget, RegistrationTxnResult, RegistrationTxnStatus,
+    RemoteSigner, SignedRegistrationBody, SignedRegistrationResponse,
+};
+
+pub type OwnerProviderType = SignerMiddleware<Provider<Http>, GasWallet>;
+
+impl OwnerSignerConfig {
+    /// Sets up the signer of the owner, connecting to the remote signer if configured.
+    pub async fn connect(&self) -> anyhow::Result<GasWallet> {
+        match self {
+            OwnerSignerConfig::Local { owner_key_hex } => Ok(GasWallet::Local(
+                owner_key_hex
+                    .trim_start_matches("0x")
+                    .parse::<LocalWallet>()
+                    .context("Invalid owner key")?,
+            )),
+            OwnerSignerConfig::Remote { url, address } => Ok(GasWallet::Remote(
+                RemoteSigner::connect(url, *address).await?,
+            )),
+        }
+    }
+}
+
+impl RegisterGatewayResponse {
+    // Whether the enclave is registered on the common chain and every request chain
+    pub fn registered(&self) -> bool {
+        std::iter::once(&self.common_chain)
+            .chain(self.request_chains.iter())
+            .all(|result| result.status != RegistrationTxnStatus::Failed)
+    }
+}
+
+impl RegistrationTxnResult {
+    fn failed(chain_id: u64, txn_hash: Option<H256>, err: anyhow::Error) -> Self {
+        error!(
+            "Failed to register the gateway on the chain {}: {:#}",
+            chain_id, err
+        );
+        RegistrationTxnResult {
+            chain_id,
+            status: RegistrationTxnStatus::Failed,
+            txn_hash,
+            error: Some(format!("{:#}", err)),
+        }
+    }
+}
+
+/// Submits the registration of the enclave on the common chain and each request chain with the
+/// transactions of the owner, skipping the chains it is already registered on. Every transaction
+/// is tracked until it is confirmed or fails, independently of the other chains.
+pub async fn submit_registration(
+    target: &RegistrationTarget,
+    owner_signer: GasWallet,
+    signed_registration: &SignedRegistrationResponse,
+    attestation: &RegistrationAttestation,
+    stake_amount: U256,
+) -> RegisterGatewayResponse {
+    let common_chain = register_on_common_chain(
+        target,
+        owner_signer.clone(),
+        signed_registration,
+        attestation,
+        stake_amount,
+    )
+    .await
+    .unwrap_or_else(|err| RegistrationTxnResult::failed(target.common_chain_id, None, err));
+
+    // The request chains are registered on in the order of the chain ids signed for the common
+    // chain
+    let mut request_chains = Vec::new();
+    for &chain_id in signed_registration.chain_ids.iter() {
+        let result = register_on_request_chain(
+            target,
+            owner_signer.clone(),
+            chain_id,
+            signed_registration,
+            attestation,
+        )
+        .await
+        .unwrap_or_else(|err| RegistrationTxnResult::failed(chain_id, None, err));
+        request_chains.push(result);
+    }
+
+    RegisterGatewayResponse {
+        common_chain,
+        request_chains,
+    }
+}
+
+async fn register_on_common_chain(
+    target: &RegistrationTarget,
+    owner_signer: GasWallet,
+    signed_registration: &SignedRegistrationResponse,
+    attestation: &RegistrationAttestation,
+    stake_amount: U256,
+) -> anyhow::Result<RegistrationTxnResult> {
+    let chain_id = target.common_chain_id;
+    let gateways_contract = GatewaysContract::new(
+        target.gateways_contract_addr,
+        owner_http_rpc_client(&target.common_chain_http_url, owner_signer, chain_id)?,
+    );
+
+    let (owner, _, _, _) = gateways_contract
+        .gateways(target.enclave_address)
+        .await
+        .context("Failed to fetch the gateway from the common chain")?;
+    if owner == signed_registration.owner {
+        return Ok(already_registered(chain_id));
+    }
+
+    let call = gateways_contract.register_gateway(
+        attestation.attestation_signature.clone(),
+        gateways_contract::Attestation {
+            enclave_pub_key: attestation.enclave_pub_key.clone(),
+            pcr0: attestation.pcr0.clone(),
+            pcr1: attestation.pcr1.clone(),
+            pcr2: attestation.pcr2.clone(),
+            timestamp_in_milliseconds: attestation.timestamp_in_milliseconds.into(),
+        },
+        signed_registration
+            .chain_ids
+            .iter()
+            .map(|&chain_id| chain_id.into())
+            .collect(),
+        decode_signature(&signed_registration.common_chain_signature)?,
+        stake_amount,
+        signed_registration.sign_timestamp.into(),
+    );
+
+    Ok(send_registration_txn(chain_id, call).await)
+}
+
+async fn register_on_request_chain(
+    target: &RegistrationTarget,
+    owner_signer: GasWallet,
+    chain_id: u64,
+    signed_registration: &SignedRegistrationResponse,
+    attestation: &RegistrationAttestation,
+) -> anyhow::Result<RegistrationTxnResult> {
+    let common_chain_http_rpc_client = Provider::<Http>::try_from(&target.common_chain_http_url)
+        .context(format!(
+            "Failed to connect to the common chain http rpc server {}",
+            target.common_chain_http_url
+        ))?;
+    let (relay_address, _, http_rpc_url, _) = GatewaysContract::new(
+        target.gateways_contract_addr,
+        Arc::new(common_chain_http_rpc_client),
+    )
+    .request_chains(U256::from(chain_id))
+    .await
+    .context(format!(
+        "Failed to fetch the request chain data for chain id {}",
+        chain_id
+    ))?;
+
+    let relay_contract = RelayContract::new(
+        relay_address,
+        owner_http_rpc_client(&http_rpc_url, owner_signer, chain_id)?,
+    );
+
+    let owner = relay_contract
+        .gateway_owners(target.enclave_address)
+        .await
+        .context("Failed to fetch the gateway owner from the request chain")?;
+    if owner == signed_registration.owner {
+        return Ok(already_registered(chain_id));
+    }
+
+    let call = relay_contract.register_gateway(
+        attestation.attestation_signature.clone(),
+        relay_contract::Attestation {
+            enclave_pub_key: attestation.enclave_pub_key.clone(),
+            pcr0: attestation.pcr0.clone(),
+            pcr1: attestation.pcr1.clone(),
+            pcr2: attestation.pcr2.clone(),
+            timestamp_in_milliseconds: attestation.timestamp_in_milliseconds.into(),
+        },
+        decode_signature(&signed_registration.request_chain_signature)?,
+        signed_registration.sign_timestamp.into(),
+    );
+
+    Ok(send_registration_txn(chain_id, call).await)
+}
+
+pub fn owner_http_rpc_client(
+    http_rpc_url: &str,
+    owner_signer: GasWallet,
+    chain_id: u64,
+) -> anyhow::Result<Arc<OwnerProviderType>> {
+    let http_rpc_client = Provider::<Http>::try_from(http_rpc_url).context(format!(
+        "Failed to connect to the http rpc server {}",
+        http_rpc_url
+    ))?;
+
+    Ok(Arc::new(SignerMiddleware::new(
+        http_rpc_client,
+        owner_signer.with_chain_id(chain_id),
+    )))
+}
+
+fn decode_signature(signature_hex: &str) -> anyhow::Result<Bytes> {
+    Ok(hex::decode(signature_hex.trim_start_matches("0x"))
+        .context("Failed to hex decode the registration signature")?
+        .into())
+}
+
+fn already_registered(chain_id: u64) -> RegistrationTxnResult {
+    info!("Gateway already registered on the chain {}", chain_id);
+    RegistrationTxnResult {
+        chain_id,
+        status: RegistrationTxnStatus::AlreadyRegistered,
+        txn_hash: None,
+        error: None,
+    }
+}
+
+// Sends the registration transaction and waits for its receipt
+async fn send_registration_txn(
+    chain_id: u64,
+    call: ContractCall<OwnerProviderType, ()>,
+) -> RegistrationTxnResult {
+    let pending_txn = match call.send().await {
+        Ok(pending_txn) => pending_txn,
+        Err(err) => {
+            return RegistrationTxnResult::failed(
+                chain_id,
+                None,
+                anyhow!("Failed to send the registration transaction: {}", err),
+            );
+        }
+    };
+    let txn_hash = pending_txn.tx_hash();
+    info!(
+        "Registration transaction {:?} sent on the chain {}",
+        txn_hash, chain_id
+    );
+
+    match pending_txn.await {
+        Ok(Some(receipt)) if receipt.status == Some(U64::one()) => {
+            info!("Gateway registered on the chain {}", chain_id);
+            RegistrationTxnResult {
+                chain_id,
+                status: RegistrationTxnStatus::Confirmed,
+                txn_hash: Some(txn_hash),
+                error: None,
+            }
+        }
+        Ok(Some(_)) => RegistrationTxnResult::failed(
+            chain_id,
+            Some(txn_hash),
+            anyhow!("Registration transaction reverted"),
+        ),
+        Ok(None) => RegistrationTxnResult::failed(
+            chain_id,
+            Some(txn_hash),
+            anyhow!("Registration transaction dropped from the mempool"),
+        ),
+        Err(err) => RegistrationTxnResult::failed(
+            chain_id,
+            Some(txn_hash),
+            anyhow!("Failed to confirm the registration transaction: {}", err),
+        ),
+    }
+}
+
+// Signer of the owner transactions submitted from this machine by the subcommands
+#[derive(Args)]
+pub struct OwnerSignerArgs {
+    // File holding the hex encoded key of the owner
+    #[clap(long, value_parser, conflicts_with = "owner_signer_url")]
+    owner_key_file: Option<String>,
+    // Remote signer holding the key of the owner address
+    #[clap(long, value_parser, requires = "owner_address")]
+    owner_signer_url: Option<String>,
+    #[clap(long, value_parser)]
+    owner_address: Option<H160>,
+}
+
+impl OwnerSignerArgs {
+    /// Sets up the signer of the owner from the key file or the remote signer.
+    pub async fn connect(self) -> anyhow::Result<GasWallet> {
+        match (
+            self.owner_key_file,
+            self.owner_signer_url,
+            self.owner_address,
+        ) {
+            (Some(owner_key_file), _, _) => OwnerSignerConfig::Local {
+                owner_key_hex: fs::read_to_string(&owner_key_file)
+                    .await
+                    .context("Failed to read the owner key file")?
+                    .trim()
+                    .to_owned(),
+            },
+            (None, Some(url), Some(address)) => OwnerSignerConfig::Remote { url, address },
+            _ => {
+                return Err(anyhow!(
+                    "Either the owner key file or the owner remote signer must be provided"
+                ))
+            }
+        }
+        .connect()
+        .await
+    }
+}
+
+// Arguments of the 'register' subcommand, registering a running gateway with the transactions of
+// its owner signed on this machine
+#[derive(Args)]
+pub struct RegisterArgs {
+    // Url of the admin server of the gateway
+    #[clap(long, value_parser, default_value = "http://127.0.0.1:6002")]
+    admin_url: String,
+    #[clap(long, value_parser)]
+    admin_auth_token: Option<String>,
+    #[clap(long, value_parser, value_delimiter = ',', required = true)]
+    chain_ids: Vec<u64>,
+    #[clap(flatten)]
+    owner_signer: OwnerSignerArgs,
+    // JSON file with the attestation of the enclave signed by the attestation verifier
+    #[clap(long, value_parser)]
+    attestation_file: String,
+    // Amount of tokens staked by the owner, in the smallest unit of the staking token
+    #[clap(long, value_parser = U256::from_dec_str, default_value = "0")]
+    stake_amount: U256,
+}
+
+impl From<&AppState> for RegistrationTarget {
+    fn from(app_state: &AppState) -> Self {
+        RegistrationTarget {
+            enclave_address: app_state.enclave_address,
+            common_chain_id: app_state.common_chain_id,
+            common_chain_http_url: app_state.common_chain_http_url.clone(),
+            gateways_contract_addr: app_state.gateways_contract_addr,
+        }
+    }
+}
+
+impl RegisterArgs {
+    /// Fetches the signed registration message from the admin server, submits the registration
+    /// transactions of the owner from here and prints the result of every chain. The owner key
+    /// never leaves this machine.
+    pub async fn run(self, config_file: &String) -> anyhow::Result<()> {
+        let config = ConfigManager::new(config_file)
+            .load_config()
+            .context("Failed to load the gateway config")?;
+
+        let owner_signer = self.owner_signer.connect().await?;
+
+        let attestation: RegistrationAttestation = serde_json::from_slice(
+            &fs::read(&self.attestation_file)
+                .await
+                .context("Failed to read the attestation file")?,
+        )
+        .context("Invalid attestation file")?;
+
+        let mut request = reqwest::Client::new()
+            .get(format!(
+                "{}/signed-registration-message",
+                self.admin_url.trim_end_matches('/')
+            ))
+            .json(&SignedRegistrationBody {
+                chain_ids: self.chain_ids,
+            });
+        if let Some(admin_auth_token) = self.admin_auth_token {
+            request = request.bearer_auth(admin_auth_token);
+        }
+        let response = request
+            .send()
+            .await
+            .context("Failed to fetch the signed registration message from the gateway")?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        let Ok(signed_registration) = serde_json::from_str::<SignedRegistrationResponse>(&body)
+        else {
+            return Err(anyhow!(
+                "Signed registration message request failed ({}): {}",
+                status,
+                body
+            ));
+        };
+
+        if owner_signer.address() != signed_registration.owner {
+            return Err(anyhow!(
+                "Owner signer address {:?} does not match the enclave owner {:?}",
+                owner_signer.address(),
+                signed_registration.owner
+            ));
+        }
+
+        let register_gateway_response = submit_registration(
+            &RegistrationTarget {
+                enclave_address: signed_registration.enclave_address,
+                common_chain_id: config.common_chain_id,
+                common_chain_http_url: config.common_chain_http_url,
+                gateways_contract_addr: config.gateways_contract_addr,
+            },
+            owner_signer,
+            &signed_registration,
+            &attestation,
+            self.stake_amount,
+        )
+        .await;
+
+        for result in std::iter::once(&register_gateway_response.common_chain)
+            .chain(register_gateway_response.request_chains.iter())
+        {
+            println!(
+                "Chain {}: {:?}{}{}",
+                result.chain_id,
+                result.status,
+                result
+                    .txn_hash
+                    .map(|txn_hash| format!(", transaction {:?}", txn_hash))
+                    .unwrap_or_default(),
+                result
+                    .error
+                    .as_ref()
+                    .map(|err| format!(", {}", err))
+                    .unwrap_or_default(),
+            );
+        }
+
+        if !register_gateway_response.registered() {
+            return Err(anyhow!("Gateway not registered on every chain"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod self_registration_tests {
+    use super::*;
+    use crate::api_impl::{normalize_chain_ids, sign_registration};
+    use crate::model::RegisterGatewayBody;
+    use crate::test_util::{generate_app_state, CHAIN_ID, OWNER_ADDRESS, OWNER_KEY};
+    use crate::typed_data::{verify_typed_data, GatewaysRegisterMessage};
+
+    fn txn_result(chain_id: u64, status: RegistrationTxnStatus) -> RegistrationTxnResult {
+        RegistrationTxnResult {
+            chain_id,
+            status,
+            txn_hash: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_registered() {
+        let mut response = RegisterGatewayResponse {
+            common_chain: txn_result(1, RegistrationTxnStatus::Confirmed),
+            request_chains: vec![
+                txn_result(2, RegistrationTxnStatus::AlreadyRegistered),
+                txn_result(3, RegistrationTxnStatus::Confirmed),
+            ],
+        };
+        assert!(response.registered());
+
+        response.request_chains[1].status = RegistrationTxnStatus::Failed;
+        assert!(!response.registered());
+
+        response.request_chains[1].status = RegistrationTxnStatus::Confirmed;
+        response.common_chain.status = RegistrationTxnStatus::Failed;
+        assert!(!response.registered());
+    }
+
+    #[tokio::test]
+    async fn test_submit_registration_signed_chain_ids() {
+        let app_state = generate_app_state().await;
+        let owner = OWNER_ADDRESS.parse::<H160>().unwrap();
+
+        let signed_registration =
+            sign_registration(&app_state, owner, &normalize_chain_ids(vec![2, 1, 1])).unwrap();
+        assert_eq!(signed_registration.chain_ids, vec![1, 2]);
+        assert!(verify_typed_data(
+            &app_state.eip712_domains.gateways,
+            &GatewaysRegisterMessage {
+                owner,
+                chain_ids: signed_registration
+                    .chain_ids
+                    .iter()
+                    .map(|&chain_id| chain_id.into())
+                    .collect(),
+                sign_timestamp: signed_registration.sign_timestamp as u64,
+            },
+            &hex::decode(&signed_registration.common_chain_signature).unwrap(),
+            app_state.enclave_address,
+        ));
+
+        // No chain is reachable, every registration fails after being tried
+        let register_gateway_response = submit_registration(
+            &RegistrationTarget {
+                enclave_address: app_state.enclave_address,
+                common_chain_id: CHAIN_ID,
+                common_chain_http_url: "http://127.0.0.1:1".to_owned(),
+                gateways_contract_addr: H160::random(),
+            },
+            OwnerSignerConfig::Local {
+                owner_key_hex: OWNER_KEY.to_owned(),
+            }
+            .connect()
+            .await
+            .unwrap(),
+            &signed_registration,
+            &RegistrationAttestation {
+                attestation_signature: Bytes::default(),
+                enclave_pub_key: Bytes::default(),
+                pcr0: Bytes::default(),
+                pcr1: Bytes::default(),
+                pcr2: Bytes::default(),
+                timestamp_in_milliseconds: 0,
+            },
+            U256::zero(),
+        )
+        .await;
+        assert!(!register_gateway_response.registered());
+        assert_eq!(
+            register_gateway_response
+                .request_chains
+                .iter()
+                .map(|result| result.chain_id)
+                .collect::<Vec<u64>>(),
+            signed_registration.chain_ids
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect_local_owner_signer() {
+        let owner_signer = OwnerSignerConfig::Local {
+            owner_key_hex: format!("0x{}", OWNER_KEY),
+        }
+        .connect()
+        .await
+        .unwrap();
+        assert_eq!(owner_signer.address(), OWNER_ADDRESS.parse().unwrap());
+
+        assert!(OwnerSignerConfig::Local {
+            owner_key_hex: "zz".to_owned(),
+        }
+        .connect()
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect_owner_signer_args() {
+        let owner_key_file =
+            std::env::temp_dir().join(format!("owner_key_{}", rand::random::<u64>()));
+        std::fs::write(&owner_key_file, format!("{}\n", OWNER_KEY)).unwrap();
+
+        let owner_signer = OwnerSignerArgs {
+            owner_key_file: Some(owner_key_file.to_str().unwrap().to_owned()),
+            owner_signer_url: None,
+            owner_address: None,
+        }
+        .connect()
+        .await
+        .unwrap();
+        assert_eq!(owner_signer.address(), OWNER_ADDRESS.parse().unwrap());
+        std::fs::remove_file(&owner_key_file).unwrap();
+
+        // Neither the owner key file nor the remote signer
+        assert!(OwnerSignerArgs {
+            owner_key_file: None,
+            owner_signer_url: None,
+            owner_address: None,
+        }
+        .connect()
+        .await
+        .is_err());
+    }
+
+    #[test]
+    fn test_register_gateway_body() {
+        let register_gateway_body: RegisterGatewayBody =
+            serde_json::from_value(serde_json::json!({
+                "chain_ids": [1],
+                "owner_signer": {
+                    "type": "remote",
+                    "url": "http://127.0.0.1:9000",
+                    "address": OWNER_ADDRESS,
+                },
+                "attestation": {
+                    "attestation_signature": "0x01",
+                    "enclave_pub_key": "0x02",
+                    "pcr0": "0x03",
+                    "pcr1": "0x04",
+                    "pcr2": "0x05",
+                    "timestamp_in_milliseconds": 1718602200000u64,
+                },
+            }))
+            .unwrap();
+
+        assert_eq!(register_gateway_body.stake_amount, U256::zero());
+        assert_eq!(register_gateway_body.attestation.pcr0, Bytes::from(vec![3]));
+        assert!(matches!(
+            register_gateway_body.owner_signer,
+            OwnerSignerConfig::Remote { address, .. } if address == OWNER_ADDRESS.parse().unwrap()
+        ));
+    }
+}
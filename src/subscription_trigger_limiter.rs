@@ -0,0 +1,509 @@
+use anyhow::{anyhow, Result};
+use ethers::providers::Middleware;
+use log::info;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::Sender;
+use tokio::time;
+
+use crate::chain_util::ChainConnector;
+use crate::job_subscription_management::{
+    record_subscription_instance, relay_subscription_instance,
+};
+use crate::model::{
+    ContractsClient, Job, JobKey, PendingSubscriptionInstance, SubscriptionInstanceStatus,
+    SubscriptionJob, SubscriptionTriggerBatch, SubscriptionTriggerConfig,
+    SubscriptionTriggerLimiter, SubscriptionTriggerQueue, TokenBucket, TriggerRateLimit,
+};
+use crate::schedule::Schedule;
+
+// Minimum wait between the dispatches of a rate limited request chain, so that a rounded down
+// wait for the next token does not spin
+const MIN_DISPATCH_WAIT: Duration = Duration::from_millis(1);
+// Maximum wait for the next token, the dispatcher takes the ready instances again once it passes
+const MAX_DISPATCH_WAIT: Duration = Duration::from_secs(60);
+
+impl SubscriptionTriggerConfig {
+    /// Checks that the configured rates are finite and positive, checked when the config is
+    /// loaded.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(default_rate_limit) = self.default_rate_limit {
+            validate_instances_per_second(default_rate_limit.instances_per_second)
+                .map_err(|err| anyhow!("default_rate_limit: {}", err))?;
+        }
+        for chain_rate_limit in self.rate_limits.iter() {
+            validate_instances_per_second(chain_rate_limit.instances_per_second).map_err(
+                |err| {
+                    anyhow!(
+                        "rate limit of the chain {}: {}",
+                        chain_rate_limit.chain_id,
+                        err
+                    )
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_instances_per_second(instances_per_second: f64) -> Result<()> {
+    if !instances_per_second.is_finite() || instances_per_second <= 0.0 {
+        return Err(anyhow!(
+            "instances_per_second must be finite and above 0, got {}",
+            instances_per_second
+        ));
+    }
+    Ok(())
+}
+
+impl TokenBucket {
+    pub fn new(rate_limit: TriggerRateLimit, now: Instant) -> Self {
+        let capacity = rate_limit.burst.max(1) as f64;
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            refill_per_second: rate_limit.instances_per_second,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// Time until the next token is available, capped at `MAX_DISPATCH_WAIT`, `None` if the
+    /// bucket is never refilled.
+    pub fn time_until_available(&mut self, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            return Some(Duration::ZERO);
+        }
+        if self.refill_per_second <= 0.0 {
+            return None;
+        }
+        let wait = Duration::try_from_secs_f64((1.0 - self.tokens) / self.refill_per_second)
+            .unwrap_or(MAX_DISPATCH_WAIT);
+        Some(wait.min(MAX_DISPATCH_WAIT))
+    }
+}
+
+// Reverse ordering to make the BinaryHeap a min-heap on the deadline
+impl PartialEq for PendingSubscriptionInstance {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PendingSubscriptionInstance {}
+
+impl PartialOrd for PendingSubscriptionInstance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingSubscriptionInstance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (
+            other.deadline,
+            other.trigger_time,
+            other.subscription_job.key(),
+        )
+            .cmp(&(
+                self.deadline,
+                self.trigger_time,
+                self.subscription_job.key(),
+            ))
+    }
+}
+
+impl SubscriptionTriggerLimiter {
+    pub fn new(config: SubscriptionTriggerConfig) -> Self {
+        SubscriptionTriggerLimiter {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn rate_limit(&self, request_chain_id: u64) -> Option<TriggerRateLimit> {
+        self.config
+            .rate_limits
+            .iter()
+            .find(|chain_rate_limit| chain_rate_limit.chain_id == request_chain_id)
+            .map(|chain_rate_limit| TriggerRateLimit {
+                instances_per_second: chain_rate_limit.instances_per_second,
+                burst: chain_rate_limit.burst,
+            })
+            .or(self.config.default_rate_limit)
+    }
+
+    pub fn is_rate_limited(&self, request_chain_id: u64) -> bool {
+        self.rate_limit(request_chain_id).is_some()
+    }
+
+    /// Queues the instance triggered at the trigger time until a token of its request chain
+    /// is available. Instances of the request chains without a rate limit are not queued.
+    pub fn enqueue(&self, subscription_job: SubscriptionJob, trigger_time: u64) {
+        let Some(rate_limit) = self.rate_limit(subscription_job.request_chain_id) else {
+            return;
+        };
+
+        // Same deadline as the relay pipeline, so that no token goes to an instance dropped there
+        let deadline = subscription_job.instance_deadline(trigger_time);
+
+        // Scope for lock on queues
+        {
+            let mut queues = self.queues.lock().unwrap();
+            queues
+                .entry(subscription_job.request_chain_id)
+                .or_insert_with(|| SubscriptionTriggerQueue {
+                    bucket: TokenBucket::new(rate_limit, Instant::now()),
+                    pending: BinaryHeap::new(),
+                })
+                .pending
+                .push(PendingSubscriptionInstance {
+                    deadline,
+                    trigger_time,
+                    subscription_job,
+                });
+        }
+
+        self.notify.notify_one();
+    }
+
+    #[cfg(test)]
+    pub fn pending_len(&self) -> usize {
+        self.queues
+            .lock()
+            .unwrap()
+            .values()
+            .map(|queue| queue.pending.len())
+            .sum()
+    }
+
+    /// Takes the queued instances that got a token, the most urgent first, and the ones
+    /// whose deadline passed while they were waiting, along with the time until a token is
+    /// available for the rest.
+    pub fn take_ready(&self, now: Instant, current_timestamp: u64) -> SubscriptionTriggerBatch {
+        let mut batch = SubscriptionTriggerBatch::default();
+
+        let mut queues = self.queues.lock().unwrap();
+        for queue in queues.values_mut() {
+            while let Some(pending_instance) = queue.pending.peek() {
+                if pending_instance.deadline <= current_timestamp {
+                    batch.expired.push(queue.pending.pop().unwrap());
+                    continue;
+                }
+
+                if queue.bucket.try_acquire(now) {
+                    batch.ready.push(queue.pending.pop().unwrap());
+                    continue;
+                }
+
+                if let Some(wait) = queue.bucket.time_until_available(now) {
+                    let wait = wait.max(MIN_DISPATCH_WAIT);
+                    batch.wait = Some(batch.wait.map_or(wait, |batch_wait| batch_wait.min(wait)));
+                }
+                break;
+            }
+        }
+
+        batch
+    }
+}
+
+/// Hands over the instances queued by the rate limited request chains to the relay pipeline
+/// as the tokens of their request chains become available.
+pub async fn subscription_trigger_dispatcher<M: Middleware + 'static, C: ChainConnector<M>>(
+    contracts_client: Arc<ContractsClient<M, C>>,
+    req_chain_tx: Sender<Job>,
+) {
+    let subscription_trigger_limiter = contracts_client.subscription_trigger_limiter.clone();
+
+    loop {
+        let notified = subscription_trigger_limiter.notify.notified();
+
+        let batch =
+            subscription_trigger_limiter.take_ready(Instant::now(), contracts_client.clock.now());
+
+        for pending_instance in batch.expired {
+            let subscription_job = pending_instance.subscription_job;
+            info!(
+                "Subscription ID: {}, instance at {} passed its deadline while rate limited",
+                subscription_job.key(),
+                pending_instance.trigger_time
+            );
+            record_subscription_instance(
+                &contracts_client,
+                &JobKey::subscription_instance(
+                    subscription_job.request_chain_id,
+                    subscription_job.subscription_id,
+                    subscription_job
+                        .schedule()
+                        .instance_index(pending_instance.trigger_time),
+                ),
+                SubscriptionInstanceStatus::Skipped {
+                    reason: "Deadline passed while rate limited".to_owned(),
+                },
+            );
+        }
+
+        for pending_instance in batch.ready {
+            let contracts_client_clone = contracts_client.clone();
+            let req_chain_tx_clone = req_chain_tx.clone();
+            tokio::spawn(async move {
+                relay_subscription_instance(
+                    contracts_client_clone,
+                    pending_instance.subscription_job,
+                    pending_instance.trigger_time,
+                    req_chain_tx_clone,
+                )
+                .await;
+            });
+        }
+
+        match batch.wait {
+            Some(wait) => {
+                tokio::select! {
+                    _ = time::sleep(wait) => {}
+                    _ = notified => {}
+                }
+            }
+            None => notified.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod subscription_trigger_limiter_tests {
+    use ethers::types::U256;
+
+    use super::*;
+    use crate::constant::REQUEST_RELAY_TIMEOUT;
+    use crate::model::ChainTriggerRateLimit;
+    use crate::test_util::{generate_generic_subscription_job, job_subscription_id, CHAIN_ID};
+
+    fn subscription_job(
+        subscription_id: u64,
+        request_chain_id: u64,
+        user_timeout: u64,
+    ) -> SubscriptionJob {
+        let mut subscription_job = generate_generic_subscription_job(Some(subscription_id), None);
+        subscription_job.request_chain_id = request_chain_id;
+        subscription_job.user_timeout = U256::from(user_timeout);
+        subscription_job
+    }
+
+    fn rate_limited_config(instances_per_second: f64, burst: u64) -> SubscriptionTriggerConfig {
+        SubscriptionTriggerConfig {
+            jitter_ms: 0,
+            default_rate_limit: None,
+            rate_limits: vec![ChainTriggerRateLimit {
+                chain_id: CHAIN_ID,
+                instances_per_second,
+                burst,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(
+            TriggerRateLimit {
+                instances_per_second: 2.0,
+                burst: 2,
+            },
+            now,
+        );
+
+        assert!(bucket.try_acquire(now));
+        assert!(bucket.try_acquire(now));
+        assert!(!bucket.try_acquire(now));
+        assert_eq!(
+            bucket.time_until_available(now),
+            Some(Duration::from_millis(500))
+        );
+
+        // Refilled at 2 tokens per second, up to the burst
+        assert!(bucket.try_acquire(now + Duration::from_millis(500)));
+        assert!(!bucket.try_acquire(now + Duration::from_millis(500)));
+        assert!(bucket.try_acquire(now + Duration::from_secs(10)));
+        assert!(bucket.try_acquire(now + Duration::from_secs(10)));
+        assert!(!bucket.try_acquire(now + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_token_bucket_slow_refill() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(
+            TriggerRateLimit {
+                instances_per_second: f64::MIN_POSITIVE,
+                burst: 1,
+            },
+            now,
+        );
+
+        assert!(bucket.try_acquire(now));
+        assert_eq!(bucket.time_until_available(now), Some(MAX_DISPATCH_WAIT));
+    }
+
+    #[test]
+    fn test_subscription_trigger_config_validate() {
+        assert!(SubscriptionTriggerConfig::default().validate().is_ok());
+        assert!(rate_limited_config(0.5, 1).validate().is_ok());
+
+        for instances_per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(rate_limited_config(instances_per_second, 1)
+                .validate()
+                .is_err());
+
+            let config = SubscriptionTriggerConfig {
+                default_rate_limit: Some(TriggerRateLimit {
+                    instances_per_second,
+                    burst: 1,
+                }),
+                ..Default::default()
+            };
+            assert!(config.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let mut config = rate_limited_config(5.0, 10);
+        let subscription_trigger_limiter = SubscriptionTriggerLimiter::new(config.clone());
+
+        assert_eq!(
+            subscription_trigger_limiter.rate_limit(CHAIN_ID),
+            Some(TriggerRateLimit {
+                instances_per_second: 5.0,
+                burst: 10
+            })
+        );
+        assert!(!subscription_trigger_limiter.is_rate_limited(CHAIN_ID + 1));
+
+        config.default_rate_limit = Some(TriggerRateLimit {
+            instances_per_second: 1.0,
+            burst: 1,
+        });
+        let subscription_trigger_limiter = SubscriptionTriggerLimiter::new(config);
+
+        assert_eq!(
+            subscription_trigger_limiter.rate_limit(CHAIN_ID + 1),
+            Some(TriggerRateLimit {
+                instances_per_second: 1.0,
+                burst: 1
+            })
+        );
+
+        // Instances of the request chains without a rate limit are not queued
+        let subscription_trigger_limiter =
+            SubscriptionTriggerLimiter::new(rate_limited_config(5.0, 10));
+        subscription_trigger_limiter.enqueue(subscription_job(1, CHAIN_ID + 1, 10_000), 1000);
+
+        assert_eq!(subscription_trigger_limiter.pending_len(), 0);
+    }
+
+    #[test]
+    fn test_take_ready_by_deadline() {
+        let subscription_trigger_limiter =
+            SubscriptionTriggerLimiter::new(rate_limited_config(1.0, 2));
+        let now = Instant::now();
+
+        // Deadlines at 1030, 1005, 1020 and 1010
+        subscription_trigger_limiter.enqueue(subscription_job(1, CHAIN_ID, 30_000), 1000);
+        subscription_trigger_limiter.enqueue(subscription_job(2, CHAIN_ID, 5_000), 1000);
+        subscription_trigger_limiter.enqueue(subscription_job(3, CHAIN_ID, 20_000), 1000);
+        subscription_trigger_limiter.enqueue(subscription_job(4, CHAIN_ID, 10_000), 1000);
+
+        let batch = subscription_trigger_limiter.take_ready(now, 1001);
+
+        assert!(batch.expired.is_empty());
+        assert_eq!(
+            batch
+                .ready
+                .iter()
+                .map(|instance| instance.subscription_job.subscription_id)
+                .collect::<Vec<U256>>(),
+            vec![
+                job_subscription_id(CHAIN_ID, 2),
+                job_subscription_id(CHAIN_ID, 4)
+            ]
+        );
+        assert_eq!(batch.wait, Some(Duration::from_secs(1)));
+        assert_eq!(subscription_trigger_limiter.pending_len(), 2);
+
+        // Subscription 3 passes its deadline before the next token
+        let batch = subscription_trigger_limiter.take_ready(now + Duration::from_secs(1), 1020);
+
+        assert_eq!(batch.expired.len(), 1);
+        assert_eq!(
+            batch.expired[0].subscription_job.subscription_id,
+            job_subscription_id(CHAIN_ID, 3)
+        );
+        assert_eq!(batch.ready.len(), 1);
+        assert_eq!(
+            batch.ready[0].subscription_job.subscription_id,
+            job_subscription_id(CHAIN_ID, 1)
+        );
+        assert_eq!(batch.wait, None);
+        assert_eq!(subscription_trigger_limiter.pending_len(), 0);
+    }
+
+    #[test]
+    fn test_take_ready_past_relay_deadline() {
+        let subscription_trigger_limiter =
+            SubscriptionTriggerLimiter::new(rate_limited_config(1.0, 1));
+        let now = Instant::now();
+
+        // User timeout outlasting the window to relay the instance on the common chain
+        subscription_trigger_limiter.enqueue(subscription_job(1, CHAIN_ID, 200_000), 1000);
+
+        let batch = subscription_trigger_limiter.take_ready(now, 1000 + 2 * REQUEST_RELAY_TIMEOUT);
+
+        assert_eq!(batch.expired.len(), 1);
+        assert!(batch.ready.is_empty());
+        assert_eq!(subscription_trigger_limiter.pending_len(), 0);
+    }
+
+    #[test]
+    fn test_take_ready_per_request_chain() {
+        let mut config = rate_limited_config(1.0, 1);
+        config.rate_limits.push(ChainTriggerRateLimit {
+            chain_id: CHAIN_ID + 1,
+            instances_per_second: 1.0,
+            burst: 1,
+        });
+        let subscription_trigger_limiter = SubscriptionTriggerLimiter::new(config);
+
+        subscription_trigger_limiter.enqueue(subscription_job(1, CHAIN_ID, 30_000), 1000);
+        subscription_trigger_limiter.enqueue(subscription_job(2, CHAIN_ID, 30_000), 1000);
+        subscription_trigger_limiter.enqueue(subscription_job(3, CHAIN_ID + 1, 30_000), 1000);
+
+        // Each request chain has its own bucket
+        let batch = subscription_trigger_limiter.take_ready(Instant::now(), 1001);
+
+        assert_eq!(batch.ready.len(), 2);
+        assert!(batch
+            .ready
+            .iter()
+            .any(|instance| instance.subscription_job.request_chain_id == CHAIN_ID + 1));
+        assert_eq!(subscription_trigger_limiter.pending_len(), 1);
+    }
+}
@@ -8,31 +8,38 @@ use anyhow::Result;
 use ethers::abi::{encode, Token};
 use ethers::prelude::*;
 use ethers::types::{Address, Log, H160};
-use ethers::utils::{keccak256, public_key_to_address};
 use k256::ecdsa::SigningKey;
-use rand::rngs::OsRng;
 use serde_json::json;
 use std::collections::HashSet;
 use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::admin_auth::mutable_config_digest;
 use crate::api_impl::{
-    export_signed_registration_message, get_gateway_details, index, inject_immutable_config,
-    inject_mutable_config,
+    deregister_gateway, export_signed_add_chains_message, export_signed_registration_message,
+    export_signed_remove_chains_message, get_gateway_details, get_schedule_preview,
+    get_subscription_preview, get_txn_queue_metrics, index, inject_immutable_config,
+    inject_mutable_config, register_gateway,
 };
-use crate::chain_util::HttpProviderLogs;
-use crate::constant::{
-    COMMON_CHAIN_JOB_RELAYED_EVENT, MIN_GATEWAY_STAKE,
-    REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT,
-    REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT,
-    REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT,
+use crate::chain_util::{GatewayJobsView, GatewaysView, HttpProviderLogs, RelayFundsView};
+use crate::clock::{Clock, SystemClock};
+use crate::constant::MIN_GATEWAY_STAKE;
+use crate::contract_abi::{
+    CommonChainJobRelayedFilter, JobSubscriptionFundsDepositedFilter,
+    JobSubscriptionFundsWithdrawnFilter, JobSubscriptionJobParamsUpdatedFilter,
+    JobSubscriptionStartedFilter, JobSubscriptionTerminatedFilter,
+    JobSubscriptionTerminationParamsUpdatedFilter, RelayJobsReturn,
 };
+use crate::enclave_signer::{EnclaveSigner, InMemoryEnclaveSigner};
 use crate::error::ServerlessError;
-use crate::model::{AppState, ContractsClient, GatewayData, Job, SubscriptionJob};
+use crate::model::{
+    AppState, ContractsClient, Eip712Domains, GatewayData, Job, RequestChainData,
+    SubscriptionCatchUpPolicy, SubscriptionJob, SubscriptionTriggerConfig,
+};
 
 // Testnet or Local blockchain (Hardhat) configurations
 #[cfg(test)]
@@ -52,6 +59,8 @@ const SUBSCRIPTION_RELAY_CONTRACT_ADDR: &str = "0xA37F74824dA3DDaF241461c11f069E
 #[cfg(test)]
 pub const OWNER_ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
 #[cfg(test)]
+pub const OWNER_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+#[cfg(test)]
 pub const GAS_WALLET_KEY: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
 #[cfg(test)]
 pub const GAS_WALLET_PUBLIC_ADDRESS: &str = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
@@ -62,6 +71,42 @@ const TIME_INTERVAL: u64 = 20;
 #[cfg(test)]
 const OFFSET_FOR_EPCOH: u64 = 4;
 
+// Clock stopped at the given unix timestamp, only moved forward by the tests
+#[cfg(test)]
+#[derive(Debug)]
+pub struct MockClock(AtomicU64);
+
+#[cfg(test)]
+impl MockClock {
+    pub fn new(now: u64) -> Self {
+        MockClock(AtomicU64::new(now))
+    }
+
+    pub fn advance(&self, secs: u64) {
+        self.0.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+// Id of the job relayed to the Relay contract of the request chain after the given number of jobs
+#[cfg(test)]
+pub fn relay_job_id(request_chain_id: u64, job_count: u64) -> U256 {
+    U256::from(request_chain_id) << 192 | U256::from(job_count)
+}
+
+// Id of the subscription started on the RelaySubscriptions contract of the request chain after the
+// given number of subscriptions
+#[cfg(test)]
+pub fn job_subscription_id(request_chain_id: u64, subscription_count: u64) -> U256 {
+    U256::from(request_chain_id) << 192 | U256::one() << 191 | U256::from(subscription_count) << 127
+}
+
 #[cfg(test)]
 pub fn new_app(
     app_state: Data<AppState>,
@@ -80,18 +125,26 @@ pub fn new_app(
         .service(inject_immutable_config)
         .service(inject_mutable_config)
         .service(export_signed_registration_message)
+        .service(export_signed_add_chains_message)
+        .service(export_signed_remove_chains_message)
+        .service(register_gateway)
+        .service(deregister_gateway)
         .service(get_gateway_details)
+        .service(get_subscription_preview)
+        .service(get_schedule_preview)
+        .service(get_txn_queue_metrics)
 }
 
 #[cfg(test)]
 pub async fn generate_app_state() -> Data<AppState> {
     // Initialize random 'secp256k1' signing key for the enclave
-    let signer_key = SigningKey::random(&mut OsRng);
+    let enclave_signer = Arc::new(InMemoryEnclaveSigner::generate());
 
     Data::new(AppState {
-        enclave_signer_key: signer_key.clone(),
-        enclave_address: public_key_to_address(&signer_key.verifying_key()),
+        enclave_address: enclave_signer.address(),
+        enclave_signer,
         wallet: None.into(),
+        staged_wallet: None.into(),
         common_chain_id: CHAIN_ID,
         common_chain_http_url: HTTP_RPC_URL.to_owned(),
         common_chain_ws_url: WS_URL.to_owned(),
@@ -100,6 +153,7 @@ pub async fn generate_app_state() -> Data<AppState> {
         request_chain_ids: HashSet::new().into(),
         registered: Arc::new(AtomicBool::new(false)),
         registration_events_listener_active: false.into(),
+        deregistering: false.into(),
         epoch: EPOCH,
         time_interval: TIME_INTERVAL,
         offset_for_epoch: OFFSET_FOR_EPCOH,
@@ -107,11 +161,73 @@ pub async fn generate_app_state() -> Data<AppState> {
         immutable_params_injected: Mutex::new(false),
         mutable_params_injected: Arc::new(AtomicBool::new(false)),
         contracts_client: Mutex::new(None),
+        clock: Arc::new(SystemClock),
+        subscription_catch_up_policy: SubscriptionCatchUpPolicy::default(),
+        subscription_trigger: SubscriptionTriggerConfig::default(),
+        admin_auth_token: None,
+        mutable_config_nonce: Mutex::new(0),
+        eip712_domains: Eip712Domains::default(),
+        sealed_state_file: None,
     })
 }
 
+// Mutable config request body for the gas key signed by the given key
+#[cfg(test)]
+pub fn sign_mutable_config(
+    app_state: &AppState,
+    gas_key_hex: &str,
+    signer_key_hex: &str,
+    nonce: u64,
+    sign_timestamp: u64,
+) -> serde_json::Value {
+    let gas_address = gas_key_hex.parse::<LocalWallet>().unwrap().address();
+    let digest = mutable_config_digest(
+        &app_state.eip712_domains.serverless_gateway,
+        app_state.enclave_address,
+        gas_address,
+        nonce,
+        sign_timestamp,
+    );
+
+    let signer_key = SigningKey::from_slice(&hex::decode(signer_key_hex).unwrap()).unwrap();
+    let (rs, v) = signer_key.sign_prehash_recoverable(&digest).unwrap();
+    let mut owner_signature = rs.to_bytes().to_vec();
+    owner_signature.push(27 + v.to_byte());
+
+    json!({
+        "gas_wallet": {
+            "type": "local",
+            "gas_key_hex": gas_key_hex,
+        },
+        "nonce": nonce,
+        "sign_timestamp": sign_timestamp,
+        "owner_signature_hex": hex::encode(owner_signature),
+    })
+}
+
+// Mutable config request body for the gas key signed by the enclave owner with the next nonce
+#[cfg(test)]
+pub fn owner_signed_mutable_config(app_state: &AppState, gas_key_hex: &str) -> serde_json::Value {
+    let nonce = *app_state.mutable_config_nonce.lock().unwrap() + 1;
+    sign_mutable_config(
+        app_state,
+        gas_key_hex,
+        OWNER_KEY,
+        nonce,
+        app_state.clock.now(),
+    )
+}
+
 #[cfg(test)]
 pub async fn generate_contracts_client() -> Arc<ContractsClient> {
+    let (_, contracts_client) = generate_app_state_with_contracts_client().await;
+
+    contracts_client
+}
+
+// App state of a configured gateway along with its contracts client
+#[cfg(test)]
+pub async fn generate_app_state_with_contracts_client() -> (Data<AppState>, Arc<ContractsClient>) {
     let app_state = generate_app_state().await;
     let app = actix_web::test::init_service(new_app(app_state.clone())).await;
 
@@ -127,9 +243,7 @@ pub async fn generate_contracts_client() -> Arc<ContractsClient> {
     // add mutable config
     let req = actix_web::test::TestRequest::post()
         .uri("/mutable-config")
-        .set_json(&json!({
-            "gas_key_hex": GAS_WALLET_KEY
-        }))
+        .set_json(&owner_signed_mutable_config(&app_state, GAS_WALLET_KEY))
         .to_request();
     actix_web::test::call_service(&app, req).await;
 
@@ -145,7 +259,17 @@ pub async fn generate_contracts_client() -> Arc<ContractsClient> {
 
     let contracts_client = app_state.contracts_client.lock().unwrap().clone().unwrap();
 
-    contracts_client
+    (app_state, contracts_client)
+}
+
+#[cfg(test)]
+pub async fn generate_contracts_client_with_clock(clock: Arc<MockClock>) -> Arc<ContractsClient> {
+    let contracts_client = generate_contracts_client().await;
+
+    Arc::new(ContractsClient {
+        clock,
+        ..(*contracts_client).clone()
+    })
 }
 
 #[cfg(test)]
@@ -155,10 +279,7 @@ pub async fn add_gateway_epoch_state(
     add_self: Option<bool>,
     cycle_delta: Option<i64>,
 ) {
-    let ts = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    let ts = contracts_client.clock.now();
     let cycle = (((ts - contracts_client.epoch - contracts_client.offset_for_epoch)
         / contracts_client.time_interval) as i64
         + cycle_delta.unwrap_or(0)) as u64;
@@ -231,16 +352,16 @@ impl HttpProviderLogs for MockHttpProvider {
             let topic0 = topic0.unwrap();
 
             // Mock logs for gateways_job_relayed_logs
-            if topic0.eq(&H256::from_slice(&keccak256(
-                COMMON_CHAIN_JOB_RELAYED_EVENT,
-            ))) {
+            if topic0.eq(&CommonChainJobRelayedFilter::signature()) {
                 let job = self.job.clone().unwrap();
-                if job.job_id == U256::one() {
+                if job.job_id == relay_job_id(CHAIN_ID, 1)
+                    || job.job_id == relay_job_id(CHAIN_ID, 3)
+                {
                     Ok(vec![Log {
                         address: H160::from_str(GATEWAY_JOBS_CONTRACT_ADDR).unwrap().into(),
                         topics: vec![
-                            keccak256(COMMON_CHAIN_JOB_RELAYED_EVENT).into(),
-                            H256::from_uint(&job.job_id),
+                            CommonChainJobRelayedFilter::signature(),
+                            H256::from_uint(&job.key().common_chain_job_id()),
                         ],
                         data: encode(&[
                             Token::Uint(U256::from(100)),
@@ -261,9 +382,7 @@ impl HttpProviderLogs for MockHttpProvider {
                 }
             }
             // Mock logs for request_chain_job_subscription_started_event
-            else if topic0.eq(&H256::from_slice(&keccak256(
-                REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT,
-            ))) {
+            else if topic0.eq(&JobSubscriptionStartedFilter::signature()) {
                 let subscription_started_still_active_event =
                     generate_job_subscription_started_log(None, Some(-50));
 
@@ -306,12 +425,93 @@ impl HttpProviderLogs for MockHttpProvider {
     }
 }
 
+// Job 1 of the request chain is relayed by the selected gateway, the state of job 3 can not be
+// fetched
+#[cfg(test)]
+impl GatewayJobsView for MockHttpProvider {
+    async fn relay_job_state(&self, job_id: U256) -> Result<RelayJobsReturn, ServerlessError> {
+        let job = self.job.clone().unwrap();
+        if job_id == relay_job_id(CHAIN_ID, 3) {
+            return Err(ServerlessError::ProviderError(
+                "relayJobs call failed".to_owned(),
+            ));
+        }
+
+        let gateway = if job_id == relay_job_id(CHAIN_ID, 1) {
+            job.gateway_address.unwrap()
+        } else {
+            Address::zero()
+        };
+
+        Ok(RelayJobsReturn {
+            exec_start_time: job.starttime,
+            usdc_deposit: U256::from(2000),
+            job_owner: job.job_owner,
+            is_resource_unavailable: false,
+            sequence_id: job.sequence_number,
+            env: job.env,
+            gateway,
+        })
+    }
+}
+
+// Execution fee of 1 per ms with a gateway fee of 100, no balance left on chain
+#[cfg(test)]
+impl RelayFundsView for MockHttpProvider {
+    async fn job_fee(&self, _env: u8, user_timeout: U256) -> Result<U256, ServerlessError> {
+        Ok(user_timeout + U256::from(100))
+    }
+
+    async fn job_subscription_balance(
+        &self,
+        _subscription_id: U256,
+    ) -> Result<U256, ServerlessError> {
+        Ok(U256::zero())
+    }
+}
+
+// Gateway registered with the given request chains, every one of them served by the test relay
+// contracts and rpc urls except the unavailable ones, whose data can't be fetched
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockGatewaysView {
+    pub chain_ids: HashSet<u64>,
+    pub unavailable_chain_ids: HashSet<u64>,
+}
+
+#[cfg(test)]
+impl GatewaysView for MockGatewaysView {
+    async fn gateway_chain_ids(
+        &self,
+        _enclave_address: Address,
+    ) -> Result<HashSet<u64>, ServerlessError> {
+        Ok(self.chain_ids.clone())
+    }
+
+    async fn request_chain_data(&self, chain_id: u64) -> Result<RequestChainData, ServerlessError> {
+        if !self.chain_ids.contains(&chain_id) || self.unavailable_chain_ids.contains(&chain_id) {
+            return Err(ServerlessError::ProviderError(format!(
+                "Request chain {} not found",
+                chain_id
+            )));
+        }
+
+        Ok(RequestChainData {
+            relay_address: RELAY_CONTRACT_ADDR.parse().unwrap(),
+            relay_subscriptions_address: SUBSCRIPTION_RELAY_CONTRACT_ADDR.parse().unwrap(),
+            http_rpc_url: HTTP_RPC_URL.to_owned(),
+            ws_rpc_url: WS_URL.to_owned(),
+            block_number: 0,
+        })
+    }
+}
+
 #[cfg(test)]
 pub fn generate_job_subscription_started_log(
     job_id: Option<u64>,
     starttime_delta: Option<i64>,
 ) -> Log {
-    let job_id = U256::from(job_id.unwrap_or(1));
+    let job_id = job_subscription_id(CHAIN_ID, job_id.unwrap_or(1));
 
     let starttime = U256::from(
         ((SystemTime::now()
@@ -326,7 +526,7 @@ pub fn generate_job_subscription_started_log(
     Log {
         address: Address::default(),
         topics: vec![
-            keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_STARTED_EVENT).into(),
+            JobSubscriptionStartedFilter::signature(),
             H256::from_uint(&job_id),
             H256::from_uint(&U256::one()),
             H256::from(Address::from_str(SUBSCRIPTION_RELAY_CONTRACT_ADDR).unwrap()),
@@ -372,7 +572,7 @@ pub fn generate_generic_subscription_job(
     let termination_time = starttime + 1000;
 
     SubscriptionJob {
-        subscription_id: U256::from(job_id.unwrap_or(1)),
+        subscription_id: job_subscription_id(CHAIN_ID, job_id.unwrap_or(1)),
         request_chain_id: CHAIN_ID,
         subscriber: Address::from_str(SUBSCRIPTION_RELAY_CONTRACT_ADDR).unwrap(),
         interval: U256::from(10),
@@ -389,6 +589,7 @@ pub fn generate_generic_subscription_job(
         .into(),
         starttime,
         env: 1u8,
+        usdc_balance: U256::from(1000),
     }
 }
 
@@ -401,8 +602,8 @@ pub fn generate_job_subscription_job_params_updated(
     Log {
         address: Address::default(),
         topics: vec![
-            keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_JOB_PARAMS_UPDATED_EVENT).into(),
-            H256::from_uint(&U256::from(job_id.unwrap_or(1))),
+            JobSubscriptionJobParamsUpdatedFilter::signature(),
+            H256::from_uint(&job_subscription_id(CHAIN_ID, job_id.unwrap_or(1))),
         ],
         data: encode(&[
             Token::FixedBytes(
@@ -432,7 +633,7 @@ pub fn generate_job_subscription_termination_params_updated(
     job_id: Option<u64>,
     termination_time: Option<u64>,
 ) -> Log {
-    let job_id = U256::from(job_id.unwrap_or(1));
+    let job_id = job_subscription_id(CHAIN_ID, job_id.unwrap_or(1));
 
     let termination_time = U256::from(
         termination_time.unwrap_or(
@@ -447,10 +648,66 @@ pub fn generate_job_subscription_termination_params_updated(
     Log {
         address: Address::default(),
         topics: vec![
-            keccak256(REQUEST_CHAIN_JOB_SUBSCRIPTION_TERMINATION_PARAMS_UPDATED_EVENT).into(),
+            JobSubscriptionTerminationParamsUpdatedFilter::signature(),
             H256::from_uint(&job_id),
         ],
         data: encode(&[Token::Uint(termination_time)]).into(),
         ..Default::default()
     }
 }
+
+#[cfg(test)]
+pub fn generate_job_subscription_terminated(job_id: Option<u64>) -> Log {
+    Log {
+        address: Address::default(),
+        topics: vec![
+            JobSubscriptionTerminatedFilter::signature(),
+            H256::from_uint(&job_subscription_id(CHAIN_ID, job_id.unwrap_or(1))),
+        ],
+        data: Bytes::default(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+pub fn generate_job_subscription_funds_deposited(
+    job_id: Option<u64>,
+    usdc_deposit: Option<u64>,
+) -> Log {
+    Log {
+        address: Address::default(),
+        topics: vec![
+            JobSubscriptionFundsDepositedFilter::signature(),
+            H256::from_uint(&job_subscription_id(CHAIN_ID, job_id.unwrap_or(1))),
+            H256::from(Address::from_str(SUBSCRIPTION_RELAY_CONTRACT_ADDR).unwrap()),
+        ],
+        data: encode(&[
+            Token::Uint(U256::from(usdc_deposit.unwrap_or(1000))),
+            Token::Uint(U256::from(100)),
+        ])
+        .into(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+pub fn generate_job_subscription_funds_withdrawn(
+    job_id: Option<u64>,
+    usdc_amount_withdrawn: Option<u64>,
+) -> Log {
+    Log {
+        address: Address::default(),
+        topics: vec![
+            JobSubscriptionFundsWithdrawnFilter::signature(),
+            H256::from_uint(&job_subscription_id(CHAIN_ID, job_id.unwrap_or(1))),
+            H256::from(Address::from_str(SUBSCRIPTION_RELAY_CONTRACT_ADDR).unwrap()),
+        ],
+        data: encode(&[
+            Token::Uint(U256::from(usdc_amount_withdrawn.unwrap_or(1000))),
+            Token::Uint(U256::from(100)),
+            Token::Bool(true),
+        ])
+        .into(),
+        ..Default::default()
+    }
+}
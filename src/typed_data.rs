@@ -0,0 +1,468 @@
+use anyhow::{anyhow, Context};
+use ethers::abi::{encode, Token};
+use ethers::types::transaction::eip712::EIP712Domain;
+use ethers::types::{Address, Bytes, H256, U256};
+use ethers::utils::{keccak256, public_key_to_address};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+use crate::enclave_signer::EnclaveSigner;
+use crate::model::Eip712Domains;
+
+/// Value of a field of an EIP-712 struct, encoded as per `encodeData`: atomic values as they are
+/// and dynamic values (`bytes`, `string` and arrays) by their keccak256 hash.
+pub trait Eip712Value {
+    fn encode_value(&self) -> Token;
+}
+
+impl Eip712Value for U256 {
+    fn encode_value(&self) -> Token {
+        Token::Uint(*self)
+    }
+}
+
+impl Eip712Value for u64 {
+    fn encode_value(&self) -> Token {
+        Token::Uint((*self).into())
+    }
+}
+
+impl Eip712Value for u8 {
+    fn encode_value(&self) -> Token {
+        Token::Uint((*self).into())
+    }
+}
+
+impl Eip712Value for Address {
+    fn encode_value(&self) -> Token {
+        Token::Address(*self)
+    }
+}
+
+impl Eip712Value for H256 {
+    fn encode_value(&self) -> Token {
+        Token::FixedBytes(self.as_bytes().to_vec())
+    }
+}
+
+impl Eip712Value for Bytes {
+    fn encode_value(&self) -> Token {
+        Token::FixedBytes(keccak256(self).to_vec())
+    }
+}
+
+impl Eip712Value for String {
+    fn encode_value(&self) -> Token {
+        Token::FixedBytes(keccak256(self.as_bytes()).to_vec())
+    }
+}
+
+impl<T: Eip712Value> Eip712Value for Vec<T> {
+    fn encode_value(&self) -> Token {
+        let encoded_values: Vec<Token> = self.iter().map(Eip712Value::encode_value).collect();
+        Token::FixedBytes(keccak256(encode(&encoded_values)).to_vec())
+    }
+}
+
+/// Struct signed as EIP-712 typed data, implemented through `eip712_struct!`.
+pub trait Eip712Struct {
+    /// Encoded type of the struct, `Name(type1 field1,type2 field2,...)`.
+    const TYPE: &'static str;
+
+    fn encode_fields(&self) -> Vec<Token>;
+
+    fn type_hash(&self) -> [u8; 32] {
+        keccak256(Self::TYPE)
+    }
+
+    fn struct_hash(&self) -> [u8; 32] {
+        let mut tokens = vec![Token::FixedBytes(self.type_hash().to_vec())];
+        tokens.extend(self.encode_fields());
+        keccak256(encode(&tokens))
+    }
+}
+
+/// Declares a struct with its fields along with their Solidity declarations, and derives its
+/// EIP-712 type and struct hash from them.
+macro_rules! eip712_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident as $type_name:literal {
+            $first_field:ident: $first_ty:ty => $first_decl:literal
+            $(, $field:ident: $ty:ty => $decl:literal)* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            pub $first_field: $first_ty,
+            $(pub $field: $ty,)*
+        }
+
+        impl $crate::typed_data::Eip712Struct for $name {
+            const TYPE: &'static str =
+                concat!($type_name, "(", $first_decl, $(",", $decl,)* ")");
+
+            fn encode_fields(&self) -> Vec<::ethers::abi::Token> {
+                vec![
+                    $crate::typed_data::Eip712Value::encode_value(&self.$first_field),
+                    $($crate::typed_data::Eip712Value::encode_value(&self.$field),)*
+                ]
+            }
+        }
+    };
+}
+
+pub(crate) use eip712_struct;
+
+/// Domain of the contracts deployed with the version 1 of their EIP-712 domain, identified by
+/// the name and the version only.
+pub fn contract_domain(name: &str) -> EIP712Domain {
+    EIP712Domain {
+        name: Some(name.to_owned()),
+        version: Some("1".to_owned()),
+        ..Default::default()
+    }
+}
+
+impl Default for Eip712Domains {
+    fn default() -> Self {
+        Eip712Domains {
+            gateways: contract_domain("marlin.oyster.Gateways"),
+            gateway_jobs: contract_domain("marlin.oyster.GatewayJobs"),
+            relay: contract_domain("marlin.oyster.Relay"),
+            relay_subscriptions: contract_domain("marlin.oyster.RelaySubscriptions"),
+            serverless_gateway: contract_domain("marlin.oyster.ServerlessGateway"),
+        }
+    }
+}
+
+/// Digest of the typed data to sign, `keccak256("\x19\x01" ‖ domainSeparator ‖ hashStruct(message))`.
+pub fn typed_data_digest(domain: &EIP712Domain, message: &impl Eip712Struct) -> [u8; 32] {
+    let mut digest_input = Vec::with_capacity(66);
+    digest_input.extend_from_slice(b"\x19\x01");
+    digest_input.extend_from_slice(&domain.separator());
+    digest_input.extend_from_slice(&message.struct_hash());
+    keccak256(digest_input)
+}
+
+/// Signs the typed data with the enclave key, returning the 65 bytes (r, s, v) signature.
+pub fn sign_typed_data(
+    enclave_signer: &dyn EnclaveSigner,
+    domain: &EIP712Domain,
+    message: &impl Eip712Struct,
+) -> Result<Vec<u8>, k256::ecdsa::Error> {
+    enclave_signer.sign_digest(&typed_data_digest(domain, message))
+}
+
+/// Recovers the address that signed the digest from a 65 bytes (r, s, v) signature, `v` being
+/// either 0/1 or 27/28.
+pub fn recover_digest_signer(digest: &[u8; 32], signature: &[u8]) -> anyhow::Result<Address> {
+    if signature.len() != 65 {
+        return Err(anyhow!(
+            "Signature must be 65 bytes long, found {} bytes",
+            signature.len()
+        ));
+    }
+
+    let rs = Signature::from_slice(&signature[0..64]).context("Invalid signature (r, s)")?;
+    let v = signature[64];
+    let recovery_id = RecoveryId::try_from(if v >= 27 { v - 27 } else { v })
+        .context("Invalid signature recovery id")?;
+
+    let verifying_key = VerifyingKey::recover_from_prehash(digest, &rs, recovery_id)
+        .context("Failed to recover the signer key")?;
+
+    Ok(public_key_to_address(&verifying_key))
+}
+
+#[cfg(test)]
+pub fn recover_typed_data_signer(
+    domain: &EIP712Domain,
+    message: &impl Eip712Struct,
+    signature: &[u8],
+) -> anyhow::Result<Address> {
+    recover_digest_signer(&typed_data_digest(domain, message), signature)
+}
+
+/// Checks that the typed data was signed by the given address.
+#[cfg(test)]
+pub fn verify_typed_data(
+    domain: &EIP712Domain,
+    message: &impl Eip712Struct,
+    signature: &[u8],
+    address: Address,
+) -> bool {
+    matches!(
+        recover_typed_data_signer(domain, message, signature),
+        Ok(signer_address) if signer_address == address
+    )
+}
+
+// Messages signed by the enclave for the contracts
+eip712_struct! {
+    #[derive(Debug, Clone)]
+    pub struct RelayJobMessage as "RelayJob" {
+        job_id: U256 => "uint256 jobId",
+        code_hash: H256 => "bytes32 codeHash",
+        code_inputs: Bytes => "bytes codeInputs",
+        deadline: U256 => "uint256 deadline",
+        job_request_timestamp: U256 => "uint256 jobRequestTimestamp",
+        sequence_id: u8 => "uint8 sequenceId",
+        job_owner: Address => "address jobOwner",
+        env: u8 => "uint8 env",
+        sign_timestamp: u64 => "uint256 signTimestamp",
+    }
+}
+
+eip712_struct! {
+    #[derive(Debug, Clone)]
+    pub struct ReassignGatewayMessage as "ReassignGateway" {
+        job_id: U256 => "uint256 jobId",
+        gateway_old: Address => "address gatewayOld",
+        job_owner: Address => "address jobOwner",
+        sequence_id: u8 => "uint8 sequenceId",
+        job_request_timestamp: U256 => "uint256 jobRequestTimestamp",
+        sign_timestamp: u64 => "uint256 signTimestamp",
+    }
+}
+
+eip712_struct! {
+    #[derive(Debug, Clone)]
+    pub struct JobResponseMessage as "JobResponse" {
+        job_id: U256 => "uint256 jobId",
+        output: Bytes => "bytes output",
+        total_time: U256 => "uint256 totalTime",
+        error_code: u8 => "uint8 errorCode",
+        sign_timestamp: u64 => "uint256 signTimestamp",
+    }
+}
+
+eip712_struct! {
+    #[derive(Debug, Clone)]
+    pub struct GatewaysRegisterMessage as "Register" {
+        owner: Address => "address owner",
+        chain_ids: Vec<U256> => "uint256[] chainIds",
+        sign_timestamp: u64 => "uint256 signTimestamp",
+    }
+}
+
+eip712_struct! {
+    #[derive(Debug, Clone)]
+    pub struct RelayRegisterMessage as "Register" {
+        owner: Address => "address owner",
+        sign_timestamp: u64 => "uint256 signTimestamp",
+    }
+}
+
+eip712_struct! {
+    #[derive(Debug, Clone)]
+    pub struct AddChainsMessage as "AddChains" {
+        chain_ids: Vec<U256> => "uint256[] chainIds",
+        sign_timestamp: u64 => "uint256 signTimestamp",
+    }
+}
+
+eip712_struct! {
+    #[derive(Debug, Clone)]
+    pub struct RemoveChainsMessage as "RemoveChains" {
+        chain_ids: Vec<U256> => "uint256[] chainIds",
+        sign_timestamp: u64 => "uint256 signTimestamp",
+    }
+}
+
+#[cfg(test)]
+mod typed_data_tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::enclave_signer::InMemoryEnclaveSigner;
+    use crate::test_util::{GAS_WALLET_PUBLIC_ADDRESS, OWNER_ADDRESS};
+
+    const SIGN_TIMESTAMP: u64 = 1700000010;
+
+    fn owner() -> Address {
+        Address::from_str(OWNER_ADDRESS).unwrap()
+    }
+
+    // Expected digests computed independently as the contracts do, with
+    // `keccak256(abi.encode(TYPEHASH, ...))` and `_hashTypedDataV4`
+    #[test]
+    fn test_relay_job_digest() {
+        let relay_job = RelayJobMessage {
+            job_id: U256::one(),
+            code_hash: H256::repeat_byte(0x11),
+            code_inputs: Bytes::from(vec![0x12, 0x34]),
+            deadline: U256::from(5000),
+            job_request_timestamp: U256::from(1700000000),
+            sequence_id: 1,
+            job_owner: owner(),
+            env: 1,
+            sign_timestamp: SIGN_TIMESTAMP,
+        };
+
+        assert_eq!(
+            RelayJobMessage::TYPE,
+            "RelayJob(uint256 jobId,bytes32 codeHash,bytes codeInputs,uint256 deadline,uint256 jobRequestTimestamp,uint8 sequenceId,address jobOwner,uint8 env,uint256 signTimestamp)"
+        );
+        assert_eq!(
+            hex::encode(typed_data_digest(
+                &Eip712Domains::default().gateway_jobs,
+                &relay_job
+            )),
+            "ea8bf239de319864e06e631d19d6e0e5cc58f27e062b5cd4172ec7b99dc33a54"
+        );
+    }
+
+    #[test]
+    fn test_reassign_gateway_digest() {
+        let reassign_gateway = ReassignGatewayMessage {
+            job_id: U256::one(),
+            gateway_old: Address::from_str(GAS_WALLET_PUBLIC_ADDRESS).unwrap(),
+            job_owner: owner(),
+            sequence_id: 2,
+            job_request_timestamp: U256::from(1700000000),
+            sign_timestamp: SIGN_TIMESTAMP,
+        };
+
+        assert_eq!(
+            hex::encode(typed_data_digest(
+                &Eip712Domains::default().gateway_jobs,
+                &reassign_gateway
+            )),
+            "ac9d240f407894f3699189b0bd3b5556d6fc755834177f96df16b9a0d48b5433"
+        );
+    }
+
+    #[test]
+    fn test_job_response_digest() {
+        let job_response = JobResponseMessage {
+            job_id: U256::one(),
+            output: Bytes::from(vec![0xab, 0xcd]),
+            total_time: U256::from(100),
+            error_code: 0,
+            sign_timestamp: SIGN_TIMESTAMP,
+        };
+        let eip712_domains = Eip712Domains::default();
+
+        assert_eq!(
+            hex::encode(typed_data_digest(&eip712_domains.relay, &job_response)),
+            "fc928a1e6a6a450047bd616ede7a08a6d28b92a7bf3ec9029fbb260050e618dc"
+        );
+        assert_eq!(
+            hex::encode(typed_data_digest(
+                &eip712_domains.relay_subscriptions,
+                &job_response
+            )),
+            "c9d910ec99db73f39bcfd0be0c50abe309e02d8013f9347009286763cba8c3a9"
+        );
+    }
+
+    #[test]
+    fn test_register_digests() {
+        let gateways_register = GatewaysRegisterMessage {
+            owner: owner(),
+            chain_ids: vec![U256::from(421614), U256::from(31337)],
+            sign_timestamp: SIGN_TIMESTAMP,
+        };
+        let relay_register = RelayRegisterMessage {
+            owner: owner(),
+            sign_timestamp: SIGN_TIMESTAMP,
+        };
+        let eip712_domains = Eip712Domains::default();
+
+        assert_eq!(
+            hex::encode(typed_data_digest(
+                &eip712_domains.gateways,
+                &gateways_register
+            )),
+            "5db1e9fe119fdea2216ea9e32f80f28c2067809180d0026d76a6e06549a9f3bc"
+        );
+        assert_eq!(
+            hex::encode(typed_data_digest(&eip712_domains.relay, &relay_register)),
+            "9c99313797d2cdcc859c08da89ac7a92533a63788adc7a6bc8069ac6d0bddf3d"
+        );
+    }
+
+    #[test]
+    fn test_chains_update_digests() {
+        let chain_ids = vec![U256::from(31337), U256::from(421614)];
+        let eip712_domains = Eip712Domains::default();
+
+        assert_eq!(
+            hex::encode(typed_data_digest(
+                &eip712_domains.gateways,
+                &AddChainsMessage {
+                    chain_ids: chain_ids.clone(),
+                    sign_timestamp: SIGN_TIMESTAMP,
+                }
+            )),
+            "b309a530b509f8849fd96ac9490f99d6cdf576fb77f1b7de4abc0a5e3e0615c6"
+        );
+        assert_eq!(
+            hex::encode(typed_data_digest(
+                &eip712_domains.gateways,
+                &RemoveChainsMessage {
+                    chain_ids,
+                    sign_timestamp: SIGN_TIMESTAMP,
+                }
+            )),
+            "082e5639e8ce9d89ccb08a215e7dfab0197c943faa34928e9d66b5b34ab5cadc"
+        );
+    }
+
+    // Domain separator of the example in the EIP-712 specification
+    #[test]
+    fn test_domain_with_chain_id_and_verifying_contract() {
+        let domain = EIP712Domain {
+            chain_id: Some(U256::one()),
+            verifying_contract: Some(
+                Address::from_str("0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC").unwrap(),
+            ),
+            ..contract_domain("Ether Mail")
+        };
+
+        assert_eq!(
+            hex::encode(domain.separator()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify_typed_data() {
+        let enclave_signer = InMemoryEnclaveSigner::generate();
+        let relay_register = RelayRegisterMessage {
+            owner: owner(),
+            sign_timestamp: SIGN_TIMESTAMP,
+        };
+        let domain = Eip712Domains::default().relay;
+
+        let signature = sign_typed_data(&enclave_signer, &domain, &relay_register).unwrap();
+
+        assert_eq!(
+            recover_typed_data_signer(&domain, &relay_register, &signature).unwrap(),
+            enclave_signer.address()
+        );
+        assert!(verify_typed_data(
+            &domain,
+            &relay_register,
+            &signature,
+            enclave_signer.address()
+        ));
+        // Signatures are bound to the domain and the message
+        assert!(!verify_typed_data(
+            &Eip712Domains::default().gateways,
+            &relay_register,
+            &signature,
+            enclave_signer.address()
+        ));
+        assert!(!verify_typed_data(
+            &domain,
+            &RelayRegisterMessage {
+                sign_timestamp: SIGN_TIMESTAMP + 1,
+                ..relay_register.clone()
+            },
+            &signature,
+            enclave_signer.address()
+        ));
+        assert!(recover_typed_data_signer(&domain, &relay_register, &signature[0..64]).is_err());
+    }
+}